@@ -15,7 +15,16 @@ rustPlatform.buildRustPackage rec {
   ];
 
   checkPhase = "";
-  cargoSha256 = "sha256:1ylhvrbjdzrlhgvpza790z3cxlqch0ndxc4jxbkd3w38bi5mlgyv";
+
+  postInstall = ''
+    install -Dm644 data/org.cerebro.policy \
+      $out/share/polkit-1/actions/org.cerebro.policy
+  '';
+
+  # Hash of the vendored dependencies, to be regenerated each time they
+  # change in Cargo.toml: build once with the fake hash and use the one
+  # reported by nix.
+  cargoSha256 = pkgs.lib.fakeSha256;
 
   meta = with stdenv.lib; {
     description = "System monitoring daemon";
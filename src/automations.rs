@@ -0,0 +1,275 @@
+use lazy_static::lazy_static;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Condvar;
+use std::thread;
+
+use crate::bus;
+use crate::config;
+use crate::error;
+use crate::event_log;
+use crate::expression;
+use crate::policy;
+use crate::sync::Mutex;
+use crate::triggers;
+
+/// Origin of a transition that applies to every state
+const ANY_STATE: &str = "*";
+
+/// Number of transitions taken at most for a single change of the values,
+/// so that a cycle of transitions cannot run forever
+const MAX_TRANSITIONS: usize = 16;
+
+lazy_static! {
+    /// Incremented when a value changes
+    static ref GENERATION: Mutex<u64> = Mutex::new(0);
+
+    /// Notified when a value changes
+    static ref CHANGED: Condvar = Condvar::new();
+}
+
+/// Transition between two states of an automation
+struct Transition {
+    from: String,
+    to: String,
+    when: expression::Expression,
+}
+
+/// State machine driven by the values of the entries
+pub struct Automation {
+    name: String,
+    state: String,
+    states: HashMap<String, config::AutomationStateConfig>,
+    transitions: Vec<Transition>,
+}
+
+impl Automation {
+    /// Automation constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the automation
+    /// * `config` - The configuration of the automation
+    fn new(name: &str, config: &config::AutomationConfig)
+        -> Result<Self, error::CerebroError> {
+
+        let check_state = |state: &str| {
+            match config.states.contains_key(state) {
+                true => success!(),
+                false => error!(Config, format!(
+                    "automation `{}`: unknown state `{}`",
+                    name,
+                    state)),
+            }
+        };
+
+        check_state(&config.initial)?;
+
+        let mut transitions = Vec::new();
+
+        for transition in config.transitions.iter() {
+            if transition.from != ANY_STATE {
+                check_state(&transition.from)?;
+            }
+
+            check_state(&transition.to)?;
+
+            let when = match expression::Expression::parse(&transition.when) {
+                Ok(w) => w,
+                Err(e) => return error!(Config, format!(
+                    "automation `{}`: invalid condition `{}`: {}",
+                    name,
+                    transition.when,
+                    e)),
+            };
+
+            transitions.push(Transition {
+                from: transition.from.clone(),
+                to: transition.to.clone(),
+                when: when,
+            });
+        }
+
+        return Ok(Self {
+            name: name.to_string(),
+            state: config.initial.clone(),
+            states: config.states.clone(),
+            transitions: transitions,
+        });
+    }
+
+    /// Find the first transition that applies to the current state
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn next_state(&self) -> Option<String> {
+        for transition in self.transitions.iter() {
+            if transition.from != ANY_STATE && transition.from != self.state {
+                continue;
+            }
+
+            // A state is not entered again
+            if transition.to == self.state {
+                continue;
+            }
+
+            match transition.when.evaluate(&|p| bus::value(p)) {
+                Some(expression::Value::Bool(true)) =>
+                    return Some(transition.to.clone()),
+
+                _ => (),
+            }
+        }
+
+        return None;
+    }
+
+    /// Run the commands of a state
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `commands` - The commands to be run
+    fn run(&self, commands: &Option<Vec<String>>) {
+        let commands = match commands {
+            Some(c) => c,
+            None => return,
+        };
+
+        for command in commands.iter() {
+            // The references are resolved at each transition
+            let result = match config::substitute(command) {
+                Ok(c) => triggers::run(&c),
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(_) => (),
+                Err(e) => log::error!(
+                    "automation `{}`: {}: {}",
+                    self.name,
+                    command,
+                    e),
+            }
+        }
+    }
+
+    /// Take the transitions that apply to the current values
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn step(&mut self) {
+        for _ in 0..MAX_TRANSITIONS {
+            let next = match self.next_state() {
+                Some(s) => s,
+                None => return,
+            };
+
+            log::info!(
+                "automation `{}`: {} -> {}",
+                self.name,
+                self.state,
+                next);
+
+            event_log::write(
+                "automation",
+                json!({
+                    "automation": self.name,
+                    "from": self.state,
+                    "to": next,
+                }));
+
+            match self.states.get(&self.state) {
+                Some(s) => self.run(&s.on_exit),
+                None => (),
+            }
+
+            self.state = next;
+
+            match self.states.get(&self.state) {
+                Some(s) => self.run(&s.on_enter),
+                None => (),
+            }
+        }
+
+        log::warn!("automation `{}`: too many transitions", self.name);
+    }
+}
+
+/// Function used to load the automations of the configuration
+///
+/// # Arguments
+///
+/// * `config` - The configuration
+pub fn load(config: &config::Config)
+    -> Result<Vec<Automation>, error::CerebroError> {
+
+    let mut configs: Vec<(String, config::AutomationConfig)> =
+        match &config.automations {
+            Some(a) => a
+                .iter()
+                .map(|(n, a)| (n.clone(), a.clone()))
+                .collect(),
+
+            None => Vec::new(),
+        };
+
+    configs.sort_by(|a, b| a.0.cmp(&b.0));
+
+    // Policies are run after the automations
+    configs.append(&mut policy::automations(config));
+
+    let mut automations = Vec::new();
+
+    for (name, automation) in configs.iter() {
+        automations.push(Automation::new(name, automation)?);
+    }
+
+    return Ok(automations);
+}
+
+/// Function used to signal that a value has changed
+pub fn notify() {
+    match GENERATION.lock() {
+        Ok(mut g) => *g += 1,
+        Err(_) => return,
+    }
+
+    CHANGED.notify_all();
+}
+
+/// Function used to evaluate the automations each time a value changes
+///
+/// The automations start in their initial state, whose commands are not
+/// run. Changes happening while the automations are evaluated are handled
+/// at once.
+///
+/// # Arguments
+///
+/// * `automations` - The automations
+pub fn start(mut automations: Vec<Automation>) {
+    if automations.is_empty() {
+        return;
+    }
+
+    thread::spawn(move || {
+        let mut generation = 0;
+
+        loop {
+            generation = match GENERATION.lock() {
+                Ok(g) => match CHANGED.wait_while(g, |g| *g == generation) {
+                    Ok(g) => *g,
+                    Err(e) => *e.into_inner(),
+                },
+
+                Err(_) => return,
+            };
+
+            for automation in automations.iter_mut() {
+                automation.step();
+            }
+        }
+    });
+}
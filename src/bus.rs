@@ -0,0 +1,63 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+
+use crate::sync::Mutex;
+
+lazy_static! {
+    static ref VALUES: Mutex<HashMap<String, String>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Function used to store the new value of an entry
+///
+/// # Arguments
+///
+/// * `module` - The name of the module
+/// * `entry` - The path of the entry in the module
+/// * `value` - The new value
+pub fn publish(module: &str, entry: &str, value: &str) {
+    match VALUES.lock() {
+        Ok(mut v) => {
+            v.insert(format!("{}/{}", module, entry), value.to_string());
+        },
+
+        Err(_) => log::error!("Cannot lock values"),
+    }
+}
+
+/// Function used to get the current value of an entry
+///
+/// # Arguments
+///
+/// * `path` - The path of the entry (e.g. `memory/used`)
+pub fn value(path: &str) -> Option<String> {
+    return match VALUES.lock() {
+        Ok(v) => v.get(path.trim_start_matches('/')).cloned(),
+        Err(_) => None,
+    };
+}
+
+/// Function used to get the current values of the entries under a path
+///
+/// # Arguments
+///
+/// * `prefix` - The path of the parent entry (e.g. `cpu/physical`)
+pub fn values(prefix: &str) -> Vec<(String, String)> {
+    let prefix = format!("{}/", prefix.trim_start_matches('/')
+        .trim_end_matches('/'));
+
+    let values = match VALUES.lock() {
+        Ok(v) => v,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut list: Vec<(String, String)> = values
+        .iter()
+        .filter(|(path, _)| path.starts_with(&prefix))
+        .map(|(path, value)| (path.clone(), value.clone()))
+        .collect();
+
+    list.sort();
+
+    return list;
+}
@@ -0,0 +1,97 @@
+use serde_json::{json, Map, Value};
+use std::env;
+use std::sync::Arc;
+
+use crate::modules::Module;
+use crate::modules::battery;
+use crate::modules::brightness;
+use crate::modules::network;
+use crate::platform;
+use crate::sync::Mutex;
+
+/// Optional features and whether they are compiled in
+const FEATURES: [(&str, bool); 6] = [
+    ("dbus", cfg!(feature = "dbus")),
+    ("http", cfg!(feature = "http")),
+    ("media", cfg!(feature = "media")),
+    ("sensors", cfg!(all(target_os = "linux", feature = "sensors"))),
+    ("sysfs", cfg!(target_os = "linux")),
+    ("weather", cfg!(feature = "weather")),
+];
+
+/// Function used to probe the modules
+///
+/// The value is `None` when the module cannot tell whether it can run.
+///
+/// # Arguments
+///
+/// * `modules` - The list of the modules
+fn probe(modules: &Vec<Arc<Mutex<dyn Module>>>)
+    -> Vec<(String, Option<bool>)> {
+
+    return modules
+        .iter()
+        .filter_map(|m| match m.lock() {
+            Ok(m) => Some((m.name().to_string(), m.probe())),
+            Err(_) => None,
+        })
+        .collect();
+}
+
+/// Function used to render the capabilities of the host in JSON
+///
+/// # Arguments
+///
+/// * `modules` - The list of the modules
+pub fn render(modules: &Vec<Arc<Mutex<dyn Module>>>) -> Value {
+    let mut features = Map::new();
+
+    for (name, enabled) in FEATURES.iter() {
+        features.insert(name.to_string(), json!(enabled));
+    }
+
+    let mut probes = Map::new();
+
+    for (name, available) in probe(modules) {
+        probes.insert(name, json!(available));
+    }
+
+    return json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "os": env::consts::OS,
+        "features": features,
+        "hardware": {
+            "sensors": platform::current().sensor_chips(),
+            "backlights": brightness::backlights(),
+            "batteries": battery::batteries(),
+            "interfaces": network::interfaces(),
+        },
+        "modules": probes,
+    });
+}
+
+/// Function used to log a summary of the capabilities at startup
+///
+/// # Arguments
+///
+/// * `modules` - The list of the modules
+pub fn banner(modules: &Vec<Arc<Mutex<dyn Module>>>) {
+    let probes = probe(modules);
+
+    let unavailable: Vec<&str> = probes
+        .iter()
+        .filter(|(_, a)| *a == Some(false))
+        .map(|(n, _)| n.as_str())
+        .collect();
+
+    log::info!(
+        "cerebro {} on {}: {} modules, {} without hardware",
+        env!("CARGO_PKG_VERSION"),
+        env::consts::OS,
+        probes.len(),
+        unavailable.len());
+
+    if !unavailable.is_empty() {
+        log::info!("No hardware for: {}", unavailable.join(", "));
+    }
+}
@@ -0,0 +1,580 @@
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::config;
+use crate::error;
+use crate::event_log;
+use crate::rrd;
+use crate::secrets;
+
+const BINARY_NAME: &str = "cerebro";
+
+/// Examples installed by the `examples` command, as (file name, content)
+const EXAMPLES: [(&str, &str); 5] = [
+    ("config.json", include_str!("../data/examples/config.json")),
+    ("battery.triggers", include_str!("../data/examples/battery.triggers")),
+    ("cpu.triggers", include_str!("../data/examples/cpu.triggers")),
+    (
+        "brightness.triggers",
+        include_str!("../data/examples/brightness.triggers")),
+    ("waybar.json", include_str!("../data/examples/waybar.json")),
+];
+
+const SYSTEM_MOUNTPOINT: &str = "/run/cerebro";
+const USER_MOUNTPOINT: &str = "/tmp/cerebro";
+
+/// Command line interface
+#[derive(Parser)]
+#[command(
+    name = BINARY_NAME,
+    version = "1.0.0",
+    author = "Mathieu H. <mhardy2008@gmail.com>",
+    about = "Monitor system information")]
+pub struct Cli {
+    #[command(flatten)]
+    pub run: RunArgs,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Options of the filesystem, also used by the commands accessing it
+#[derive(Args)]
+pub struct RunArgs {
+    /// Path where the filesystem will be mounted
+    #[arg(short, long, global = true)]
+    pub mountpoint: Option<String>,
+
+    /// Path of a file where the logs should be printed
+    #[arg(short, long, global = true)]
+    pub logfile: Option<String>,
+
+    /// Path of the configuration file
+    #[arg(short, long, global = true)]
+    pub config: Option<String>,
+
+    /// Path of the directory containing the triggers
+    #[arg(short, long, global = true)]
+    pub triggers_dir: Option<String>,
+
+    /// Profile of the configuration to be used instead of the one matching
+    /// the name of the machine
+    #[arg(short, long, global = true)]
+    pub profile: Option<String>,
+
+    /// Run as a system service shared by all the users
+    #[arg(long, global = true)]
+    pub system: bool,
+
+    /// Only run the modules that work in unprivileged containers
+    #[arg(long)]
+    pub minimal: bool,
+
+    /// Mount even if the mountpoint is not empty
+    #[arg(long)]
+    pub force: bool,
+
+    /// Unmount a previous filesystem found at the mountpoint
+    #[arg(long)]
+    pub force_remount: bool,
+
+    /// Print the modules and the hardware available on this host in JSON
+    #[arg(long)]
+    pub print_capabilities: bool,
+}
+
+impl RunArgs {
+    /// Get the mountpoint, which depends on the mode when not given
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    pub fn mountpoint(&self) -> String {
+        return match &self.mountpoint {
+            Some(m) => m.clone(),
+            None if self.system => SYSTEM_MOUNTPOINT.to_string(),
+            None => USER_MOUNTPOINT.to_string(),
+        };
+    }
+}
+
+/// List of commands, the filesystem is mounted when none is given
+#[derive(Subcommand)]
+pub enum Command {
+    /// Mount the filesystem
+    Run,
+
+    /// Print the values of all the entries of a running instance
+    Snapshot,
+
+    /// Print the value of an entry of a running instance
+    Get {
+        /// Path of the entry (e.g. `cpu/logical/averrage`)
+        path: String,
+    },
+
+    /// Write a value to an entry of a running instance
+    Set {
+        /// Path of the entry (e.g. `brightness/intel_backlight/value`)
+        path: String,
+
+        /// Value to be written
+        value: String,
+    },
+
+    /// Check that the configuration and the triggers can be loaded
+    ValidateConfig,
+
+    /// Start the enabled modules once and check the values of their entries
+    Selftest {
+        /// Time given to the modules to produce their values, in seconds
+        #[arg(long, default_value_t = 10)]
+        timeout_s: u64,
+    },
+
+    /// Execute the triggers of an entry as if its value had changed
+    TestTrigger {
+        /// Path of the entry (e.g. `battery/percent`)
+        path: String,
+
+        /// Value before the change
+        old_value: String,
+
+        /// Value after the change
+        new_value: String,
+    },
+
+    /// Print the values of an entry and of the entries below it kept in the
+    /// event log or in the history
+    Export {
+        /// Path of the entry (e.g. `battery/percent`)
+        #[arg(long)]
+        path: String,
+
+        /// Only print the values of this period (e.g. `30m`, `1h`, `2d`)
+        #[arg(long)]
+        since: Option<String>,
+
+        /// Format of the output
+        #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+
+        /// Read the averages recorded by the history module at this
+        /// resolution (`1s`, `1m` or `1h`) instead of the event log
+        #[arg(long)]
+        resolution: Option<String>,
+    },
+
+    /// Manage the secrets referenced in the configuration as `secret://name`
+    Secret {
+        #[command(subcommand)]
+        command: SecretCommand,
+    },
+
+    /// Print the shell completion script
+    Completions {
+        /// Shell for which the script is generated
+        shell: clap_complete::Shell,
+    },
+
+    /// Print the man page
+    Man,
+
+    /// Install sample configuration, triggers and waybar snippet
+    Examples {
+        /// Directory where the examples are installed
+        dir: String,
+    },
+
+    /// Serve the privileged reads requested by the daemon
+    #[command(hide = true)]
+    Helper,
+}
+
+/// Formats of the exported values
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    /// One `timestamp,path,value` line per value, after a header
+    Csv,
+
+    /// Array of objects
+    Json,
+}
+
+/// List of commands managing the secrets
+#[derive(Subcommand)]
+pub enum SecretCommand {
+    /// Store a secret, its value is read from the standard input
+    Set {
+        /// Name of the secret
+        name: String,
+    },
+}
+
+/// Function used to store a secret read from the standard input
+///
+/// # Arguments
+///
+/// * `name` - The name of the secret
+pub fn set_secret(name: &str) -> error::Return {
+    let mut value = String::new();
+
+    match io::stdin().read_line(&mut value) {
+        Ok(_) => (),
+        Err(_) => return error!("Cannot read secret"),
+    }
+
+    let value = value.trim_end_matches(&['\r', '\n'][..]);
+
+    if value.is_empty() {
+        return error!("Empty secret");
+    }
+
+    return secrets::set(name, value);
+}
+
+/// Function used to print the completion script of a shell
+///
+/// # Arguments
+///
+/// * `shell` - The shell for which the script is generated
+pub fn print_completions(shell: clap_complete::Shell) {
+    clap_complete::generate(
+        shell,
+        &mut Cli::command(),
+        BINARY_NAME,
+        &mut io::stdout());
+}
+
+/// Function used to print the man page
+pub fn print_man_page() -> error::Return {
+    let man = clap_mangen::Man::new(Cli::command());
+
+    return match man.render(&mut io::stdout()) {
+        Ok(_) => success!(),
+        Err(_) => error!("Cannot render man page"),
+    };
+}
+
+/// Function used to install the examples in a directory
+///
+/// Existing files are kept. The commands used to run the examples and to
+/// check them with a snapshot are printed.
+///
+/// # Arguments
+///
+/// * `dir` - The directory where the examples are installed
+pub fn install_examples(dir: &str) -> error::Return {
+    let dir = Path::new(dir);
+
+    match fs::create_dir_all(dir) {
+        Ok(_) => (),
+        Err(e) => return Err(error::CerebroError::io(
+            "Cannot create examples directory",
+            e)),
+    }
+
+    for (name, content) in EXAMPLES.iter() {
+        let path = dir.join(name);
+
+        if path.exists() {
+            println!("Keeping existing {}", path.display());
+            continue;
+        }
+
+        match fs::write(&path, content) {
+            Ok(_) => println!("Installed {}", path.display()),
+            Err(e) => return Err(error::CerebroError::io(
+                "Cannot write example",
+                e)),
+        }
+    }
+
+    let mountpoint = dir.join("mnt");
+
+    println!();
+    println!("Run the examples with:");
+    println!(
+        "    {} -c {} -t {} -m {}",
+        BINARY_NAME,
+        dir.join("config.json").display(),
+        dir.display(),
+        mountpoint.display());
+
+    println!("Check them from another terminal with:");
+    println!("    {} -m {} snapshot", BINARY_NAME, mountpoint.display());
+
+    println!(
+        "The waybar snippet reads the default mountpoint ({}).",
+        USER_MOUNTPOINT);
+
+    return success!();
+}
+
+/// Function used to split a path into the module name and the entry name
+///
+/// # Arguments
+///
+/// * `path` - The path of the entry
+pub fn split_path(path: &str)
+    -> Result<(String, String), error::CerebroError> {
+
+    let path = path.trim_start_matches('/');
+
+    return match path.find('/') {
+        Some(i) => Ok((path[..i].to_string(), path[i + 1..].to_string())),
+        None => error!("Path must be formatted as <module>/<entry>"),
+    };
+}
+
+/// Function used to print the value of an entry
+///
+/// # Arguments
+///
+/// * `mountpoint` - The mountpoint of the running instance
+/// * `path` - The path of the entry
+pub fn get(mountpoint: &str, path: &str) -> error::Return {
+    let path = Path::new(mountpoint).join(path.trim_start_matches('/'));
+
+    return match fs::read_to_string(path) {
+        Ok(v) => {
+            println!("{}", v);
+            success!()
+        },
+
+        Err(_) => error!("Cannot read entry"),
+    };
+}
+
+/// Function used to write the value of an entry
+///
+/// # Arguments
+///
+/// * `mountpoint` - The mountpoint of the running instance
+/// * `path` - The path of the entry
+/// * `value` - The value to be written
+pub fn set(mountpoint: &str, path: &str, value: &str) -> error::Return {
+    let path = Path::new(mountpoint).join(path.trim_start_matches('/'));
+
+    return match fs::write(path, value) {
+        Ok(_) => success!(),
+        Err(_) => error!("Cannot write entry"),
+    };
+}
+
+/// Function used to print the values of all the entries of a tree
+///
+/// # Arguments
+///
+/// * `root` - The root of the tree
+/// * `dir` - The directory currently listed
+fn print_tree(root: &Path, dir: &Path) -> error::Return {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return error!("Cannot list entries"),
+    };
+
+    let mut paths: Vec<_> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .collect();
+
+    paths.sort();
+
+    for path in paths {
+        let name = match path.strip_prefix(root) {
+            Ok(n) => n.display().to_string(),
+            Err(_) => continue,
+        };
+
+        // Blocking entries would never return, the others are duplicates
+        if name.ends_with(".wait")
+            || name.ends_with(".atomic")
+            || name.ends_with(".values") {
+
+            continue;
+        }
+
+        // Frozen copies of the tree and results of the last query
+        if name == ".snapshot" || name == "query" {
+            continue;
+        }
+
+        match fs::symlink_metadata(&path) {
+            Ok(m) if m.is_dir() => print_tree(root, &path)?,
+
+            Ok(m) if m.is_file() => match fs::read_to_string(&path) {
+                Ok(v) => println!("{}={}", name, v.trim_end()),
+                Err(_) => (),
+            },
+
+            _ => (),
+        }
+    }
+
+    return success!();
+}
+
+/// Function used to print the values of all the entries
+///
+/// # Arguments
+///
+/// * `mountpoint` - The mountpoint of the running instance
+pub fn snapshot(mountpoint: &str) -> error::Return {
+    let root = Path::new(mountpoint);
+
+    return print_tree(root, root);
+}
+
+/// Function used to parse a duration (e.g. `90s`, `30m`, `1h`, `2d`)
+///
+/// A number without unit is a number of seconds.
+///
+/// # Arguments
+///
+/// * `text` - The duration
+fn parse_duration(text: &str) -> Result<i64, error::CerebroError> {
+    let (number, factor) = match text.chars().last() {
+        Some('s') => (&text[..text.len() - 1], 1),
+        Some('m') => (&text[..text.len() - 1], 60),
+        Some('h') => (&text[..text.len() - 1], 3600),
+        Some('d') => (&text[..text.len() - 1], 86400),
+        _ => (text, 1),
+    };
+
+    return match number.parse::<i64>() {
+        Ok(n) if n >= 0 => Ok(n * factor),
+        _ => error!(&format!("Invalid duration `{}`", text)),
+    };
+}
+
+/// Function used to quote a field of a CSV line if needed
+///
+/// # Arguments
+///
+/// * `field` - The field
+fn csv_field(field: &str) -> String {
+    if !field.contains(&[',', '"', '\n', '\r'][..]) {
+        return field.to_string();
+    }
+
+    return format!("\"{}\"", field.replace('"', "\"\""));
+}
+
+/// Function used to read the values of an entry recorded by the history
+/// module
+///
+/// # Arguments
+///
+/// * `config` - The configuration
+/// * `path` - The path of the entry
+/// * `since` - The number of seconds since the epoch of the oldest value
+/// * `resolution` - The resolution of the values
+fn recorded(
+    config: &config::Config,
+    path: &str,
+    since: Option<i64>,
+    resolution: &str) -> Result<Vec<event_log::Sample>, error::CerebroError> {
+
+    let dir = match config.modules.get("history") {
+        Some(m) => match &m.history {
+            Some(h) => rrd::directory(h),
+            None => None,
+        },
+
+        None => None,
+    };
+
+    let dir = match dir {
+        Some(d) => d,
+        None => return error!(Config, "History is not enabled"),
+    };
+
+    let path = path.trim_matches('/');
+    let series = rrd::load(&dir, path)?;
+
+    let points = match series.points(parse_duration(resolution)?, since) {
+        Some(p) => p,
+        None => return error!(&format!(
+            "Invalid resolution `{}`",
+            resolution)),
+    };
+
+    return Ok(points
+        .iter()
+        .map(|(t, v)| event_log::Sample {
+            timestamp: event_log::format_timestamp(*t as libc::time_t),
+            path: format!("/{}", path),
+            value: format!("{}", v),
+        })
+        .collect());
+}
+
+/// Function used to print the values of entries kept in the event log or
+/// in the history
+///
+/// # Arguments
+///
+/// * `config` - The configuration
+/// * `path` - The path of the entry
+/// * `since` - The period of the values to be printed
+/// * `format` - The format of the output
+/// * `resolution` - The resolution of the values read from the history
+pub fn export(
+    config: &config::Config,
+    path: &str,
+    since: &Option<String>,
+    format: ExportFormat,
+    resolution: &Option<String>) -> error::Return {
+
+    let since = match since {
+        Some(s) => {
+            let now = unsafe { libc::time(std::ptr::null_mut()) } as i64;
+            Some(now - parse_duration(s)?)
+        },
+
+        None => None,
+    };
+
+    let samples = match resolution {
+        Some(r) => recorded(config, path, since, r)?,
+        None => match &config.event_log {
+            Some(e) => event_log::history(e, path, since)?,
+            None => return error!(Config, "Event log is not enabled"),
+        },
+    };
+
+    match format {
+        ExportFormat::Csv => {
+            println!("timestamp,path,value");
+
+            for sample in samples.iter() {
+                println!(
+                    "{},{},{}",
+                    csv_field(&sample.timestamp),
+                    csv_field(&sample.path),
+                    csv_field(&sample.value));
+            }
+        },
+
+        ExportFormat::Json => {
+            let values: Vec<serde_json::Value> = samples
+                .iter()
+                .map(|s| serde_json::json!({
+                    "timestamp": s.timestamp,
+                    "path": s.path,
+                    "value": s.value,
+                }))
+                .collect();
+
+            match serde_json::to_string_pretty(&values) {
+                Ok(j) => println!("{}", j),
+                Err(_) => return error!("Cannot format values"),
+            }
+        },
+    }
+
+    return success!();
+}
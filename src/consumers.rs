@@ -0,0 +1,134 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::fs;
+use std::time::{Duration, Instant};
+
+use crate::sync::Mutex;
+
+/// Number of (consumer, entry) pairs tracked, the least recently read ones
+/// are dropped
+const MAX_ACCESSES: usize = 1024;
+
+/// Reads of an entry by a consumer
+struct Access {
+    count: u64,
+    last: Instant,
+}
+
+lazy_static! {
+    static ref ACCESSES: Mutex<HashMap<(u32, u32, String), Access>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Function used to get the name of a process
+///
+/// # Arguments
+///
+/// * `pid` - The identifier of the process
+fn process_name(pid: u32) -> String {
+    return match fs::read_to_string(format!("/proc/{}/comm", pid)) {
+        Ok(n) => n.trim().to_string(),
+        Err(_) => "?".to_string(),
+    };
+}
+
+/// Function used to record a read of an entry
+///
+/// # Arguments
+///
+/// * `uid` - The user of the reader
+/// * `pid` - The process of the reader
+/// * `path` - The path of the entry (e.g. `battery/percent`)
+pub fn record(uid: u32, pid: u32, path: &str) {
+    let mut accesses = match ACCESSES.lock() {
+        Ok(a) => a,
+        Err(_) => return,
+    };
+
+    let now = Instant::now();
+
+    let access = accesses
+        .entry((uid, pid, path.to_string()))
+        .or_insert(Access {
+            count: 0,
+            last: now,
+        });
+
+    access.count += 1;
+    access.last = now;
+
+    if accesses.len() <= MAX_ACCESSES {
+        return;
+    }
+
+    let oldest = accesses
+        .iter()
+        .min_by_key(|(_, a)| a.last)
+        .map(|(k, _)| k.clone());
+
+    match oldest {
+        Some(k) => {
+            accesses.remove(&k);
+        },
+
+        None => (),
+    }
+}
+
+/// Function used to get the time elapsed since the last read of an entry
+///
+/// Returns None if the entry, or any entry under it, has never been read.
+///
+/// # Arguments
+///
+/// * `path` - The path of the entry or of a parent entry
+pub fn last_read(path: &str) -> Option<Duration> {
+    let accesses = match ACCESSES.lock() {
+        Ok(a) => a,
+        Err(_) => return None,
+    };
+
+    let prefix = format!("{}/", path);
+
+    return accesses
+        .iter()
+        .filter(|((_, _, p), _)| p == path || p.starts_with(&prefix))
+        .map(|(_, a)| a.last.elapsed())
+        .min();
+}
+
+/// Function used to render the reads of the entries by each consumer
+///
+/// The most read entries are given first, one per line.
+pub fn render() -> String {
+    let accesses = match ACCESSES.lock() {
+        Ok(a) => a,
+        Err(_) => return "".to_string(),
+    };
+
+    let mut lines: Vec<(&(u32, u32, String), &Access)> =
+        accesses.iter().collect();
+
+    lines.sort_by(|a, b| b.1.count.cmp(&a.1.count).then(a.0.cmp(b.0)));
+
+    let mut names: HashMap<u32, String> = HashMap::new();
+    let mut output = String::new();
+
+    for ((uid, pid, path), access) in lines {
+        let name = names
+            .entry(*pid)
+            .or_insert_with(|| process_name(*pid))
+            .clone();
+
+        output += &format!(
+            "uid={} pid={} process={} path={} reads={} last_read_s={:.1}\n",
+            uid,
+            pid,
+            name,
+            path,
+            access.count,
+            access.last.elapsed().as_secs_f64());
+    }
+
+    return output;
+}
@@ -0,0 +1,238 @@
+use serde_json::Value;
+use std::io::{BufRead, BufReader};
+use std::process;
+
+use crate::error;
+use crate::modules::module;
+
+/// Bus on which a D-Bus request is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Bus {
+    Session,
+    System,
+}
+
+impl Bus {
+    /// Get the busctl flag selecting the bus
+    fn flag(&self) -> &str {
+        return match self {
+            Bus::Session => "--user",
+            Bus::System => "--system",
+        };
+    }
+}
+
+/// Function used to run busctl and get its standard output
+fn busctl(bus: Bus, args: &[&str]) -> Result<String, error::CerebroError> {
+    let output = match process::Command::new("busctl")
+        .arg(bus.flag())
+        .args(args)
+        .output() {
+
+        Ok(o) => o,
+        Err(e) => return error!(&format!("Cannot execute busctl: {:?}", e)),
+    };
+
+    if !output.status.success() {
+        return error!("busctl command is not successful");
+    }
+
+    return match String::from_utf8(output.stdout) {
+        Ok(s) => Ok(s),
+        Err(_) => error!("Invalid busctl output"),
+    };
+}
+
+/// Function used to extract the data of a busctl JSON reply
+fn parse_data(output: &str) -> Result<Value, error::CerebroError> {
+    let json: Value = match serde_json::from_str(output) {
+        Ok(j) => j,
+        Err(_) => return error!("Cannot parse busctl output"),
+    };
+
+    return match json.get("data") {
+        Some(d) => Ok(d.clone()),
+        None => error!("No data in busctl output"),
+    };
+}
+
+/// Function used to list the object paths exposed by a service
+#[cfg_attr(not(feature = "dbus"), allow(dead_code))]
+pub fn tree(bus: Bus, service: &str)
+    -> Result<Vec<String>, error::CerebroError> {
+
+    let output = busctl(bus, &["--list", "tree", service])?;
+
+    return Ok(output
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect());
+}
+
+/// Function used to call a method of an object
+pub fn call(
+    bus: Bus,
+    service: &str,
+    path: &str,
+    interface: &str,
+    method: &str,
+    signature: &str,
+    args: &[&str]) -> Result<Value, error::CerebroError> {
+
+    let mut busctl_args =
+        vec!["--json=short", "call", service, path, interface, method];
+
+    if !signature.is_empty() {
+        busctl_args.push(signature);
+        busctl_args.extend_from_slice(args);
+    }
+
+    let output = busctl(bus, &busctl_args)?;
+
+    if output.trim().is_empty() {
+        return Ok(Value::Null);
+    }
+
+    return parse_data(&output);
+}
+
+/// Function used to get a property of an object
+pub fn get_property(
+    bus: Bus,
+    service: &str,
+    path: &str,
+    interface: &str,
+    property: &str) -> Result<Value, error::CerebroError> {
+
+    let output = busctl(
+        bus,
+        &["--json=short", "get-property", service, path, interface, property])?;
+
+    return parse_data(&output);
+}
+
+/// Function used to set a property of an object
+#[cfg_attr(not(feature = "dbus"), allow(dead_code))]
+pub fn set_property(
+    bus: Bus,
+    service: &str,
+    path: &str,
+    interface: &str,
+    property: &str,
+    signature: &str,
+    value: &str) -> error::Return {
+
+    busctl(bus, &[
+        "set-property",
+        service,
+        path,
+        interface,
+        property,
+        signature,
+        value])?;
+
+    return success!();
+}
+
+/// Stream of the signals emitted by a service
+pub struct Monitor {
+    child: process::Child,
+    reader: BufReader<process::ChildStdout>,
+    stop: Option<module::OnStop>,
+}
+
+impl Monitor {
+    /// Start monitoring the messages of a service
+    ///
+    /// # Arguments
+    ///
+    /// * `bus` - The bus of the service
+    /// * `service` - The name of the service
+    pub fn new(bus: Bus, service: &str)
+        -> Result<Monitor, error::CerebroError> {
+
+        return Monitor::with_matches(bus, service, &[]);
+    }
+
+    /// Start monitoring the messages of a service and the messages matching
+    /// some rules (e.g. the signals of the bus)
+    ///
+    /// # Arguments
+    ///
+    /// * `bus` - The bus of the service
+    /// * `service` - The name of the service
+    /// * `matches` - The match rules of the other messages
+    pub fn with_matches(bus: Bus, service: &str, matches: &[&str])
+        -> Result<Monitor, error::CerebroError> {
+
+        let mut child = match process::Command::new("busctl")
+            .arg(bus.flag())
+            .args(&["--json=short", "monitor", service])
+            .args(matches.iter().map(|m| format!("--match={}", m)))
+            .stdout(process::Stdio::piped())
+            .spawn() {
+
+            Ok(c) => c,
+            Err(e) => return error!(&format!("Cannot execute busctl: {:?}", e)),
+        };
+
+        let stdout = match child.stdout.take() {
+            Some(s) => s,
+            None => return error!("Cannot read busctl output"),
+        };
+
+        // Stopping the module ends the monitoring
+        let stop = module::kill_on_stop(&child);
+
+        return Ok(Monitor {
+            child: child,
+            reader: BufReader::new(stdout),
+            stop: Some(stop),
+        });
+    }
+
+    /// Wait for the next message (method call, reply, signal, ...)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    pub fn next_message(&mut self) -> Result<Value, error::CerebroError> {
+        loop {
+            let mut line = String::new();
+
+            match self.reader.read_line(&mut line) {
+                Ok(0) | Err(_) => return error!("busctl monitor has exited"),
+                Ok(_) => (),
+            }
+
+            match serde_json::from_str(&line) {
+                Ok(m) => return Ok(m),
+                Err(_) => continue,
+            }
+        }
+    }
+
+    /// Wait for the next signal
+    ///
+    /// Method calls and replies are skipped, which also avoids reacting to
+    /// the requests sent by the caller itself.
+    pub fn next_signal(&mut self) -> Result<Value, error::CerebroError> {
+        loop {
+            let message = self.next_message()?;
+
+            if message["type"] == "signal" {
+                return Ok(message);
+            }
+        }
+    }
+}
+
+impl Drop for Monitor {
+    fn drop(&mut self) {
+        self.stop.take();
+
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
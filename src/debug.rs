@@ -0,0 +1,79 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::event_log;
+use crate::sync::Mutex;
+
+/// Last update of a module
+struct Update {
+    timestamp: String,
+    duration: Duration,
+    error: Option<String>,
+    raw: Option<String>,
+}
+
+lazy_static! {
+    static ref UPDATES: Mutex<HashMap<String, Update>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Function used to record the last update of a module
+///
+/// # Arguments
+///
+/// * `module` - The name of the module
+/// * `duration` - The time spent by the update
+/// * `error` - The error of the update, if any
+/// * `raw` - The raw data gathered by the update, if provided by the module
+pub fn record(
+    module: &str,
+    duration: Duration,
+    error: Option<String>,
+    raw: Option<String>) {
+
+    match UPDATES.lock() {
+        Ok(mut u) => {
+            u.insert(module.to_string(), Update {
+                timestamp: event_log::timestamp(),
+                duration: duration,
+                error: error,
+                raw: raw,
+            });
+        },
+
+        Err(_) => log::error!("Cannot lock debug"),
+    }
+}
+
+/// Function used to render the last update of a module
+///
+/// The timing is given first, followed by the raw data.
+///
+/// # Arguments
+///
+/// * `module` - The name of the module
+pub fn render(module: &str) -> String {
+    let updates = match UPDATES.lock() {
+        Ok(u) => u,
+        Err(_) => return "".to_string(),
+    };
+
+    let update = match updates.get(module) {
+        Some(u) => u,
+        None => return "no update yet\n".to_string(),
+    };
+
+    let mut output = format!(
+        "timestamp: {}\nduration_ms: {:.3}\nerror: {}\n",
+        update.timestamp,
+        update.duration.as_secs_f64() * 1000.0,
+        update.error.as_deref().unwrap_or("-"));
+
+    match &update.raw {
+        Some(r) => output += &format!("\n{}\n", r),
+        None => output += "\nno raw data provided by the module\n",
+    }
+
+    return output;
+}
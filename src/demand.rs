@@ -0,0 +1,248 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Condvar, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const DEFAULT_IDLE_S: u64 = 300;
+
+/// Reads of the entries of a module
+struct Access {
+    last: Instant,
+    generation: u64,
+    idle: bool,
+}
+
+/// The structure used to store the state of the demand-driven polling
+struct State {
+    configs: HashMap<String, config::DemandConfig>,
+    referenced: HashSet<String>,
+    accesses: HashMap<String, Access>,
+    wakeups: HashMap<String, u64>,
+}
+
+lazy_static! {
+    static ref STATE: Mutex<State> = Mutex::new(State {
+        configs: HashMap::new(),
+        referenced: HashSet::new(),
+        accesses: HashMap::new(),
+        wakeups: HashMap::new(),
+    });
+
+    /// Notified when the entries of a module are read
+    static ref ACCESSED: Condvar = Condvar::new();
+}
+
+/// Function used to check if a trigger may match the entries of a module
+///
+/// # Arguments
+///
+/// * `trigger` - The trigger
+/// * `module` - The name of the module
+fn references(trigger: &triggers::Trigger, module: &str) -> bool {
+    let first = match trigger.path.trim_start_matches('/').split('/').next() {
+        Some(f) => f,
+        None => return false,
+    };
+
+    // The entries of an instance are under the directory of its type
+    let group = match module.find(config::INSTANCE_SEPARATOR) {
+        Some(i) => &module[..i],
+        None => module,
+    };
+
+    return match Regex::new(&format!("^(?:{})$", first)) {
+        Ok(r) => r.is_match(group),
+        Err(_) => true,
+    };
+}
+
+/// Function used to load the modules polled on demand
+///
+/// # Arguments
+///
+/// * `config` - The configuration
+/// * `triggers` - The triggers, the modules they reference are never idle
+pub fn init(config: &config::Config, triggers: &Vec<triggers::Trigger>) {
+    let mut state = match STATE.lock() {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+
+    state.configs.clear();
+    state.referenced.clear();
+    state.accesses.clear();
+
+    for (module, module_config) in config.modules.iter() {
+        let demand = match &module_config.demand {
+            Some(d) if d.enabled == Some(true) => d,
+            _ => continue,
+        };
+
+        if triggers.iter().any(|t| references(t, module)) {
+            log::info!("module `{}` is referenced by triggers", module);
+            state.referenced.insert(module.clone());
+        }
+
+        state.configs.insert(module.clone(), demand.clone());
+    }
+}
+
+/// Function used to record a read of an entry
+///
+/// A module waiting for its entries to be read is resumed.
+///
+/// # Arguments
+///
+/// * `path` - The path of the entry (e.g. `battery/percent`)
+pub fn touch(path: &str) {
+    let mut state = match STATE.lock() {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+
+    let (module, _) =
+        config::split_path(path, |n| state.configs.contains_key(n));
+
+    if !state.configs.contains_key(&module) {
+        return;
+    }
+
+    match state.accesses.get_mut(&module) {
+        Some(a) => {
+            a.last = Instant::now();
+            a.generation += 1;
+
+            if a.idle {
+                log::info!("module `{}` resumed", module);
+                a.idle = false;
+            }
+        },
+
+        None => (),
+    }
+
+    ACCESSED.notify_all();
+}
+
+/// Function used to wake up a module waiting before its next update
+///
+/// This is used to stop the module or to update it at once, it's not
+/// considered as a read.
+///
+/// # Arguments
+///
+/// * `module` - The name of the module
+pub fn wake(module: &str) {
+    let mut state = match STATE.lock() {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+
+    *state.wakeups.entry(module.to_string()).or_insert(0) += 1;
+
+    ACCESSED.notify_all();
+}
+
+/// Function used to wait before the next update of a module
+///
+/// Modules whose entries have not been read for a while, and that no
+/// trigger references, wait longer or until their entries are read. Every
+/// module stops waiting when it's woken up.
+///
+/// # Arguments
+///
+/// * `module` - The name of the module
+/// * `delay` - The delay before the next update when the module is used
+pub fn sleep(module: &str, delay: Duration) {
+    let mut state = match STATE.lock() {
+        Ok(s) => s,
+        Err(_) => return thread::sleep(delay),
+    };
+
+    let wakeup = state.wakeups.get(module).copied().unwrap_or(0);
+
+    let woken = move |s: &State| {
+        s.wakeups.get(module).copied().unwrap_or(0) != wakeup
+    };
+
+    let config = match state.configs.get(module) {
+        Some(c) if !state.referenced.contains(module) => c.clone(),
+        _ => return wait(state, Some(delay), |s| !woken(s)),
+    };
+
+    let idle_s = config.idle_s.unwrap_or(DEFAULT_IDLE_S);
+
+    // The start of the module counts as a read
+    let access = state
+        .accesses
+        .entry(module.to_string())
+        .or_insert(Access {
+            last: Instant::now(),
+            generation: 0,
+            idle: false,
+        });
+
+    if access.last.elapsed() < Duration::from_secs(idle_s) {
+        return wait(state, Some(delay), |s| !woken(s));
+    }
+
+    if !access.idle {
+        match config.idle_interval_s {
+            Some(i) => log::info!(
+                "module `{}` idle, polled every {} s",
+                module,
+                i),
+
+            None => log::info!("module `{}` idle, paused", module),
+        }
+
+        access.idle = true;
+    }
+
+    let generation = access.generation;
+
+    let waiting = |s: &mut State| {
+        let read = match s.accesses.get(module) {
+            Some(a) => a.generation != generation,
+            None => true,
+        };
+
+        !read && !woken(s)
+    };
+
+    let timeout = match config.idle_interval_s {
+        Some(i) => Some(Duration::from_secs(i).max(delay)),
+        None => None,
+    };
+
+    wait(state, timeout, waiting);
+}
+
+/// Function used to wait until a condition is false or a timeout expires
+///
+/// # Arguments
+///
+/// * `state` - The locked state
+/// * `timeout` - The longest wait, None to wait for the condition only
+/// * `waiting` - The condition
+fn wait<F>(state: MutexGuard<'_, State>, timeout: Option<Duration>, waiting: F)
+    where F: FnMut(&mut State) -> bool {
+
+    let _state: MutexGuard<'_, State> = match timeout {
+        Some(t) => match ACCESSED.wait_timeout_while(state, t, waiting) {
+            Ok((s, _)) => s,
+            Err(e) => e.into_inner().0,
+        },
+
+        None => match ACCESSED.wait_while(state, waiting) {
+            Ok(s) => s,
+            Err(e) => e.into_inner(),
+        },
+    };
+}
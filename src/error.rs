@@ -1,38 +1,224 @@
 use std::error;
 use std::fmt;
+use std::io;
 
 /// A type to be used for the return of basic methods
 pub type Return = Result<(), CerebroError>;
 
-/// A struture used to report errors
+/// Exit codes, as defined by sysexits.h
+const EX_FAILURE: i32 = 1;
+const EX_DATAERR: i32 = 65;
+const EX_UNAVAILABLE: i32 = 69;
+const EX_SOFTWARE: i32 = 70;
+const EX_IOERR: i32 = 74;
+const EX_CONFIG: i32 = 78;
+
+/// A structure used to report errors
 #[derive(Debug)]
-pub struct CerebroError {
-    description: String
+pub enum CerebroError {
+    /// Failure of an I/O operation
+    Io {
+        message: String,
+        source: Option<io::Error>,
+    },
+
+    /// Invalid configuration
+    Config(String),
+
+    /// Data that cannot be parsed
+    Parse {
+        message: String,
+        source: Option<Box<dyn error::Error + Send + Sync>>,
+    },
+
+    /// Failure of the filesystem
+    Fuse {
+        message: String,
+        errno: i32,
+    },
+
+    /// Failure of a module
+    Module {
+        name: String,
+        source: Box<CerebroError>,
+    },
+
+    /// Invalid or failing trigger
+    Trigger {
+        line: usize,
+        message: String,
+    },
+
+    /// Lock that cannot be acquired
+    Lock(String),
+
+    /// Any other failure
+    Other(String),
 }
 
 impl CerebroError {
     pub fn new(msg: &str) -> Self {
-        Self {
-            description: msg.to_string(),
-        }
+        return CerebroError::Other(msg.to_string());
+    }
+
+    /// Create an I/O error
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The description of the error
+    /// * `source` - The error of the operation
+    pub fn io(msg: &str, source: io::Error) -> Self {
+        return CerebroError::Io {
+            message: msg.to_string(),
+            source: Some(source),
+        };
+    }
+
+    /// Create a parse error
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The description of the error
+    /// * `source` - The error of the parser
+    pub fn parse<E>(msg: &str, source: E) -> Self
+        where E: error::Error + Send + Sync + 'static {
+
+        return CerebroError::Parse {
+            message: msg.to_string(),
+            source: Some(Box::new(source)),
+        };
+    }
+
+    /// Create a filesystem error
+    ///
+    /// # Arguments
+    ///
+    /// * `msg` - The description of the error
+    /// * `errno` - The error number returned to the caller
+    pub fn fuse(msg: &str, errno: i32) -> Self {
+        return CerebroError::Fuse {
+            message: msg.to_string(),
+            errno: errno,
+        };
+    }
+
+    /// Attach the name of the module to an error
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the module
+    /// * `source` - The error of the module
+    pub fn module(name: &str, source: CerebroError) -> Self {
+        return CerebroError::Module {
+            name: name.to_string(),
+            source: Box::new(source),
+        };
+    }
+
+    /// Create a trigger error
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The line of the trigger in its file
+    /// * `msg` - The description of the error
+    pub fn trigger(line: usize, msg: &str) -> Self {
+        return CerebroError::Trigger {
+            line: line,
+            message: msg.to_string(),
+        };
+    }
+
+    /// Get the exit code of the process failing with this error
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    pub fn exit_code(&self) -> i32 {
+        return match self {
+            CerebroError::Io { .. } => EX_IOERR,
+            CerebroError::Config(_) => EX_CONFIG,
+            CerebroError::Parse { .. } => EX_DATAERR,
+            CerebroError::Fuse { .. } => EX_UNAVAILABLE,
+            CerebroError::Module { source, .. } => source.exit_code(),
+            CerebroError::Trigger { .. } => EX_CONFIG,
+            CerebroError::Lock(_) => EX_SOFTWARE,
+            CerebroError::Other(_) => EX_FAILURE,
+        };
+    }
+
+    /// Get the error number returned to a filesystem operation failing with
+    /// this error
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    pub fn errno(&self) -> i32 {
+        return match self {
+            CerebroError::Io { source: Some(s), .. } =>
+                s.raw_os_error().unwrap_or(libc::EIO),
+
+            CerebroError::Io { source: None, .. } => libc::EIO,
+            CerebroError::Config(_) => libc::EINVAL,
+            CerebroError::Parse { .. } => libc::EINVAL,
+            CerebroError::Fuse { errno, .. } => *errno,
+            CerebroError::Module { source, .. } => source.errno(),
+            CerebroError::Trigger { .. } => libc::EINVAL,
+            CerebroError::Lock(_) => libc::EAGAIN,
+            CerebroError::Other(_) => libc::EIO,
+        };
     }
 }
 
 impl fmt::Display for CerebroError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        return write!(f,"{}", self.description);
+        return match self {
+            CerebroError::Io { message, source: Some(s) } =>
+                write!(f, "{}: {}", message, s),
+
+            CerebroError::Io { message, source: None } =>
+                write!(f, "{}", message),
+
+            CerebroError::Config(message) => write!(f, "{}", message),
+
+            CerebroError::Parse { message, source: Some(s) } =>
+                write!(f, "{}: {}", message, s),
+
+            CerebroError::Parse { message, source: None } =>
+                write!(f, "{}", message),
+
+            CerebroError::Fuse { message, .. } => write!(f, "{}", message),
+
+            CerebroError::Module { name, source } =>
+                write!(f, "{}: {}", name, source),
+
+            CerebroError::Trigger { line, message } =>
+                write!(f, "line {}: {}", line, message),
+
+            CerebroError::Lock(message) => write!(f, "{}", message),
+            CerebroError::Other(message) => write!(f, "{}", message),
+        };
     }
 }
 
 impl error::Error for CerebroError {
-    fn description(&self) -> &str {
-        return &self.description;
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        return match self {
+            CerebroError::Io { source: Some(s), .. } => Some(s),
+            CerebroError::Parse { source: Some(s), .. } => Some(s.as_ref()),
+            CerebroError::Module { source, .. } => Some(source.as_ref()),
+            _ => None,
+        };
     }
 }
 
 #[macro_export]
 macro_rules! error {
-    ($description: expr) => { Err(error::CerebroError::new($description)) }
+    ($description: expr) => { Err(error::CerebroError::new($description)) };
+
+    // Errors of a given kind (Config, Lock or Other)
+    ($kind: ident, $description: expr) => {
+        Err(error::CerebroError::$kind($description.to_string()))
+    };
 }
 
 #[macro_export]
@@ -0,0 +1,387 @@
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config;
+use crate::error;
+use crate::sync::Mutex;
+
+const DEFAULT_MAX_SIZE_KB: u64 = 1024;
+const DEFAULT_MAX_FILES: u32 = 3;
+
+/// The structure used to store the state of the event log
+struct EventLog {
+    path: PathBuf,
+    max_size: u64,
+    max_files: u32,
+}
+
+lazy_static! {
+    static ref EVENT_LOG: Mutex<Option<EventLog>> = Mutex::new(None);
+}
+
+/// Value of an entry found in the event log
+pub struct Sample {
+    pub timestamp: String,
+    pub path: String,
+    pub value: String,
+}
+
+impl EventLog {
+    /// EventLog constructor
+    ///
+    /// Returns `None` if the event log is not enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration of the event log
+    fn new(config: &config::EventLogConfig) -> Option<Self> {
+        let path = match &config.path {
+            Some(p) => PathBuf::from(p),
+            None => return None,
+        };
+
+        return Some(Self {
+            path: path,
+            max_size: config.max_size_kb.unwrap_or(DEFAULT_MAX_SIZE_KB) * 1024,
+            max_files: config.max_files.unwrap_or(DEFAULT_MAX_FILES),
+        });
+    }
+
+    /// Get the path of a rotated file
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `index` - The index of the rotated file
+    fn rotated_path(&self, index: u32) -> PathBuf {
+        let mut path = self.path.clone().into_os_string();
+        path.push(format!(".{}", index));
+
+        return PathBuf::from(path);
+    }
+
+    /// Rotate the files if the current one is too large
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn rotate_if_needed(&self) -> error::Return {
+        let size = match fs::metadata(&self.path) {
+            Ok(m) => m.len(),
+            Err(_) => return success!(),
+        };
+
+        if size < self.max_size {
+            return success!();
+        }
+
+        return self.rotate();
+    }
+
+    /// Rotate the files
+    ///
+    /// `events.jsonl` becomes `events.jsonl.1`, `events.jsonl.1` becomes
+    /// `events.jsonl.2` and so on, the oldest one is removed.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn rotate(&self) -> error::Return {
+        if !self.path.exists() {
+            return success!();
+        }
+
+        for index in (1..self.max_files).rev() {
+            let from = self.rotated_path(index);
+
+            if Path::new(&from).exists() {
+                match fs::rename(&from, self.rotated_path(index + 1)) {
+                    Ok(_) => (),
+                    Err(_) => return error!("Cannot rotate event log"),
+                }
+            }
+        }
+
+        let result = match self.max_files {
+            0 => fs::remove_file(&self.path),
+            _ => fs::rename(&self.path, self.rotated_path(1)),
+        };
+
+        return match result {
+            Ok(_) => success!(),
+            Err(_) => error!("Cannot rotate event log"),
+        };
+    }
+
+    /// Get the existing files, from the oldest to the current one
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = (1..=self.max_files)
+            .rev()
+            .map(|i| self.rotated_path(i))
+            .collect();
+
+        files.push(self.path.clone());
+
+        return files.into_iter().filter(|f| f.exists()).collect();
+    }
+
+    /// Append an event to the file
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `event` - The event to be written
+    fn append(&self, event: &Value) -> error::Return {
+        self.rotate_if_needed()?;
+
+        let mut file = match fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path) {
+
+            Ok(f) => f,
+            Err(_) => return error!("Cannot open event log"),
+        };
+
+        return match writeln!(file, "{}", event) {
+            Ok(_) => success!(),
+            Err(_) => error!("Cannot write event log"),
+        };
+    }
+}
+
+/// Function used to get the local time in the ISO 8601 format
+pub fn timestamp() -> String {
+    return format_timestamp(unsafe { libc::time(std::ptr::null_mut()) });
+}
+
+/// Function used to format a time as a local time in the ISO 8601 format
+///
+/// # Arguments
+///
+/// * `time` - The number of seconds since the epoch
+pub fn format_timestamp(time: libc::time_t) -> String {
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+
+    unsafe {
+        libc::localtime_r(&time, &mut tm);
+    }
+
+    let offset = tm.tm_gmtoff / 60;
+
+    return format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}{}{:02}:{:02}",
+        tm.tm_year + 1900,
+        tm.tm_mon + 1,
+        tm.tm_mday,
+        tm.tm_hour,
+        tm.tm_min,
+        tm.tm_sec,
+        if offset < 0 { "-" } else { "+" },
+        offset.abs() / 60,
+        offset.abs() % 60);
+}
+
+/// Function used to get the number of days since the epoch of a date
+///
+/// # Arguments
+///
+/// * `year` - The year
+/// * `month` - The month (from 1 to 12)
+/// * `day` - The day of the month
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = match month <= 2 {
+        true => year - 1,
+        false => year,
+    };
+
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+
+    let month = match month > 2 {
+        true => month - 3,
+        false => month + 9,
+    };
+
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 +
+        day_of_year;
+
+    return era * 146097 + day_of_era - 719468;
+}
+
+/// Function used to get the number of seconds since the epoch of a
+/// timestamp written by `timestamp()`
+///
+/// # Arguments
+///
+/// * `text` - The timestamp
+pub fn parse_timestamp(text: &str) -> Option<i64> {
+    let field = |start: usize, end: usize| {
+        return match text.get(start..end) {
+            Some(f) => f.parse::<i64>().ok(),
+            None => None,
+        };
+    };
+
+    let days = days_from_civil(field(0, 4)?, field(5, 7)?, field(8, 10)?);
+    let seconds = field(11, 13)? * 3600 + field(14, 16)? * 60 +
+        field(17, 19)?;
+
+    let offset = field(20, 22)? * 3600 + field(23, 25)? * 60;
+
+    let offset = match text.get(19..20) {
+        Some("-") => -offset,
+        Some("+") => offset,
+        _ => return None,
+    };
+
+    return Some(days * 86400 + seconds - offset);
+}
+
+/// Function used to read the values of entries from the event log
+///
+/// The values of the entry and of the entries below it are returned from
+/// the oldest to the most recent one. Only the changes found in the files
+/// that have not been removed by the rotation are available.
+///
+/// # Arguments
+///
+/// * `config` - The configuration of the event log
+/// * `path` - The path of the entry (e.g. `battery/percent`)
+/// * `since` - The number of seconds since the epoch of the oldest value
+pub fn history(
+    config: &config::EventLogConfig,
+    path: &str,
+    since: Option<i64>) -> Result<Vec<Sample>, error::CerebroError> {
+
+    let event_log = match EventLog::new(config) {
+        Some(l) => l,
+        None => return error!(Config, "Event log is not enabled"),
+    };
+
+    let path = format!("/{}", path.trim_matches('/'));
+    let parent = format!("{}/", path);
+
+    let mut samples = Vec::new();
+
+    for file in event_log.files() {
+        let file = match fs::File::open(&file) {
+            Ok(f) => f,
+            Err(e) => return Err(error::CerebroError::io(
+                "Cannot open event log",
+                e)),
+        };
+
+        for line in BufReader::new(file).lines() {
+            let line = match line {
+                Ok(l) => l,
+                Err(e) => return Err(error::CerebroError::io(
+                    "Cannot read event log",
+                    e)),
+            };
+
+            let event: Value = match serde_json::from_str(&line) {
+                Ok(e) => e,
+                Err(_) => continue,
+            };
+
+            // Deleted entries have no value
+            if event["event"] != "value" || event["kind"] == "Delete" {
+                continue;
+            }
+
+            let (entry, timestamp, value) = match (
+                event["path"].as_str(),
+                event["timestamp"].as_str(),
+                event["new"].as_str()) {
+
+                (Some(p), Some(t), Some(v)) => (p, t, v),
+                _ => continue,
+            };
+
+            if entry != path && !entry.starts_with(&parent) {
+                continue;
+            }
+
+            match (since, parse_timestamp(timestamp)) {
+                (Some(s), Some(t)) if t < s => continue,
+                _ => (),
+            }
+
+            samples.push(Sample {
+                timestamp: timestamp.to_string(),
+                path: entry.to_string(),
+                value: value.to_string(),
+            });
+        }
+    }
+
+    return Ok(samples);
+}
+
+/// Function used to rotate the files of the event log, whatever their size
+pub fn rotate() -> error::Return {
+    let event_log = match EVENT_LOG.lock() {
+        Ok(l) => l,
+        Err(_) => return error!(Lock, "Cannot lock event log"),
+    };
+
+    return match &*event_log {
+        Some(l) => l.rotate(),
+        None => error!("Event log is not enabled"),
+    };
+}
+
+/// Function used to enable the event log
+pub fn init(config: &config::EventLogConfig) {
+    let event_log = match EventLog::new(config) {
+        Some(l) => l,
+        None => return,
+    };
+
+    match event_log.path.parent() {
+        Some(d) => match fs::create_dir_all(d) {
+            Ok(_) => (),
+            Err(_) => log::error!("Cannot create event log directory"),
+        },
+
+        None => (),
+    }
+
+    match EVENT_LOG.lock() {
+        Ok(mut l) => *l = Some(event_log),
+        Err(_) => log::error!("Cannot lock event log"),
+    }
+}
+
+/// Function used to append an event to the log (if enabled)
+///
+/// The event is a JSON object to which the timestamp and the kind of event
+/// are added.
+pub fn write(event: &str, mut fields: Value) {
+    let event_log = match EVENT_LOG.lock() {
+        Ok(l) => l,
+        Err(_) => return,
+    };
+
+    let event_log = match &*event_log {
+        Some(l) => l,
+        None => return,
+    };
+
+    fields["timestamp"] = json!(timestamp());
+    fields["event"] = json!(event);
+
+    match event_log.append(&fields) {
+        Ok(_) => (),
+        Err(e) => log::error!("{}", e),
+    }
+}
@@ -1,8 +1,9 @@
 use std::sync::mpsc::{channel, Receiver, Sender};
 
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 
 use crate::events::Events;
+use crate::sync::Mutex;
 
 #[derive(Debug)]
 pub struct EventManager {
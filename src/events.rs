@@ -1,4 +1,10 @@
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Events {
     ModuleUpdated(String),
+
+    /// Value written to an entry (path, value) by a trigger
+    WriteEntry(String, String),
+
+    /// Snapshot of the values requested by a trigger
+    TakeSnapshot,
 }
@@ -0,0 +1,373 @@
+use std::fmt;
+
+use crate::error;
+
+const VALUE_FALSE: &str = "false";
+const VALUE_TRUE: &str = "true";
+
+/// Result of an expression
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Bool(bool),
+}
+
+impl Value {
+    /// Parse the value of an entry
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to be parsed
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+
+        return match value {
+            VALUE_TRUE => Some(Value::Bool(true)),
+            VALUE_FALSE => Some(Value::Bool(false)),
+            _ => value.parse::<f64>().ok().map(Value::Number),
+        };
+    }
+
+    fn number(&self) -> f64 {
+        return match self {
+            Value::Number(n) => *n,
+            Value::Bool(true) => 1.0,
+            Value::Bool(false) => 0.0,
+        };
+    }
+
+    fn boolean(&self) -> bool {
+        return match self {
+            Value::Number(n) => *n != 0.0,
+            Value::Bool(b) => *b,
+        };
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return match self {
+            Value::Bool(true) => write!(f, "{}", VALUE_TRUE),
+            Value::Bool(false) => write!(f, "{}", VALUE_FALSE),
+            Value::Number(n) if n.fract() == 0.0 && n.abs() < 1e15 =>
+                write!(f, "{}", *n as i64),
+            Value::Number(n) => write!(f, "{:.2}", n),
+        };
+    }
+}
+
+/// Token of an expression
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Path(String),
+    Operator(&'static str),
+    Open,
+    Close,
+}
+
+/// Operators sorted so that the longest ones are matched first
+const OPERATORS: [&str; 14] = [
+    "==", "!=", ">=", "<=", "&&", "||",
+    ">", "<", "+", "-", "*", "/", "%", "!",
+];
+
+/// Node of the tree of an expression
+#[derive(Debug, Clone)]
+enum Node {
+    Constant(Value),
+    Path(String),
+    Unary(&'static str, Box<Node>),
+    Binary(&'static str, Box<Node>, Box<Node>),
+}
+
+/// Split an expression into tokens
+///
+/// A path is made of letters, digits, underscores and slashes and starts
+/// with a letter, so `memory/used / memory/total` is a division of two paths
+/// while `memory/used/memory/total` is a single path.
+///
+/// # Arguments
+///
+/// * `text` - The expression
+fn tokenize(text: &str) -> Result<Vec<Token>, error::CerebroError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        if c == '(' || c == ')' {
+            tokens.push(match c {
+                '(' => Token::Open,
+                _ => Token::Close,
+            });
+
+            i += 1;
+            continue;
+        }
+
+        if c.is_ascii_digit() || c == '.' {
+            let start = i;
+
+            while i < chars.len() &&
+                (chars[i].is_ascii_digit() || chars[i] == '.') {
+
+                i += 1;
+            }
+
+            let number: String = chars[start..i].iter().collect();
+
+            match number.parse::<f64>() {
+                Ok(n) => tokens.push(Token::Number(n)),
+                Err(_) => return error!(&format!("Invalid number {}", number)),
+            }
+
+            continue;
+        }
+
+        if c.is_alphabetic() {
+            let start = i;
+
+            while i < chars.len() &&
+                (chars[i].is_alphanumeric() || chars[i] == '_' ||
+                    chars[i] == '/') {
+
+                i += 1;
+            }
+
+            // A trailing slash is a division
+            while chars[i - 1] == '/' {
+                i -= 1;
+            }
+
+            tokens.push(Token::Path(chars[start..i].iter().collect()));
+            continue;
+        }
+
+        let rest: String = chars[i..].iter().collect();
+
+        match OPERATORS.iter().find(|o| rest.starts_with(*o)) {
+            Some(o) => {
+                tokens.push(Token::Operator(o));
+                i += o.len();
+            },
+
+            None => return error!(&format!("Unexpected character {}", c)),
+        }
+    }
+
+    return Ok(tokens);
+}
+
+/// Recursive descent parser of the tokens
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        return self.tokens.get(self.position);
+    }
+
+    /// Consume the next token if it's one of the operators
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `operators` - The operators expected
+    fn operator(&mut self, operators: &[&str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Operator(o)) if operators.contains(o) => {
+                let o = *o;
+                self.position += 1;
+                return Some(o);
+            },
+
+            _ => return None,
+        }
+    }
+
+    /// Parse binary operations of the same precedence
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `operators` - The operators of this precedence
+    /// * `next` - The parser of the higher precedence
+    fn binary(
+        &mut self,
+        operators: &[&str],
+        next: fn(&mut Parser) -> Result<Node, error::CerebroError>)
+        -> Result<Node, error::CerebroError> {
+
+        let mut node = next(self)?;
+
+        while let Some(o) = self.operator(operators) {
+            node = Node::Binary(o, Box::new(node), Box::new(next(self)?));
+        }
+
+        return Ok(node);
+    }
+
+    fn or(&mut self) -> Result<Node, error::CerebroError> {
+        return self.binary(&["||"], Parser::and);
+    }
+
+    fn and(&mut self) -> Result<Node, error::CerebroError> {
+        return self.binary(&["&&"], Parser::comparison);
+    }
+
+    fn comparison(&mut self) -> Result<Node, error::CerebroError> {
+        return self.binary(
+            &["==", "!=", ">=", "<=", ">", "<"],
+            Parser::sum);
+    }
+
+    fn sum(&mut self) -> Result<Node, error::CerebroError> {
+        return self.binary(&["+", "-"], Parser::product);
+    }
+
+    fn product(&mut self) -> Result<Node, error::CerebroError> {
+        return self.binary(&["*", "/", "%"], Parser::unary);
+    }
+
+    fn unary(&mut self) -> Result<Node, error::CerebroError> {
+        match self.operator(&["-", "!"]) {
+            Some(o) => return Ok(Node::Unary(o, Box::new(self.unary()?))),
+            None => return self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Node, error::CerebroError> {
+        let token = match self.peek() {
+            Some(t) => t.clone(),
+            None => return error!("Unexpected end of expression"),
+        };
+
+        self.position += 1;
+
+        match token {
+            Token::Number(n) => return Ok(Node::Constant(Value::Number(n))),
+
+            Token::Path(p) => return match p.as_str() {
+                VALUE_TRUE => Ok(Node::Constant(Value::Bool(true))),
+                VALUE_FALSE => Ok(Node::Constant(Value::Bool(false))),
+                _ => Ok(Node::Path(p)),
+            },
+
+            Token::Open => {
+                let node = self.or()?;
+
+                match self.peek() {
+                    Some(Token::Close) => self.position += 1,
+                    _ => return error!("Missing closing parenthesis"),
+                }
+
+                return Ok(node);
+            },
+
+            t => return error!(&format!("Unexpected token {:?}", t)),
+        }
+    }
+}
+
+/// Expression computed from the values of entries
+///
+/// Supported operators are `+ - * / %`, comparisons (`== != > >= < <=`) and
+/// logical ones (`&& || !`). Comparisons give `true` or `false`.
+#[derive(Debug, Clone)]
+pub struct Expression {
+    root: Node,
+}
+
+impl Expression {
+    /// Parse an expression
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The expression (e.g. `memory/used / memory/total * 100`)
+    pub fn parse(text: &str) -> Result<Self, error::CerebroError> {
+        let mut parser = Parser {
+            tokens: tokenize(text)?,
+            position: 0,
+        };
+
+        let root = parser.or()?;
+
+        if parser.position != parser.tokens.len() {
+            return error!("Unexpected trailing tokens");
+        }
+
+        return Ok(Self {
+            root: root,
+        });
+    }
+
+    /// Compute the value of the expression
+    ///
+    /// Returns None if an entry has no value or isn't a number.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `resolve` - The function giving the value of an entry
+    pub fn evaluate(&self, resolve: &dyn Fn(&str) -> Option<String>)
+        -> Option<Value> {
+
+        return Expression::evaluate_node(&self.root, resolve);
+    }
+
+    fn evaluate_node(
+        node: &Node,
+        resolve: &dyn Fn(&str) -> Option<String>) -> Option<Value> {
+
+        match node {
+            Node::Constant(v) => return Some(*v),
+
+            Node::Path(p) => return Value::parse(&resolve(p)?),
+
+            Node::Unary(o, n) => {
+                let value = Expression::evaluate_node(n, resolve)?;
+
+                return match *o {
+                    "-" => Some(Value::Number(-value.number())),
+                    _ => Some(Value::Bool(!value.boolean())),
+                };
+            },
+
+            Node::Binary(o, l, r) => {
+                let l = Expression::evaluate_node(l, resolve)?;
+                let r = Expression::evaluate_node(r, resolve)?;
+
+                let (a, b) = (l.number(), r.number());
+
+                return match *o {
+                    "+" => Some(Value::Number(a + b)),
+                    "-" => Some(Value::Number(a - b)),
+                    "*" => Some(Value::Number(a * b)),
+                    "/" if b == 0.0 => None,
+                    "/" => Some(Value::Number(a / b)),
+                    "%" if b == 0.0 => None,
+                    "%" => Some(Value::Number(a % b)),
+                    "==" => Some(Value::Bool(a == b)),
+                    "!=" => Some(Value::Bool(a != b)),
+                    ">=" => Some(Value::Bool(a >= b)),
+                    "<=" => Some(Value::Bool(a <= b)),
+                    ">" => Some(Value::Bool(a > b)),
+                    "<" => Some(Value::Bool(a < b)),
+                    "&&" => Some(Value::Bool(l.boolean() && r.boolean())),
+                    "||" => Some(Value::Bool(l.boolean() || r.boolean())),
+                    _ => None,
+                };
+            },
+        }
+    }
+}
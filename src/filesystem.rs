@@ -1,8 +1,12 @@
 use lazy_static::lazy_static;
-use libc::ENOENT;
+use libc::{EACCES, EINVAL, ENOENT, ERANGE};
+use regex::Regex;
+use serde_json::{json, Value};
 use std::cmp;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, MutexGuard};
 use std::sync::mpsc::Receiver;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use std::thread;
@@ -14,23 +18,91 @@ use fuse::{
     ReplyAttr,
     ReplyData,
     ReplyDirectory,
+    ReplyEmpty,
     ReplyEntry,
+    ReplyOpen,
     ReplyWrite,
+    ReplyXattr,
     Request};
 
+use crate::bus;
 use crate::config;
+use crate::consumers;
+use crate::debug;
+use crate::demand;
+use crate::error;
+use crate::event_log;
 use crate::event_manager;
 use crate::events;
+use crate::gauge;
+use crate::health;
+use crate::metrics;
 use crate::modules::module;
+use crate::notifier;
+use crate::polkit;
+use crate::stream;
+use crate::sync::Mutex;
+use crate::trace;
+use crate::units;
+use crate::visibility;
 
 const INODE_INVALID: u64 = 0;
 const INODE_ROOT: u64 = 1;
 
+const ENTRY_ATOMIC: &str = ".atomic";
+const ENTRY_CONFIG: &str = ".config";
+const ENTRY_CONFIG_EFFECTIVE: &str = "effective.json";
+const ENTRY_CONFIG_MODULES: &str = "modules";
+const ENTRY_CONSUMERS: &str = "consumers";
+const ENTRY_DEBUG: &str = ".debug";
+const ENTRY_DEBUG_LAST_UPDATE: &str = "last_update";
 const ENTRY_JSON: &str = "json";
+const ENTRY_JSON_DELTA: &str = "json.delta";
+const ENTRY_EVENTS: &str = "events";
+const ENTRY_HEALTH: &str = "health";
+const ENTRY_METRICS: &str = "metrics";
+const ENTRY_QUERY: &str = "query";
 const ENTRY_SHELL: &str = "shell";
+const ENTRY_SNAPSHOT: &str = ".snapshot";
+const ENTRY_SNAPSHOT_TAKE: &str = "take";
+const ENTRY_STATS: &str = ".stats";
+const ENTRY_TRACE: &str = "trace";
 
+/// Number of snapshots kept in memory, the oldest ones are dropped
+const MAX_SNAPSHOTS: usize = 16;
+
+const BAR_SUFFIX: &str = ".bar";
+const COLOR_SUFFIX: &str = ".color";
+const ICON_SUFFIX: &str = ".icon";
+const SEQ_SUFFIX: &str = ".seq";
+const SPARK_SUFFIX: &str = ".spark";
+const UNIT_SUFFIX: &str = ".unit";
+const VALUES_SUFFIX: &str = ".values";
+const WAIT_SUFFIX: &str = ".wait";
+
+/// Number of characters of the gauges when not configured
+const DEFAULT_BAR_WIDTH: usize = 10;
+const DEFAULT_SPARK_WIDTH: usize = 8;
+
+/// Extended attribute holding the unit of an entry
+const XATTR_UNIT: &str = "user.unit";
+
+/// Error returned when an extended attribute doesn't exist
+#[cfg(target_os = "linux")]
+const ENOATTR: i32 = libc::ENODATA;
+
+#[cfg(not(target_os = "linux"))]
+const ENOATTR: i32 = libc::ENOATTR;
+
+/// Value of a converted entry that cannot be computed
+const VALUE_UNKNOWN: &str = "?";
+
+/// Default duration during which the kernel caches the attributes
 const TTL: Duration = Duration::from_secs(1);
 
+/// Bypass the page cache, needed when the content depends on the reader
+const FOPEN_DIRECT_IO: u32 = 1 << 0;
+
 lazy_static! {
     static ref INODE_INDEX: Mutex<u64> = Mutex::new(INODE_ROOT);
 }
@@ -39,8 +111,11 @@ lazy_static! {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Mode {
     ReadOnly,
-    //ReadWrite,
+    ReadWrite,
     WriteOnly,
+
+    /// Readable by everyone, writable by the owner only
+    OwnerReadWrite,
 }
 
 /// Filesystem entry: file or directory
@@ -50,6 +125,8 @@ pub struct FsEntry {
     pub file_type: FileType,
     pub name: String,
     pub mode: Mode,
+    pub ttl: Duration,
+    pub unit: Option<units::Unit>,
     pub fs_entries: Vec<FsEntry>,
 }
 
@@ -67,10 +144,23 @@ impl FsEntry {
             file_type: file_type,
             name: name.to_string(),
             mode: mode,
+            ttl: TTL,
+            unit: None,
             fs_entries: fs_entries.to_vec(),
         }
     }
 
+    /// Set the unit of the value of the entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `unit` - The unit of the value
+    pub fn with_unit(mut self, unit: units::Unit) -> Self {
+        self.unit = Some(unit);
+        return self;
+    }
+
     /// Create a new unique inode value
     pub fn create_inode() -> u64 {
         let mut guard = match INODE_INDEX.lock() {
@@ -96,8 +186,10 @@ impl FsEntry {
             FileType::RegularFile => match self.mode {
                 Mode::WriteOnly => 0o222,
                 Mode::ReadOnly => 0o444,
-                //Mode::ReadWrite => 0o666,
+                Mode::ReadWrite => 0o666,
+                Mode::OwnerReadWrite => 0o644,
             },
+            FileType::Symlink => 0o777,
             _ => 0o555,
         };
 
@@ -107,7 +199,7 @@ impl FsEntry {
         };
 
         let nlink = match self.file_type {
-            FileType::RegularFile => 1,
+            FileType::RegularFile | FileType::Symlink => 1,
             _ => 2,
         };
 
@@ -150,6 +242,46 @@ impl FsEntry {
         return None;
     }
 
+    /// Get the path of a filesystem entry relatively to the current one
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the entry to search
+    pub fn find_path(&self, inode: u64) -> Option<String> {
+        for entry in self.fs_entries.iter() {
+            if entry.inode == inode {
+                return Some(entry.name.clone());
+            }
+
+            match entry.find_path(inode) {
+                Some(p) => return Some(format!("{}/{}", entry.name, p)),
+                None => (),
+            }
+        }
+
+        return None;
+    }
+
+    /// Find a filesystem entry into the current one by its path
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `path` - The path of the entry relatively to the current one
+    pub fn find_by_path<'i>(&'i self, path: &str) -> Option<&'i FsEntry> {
+        let mut entry = self;
+
+        for name in path.split('/').filter(|n| !n.is_empty()) {
+            entry = match entry.fs_entries.iter().find(|e| e.name == name) {
+                Some(e) => e,
+                None => return None,
+            };
+        }
+
+        return Some(entry);
+    }
+
     /// Find a filesystem entry into the current one by its name
     ///
     /// # Arguments
@@ -170,11 +302,97 @@ impl FsEntry {
 
         return None;
     }
+
+    /// Check if two lists of entries describe the same tree
+    ///
+    /// # Arguments
+    ///
+    /// * `a` - The first list of entries
+    /// * `b` - The second list of entries
+    /// * `inodes` - Whether the inodes must be the same too
+    pub fn same_tree(a: &Vec<FsEntry>, b: &Vec<FsEntry>, inodes: bool)
+        -> bool {
+
+        if a.len() != b.len() {
+            return false;
+        }
+
+        return a.iter().zip(b.iter()).all(|(x, y)| {
+            (!inodes || x.inode == y.inode)
+                && x.name == y.name
+                && x.file_type == y.file_type
+                && x.mode == y.mode
+                && FsEntry::same_tree(&x.fs_entries, &y.fs_entries, inodes)
+        });
+    }
+}
+
+/// Kind of an entry added next to a module entry
+#[derive(Clone, Copy, PartialEq)]
+enum SiblingKind {
+    Seq,
+    Wait,
+    Unit,
+    Values,
+    Convert(units::Unit),
+    Spark(usize),
+    Bar(usize),
+    Color,
+    Icon,
+}
+
+impl SiblingKind {
+    /// Get the suffix appended to the name of the module entry
+    fn suffix(&self) -> String {
+        return match self {
+            SiblingKind::Seq => SEQ_SUFFIX.to_string(),
+            SiblingKind::Wait => WAIT_SUFFIX.to_string(),
+            SiblingKind::Unit => UNIT_SUFFIX.to_string(),
+            SiblingKind::Values => VALUES_SUFFIX.to_string(),
+            SiblingKind::Convert(u) => format!(".{}", u.name()),
+            SiblingKind::Spark(_) => SPARK_SUFFIX.to_string(),
+            SiblingKind::Bar(_) => BAR_SUFFIX.to_string(),
+            SiblingKind::Color => COLOR_SUFFIX.to_string(),
+            SiblingKind::Icon => ICON_SUFFIX.to_string(),
+        };
+    }
+}
+
+/// Entry added next to a module entry
+struct Sibling {
+    kind: SiblingKind,
+    inode: u64,
+    path: String,
+}
+
+/// File of the configuration directory
+///
+/// The effective configuration has no module, the configuration of a
+/// module has no field.
+struct ConfigFile {
+    module: Option<String>,
+    field: Option<String>,
 }
 
 /// Filesystem backend structure used to store data
 pub struct FsBackend {
     root: FsEntry,
+    inode_events: u64,
+    inode_health: u64,
+    inode_metrics: u64,
+    inode_trace: u64,
+    inode_query: u64,
+    inode_stats: u64,
+    inode_consumers: u64,
+    inode_snapshot: u64,
+    inode_snapshot_take: u64,
+    snapshots: Vec<FsEntry>,
+    snapshot_values: HashMap<u64, String>,
+    config_entry: FsEntry,
+    config_files: HashMap<u64, ConfigFile>,
+    config_file: PathBuf,
+    siblings: HashMap<u64, Sibling>,
+    registered: HashMap<String, Vec<FsEntry>>,
     modules: Vec<Arc<Mutex<dyn module::Module>>>,
     config: config::Config,
 }
@@ -183,7 +401,10 @@ impl FsBackend {
     /// Constructor
     pub fn new(
         modules: &Vec<Arc<Mutex<dyn module::Module>>>,
-        config: &config::Config) -> Self {
+        config: &config::Config,
+        config_file: &Path) -> Self {
+
+        let (config_entry, config_files) = FsBackend::config_entry(config);
 
         Self {
             root: FsEntry::new(
@@ -192,11 +413,319 @@ impl FsBackend {
                 "/",
                 Mode::ReadOnly,
                 &Vec::new()),
+            inode_events: FsEntry::create_inode(),
+            inode_health: FsEntry::create_inode(),
+            inode_metrics: FsEntry::create_inode(),
+            inode_trace: FsEntry::create_inode(),
+            inode_query: FsEntry::create_inode(),
+            inode_stats: FsEntry::create_inode(),
+            inode_consumers: FsEntry::create_inode(),
+            inode_snapshot: FsEntry::create_inode(),
+            inode_snapshot_take: FsEntry::create_inode(),
+            snapshots: Vec::new(),
+            snapshot_values: HashMap::new(),
+            config_entry: config_entry,
+            config_files: config_files,
+            config_file: config_file.to_path_buf(),
+            siblings: HashMap::new(),
+            registered: HashMap::new(),
             modules: modules.to_vec(),
             config: config.clone(),
         }
     }
 
+    /// Get the value of an entry that is not provided by a module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the entry
+    fn own_value(&self, inode: u64) -> Option<String> {
+        if inode == self.inode_metrics {
+            return Some(metrics::render());
+        }
+
+        if inode == self.inode_trace {
+            return Some(trace::status());
+        }
+
+        if inode == self.inode_consumers {
+            return Some(consumers::render());
+        }
+
+        if inode == self.inode_snapshot_take {
+            return Some(String::new());
+        }
+
+        match self.snapshot_values.get(&inode) {
+            Some(v) => return Some(v.clone()),
+            None => (),
+        }
+
+        match self.config_files.get(&inode) {
+            Some(f) => return Some(self.config_value(f)),
+            None => (),
+        }
+
+        if inode == self.inode_health {
+            let modules: Vec<(String, bool)> = self.modules
+                .iter()
+                .filter_map(|m| {
+                    let module = m.lock().ok()?;
+
+                    match self.config.modules.get(module.name()) {
+                        Some(c) if c.enabled == Some(true) => (),
+                        _ => return None,
+                    }
+
+                    return Some((
+                        module.name().to_string(),
+                        module.is_running()));
+                })
+                .collect();
+
+            return Some(health::render(&modules));
+        }
+
+        let sibling = match self.siblings.get(&inode) {
+            Some(s) => s,
+            None => return None,
+        };
+
+        let unit = match self.root.find(sibling.inode) {
+            Some(e) => e.unit,
+            None => None,
+        };
+
+        match sibling.kind {
+            SiblingKind::Seq => {
+                return Some(notifier::generation(&sibling.path).to_string());
+            },
+
+            SiblingKind::Unit => {
+                return Some(match unit {
+                    Some(u) => u.symbol().to_string(),
+                    None => String::new(),
+                });
+            },
+
+            SiblingKind::Convert(to) => {
+                let value = match self.find_module(sibling.inode) {
+                    Some(m) => match m.lock() {
+                        Ok(m) => m.value(sibling.inode),
+                        Err(_) => return Some(VALUE_UNKNOWN.to_string()),
+                    },
+
+                    None => return Some(VALUE_UNKNOWN.to_string()),
+                };
+
+                let converted = match (unit, value.trim().parse::<f64>()) {
+                    (Some(u), Ok(v)) => u.convert(v, to),
+                    _ => None,
+                };
+
+                return Some(match converted {
+                    Some(c) => format!("{:.2}", c),
+                    None => VALUE_UNKNOWN.to_string(),
+                });
+            },
+
+            SiblingKind::Values => {
+                return Some(self.directory_values(&sibling.path));
+            },
+
+            SiblingKind::Spark(width) => {
+                return Some(gauge::sparkline(&sibling.path, width));
+            },
+
+            SiblingKind::Bar(width) => {
+                return Some(match bus::value(&sibling.path) {
+                    Some(v) => gauge::bar(&v, width),
+                    None => String::new(),
+                });
+            },
+
+            SiblingKind::Color => {
+                let path = sibling.path.trim_start_matches('/');
+
+                let (module, entry) = config::split_path(
+                    path,
+                    |n| self.config.modules.contains_key(n));
+
+                let config = match self.config.modules.get(&module) {
+                    Some(m) => match &m.colors {
+                        Some(c) => c,
+                        None => return Some(String::new()),
+                    },
+
+                    None => return Some(String::new()),
+                };
+
+                return Some(match bus::value(path) {
+                    Some(v) => gauge::color(config, entry, &v),
+                    None => String::new(),
+                });
+            },
+
+            SiblingKind::Icon => {
+                let path = sibling.path.trim_start_matches('/');
+
+                let (module, entry) = config::split_path(
+                    path,
+                    |n| self.config.modules.contains_key(n));
+
+                let icons = match self.config.modules.get(&module) {
+                    Some(m) => match &m.icons {
+                        Some(i) => match i.get(entry) {
+                            Some(i) => i,
+                            None => return Some(String::new()),
+                        },
+
+                        None => return Some(String::new()),
+                    },
+
+                    None => return Some(String::new()),
+                };
+
+                return Some(match bus::value(path) {
+                    Some(v) => gauge::icon(
+                        icons,
+                        &config::module_path(&module),
+                        &v),
+                    None => String::new(),
+                });
+            },
+
+            SiblingKind::Wait => return None,
+        }
+    }
+
+    /// Get the `name=value` lines of the files under a directory of a
+    /// module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `path` - The path of the directory (e.g. `/battery/devices`)
+    fn directory_values(&self, path: &str) -> String {
+        let (name, directory) = config::split_path(
+            path,
+            |n| self.config.modules.contains_key(n));
+
+        let module = match self.find_module_by_name(name.clone()) {
+            Some(m) => m,
+            None => return String::new(),
+        };
+
+        let module = match module.lock() {
+            Ok(m) => m,
+            Err(_) => return String::new(),
+        };
+
+        let mut entries =
+            visibility::filter_entries(&name, &module.fs_entries());
+
+        for component in directory.split('/').filter(|c| !c.is_empty()) {
+            entries = match entries.iter().find(|e| e.name == component) {
+                Some(e) => e.fs_entries.clone(),
+                None => return String::new(),
+            };
+        }
+
+        return format_values(&*module, &entries);
+    }
+
+    /// Refresh the module owning an entry if it is updated on access
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the entry
+    fn refresh(&self, inode: u64) {
+        for (name, module_entry) in self.module_entries() {
+            match module_entry.find(inode) {
+                Some(_) => return module::refresh(&name),
+                None => (),
+            }
+        }
+    }
+
+    /// Get the polkit action needed to write an entry, if any
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the entry
+    fn polkit_action(&self, inode: u64) -> Option<String> {
+        for (name, module_entry) in self.module_entries() {
+            let path = match module_entry.find_path(inode) {
+                Some(p) => p,
+                None => continue,
+            };
+
+            let config = match self.config.modules.get(&name) {
+                Some(c) => match &c.polkit {
+                    Some(p) => p,
+                    None => return None,
+                },
+
+                None => return None,
+            };
+
+            let required = match &config.entries {
+                Some(e) => e.contains(&path),
+                None => true,
+            };
+
+            if !required {
+                return None;
+            }
+
+            return match &config.action_id {
+                Some(a) => Some(a.clone()),
+                None => Some(polkit::DEFAULT_ACTION_ID.to_string()),
+            };
+        }
+
+        return None;
+    }
+
+    /// Get the unit of an entry, if any
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the entry
+    fn unit(&self, inode: u64) -> Option<units::Unit> {
+        return match self.root.find(inode) {
+            Some(e) => e.unit,
+            None => None,
+        };
+    }
+
+    /// Check if an entry is read as a stream (blocking reads)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the entry
+    fn is_stream(&self, inode: u64) -> bool {
+        return inode == self.inode_events || self.find_wait(inode).is_some();
+    }
+
+    /// Find the module entry a `.wait` entry is waiting for
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the `.wait` entry
+    fn find_wait(&self, inode: u64) -> Option<&Sibling> {
+        return match self.siblings.get(&inode) {
+            Some(s) if s.kind == SiblingKind::Wait => Some(s),
+            _ => None,
+        };
+    }
+
     /// Find the module by its name
     ///
     /// # Arguments
@@ -220,6 +749,41 @@ impl FsBackend {
         return None;
     }
 
+    /// Get the directories of the modules registered, by name of module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn module_entries(&self) -> Vec<(String, &FsEntry)> {
+        return self.registered
+            .keys()
+            .filter_map(|name| {
+                match self.root.find_by_path(&config::module_path(name)) {
+                    Some(e) => Some((name.clone(), e)),
+                    None => None,
+                }
+            })
+            .collect();
+    }
+
+    /// Find the module whose entries are under a directory
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the directory
+    fn find_module_by_entry(&self, inode: u64)
+        -> Option<Arc<Mutex<dyn module::Module>>> {
+
+        return match self.module_entries()
+            .into_iter()
+            .find(|(_, e)| e.inode == inode) {
+
+            Some((name, _)) => self.find_module_by_name(name),
+            None => None,
+        };
+    }
+
     /// Find the module that owns a filesystem entry
     ///
     /// # Arguments
@@ -247,6 +811,40 @@ impl FsBackend {
         return None;
     }
 
+    /// Write a value to a writable entry of a module
+    ///
+    /// This is used by the triggers, no authorization is required.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `path` - The path of the entry (e.g. `brightness/acpi_video0/value`)
+    /// * `value` - The value to be written
+    pub fn write_entry(&self, path: &str, value: &str) -> error::Return {
+        let entry = match self.root.find_by_path(path) {
+            Some(e) if e.file_type == FileType::RegularFile => e,
+            _ => return error!(&format!("No such entry: {}", path)),
+        };
+
+        if entry.mode == Mode::ReadOnly {
+            return error!(&format!("Entry is not writable: {}", path));
+        }
+
+        let module = match self.find_module(entry.inode) {
+            Some(m) => m,
+            None => return error!(&format!("Entry has no module: {}", path)),
+        };
+
+        return match module.lock() {
+            Ok(mut m) => {
+                m.set_value(entry.inode, value.as_bytes());
+                success!()
+            },
+
+            Err(_) => error!(Lock, "Cannot lock module"),
+        };
+    }
+
     /// Register a module in to the filesystem giving its name
     ///
     /// # Arguments
@@ -256,22 +854,72 @@ impl FsBackend {
     pub fn register_module_by_name(&mut self, name: String) {
         match self.find_module_by_name(name) {
             Some(m) => {
-                FsBackend::register_module(&self.config, m, &mut self.root);
+                FsBackend::register_module(
+                    &self.config,
+                    m,
+                    &mut self.root,
+                    &mut self.siblings,
+                    &mut self.registered);
             },
 
             None => (),
         }
     }
 
+    /// Get the directory holding the entries of a module and their name
+    ///
+    /// The directory of the type of an instance is created if needed (e.g.
+    /// `ping` for `ping:home`).
+    ///
+    /// # Arguments
+    ///
+    /// * `root` - The root of the filesystem
+    /// * `name` - The name of the module
+    fn module_parent<'r>(root: &'r mut FsEntry, name: &str)
+        -> (&'r mut FsEntry, String) {
+
+        let (group, leaf) = match name.find(config::INSTANCE_SEPARATOR) {
+            Some(i) => (&name[..i], &name[i + 1..]),
+            None => return (root, name.to_string()),
+        };
+
+        let index = match root.fs_entries.iter().position(
+            |x| x.name == group) {
+
+            Some(i) => i,
+            None => {
+                let mut entry = FsEntry::new(
+                    FsEntry::create_inode(),
+                    FileType::Directory,
+                    group,
+                    Mode::ReadOnly,
+                    &Vec::new());
+
+                entry.ttl = root.ttl;
+
+                root.fs_entries.push(entry);
+                root.fs_entries.len() - 1
+            },
+        };
+
+        return (&mut root.fs_entries[index], leaf.to_string());
+    }
+
     /// Register a module in to the filesystem
     ///
+    /// The module is always restarted, its thread exits when its entries
+    /// change. The filesystem is only rebuilt if the entries differ from the
+    /// ones of the last registration.
+    ///
     /// # Arguments
     ///
     /// * `self` - The instance handle
-    pub fn register_module(
+    fn register_module(
         config: &config::Config,
         module: Arc<Mutex<dyn module::Module>>,
-        root: &mut FsEntry) {
+        root: &mut FsEntry,
+        siblings: &mut HashMap<u64, Sibling>,
+        registered: &mut HashMap<String, Vec<FsEntry>>) {
 
         let mut module = match module.lock() {
             Ok(m) => m,
@@ -283,6 +931,14 @@ impl FsBackend {
             return;
         }
 
+        // Entries of an instance are under the directory of its type
+        let path = config::module_path(module.name());
+
+        let ttl = match FsBackend::configured_ttl(&config.ttl, &path) {
+            Some(t) => t,
+            None => root.ttl,
+        };
+
         let config = &config.modules[module.name()];
 
         // Check if enabled
@@ -291,6 +947,14 @@ impl FsBackend {
             _ => return,
         }
 
+        // Compare with the entries already registered
+        let fs_entries = module.fs_entries();
+
+        let unchanged = match registered.get(module.name()) {
+            Some(r) => FsEntry::same_tree(r, &fs_entries, true),
+            None => false,
+        };
+
         // Stop module
         log::info!("stop module: {}", module.name());
 
@@ -302,20 +966,31 @@ impl FsBackend {
             },
         }
 
+        if unchanged {
+            log::debug!("{}: entries are unchanged", module.name());
+            return FsBackend::start_module(&mut *module, config);
+        }
+
         // Unregister its old filesystem
-        let index = match root.fs_entries.iter().position(
-            |x| x.name == module.name()) {
+        let (parent, leaf) = FsBackend::module_parent(root, module.name());
+
+        let index = match parent.fs_entries.iter().position(
+            |x| x.name == leaf) {
 
             Some(i) => i,
             None => usize::MAX,
         };
 
         if index != usize::MAX {
-            root.fs_entries.remove(index);
+            parent.fs_entries.remove(index);
         }
 
+        let values_name = format!("{}{}", leaf, VALUES_SUFFIX);
+
+        parent.fs_entries.retain(|x| x.name != values_name);
+
         // Register its filesystem
-        match root.fs_entries.iter().find(|x| &x.name == module.name()) {
+        match parent.fs_entries.iter().find(|x| x.name == leaf) {
             Some(_) => log::debug!("Module is already registered"),
             None => (),
         }
@@ -323,33 +998,1135 @@ impl FsBackend {
         let mut entry = FsEntry::new(
             FsEntry::create_inode(),
             FileType::Directory,
-            module.name(),
+            &leaf,
             Mode::ReadOnly,
-            &module.fs_entries());
+            &visibility::filter_entries(module.name(), &fs_entries));
 
-        FsBackend::register_custom_entries(config, &mut entry);
+        // Sequence numbers and blocking variants of the entries
+        let module_path = format!("/{}", path);
 
-        root.fs_entries.push(entry);
+        let prefix = format!("{}/", module_path);
 
-        // Start module
-        log::info!("start module: {}", module.name());
+        siblings.retain(|_, s| {
+            !s.path.starts_with(&prefix) && s.path != module_path
+        });
+
+        match &config.seq {
+            Some(c) if c.enabled == Some(true) => {
+                FsBackend::register_sibling_entries(
+                    &mut entry,
+                    &module_path,
+                    SiblingKind::Seq,
+                    &|_, _| true,
+                    siblings);
+            },
+
+            _ => (),
+        }
+
+        match &config.wait {
+            Some(c) if c.enabled == Some(true) => {
+                FsBackend::register_sibling_entries(
+                    &mut entry,
+                    &module_path,
+                    SiblingKind::Wait,
+                    &|_, _| true,
+                    siblings);
+            },
+
+            _ => (),
+        }
+
+        match &config.gauge {
+            Some(c) if c.enabled == Some(true) => {
+                FsBackend::register_gauge_entries(
+                    c,
+                    &mut entry,
+                    &module_path,
+                    siblings);
+            },
+
+            _ => (),
+        }
+
+        match &config.colors {
+            Some(c) => {
+                FsBackend::register_sibling_entries(
+                    &mut entry,
+                    &module_path,
+                    SiblingKind::Color,
+                    &|p, _| match p.strip_prefix(&prefix) {
+                        Some(e) => c.thresholds.contains_key(e),
+                        None => false,
+                    },
+                    siblings);
+            },
+
+            None => (),
+        }
+
+        match &config.icons {
+            Some(i) => {
+                FsBackend::register_sibling_entries(
+                    &mut entry,
+                    &module_path,
+                    SiblingKind::Icon,
+                    &|p, _| match p.strip_prefix(&prefix) {
+                        Some(e) => i.contains_key(e),
+                        None => false,
+                    },
+                    siblings);
+            },
+
+            None => (),
+        }
+
+        match &config.units {
+            Some(c) => FsBackend::register_unit_entries(
+                c,
+                &mut entry,
+                &module_path,
+                siblings),
+
+            None => (),
+        }
+
+        // Values of the directories, the one of the module is at the root
+        match &config.values {
+            Some(c) if c.enabled == Some(true) => {
+                FsBackend::register_sibling_entries(
+                    &mut entry,
+                    &module_path,
+                    SiblingKind::Values,
+                    &|_, _| true,
+                    siblings);
+
+                let inode = FsEntry::create_inode();
+
+                parent.fs_entries.push(FsEntry::new(
+                    inode,
+                    FileType::RegularFile,
+                    &values_name,
+                    Mode::ReadOnly,
+                    &Vec::new()));
+
+                siblings.insert(inode, Sibling {
+                    kind: SiblingKind::Values,
+                    inode: entry.inode,
+                    path: module_path.clone(),
+                });
+            },
+
+            _ => (),
+        }
+
+        FsBackend::register_custom_entries(config, &mut entry);
+
+        FsBackend::apply_ttl(&mut entry, "", &config.ttl, ttl);
+
+        match &config.fast {
+            Some(c) => FsBackend::apply_fast_ttl(&mut entry, "", c),
+            None => (),
+        }
+
+        parent.fs_entries.push(entry);
+
+        registered.insert(module.name().to_string(), fs_entries);
+
+        FsBackend::start_module(&mut *module, config);
+    }
+
+    /// Start a module and log the result
+    ///
+    /// # Arguments
+    ///
+    /// * `module` - The module
+    /// * `config` - The configuration of the module
+    fn start_module(
+        module: &mut dyn module::Module,
+        config: &config::ModuleConfig) {
+
+        log::info!("start module: {}", module.name());
+
+        let result = module.start(config);
+
+        event_log::write(
+            "module",
+            json!({
+                "module": module.name(),
+                "action": "start",
+                "error": match &result {
+                    Ok(_) => None,
+                    Err(e) => Some(e.to_string()),
+                },
+            }));
+
+        match result {
+            Ok(_) => (),
+            Err(e) => log::error!("Cannot start module: {}", e),
+        }
+    }
+
+    /// Register modules into the filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    pub fn register_modules(&mut self) {
+        self.root.fs_entries.clear();
+        self.registered.clear();
+
+        self.root.fs_entries.push(FsEntry::new(
+            self.inode_events,
+            FileType::RegularFile,
+            ENTRY_EVENTS,
+            Mode::ReadOnly,
+            &Vec::new()));
+
+        self.root.fs_entries.push(FsEntry::new(
+            self.inode_health,
+            FileType::RegularFile,
+            ENTRY_HEALTH,
+            Mode::ReadOnly,
+            &Vec::new()));
+
+        self.root.fs_entries.push(FsEntry::new(
+            self.inode_metrics,
+            FileType::RegularFile,
+            ENTRY_METRICS,
+            Mode::ReadOnly,
+            &Vec::new()));
+
+        self.root.fs_entries.push(FsEntry::new(
+            self.inode_query,
+            FileType::RegularFile,
+            ENTRY_QUERY,
+            Mode::ReadWrite,
+            &Vec::new()));
+
+        // Statistics of the accesses
+        self.root.fs_entries.push(FsEntry::new(
+            self.inode_stats,
+            FileType::Directory,
+            ENTRY_STATS,
+            Mode::ReadOnly,
+            &vec![
+                FsEntry::new(
+                    self.inode_consumers,
+                    FileType::RegularFile,
+                    ENTRY_CONSUMERS,
+                    Mode::ReadOnly,
+                    &Vec::new()),
+            ]));
+
+        // Control file of the recording of the spans
+        match &self.config.trace {
+            Some(c) if c.enabled == Some(true) => {
+                self.root.fs_entries.push(FsEntry::new(
+                    self.inode_trace,
+                    FileType::RegularFile,
+                    ENTRY_TRACE,
+                    Mode::ReadWrite,
+                    &Vec::new()));
+            },
+
+            _ => (),
+        }
+
+        // Snapshots of the tree, kept across the registrations
+        self.root.fs_entries.push(self.snapshot_entry());
+
+        // Configuration used by the instance
+        self.root.fs_entries.push(self.config_entry.clone());
+
+        FsBackend::apply_ttl(&mut self.root, "", &self.config.ttl, TTL);
+
+        for m in self.ordered_modules().iter() {
+            FsBackend::register_module(
+                &self.config,
+                m.clone(),
+                &mut self.root,
+                &mut self.siblings,
+                &mut self.registered);
+        }
+    }
+
+    /// Collect the values of the entries whose path matches a query
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `entries` - The entries to be searched
+    /// * `parent` - The path of the parent of the entries
+    /// * `module` - The module whose tree contains the entries, if any
+    /// * `query` - The query matched against the paths
+    /// * `output` - The `path=value` lines of the matching entries
+    fn collect_query(
+        &self,
+        entries: &Vec<FsEntry>,
+        parent: &str,
+        module: Option<&Arc<Mutex<dyn module::Module>>>,
+        query: &Regex,
+        output: &mut String) {
+
+        for entry in entries.iter() {
+            // The configuration isn't made of values
+            if entry.inode == self.config_entry.inode {
+                continue;
+            }
+
+            let path = match parent.is_empty() {
+                true => entry.name.clone(),
+                false => format!("{}/{}", parent, entry.name),
+            };
+
+            if entry.file_type == FileType::Directory {
+                let owner = match module {
+                    Some(m) => Some(m.clone()),
+                    None => self.find_module_by_entry(entry.inode),
+                };
+
+                self.collect_query(
+                    &entry.fs_entries,
+                    &path,
+                    owner.as_ref(),
+                    query,
+                    output);
+
+                continue;
+            }
+
+            if !query.is_match(&path) {
+                continue;
+            }
+
+            match self.current_value(entry, module) {
+                Some(v) => *output += &format!("{}={}\n", path, v.trim_end()),
+                None => (),
+            }
+        }
+    }
+
+    /// Get the `path=value` lines of the entries matching a glob
+    ///
+    /// `*` matches any part of a name, `**` any part of a path and `?` a
+    /// single character.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `glob` - The glob matched against the paths (e.g. `*/timestamp`)
+    fn query(&self, glob: &str) -> String {
+        let query = match glob_regex(glob) {
+            Some(q) => q,
+            None => return String::new(),
+        };
+
+        let mut output = String::new();
+
+        self.collect_query(
+            &self.root.fs_entries,
+            "",
+            None,
+            &query,
+            &mut output);
+
+        return output;
+    }
+
+    /// Build the directory showing the effective configuration
+    ///
+    /// Returns the directory and its files by inode. The fields of the
+    /// modules that can be edited have their own files, only writable by the
+    /// owner of the filesystem as they change the persisted configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - The configuration (merged fragments and profile)
+    fn config_entry(config: &config::Config)
+        -> (FsEntry, HashMap<u64, ConfigFile>) {
+
+        let mut files = HashMap::new();
+
+        let effective = FsEntry::create_inode();
+
+        files.insert(effective, ConfigFile {
+            module: None,
+            field: None,
+        });
+
+        let mut names: Vec<&String> = config.modules.keys().collect();
+        names.sort();
+
+        let mut modules = Vec::new();
+
+        for name in names {
+            let inode = FsEntry::create_inode();
+
+            files.insert(inode, ConfigFile {
+                module: Some(name.clone()),
+                field: None,
+            });
+
+            modules.push(FsEntry::new(
+                inode,
+                FileType::RegularFile,
+                &format!("{}.json", name),
+                Mode::ReadOnly,
+                &Vec::new()));
+
+            let fields = config::EDITABLE_FIELDS
+                .iter()
+                .map(|f| {
+                    let inode = FsEntry::create_inode();
+
+                    files.insert(inode, ConfigFile {
+                        module: Some(name.clone()),
+                        field: Some(f.to_string()),
+                    });
+
+                    return FsEntry::new(
+                        inode,
+                        FileType::RegularFile,
+                        f,
+                        Mode::OwnerReadWrite,
+                        &Vec::new());
+                })
+                .collect();
+
+            modules.push(FsEntry::new(
+                FsEntry::create_inode(),
+                FileType::Directory,
+                name,
+                Mode::ReadOnly,
+                &fields));
+        }
+
+        let entry = FsEntry::new(
+            FsEntry::create_inode(),
+            FileType::Directory,
+            ENTRY_CONFIG,
+            Mode::ReadOnly,
+            &vec![
+                FsEntry::new(
+                    effective,
+                    FileType::RegularFile,
+                    ENTRY_CONFIG_EFFECTIVE,
+                    Mode::ReadOnly,
+                    &Vec::new()),
+
+                FsEntry::new(
+                    FsEntry::create_inode(),
+                    FileType::Directory,
+                    ENTRY_CONFIG_MODULES,
+                    Mode::ReadOnly,
+                    &modules),
+            ]);
+
+        return (entry, files);
+    }
+
+    /// Get the content of a file of the configuration directory
+    ///
+    /// The secrets of the configuration are hidden.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `file` - The file
+    fn config_value(&self, file: &ConfigFile) -> String {
+        match (&file.module, &file.field) {
+            (Some(m), Some(f)) => return match self.config.modules.get(m) {
+                Some(c) => c.field(f),
+                None => String::new(),
+            },
+
+            _ => (),
+        }
+
+        let json = self.config.redacted();
+
+        let value = match &file.module {
+            Some(m) => &json["modules"][m.as_str()],
+            None => &json,
+        };
+
+        return match serde_json::to_string_pretty(value) {
+            Ok(s) => format!("{}\n", s),
+            Err(_) => String::new(),
+        };
+    }
+
+    /// Edit a field of a module from a file of the configuration directory
+    ///
+    /// The module is restarted with its new configuration, which is saved
+    /// to the config file if enabled.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the file
+    /// * `data` - The data written to the file
+    fn write_config(&mut self, inode: u64, data: &[u8]) -> error::Return {
+        let (module, field) = match self.config_files.get(&inode) {
+            Some(ConfigFile { module: Some(m), field: Some(f) }) =>
+                (m.clone(), f.clone()),
+
+            _ => return error!("Configuration file is read-only"),
+        };
+
+        let text = String::from_utf8_lossy(data).to_string();
+
+        let mut module_config = match self.config.modules.get(&module) {
+            Some(c) => c.clone(),
+            None => return error!(Config, "Module is not configured"),
+        };
+
+        let value = module_config.set_field(&field, &text)?;
+
+        // The edit is refused if it cannot be saved
+        match &self.config.edit {
+            Some(c) if c.persist == Some(true) => config::persist(
+                &self.config_file,
+                self.config.profile.as_ref(),
+                &module,
+                &field,
+                value.clone())?,
+
+            _ => (),
+        }
+
+        log::info!("{}: {} set to {}", module, field, value);
+
+        self.config.modules.insert(module.clone(), module_config);
+
+        // Restart the module with its new configuration
+        self.unregister_module(&module);
+        self.register_module_by_name(module.clone());
+
+        return success!();
+    }
+
+    /// Stop a module and remove its entries from the filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `name` - The name of the module
+    fn unregister_module(&mut self, name: &str) {
+        match self.find_module_by_name(name.to_string()) {
+            Some(m) => match m.lock() {
+                Ok(mut m) => {
+                    log::info!("stop module: {}", name);
+
+                    match m.stop() {
+                        Ok(_) => (),
+                        Err(e) => log::error!("Cannot stop module: {}", e),
+                    }
+                },
+
+                Err(_) => (),
+            },
+
+            None => return,
+        }
+
+        let (parent, leaf) = FsBackend::module_parent(&mut self.root, name);
+        let values_name = format!("{}{}", leaf, VALUES_SUFFIX);
+
+        parent.fs_entries
+            .retain(|x| x.name != leaf && x.name != values_name);
+
+        // The directory of the type goes with its last instance
+        match name.find(config::INSTANCE_SEPARATOR) {
+            Some(i) => self.root.fs_entries.retain(|x| {
+                x.name != name[..i] || !x.fs_entries.is_empty()
+            }),
+
+            None => (),
+        }
+
+        let module_path = format!("/{}", config::module_path(name));
+        let prefix = format!("{}/", module_path);
+
+        self.siblings.retain(|_, s| {
+            !s.path.starts_with(&prefix) && s.path != module_path
+        });
+
+        self.registered.remove(name);
+    }
+
+    /// Build the directory listing the snapshots of the tree
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn snapshot_entry(&self) -> FsEntry {
+        let mut entries = vec![
+            FsEntry::new(
+                self.inode_snapshot_take,
+                FileType::RegularFile,
+                ENTRY_SNAPSHOT_TAKE,
+                Mode::WriteOnly,
+                &Vec::new()),
+        ];
+
+        entries.extend(self.snapshots.iter().cloned());
+
+        return FsEntry::new(
+            self.inode_snapshot,
+            FileType::Directory,
+            ENTRY_SNAPSHOT,
+            Mode::ReadOnly,
+            &entries);
+    }
+
+    /// Get the current value of an entry, as frozen in a snapshot or
+    /// returned by a query
+    ///
+    /// Returns None for the entries without a single value (streams,
+    /// entries depending on the reader, ...).
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `entry` - The entry
+    /// * `module` - The module whose tree contains the entry, if any
+    fn current_value(
+        &self,
+        entry: &FsEntry,
+        module: Option<&Arc<Mutex<dyn module::Module>>>) -> Option<String> {
+
+        if entry.mode == Mode::WriteOnly || self.is_stream(entry.inode) {
+            return None;
+        }
+
+        self.refresh(entry.inode);
+
+        match self.own_value(entry.inode) {
+            Some(v) => return Some(v),
+            None => (),
+        }
+
+        match self.find_module(entry.inode) {
+            Some(m) => return match m.lock() {
+                Ok(m) => Some(m.value(entry.inode)),
+                Err(_) => None,
+            },
+
+            None => (),
+        }
+
+        // Custom entries (json, ...)
+        let module = match module {
+            Some(m) => match m.lock() {
+                Ok(m) => m,
+                Err(_) => return None,
+            },
+
+            None => return None,
+        };
+
+        return match entry.name.as_str() {
+            ENTRY_JSON => Some(module_json(&*module)),
+            ENTRY_SHELL => Some(module.shell()),
+            ENTRY_DEBUG_LAST_UPDATE => Some(debug::render(module.name())),
+            ENTRY_ATOMIC => Some(module_atomic(&*module)),
+            _ => None,
+        };
+    }
+
+    /// Copy an entry and its current values into a read-only entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `entry` - The entry to be copied
+    /// * `module` - The module whose tree contains the entry, if any
+    /// * `values` - The values of the copied entries, by inode
+    fn freeze(
+        &self,
+        entry: &FsEntry,
+        module: Option<&Arc<Mutex<dyn module::Module>>>,
+        values: &mut HashMap<u64, String>) -> Option<FsEntry> {
+
+        let inode = FsEntry::create_inode();
+
+        if entry.file_type == FileType::Directory {
+            // Directory of a module, possibly under the one of its type
+            let owner = match module {
+                Some(m) => Some(m.clone()),
+                None => self.find_module_by_entry(entry.inode),
+            };
+
+            let entries: Vec<FsEntry> = entry.fs_entries
+                .iter()
+                .filter_map(|e| self.freeze(e, owner.as_ref(), values))
+                .collect();
+
+            return Some(FsEntry::new(
+                inode,
+                FileType::Directory,
+                &entry.name,
+                Mode::ReadOnly,
+                &entries));
+        }
+
+        let value = self.current_value(entry, module)?;
+
+        values.insert(inode, value);
+
+        let mut frozen = FsEntry::new(
+            inode,
+            entry.file_type,
+            &entry.name,
+            Mode::ReadOnly,
+            &Vec::new());
+
+        frozen.unit = entry.unit;
+
+        return Some(frozen);
+    }
+
+    /// Freeze a copy of the whole tree under `.snapshot/<timestamp>`
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn take_snapshot(&mut self) {
+        let mut values = HashMap::new();
+        let mut entries = Vec::new();
+
+        for entry in self.root.fs_entries.iter() {
+            // The configuration doesn't change between snapshots
+            if entry.inode == self.inode_snapshot ||
+                entry.inode == self.config_entry.inode {
+
+                continue;
+            }
+
+            match self.freeze(entry, None, &mut values) {
+                Some(e) => entries.push(e),
+                None => (),
+            }
+        }
+
+        // Snapshots taken during the same second get a suffix
+        let timestamp = event_log::timestamp();
+        let mut name = timestamp.clone();
+        let mut index = 1;
+
+        while self.snapshots.iter().any(|s| s.name == name) {
+            index += 1;
+            name = format!("{}-{}", timestamp, index);
+        }
+
+        log::info!("Snapshot taken: {}", name);
+
+        self.snapshots.push(FsEntry::new(
+            FsEntry::create_inode(),
+            FileType::Directory,
+            &name,
+            Mode::ReadOnly,
+            &entries));
+
+        self.snapshot_values.extend(values);
+
+        // Drop the oldest snapshots and their values
+        while self.snapshots.len() > MAX_SNAPSHOTS {
+            let dropped = self.snapshots.remove(0);
+
+            self.snapshot_values.retain(|i, _| dropped.find(*i).is_none());
+        }
+
+        let snapshot = self.snapshot_entry();
+        let inode = self.inode_snapshot;
+
+        match self.root.fs_entries.iter_mut().find(|e| e.inode == inode) {
+
+            Some(e) => *e = snapshot,
+            None => self.root.fs_entries.push(snapshot),
+        }
+    }
+
+    /// Sort the modules so that each one is started after its dependencies
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn ordered_modules(&self) -> Vec<Arc<Mutex<dyn module::Module>>> {
+        let mut dependencies: HashMap<String, Vec<String>> = HashMap::new();
+        let mut names = Vec::new();
+
+        for m in self.modules.iter() {
+            let module = match m.lock() {
+                Ok(m) => m,
+                Err(_) => continue,
+            };
+
+            let name = module.name().to_string();
+
+            for dependency in module.dependencies().iter() {
+                let enabled = match self.config.modules.get(*dependency) {
+                    Some(c) => c.enabled == Some(true),
+                    None => false,
+                };
+
+                if !enabled && self.config.modules.contains_key(&name) {
+                    log::warn!(
+                        "{}: dependency `{}` is not enabled",
+                        name,
+                        dependency);
+                }
+            }
+
+            dependencies.insert(
+                name.clone(),
+                module.dependencies().iter().map(|d| d.to_string()).collect());
+
+            names.push(name);
+        }
+
+        let mut ordered = Vec::new();
+        let mut visiting = HashSet::new();
+
+        for name in names.iter() {
+            FsBackend::visit(name, &dependencies, &mut visiting, &mut ordered);
+        }
+
+        return ordered
+            .iter()
+            .filter_map(|name| self.find_module_by_name(name.to_string()))
+            .collect();
+    }
 
-        match module.start(&config) {
-            Ok(_) => (),
-            Err(e) => log::error!("Cannot start module: {}", e),
+    /// Add a module to the ordered list after its dependencies
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the module
+    /// * `dependencies` - The dependencies of every module
+    /// * `visiting` - The modules already visited
+    /// * `ordered` - The ordered list of modules
+    fn visit(
+        name: &str,
+        dependencies: &HashMap<String, Vec<String>>,
+        visiting: &mut HashSet<String>,
+        ordered: &mut Vec<String>) {
+
+        if !visiting.insert(name.to_string()) {
+            if !ordered.iter().any(|n| n == name) {
+                log::error!("Circular dependency on module `{}`", name);
+            }
+
+            return;
+        }
+
+        let list = match dependencies.get(name) {
+            Some(d) => d,
+            None => return,
+        };
+
+        for dependency in list.iter() {
+            FsBackend::visit(dependency, dependencies, visiting, ordered);
         }
+
+        ordered.push(name.to_string());
     }
 
-    /// Register modules into the filesystem
+    /// Get the caching duration configured for a path
     ///
     /// # Arguments
     ///
-    /// * `self` - The instance handle
-    pub fn register_modules(&mut self) {
-        self.root.fs_entries.clear();
+    /// * `config` - The caching configuration
+    /// * `path` - The path of the entry, empty for the top of the tree
+    fn configured_ttl(config: &Option<config::TtlConfig>, path: &str)
+        -> Option<Duration> {
+
+        let config = match config {
+            Some(c) => c,
+            None => return None,
+        };
+
+        if path.is_empty() {
+            return match config.default_ms {
+                Some(ms) => Some(Duration::from_millis(ms)),
+                None => None,
+            };
+        }
+
+        return match &config.entries {
+            Some(entries) => match entries.get(path) {
+                Some(ms) => Some(Duration::from_millis(*ms)),
+                None => None,
+            },
+
+            None => None,
+        };
+    }
+
+    /// Set the caching duration of the entries of a tree
+    ///
+    /// An entry without its own configuration inherits the duration of its
+    /// parent so that a whole subtree can be configured at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - Filesystem entry of the tree
+    /// * `path` - Path of the entry relatively to the top of the tree
+    /// * `config` - The caching configuration
+    /// * `default` - The caching duration of the parent
+    fn apply_ttl(
+        entry: &mut FsEntry,
+        path: &str,
+        config: &Option<config::TtlConfig>,
+        default: Duration) {
+
+        entry.ttl = match FsBackend::configured_ttl(config, path) {
+            Some(t) => t,
+            None => default,
+        };
+
+        for e in entry.fs_entries.iter_mut() {
+            let entry_path = match path.is_empty() {
+                true => e.name.clone(),
+                false => format!("{}/{}", path, e.name),
+            };
+
+            FsBackend::apply_ttl(e, &entry_path, config, entry.ttl);
+        }
+    }
+
+    /// Shorten the caching duration of the entries updated at high frequency
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - Filesystem entry of the tree
+    /// * `path` - Path of the entry relatively to the top of the tree
+    /// * `config` - The high-frequency configuration
+    fn apply_fast_ttl(
+        entry: &mut FsEntry,
+        path: &str,
+        config: &config::FastConfig) {
+
+        let interval = Duration::from_millis(
+            config.interval_ms.unwrap_or(module::DEFAULT_FAST_INTERVAL_MS));
+
+        if !path.is_empty() && config.contains(path) {
+            entry.ttl = cmp::min(entry.ttl, interval);
+        }
+
+        for e in entry.fs_entries.iter_mut() {
+            let entry_path = match path.is_empty() {
+                true => e.name.clone(),
+                false => format!("{}/{}", path, e.name),
+            };
+
+            FsBackend::apply_fast_ttl(e, &entry_path, config);
+        }
+    }
+
+    /// Add an entry next to each readable file of a tree
+    ///
+    /// A `.seq` entry holds the number of changes of the value of the file
+    /// and reading a `.wait` entry blocks until the value of the file changes
+    /// (a minute at most). A `.values` entry is added next to each directory instead, it holds
+    /// the values of the files under the directory.
+    ///
+    /// # Arguments
+    ///
+    /// * `entry` - Filesystem entry of the tree
+    /// * `path` - Path of the tree
+    /// * `kind` - Kind of the entries to be added
+    /// * `accept` - Filter of the files (given their path) to be considered
+    /// * `siblings` - Map of the added entries to the files they relate to
+    fn register_sibling_entries(
+        entry: &mut FsEntry,
+        path: &str,
+        kind: SiblingKind,
+        accept: &dyn Fn(&str, &FsEntry) -> bool,
+        siblings: &mut HashMap<u64, Sibling>) {
+
+        let mut sibling_entries: Vec<FsEntry> = Vec::new();
+
+        for e in entry.fs_entries.iter_mut() {
+            // Siblings of a previous kind don't get siblings themselves
+            if siblings.contains_key(&e.inode) {
+                continue;
+            }
+
+            let entry_path = format!("{}/{}", path, e.name);
+
+            match e.file_type {
+                FileType::Directory => {
+                    FsBackend::register_sibling_entries(
+                        e,
+                        &entry_path,
+                        kind,
+                        accept,
+                        siblings);
+
+                    if kind != SiblingKind::Values {
+                        continue;
+                    }
+
+                    let inode = FsEntry::create_inode();
+
+                    sibling_entries.push(FsEntry::new(
+                        inode,
+                        FileType::RegularFile,
+                        &format!("{}{}", e.name, kind.suffix()),
+                        Mode::ReadOnly,
+                        &Vec::new()));
+
+                    siblings.insert(inode, Sibling {
+                        kind: kind,
+                        inode: e.inode,
+                        path: entry_path,
+                    });
+                },
+
+                // The values of the files are read through their directory
+                FileType::RegularFile if kind == SiblingKind::Values => (),
+
+                FileType::RegularFile if e.mode != Mode::WriteOnly => {
+                    if !accept(&entry_path, e) {
+                        continue;
+                    }
+
+                    let inode = FsEntry::create_inode();
+
+                    let mut sibling = FsEntry::new(
+                        inode,
+                        FileType::RegularFile,
+                        &format!("{}{}", e.name, kind.suffix()),
+                        Mode::ReadOnly,
+                        &Vec::new());
+
+                    match kind {
+                        SiblingKind::Convert(u) => sibling.unit = Some(u),
+                        _ => (),
+                    }
 
-        for m in self.modules.iter_mut() {
-            FsBackend::register_module(&self.config, m.clone(), &mut self.root);
+                    sibling_entries.push(sibling);
+
+                    siblings.insert(inode, Sibling {
+                        kind: kind,
+                        inode: e.inode,
+                        path: entry_path,
+                    });
+                },
+
+                _ => (),
+            }
+        }
+
+        entry.fs_entries.append(&mut sibling_entries);
+    }
+
+    /// Add the sparklines of the entries and the bars of the percentages
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Gauges configuration of the module
+    /// * `entry` - Filesystem entry of the module
+    /// * `path` - Path of the module
+    /// * `siblings` - Map of the added entries to the files they relate to
+    fn register_gauge_entries(
+        config: &config::GaugeConfig,
+        entry: &mut FsEntry,
+        path: &str,
+        siblings: &mut HashMap<u64, Sibling>) {
+
+        let spark_width = cmp::min(
+            config.spark_width.unwrap_or(DEFAULT_SPARK_WIDTH),
+            gauge::MAX_WIDTH);
+
+        FsBackend::register_sibling_entries(
+            entry,
+            path,
+            SiblingKind::Spark(spark_width),
+            &|_, _| true,
+            siblings);
+
+        FsBackend::register_sibling_entries(
+            entry,
+            path,
+            SiblingKind::Bar(config.bar_width.unwrap_or(DEFAULT_BAR_WIDTH)),
+            &|_, e| e.unit == Some(units::Unit::Percent),
+            siblings);
+    }
+
+    /// Add the unit of the entries and their converted values
+    ///
+    /// A `.unit` entry holds the unit of the value of a file, and entries
+    /// named after the configured units (e.g. `used.gib`) hold the converted
+    /// values.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Units configuration of the module
+    /// * `entry` - Filesystem entry of the module
+    /// * `path` - Path of the module
+    /// * `siblings` - Map of the added entries to the files they relate to
+    fn register_unit_entries(
+        config: &config::UnitsConfig,
+        entry: &mut FsEntry,
+        path: &str,
+        siblings: &mut HashMap<u64, Sibling>) {
+
+        match config.enabled {
+            Some(true) => FsBackend::register_sibling_entries(
+                entry,
+                path,
+                SiblingKind::Unit,
+                &|_, e| e.unit.is_some(),
+                siblings),
+
+            _ => (),
+        }
+
+        let convert = match &config.convert {
+            Some(c) => c,
+            None => return,
+        };
+
+        let mut targets: Vec<&String> = convert.values().flatten().collect();
+
+        targets.sort();
+        targets.dedup();
+
+        for name in targets {
+            let to = match units::Unit::from_name(name) {
+                Some(u) => u,
+                None => {
+                    log::error!("Unknown unit: {}", name);
+                    continue;
+                },
+            };
+
+            let prefix = format!("{}/", path);
+
+            FsBackend::register_sibling_entries(
+                entry,
+                path,
+                SiblingKind::Convert(to),
+                &|entry_path, e| {
+                    let relative = entry_path.trim_start_matches(&prefix);
+
+                    let requested = match convert.get(relative) {
+                        Some(t) => t.contains(name),
+                        None => false,
+                    };
+
+                    let convertible = match e.unit {
+                        Some(u) => u.convert(0.0, to).is_some(),
+                        None => false,
+                    };
+
+                    requested && convertible
+                },
+                siblings);
         }
     }
 
@@ -364,6 +2141,14 @@ impl FsBackend {
         config: &config::ModuleConfig,
         entry: &mut FsEntry) {
 
+        // Values of all the entries, from the same update
+        entry.fs_entries.push(FsEntry::new(
+            FsEntry::create_inode(),
+            FileType::RegularFile,
+            ENTRY_ATOMIC,
+            Mode::ReadOnly,
+            &Vec::new()));
+
         // JSON
         match &config.json {
             Some(c) => {
@@ -375,6 +2160,13 @@ impl FsBackend {
                             ENTRY_JSON,
                             Mode::ReadOnly,
                             &Vec::new()));
+
+                        entry.fs_entries.push(FsEntry::new(
+                            FsEntry::create_inode(),
+                            FileType::RegularFile,
+                            ENTRY_JSON_DELTA,
+                            Mode::ReadOnly,
+                            &Vec::new()));
                     },
 
                     _ => (),
@@ -403,6 +2195,189 @@ impl FsBackend {
 
             None => (),
         }
+
+        // Debug
+        match &config.debug {
+            Some(c) if c.enabled == Some(true) => {
+                entry.fs_entries.push(FsEntry::new(
+                    FsEntry::create_inode(),
+                    FileType::Directory,
+                    ENTRY_DEBUG,
+                    Mode::ReadOnly,
+                    &vec![
+                        FsEntry::new(
+                            FsEntry::create_inode(),
+                            FileType::RegularFile,
+                            ENTRY_DEBUG_LAST_UPDATE,
+                            Mode::ReadOnly,
+                            &Vec::new()),
+                    ]));
+            },
+
+            _ => (),
+        }
+    }
+}
+
+/// Function used to convert a glob into a regex matching whole paths
+///
+/// # Arguments
+///
+/// * `glob` - The glob (e.g. `cpu/logical/*/usage_percent`)
+fn glob_regex(glob: &str) -> Option<Regex> {
+    let glob = glob.trim().trim_start_matches('/');
+
+    if glob.is_empty() {
+        return None;
+    }
+
+    let pattern = regex::escape(glob)
+        .replace(r"\*\*", ".*")
+        .replace(r"\*", "[^/]*")
+        .replace(r"\?", "[^/]");
+
+    return match Regex::new(&format!("^{}$", pattern)) {
+        Ok(r) => Some(r),
+        Err(_) => None,
+    };
+}
+
+/// Function used to get the JSON output of a module without its hidden
+/// entries
+///
+/// # Arguments
+///
+/// * `module` - The module
+fn module_json(module: &dyn module::Module) -> String {
+    return visibility::filter_json(module.name(), &module.json());
+}
+
+/// Function used to collect the readable entries of a tree
+///
+/// # Arguments
+///
+/// * `entries` - The entries of the tree
+/// * `parent` - The path of the parent of the entries
+/// * `output` - The paths and the inodes of the readable entries
+fn readable_entries(
+    entries: &Vec<FsEntry>,
+    parent: &str,
+    output: &mut Vec<(String, u64)>) {
+
+    for entry in entries.iter() {
+        let path = match parent.is_empty() {
+            true => entry.name.clone(),
+            false => format!("{}/{}", parent, entry.name),
+        };
+
+        match entry.file_type {
+            FileType::Directory =>
+                readable_entries(&entry.fs_entries, &path, output),
+
+            _ if entry.mode == Mode::WriteOnly => (),
+            _ => output.push((path, entry.inode)),
+        }
+    }
+}
+
+/// Function used to get the `path=value` lines of all the entries of a
+/// module, read under a single lock of its data
+///
+/// # Arguments
+///
+/// * `module` - The module
+fn module_atomic(module: &dyn module::Module) -> String {
+    let entries = visibility::filter_entries(
+        module.name(),
+        &module.fs_entries());
+
+    return format_values(module, &entries);
+}
+
+/// Function used to get the `path=value` lines of the readable entries of
+/// a tree, read under a single lock of the data of their module
+///
+/// # Arguments
+///
+/// * `module` - The module owning the entries
+/// * `entries` - The entries of the tree
+fn format_values(module: &dyn module::Module, entries: &Vec<FsEntry>)
+    -> String {
+
+    let mut readable = Vec::new();
+
+    readable_entries(entries, "", &mut readable);
+
+    let inodes: Vec<u64> = readable.iter().map(|(_, i)| *i).collect();
+
+    return readable
+        .iter()
+        .zip(module.values(&inodes).iter())
+        .map(|((p, _), v)| format!("{}={}\n", p, v.trim_end()))
+        .collect();
+}
+
+/// State of a file opened by a reader
+struct Handle {
+    /// Last JSON document returned to the reader
+    snapshot: Option<Value>,
+
+    /// Content returned by the current read sequence
+    content: String,
+
+    /// Glob written to the query entry
+    query: Option<String>,
+}
+
+impl Handle {
+    /// Handle constructor
+    fn new() -> Self {
+        Self {
+            snapshot: None,
+            content: "".to_string(),
+            query: None,
+        }
+    }
+
+    /// Compute the fields that have changed since the last read
+    ///
+    /// Removed fields are reported as `null`, the whole document is
+    /// returned on the first read.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `json` - The current JSON document
+    fn update_delta(&mut self, json: &str) {
+        let current: Value = match serde_json::from_str(json) {
+            Ok(v) => v,
+            Err(_) => Value::Null,
+        };
+
+        let delta = match (&self.snapshot, &current) {
+            (Some(Value::Object(old)), Value::Object(new)) => {
+                let mut delta = serde_json::Map::new();
+
+                for (key, value) in new.iter() {
+                    if old.get(key) != Some(value) {
+                        delta.insert(key.clone(), value.clone());
+                    }
+                }
+
+                for key in old.keys() {
+                    if !new.contains_key(key) {
+                        delta.insert(key.clone(), Value::Null);
+                    }
+                }
+
+                Value::Object(delta)
+            },
+
+            _ => current.clone(),
+        };
+
+        self.content = delta.to_string();
+        self.snapshot = Some(current);
     }
 }
 
@@ -410,6 +2385,9 @@ impl FsBackend {
 pub struct Fs {
     backend: Arc<Mutex<FsBackend>>,
     receiver: Arc<Mutex<Receiver<events::Events>>>,
+    handles: HashMap<u64, Handle>,
+    next_handle: u64,
+    last_query: Option<String>,
 }
 
 impl Fs {
@@ -417,11 +2395,18 @@ impl Fs {
     pub fn new(
         modules: &Vec<Arc<Mutex<dyn module::Module>>>,
         config: &config::Config,
+        config_file: &Path,
         event_manager: &mut event_manager::EventManager) -> Self {
 
         Self {
-            backend: Arc::new(Mutex::new(FsBackend::new(modules, config))),
+            backend: Arc::new(Mutex::new(FsBackend::new(
+                modules,
+                config,
+                config_file))),
             receiver: event_manager.receiver(),
+            handles: HashMap::new(),
+            next_handle: 1,
+            last_query: None,
         }
     }
 }
@@ -452,6 +2437,15 @@ impl Filesystem for Fs {
                 events::Events::ModuleUpdated(module) => {
                     backend.register_module_by_name(module);
                 },
+
+                events::Events::WriteEntry(path, value) => {
+                    match backend.write_entry(&path, &value) {
+                        Ok(_) => (),
+                        Err(e) => log::error!("{}", e),
+                    }
+                },
+
+                events::Events::TakeSnapshot => backend.take_snapshot(),
             }
         });
 
@@ -547,7 +2541,27 @@ impl Filesystem for Fs {
         };
 
         if entry.file_type == FileType::Directory {
-            reply.entry(&TTL, &entry.attrs(0), 0);
+            reply.entry(&entry.ttl, &entry.attrs(0), 0);
+            return;
+        }
+
+        backend.refresh(entry.inode);
+
+        match backend.own_value(entry.inode) {
+            Some(v) => {
+                let size = v.as_bytes().len() as u32;
+                reply.entry(&entry.ttl, &entry.attrs(size), 0);
+                return;
+            },
+
+            None => (),
+        }
+
+        // The content of a stream or of a query is unknown until it's read
+        if backend.is_stream(entry.inode)
+            || entry.inode == backend.inode_query {
+
+            reply.entry(&entry.ttl, &entry.attrs(0), 0);
             return;
         }
 
@@ -557,7 +2571,7 @@ impl Filesystem for Fs {
                 match m.lock() {
                     Ok(m) => {
                         let size = m.value(entry.inode).as_bytes().len() as u32;
-                        reply.entry(&TTL, &entry.attrs(size), 0);
+                        reply.entry(&entry.ttl, &entry.attrs(size), 0);
                         return;
                     },
 
@@ -568,24 +2582,36 @@ impl Filesystem for Fs {
             None => (),
         }
 
-        // It must be a custom entry (json, ...)
+        // It must be a custom entry (json, ...), possibly in a
+        // subdirectory of the module
+        let module_name = match backend.module_entries()
+            .into_iter()
+            .find(|(_, m)| m.find(parent).is_some()) {
+
+            Some((n, _)) => n,
+            None => parent_entry.name.clone(),
+        };
+
         for module in backend.modules.iter() {
             let module = match module.lock() {
                 Ok(m) => m,
                 Err(_) => continue,
             };
 
-            if module.name() != parent_entry.name {
+            if module.name() != module_name {
                 continue;
             }
 
             let size = match entry.name.as_str() {
-                ENTRY_JSON => module.json().as_bytes().len() as u32,
+                ENTRY_JSON => module_json(&*module).as_bytes().len() as u32,
                 ENTRY_SHELL => module.shell().as_bytes().len() as u32,
+                ENTRY_DEBUG_LAST_UPDATE =>
+                    debug::render(module.name()).as_bytes().len() as u32,
+                ENTRY_ATOMIC => module_atomic(&*module).as_bytes().len() as u32,
                 _ => 0,
             };
 
-            reply.entry(&TTL, &entry.attrs(size), 0);
+            reply.entry(&entry.ttl, &entry.attrs(size), 0);
 
             return;
         }
@@ -612,7 +2638,27 @@ impl Filesystem for Fs {
         };
 
         if entry.file_type == FileType::Directory {
-            reply.attr(&TTL, &entry.attrs(0));
+            reply.attr(&entry.ttl, &entry.attrs(0));
+            return;
+        }
+
+        backend.refresh(entry.inode);
+
+        match backend.own_value(entry.inode) {
+            Some(v) => {
+                let size = v.as_bytes().len() as u32;
+                reply.attr(&entry.ttl, &entry.attrs(size));
+                return;
+            },
+
+            None => (),
+        }
+
+        // The content of a stream or of a query is unknown until it's read
+        if backend.is_stream(entry.inode)
+            || entry.inode == backend.inode_query {
+
+            reply.attr(&entry.ttl, &entry.attrs(0));
             return;
         }
 
@@ -622,7 +2668,7 @@ impl Filesystem for Fs {
                 match m.lock() {
                     Ok(m) => {
                         let size = m.value(entry.inode).as_bytes().len() as u32;
-                        reply.attr(&TTL, &entry.attrs(size));
+                        reply.attr(&entry.ttl, &entry.attrs(size));
                         return;
                     },
 
@@ -634,7 +2680,7 @@ impl Filesystem for Fs {
         }
 
         // It must be a custom entry (json, ...)
-        for module_entry in backend.root.fs_entries.iter() {
+        for (name, module_entry) in backend.module_entries() {
             match module_entry.find(entry.inode) {
                 Some(_) => (),
                 None => continue,
@@ -646,17 +2692,21 @@ impl Filesystem for Fs {
                     Err(_) => continue,
                 };
 
-                if module.name() != module_entry.name {
+                if module.name() != name {
                     continue;
                 }
 
                 let size = match entry.name.as_str() {
-                    ENTRY_JSON => module.json().as_bytes().len() as u32,
+                    ENTRY_JSON => module_json(&*module).as_bytes().len() as u32,
                     ENTRY_SHELL => module.shell().as_bytes().len() as u32,
+                    ENTRY_DEBUG_LAST_UPDATE =>
+                        debug::render(module.name()).as_bytes().len() as u32,
+                    ENTRY_ATOMIC =>
+                        module_atomic(&*module).as_bytes().len() as u32,
                     _ => 0,
                 };
 
-                reply.attr(&TTL, &entry.attrs(size));
+                reply.attr(&entry.ttl, &entry.attrs(size));
 
                 return;
             }
@@ -669,9 +2719,9 @@ impl Filesystem for Fs {
 
     fn read(
         &mut self,
-        _req: &Request,
+        req: &Request,
         ino: u64,
-        _fh: u64,
+        fh: u64,
         offset: i64,
         size: u32,
         reply: ReplyData) {
@@ -699,7 +2749,125 @@ impl Filesystem for Fs {
                 return;
             },
 
-            _ => (),
+            _ => (),
+        }
+
+        // Only the first read of a sequence is counted
+        if offset == 0 {
+            match backend.root.find_path(ino) {
+                Some(p) => {
+                    consumers::record(req.uid(), req.pid(), &p);
+                    demand::touch(&p);
+                },
+
+                None => (),
+            }
+        }
+
+        // Wait for the next changes in another thread so that the other
+        // requests are not blocked
+        if entry.inode == backend.inode_events {
+            thread::spawn(move || {
+                match stream::next(fh, size as usize) {
+                    Some(data) => reply.data(&data),
+                    None => reply.data(&[]),
+                }
+            });
+
+            return;
+        }
+
+        // Wait for the value to change in another thread so that the other
+        // requests are not blocked
+        match backend.find_wait(entry.inode) {
+            Some(w) => {
+                if offset > 0 {
+                    reply.data(&[]);
+                    return;
+                }
+
+                let inode = w.inode;
+                let path = w.path.clone();
+                let generation = notifier::generation(&path);
+
+                let module = match backend.find_module(inode) {
+                    Some(m) => m.clone(),
+                    None => {
+                        reply.error(ENOENT);
+                        return;
+                    },
+                };
+
+                thread::spawn(move || {
+                    notifier::wait(&path, generation);
+
+                    match module.lock() {
+                        Ok(m) => reply.data(m.value(inode).as_bytes()),
+                        Err(_) => reply.error(ENOENT),
+                    }
+                });
+
+                return;
+            },
+
+            None => (),
+        }
+
+        // The content of a query is computed once per read sequence
+        if entry.inode == backend.inode_query {
+            let last_query = self.last_query.clone();
+
+            let handle = match self.handles.get_mut(&fh) {
+                Some(h) => h,
+                None => {
+                    reply.error(ENOENT);
+                    return;
+                },
+            };
+
+            if offset == 0 {
+                handle.content = match handle.query.as_ref().or(
+                    last_query.as_ref()) {
+
+                    Some(q) => backend.query(q),
+                    None => String::new(),
+                };
+            }
+
+            let bytes = handle.content.as_bytes();
+            let length = bytes.len() as u32;
+
+            if offset >= 0 && (offset as u32) < length {
+                let end = cmp::min(offset as u32 + size, length);
+                reply.data(&bytes[offset as usize..end as usize]);
+            }
+            else {
+                reply.data(&[]);
+            }
+
+            return;
+        }
+
+        backend.refresh(entry.inode);
+
+        match backend.own_value(entry.inode) {
+            Some(v) => {
+                let bytes = v.as_bytes();
+                let length = bytes.len() as u32;
+
+                // May be larger than a single read
+                if offset >= 0 && (offset as u32) < length {
+                    let end = cmp::min(offset as u32 + size, length);
+                    reply.data(&bytes[offset as usize..end as usize]);
+                }
+                else {
+                    reply.data(&[]);
+                }
+
+                return;
+            },
+
+            None => (),
         }
 
         // Try to find the module owning this entry
@@ -727,7 +2895,7 @@ impl Filesystem for Fs {
         }
 
         // It must be a custom entry (json, ...)
-        for module_entry in backend.root.fs_entries.iter() {
+        for (name, module_entry) in backend.module_entries() {
             match module_entry.find(entry.inode) {
                 Some(_) => (),
                 None => continue,
@@ -739,13 +2907,42 @@ impl Filesystem for Fs {
                     Err(_) => continue,
                 };
 
-                if module.name() != module_entry.name {
+                if module.name() != name {
                     continue;
                 }
 
                 let value = match entry.name.as_str() {
-                    ENTRY_JSON => module.json().to_string(),
+                    ENTRY_JSON => module_json(&*module).to_string(),
                     ENTRY_SHELL => module.shell().to_string(),
+                    ENTRY_DEBUG_LAST_UPDATE => debug::render(module.name()),
+                    ENTRY_ATOMIC => module_atomic(&*module),
+                    ENTRY_JSON_DELTA => {
+                        let handle = match self.handles.get_mut(&fh) {
+                            Some(h) => h,
+                            None => {
+                                reply.error(ENOENT);
+                                return;
+                            },
+                        };
+
+                        // The delta is computed once per read sequence
+                        if offset == 0 {
+                            handle.update_delta(&module_json(&*module));
+                        }
+
+                        let bytes = handle.content.as_bytes();
+                        let length = bytes.len() as u32;
+
+                        if offset >= 0 && (offset as u32) < length {
+                            let end = cmp::min(offset as u32 + size, length);
+                            reply.data(&bytes[offset as usize..end as usize]);
+                        }
+                        else {
+                            reply.data(&[]);
+                        }
+
+                        return;
+                    },
                     _ => {
                         reply.error(ENOENT);
                         return;
@@ -769,17 +2966,128 @@ impl Filesystem for Fs {
         reply.error(ENOENT);
     }
 
-    fn write(
+    fn open(
         &mut self,
         _req: &Request,
         ino: u64,
-        _fh: u64,
+        _flags: u32,
+        reply: ReplyOpen) {
+
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => {
+                reply.error(ENOENT);
+                return;
+            },
+        };
+
+        let entry = match backend.root.find(ino) {
+            Some(e) => e,
+            None => {
+                reply.error(ENOENT);
+                return;
+            },
+        };
+
+        if backend.find_wait(entry.inode).is_some() {
+            reply.opened(0, FOPEN_DIRECT_IO);
+            return;
+        }
+
+        // Each reader of the events gets its own queue
+        if entry.inode == backend.inode_events {
+            let fh = self.next_handle;
+
+            self.next_handle += 1;
+            self.handles.insert(fh, Handle::new());
+
+            stream::open(fh);
+
+            reply.opened(fh, FOPEN_DIRECT_IO);
+            return;
+        }
+
+        // Only the entries whose content depends on the reader need a handle
+        if entry.name != ENTRY_JSON_DELTA
+            && entry.inode != backend.inode_query {
+
+            reply.opened(0, 0);
+            return;
+        }
+
+        let fh = self.next_handle;
+
+        self.next_handle += 1;
+        self.handles.insert(fh, Handle::new());
+
+        reply.opened(fh, FOPEN_DIRECT_IO);
+    }
+
+    fn release(
+        &mut self,
+        _req: &Request,
+        _ino: u64,
+        fh: u64,
+        _flags: u32,
+        _lock_owner: u64,
+        _flush: bool,
+        reply: ReplyEmpty) {
+
+        self.handles.remove(&fh);
+
+        stream::close(fh);
+
+        reply.ok();
+    }
+
+    fn readlink(&mut self, _req: &Request, ino: u64, reply: ReplyData) {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => {
+                reply.error(ENOENT);
+                return;
+            },
+        };
+
+        // The target of a symbolic link is the value of the entry
+        match backend.snapshot_values.get(&ino) {
+            Some(v) => {
+                reply.data(v.as_bytes());
+                return;
+            },
+
+            None => (),
+        }
+
+        match backend.find_module(ino) {
+            Some(m) => {
+                match m.lock() {
+                    Ok(m) => {
+                        reply.data(m.value(ino).as_bytes());
+                        return;
+                    },
+
+                    Err(_) => (),
+                }
+            },
+
+            None => (),
+        }
+
+        reply.error(ENOENT);
+    }
+
+    fn write(
+        &mut self,
+        req: &Request,
+        ino: u64,
+        fh: u64,
         _offset: i64,
         data: &[u8],
         _flags: u32,
         reply: ReplyWrite) {
 
-        let backend = match self.backend.lock() {
+        let mut backend = match self.backend.lock() {
             Ok(b) => b,
             Err(_) => {
                 reply.error(ENOENT);
@@ -805,6 +3113,98 @@ impl Filesystem for Fs {
             _ => (),
         }
 
+        // The glob is used by the next reads of the handle, and of the
+        // other handles that have not written any
+        if entry.inode == backend.inode_query {
+            let query = String::from_utf8_lossy(data).trim().to_string();
+
+            if glob_regex(&query).is_none() {
+                reply.error(EINVAL);
+                return;
+            }
+
+            match self.handles.get_mut(&fh) {
+                Some(h) => h.query = Some(query.clone()),
+                None => (),
+            }
+
+            self.last_query = Some(query);
+
+            reply.written(data.len() as u32);
+            return;
+        }
+
+        if entry.inode == backend.inode_snapshot_take {
+            backend.take_snapshot();
+            reply.written(data.len() as u32);
+            return;
+        }
+
+        if backend.config_files.contains_key(&entry.inode) {
+            let inode = entry.inode;
+
+            match backend.write_config(inode, data) {
+                Ok(_) => reply.written(data.len() as u32),
+                Err(e) => {
+                    log::error!("{}", e);
+                    reply.error(EINVAL);
+                },
+            }
+
+            return;
+        }
+
+        if entry.inode == backend.inode_trace {
+            match trace::control(data) {
+                Ok(_) => reply.written(data.len() as u32),
+                Err(e) => {
+                    log::error!("{}", e);
+                    reply.error(e.errno());
+                },
+            }
+
+            return;
+        }
+
+        // Check the authorization in another thread as the user may need to
+        // authenticate
+        match backend.polkit_action(entry.inode) {
+            Some(action_id) => {
+                let module = match backend.find_module(entry.inode) {
+                    Some(m) => m.clone(),
+                    None => {
+                        reply.error(ENOENT);
+                        return;
+                    },
+                };
+
+                let inode = entry.inode;
+                let pid = req.pid();
+                let uid = req.uid();
+                let data = data.to_vec();
+
+                thread::spawn(move || {
+                    if !polkit::authorize(&action_id, pid, uid) {
+                        reply.error(EACCES);
+                        return;
+                    }
+
+                    match module.lock() {
+                        Ok(mut m) => {
+                            m.set_value(inode, &data);
+                            reply.written(data.len() as u32);
+                        },
+
+                        Err(_) => reply.error(ENOENT),
+                    }
+                });
+
+                return;
+            },
+
+            None => (),
+        }
+
         // Try to find the module owning this entry
         match backend.find_module(entry.inode) {
             Some(m) => {
@@ -844,6 +3244,80 @@ impl Filesystem for Fs {
     {
         self.getattr(req, ino, reply);
     }
+
+    fn getxattr(
+        &mut self,
+        _req: &Request,
+        ino: u64,
+        name: &OsStr,
+        size: u32,
+        reply: ReplyXattr) {
+
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => {
+                reply.error(ENOENT);
+                return;
+            },
+        };
+
+        let unit = match backend.unit(ino) {
+            Some(u) if name == OsStr::new(XATTR_UNIT) => u,
+            _ => {
+                reply.error(ENOATTR);
+                return;
+            },
+        };
+
+        reply_xattr(unit.symbol().as_bytes(), size, reply);
+    }
+
+    fn listxattr(
+        &mut self,
+        _req: &Request,
+        ino: u64,
+        size: u32,
+        reply: ReplyXattr) {
+
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => {
+                reply.error(ENOENT);
+                return;
+            },
+        };
+
+        // Names are separated by null characters
+        let names = match backend.unit(ino) {
+            Some(_) => format!("{}\0", XATTR_UNIT),
+            None => String::new(),
+        };
+
+        reply_xattr(names.as_bytes(), size, reply);
+    }
+}
+
+/// Reply to an extended attribute request
+///
+/// A request with a zero size asks for the size of the data.
+///
+/// # Arguments
+///
+/// * `data` - The data of the attribute
+/// * `size` - The size of the buffer of the caller
+/// * `reply` - The reply to be sent
+fn reply_xattr(data: &[u8], size: u32, reply: ReplyXattr) {
+    let length = data.len() as u32;
+
+    if size == 0 {
+        reply.size(length);
+    }
+    else if length > size {
+        reply.error(ERANGE);
+    }
+    else {
+        reply.data(data);
+    }
 }
 
 /// Frontend filesysem struture
@@ -858,6 +3332,19 @@ impl FsFrontend {
             fs: fs.clone(),
         }
     }
+
+    /// Lock the filesystem
+    ///
+    /// The wait for the lock is measured by its own span.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn lock(&self) -> Option<MutexGuard<'_, Fs>> {
+        let _span = tracing::trace_span!("fuse.lock").entered();
+
+        return self.fs.lock().ok();
+    }
 }
 
 impl Filesystem for FsFrontend {
@@ -878,9 +3365,11 @@ impl Filesystem for FsFrontend {
         offset: i64,
         reply: ReplyDirectory) {
 
-        let mut fs = match self.fs.lock() {
-            Ok(f) => f,
-            Err(_) => return,
+        let _span = tracing::trace_span!("fuse.readdir", ino).entered();
+
+        let mut fs = match self.lock() {
+            Some(f) => f,
+            None => return,
         };
 
         fs.readdir(req, ino, fh, offset, reply);
@@ -893,18 +3382,22 @@ impl Filesystem for FsFrontend {
         name: &OsStr,
         reply: ReplyEntry) {
 
-        let mut fs = match self.fs.lock() {
-            Ok(f) => f,
-            Err(_) => return,
+        let _span = tracing::trace_span!("fuse.lookup", parent).entered();
+
+        let mut fs = match self.lock() {
+            Some(f) => f,
+            None => return,
         };
 
         fs.lookup(req, parent, name, reply);
     }
 
     fn getattr(&mut self, req: &Request, ino: u64, reply: ReplyAttr) {
-        let mut fs = match self.fs.lock() {
-            Ok(f) => f,
-            Err(_) => return,
+        let _span = tracing::trace_span!("fuse.getattr", ino).entered();
+
+        let mut fs = match self.lock() {
+            Some(f) => f,
+            None => return,
         };
 
         fs.getattr(req, ino, reply);
@@ -919,14 +3412,58 @@ impl Filesystem for FsFrontend {
         size: u32,
         reply: ReplyData) {
 
-        let mut fs = match self.fs.lock() {
-            Ok(f) => f,
-            Err(_) => return,
+        let _span = tracing::trace_span!("fuse.read", ino).entered();
+
+        let mut fs = match self.lock() {
+            Some(f) => f,
+            None => return,
         };
 
         fs.read(req, ino, fh, offset, size, reply);
     }
 
+    fn open(&mut self, req: &Request, ino: u64, flags: u32, reply: ReplyOpen) {
+        let _span = tracing::trace_span!("fuse.open", ino).entered();
+
+        let mut fs = match self.lock() {
+            Some(f) => f,
+            None => return,
+        };
+
+        fs.open(req, ino, flags, reply);
+    }
+
+    fn release(
+        &mut self,
+        req: &Request,
+        ino: u64,
+        fh: u64,
+        flags: u32,
+        lock_owner: u64,
+        flush: bool,
+        reply: ReplyEmpty) {
+
+        let _span = tracing::trace_span!("fuse.release", ino).entered();
+
+        let mut fs = match self.lock() {
+            Some(f) => f,
+            None => return,
+        };
+
+        fs.release(req, ino, fh, flags, lock_owner, flush, reply);
+    }
+
+    fn readlink(&mut self, req: &Request, ino: u64, reply: ReplyData) {
+        let _span = tracing::trace_span!("fuse.readlink", ino).entered();
+
+        let mut fs = match self.lock() {
+            Some(f) => f,
+            None => return,
+        };
+
+        fs.readlink(req, ino, reply);
+    }
+
     fn write(
         &mut self,
         req: &Request,
@@ -937,9 +3474,11 @@ impl Filesystem for FsFrontend {
         flags: u32,
         reply: ReplyWrite) {
 
-        let mut fs = match self.fs.lock() {
-            Ok(f) => f,
-            Err(_) => return,
+        let _span = tracing::trace_span!("fuse.write", ino).entered();
+
+        let mut fs = match self.lock() {
+            Some(f) => f,
+            None => return,
         };
 
         fs.write(req, ino, fh, offset, data, flags, reply);
@@ -962,9 +3501,11 @@ impl Filesystem for FsFrontend {
         flags: Option<u32>,
         reply: ReplyAttr)
     {
-        let mut fs = match self.fs.lock() {
-            Ok(f) => f,
-            Err(_) => return,
+        let _span = tracing::trace_span!("fuse.setattr", ino).entered();
+
+        let mut fs = match self.lock() {
+            Some(f) => f,
+            None => return,
         };
 
         fs.setattr(
@@ -983,4 +3524,39 @@ impl Filesystem for FsFrontend {
             flags,
             reply);
     }
+
+    fn getxattr(
+        &mut self,
+        req: &Request,
+        ino: u64,
+        name: &OsStr,
+        size: u32,
+        reply: ReplyXattr) {
+
+        let _span = tracing::trace_span!("fuse.getxattr", ino).entered();
+
+        let mut fs = match self.lock() {
+            Some(f) => f,
+            None => return,
+        };
+
+        fs.getxattr(req, ino, name, size, reply);
+    }
+
+    fn listxattr(
+        &mut self,
+        req: &Request,
+        ino: u64,
+        size: u32,
+        reply: ReplyXattr) {
+
+        let _span = tracing::trace_span!("fuse.listxattr", ino).entered();
+
+        let mut fs = match self.lock() {
+            Some(f) => f,
+            None => return,
+        };
+
+        fs.listxattr(req, ino, size, reply);
+    }
 }
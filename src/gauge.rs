@@ -0,0 +1,210 @@
+use lazy_static::lazy_static;
+use std::collections::{HashMap, VecDeque};
+
+use crate::bus;
+use crate::config;
+use crate::sync::Mutex;
+
+/// Number of values kept for each entry, the widest possible sparkline
+pub const MAX_WIDTH: usize = 64;
+
+/// Blocks used to draw the sparklines, from the lowest to the highest value
+const SPARK_BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+const BAR_EMPTY: char = '░';
+const BAR_FULL: char = '█';
+
+const DEFAULT_COLOR_CRITICAL: &str = "#ff0000";
+const DEFAULT_COLOR_OK: &str = "#00ff00";
+const DEFAULT_COLOR_WARNING: &str = "#ffaa00";
+
+lazy_static! {
+    static ref RECENT: Mutex<HashMap<String, VecDeque<f64>>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Function used to keep the new value of an entry
+///
+/// Values that are not numbers are ignored.
+///
+/// # Arguments
+///
+/// * `path` - The path of the entry (e.g. `/memory/used`)
+/// * `value` - The new value
+pub fn record(path: &str, value: &str) {
+    let value = match value.trim().parse::<f64>() {
+        Ok(v) if v.is_finite() => v,
+        _ => return,
+    };
+
+    let mut recent = match RECENT.lock() {
+        Ok(r) => r,
+        Err(_) => return,
+    };
+
+    let values = recent.entry(path.to_string()).or_insert(VecDeque::new());
+
+    values.push_back(value);
+
+    while values.len() > MAX_WIDTH {
+        values.pop_front();
+    }
+}
+
+/// Function used to render the last values of an entry as a sparkline
+///
+/// The blocks are scaled between the lowest and the highest of the values.
+///
+/// # Arguments
+///
+/// * `path` - The path of the entry (e.g. `/memory/used`)
+/// * `width` - The number of values rendered
+pub fn sparkline(path: &str, width: usize) -> String {
+    let recent = match RECENT.lock() {
+        Ok(r) => r,
+        Err(_) => return String::new(),
+    };
+
+    let values: Vec<f64> = match recent.get(path) {
+        Some(v) => v
+            .iter()
+            .skip(v.len().saturating_sub(width))
+            .cloned()
+            .collect(),
+
+        None => return String::new(),
+    };
+
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+
+    let last = (SPARK_BLOCKS.len() - 1) as f64;
+
+    return values
+        .iter()
+        .map(|v| match max > min {
+            true => SPARK_BLOCKS[((v - min) / (max - min) * last).round()
+                as usize],
+
+            false => SPARK_BLOCKS[0],
+        })
+        .collect();
+}
+
+/// Function used to render a percentage as a bar
+///
+/// Returns an empty string if the value is not a number.
+///
+/// # Arguments
+///
+/// * `value` - The percentage
+/// * `width` - The number of characters of the bar
+pub fn bar(value: &str, width: usize) -> String {
+    let percent = match value.trim().parse::<f64>() {
+        Ok(v) if v.is_finite() => v.max(0.0).min(100.0),
+        _ => return String::new(),
+    };
+
+    let full = (percent / 100.0 * width as f64).round() as usize;
+
+    return std::iter::repeat(BAR_FULL)
+        .take(full)
+        .chain(std::iter::repeat(BAR_EMPTY).take(width - full))
+        .collect();
+}
+
+/// Function used to get the color matching the value of an entry
+///
+/// Returns an empty string if the value is not a number or if the entry has
+/// no thresholds.
+///
+/// # Arguments
+///
+/// * `config` - The colors configuration of the module
+/// * `entry` - The path of the entry in the module (e.g. `percent`)
+/// * `value` - The value
+pub fn color(config: &config::ColorsConfig, entry: &str, value: &str)
+    -> String {
+
+    let thresholds = match config.thresholds.get(entry) {
+        Some(t) => t,
+        None => return String::new(),
+    };
+
+    let value = match value.trim().parse::<f64>() {
+        Ok(v) if v.is_finite() => v,
+        _ => return String::new(),
+    };
+
+    // The lower values are the worse ones
+    let reached = |threshold: f64| {
+        return match thresholds.critical < thresholds.warning {
+            true => value <= threshold,
+            false => value >= threshold,
+        };
+    };
+
+    let (color, default) = match (
+        reached(thresholds.critical),
+        reached(thresholds.warning)) {
+
+        (true, _) => (&config.critical, DEFAULT_COLOR_CRITICAL),
+        (false, true) => (&config.warning, DEFAULT_COLOR_WARNING),
+        (false, false) => (&config.ok, DEFAULT_COLOR_OK),
+    };
+
+    return match color {
+        Some(c) => c.clone(),
+        None => default.to_string(),
+    };
+}
+
+/// Function used to get the icon matching the value of an entry
+///
+/// Returns an empty string if no icon matches.
+///
+/// # Arguments
+///
+/// * `icons` - The icons of the entry, the first matching one is used
+/// * `module` - The name of the module of the entry
+/// * `value` - The value
+pub fn icon(icons: &Vec<config::IconConfig>, module: &str, value: &str)
+    -> String {
+
+    let number = value.trim().parse::<f64>().ok();
+
+    let matches = |icon: &config::IconConfig| {
+        match &icon.value {
+            Some(v) if v != value.trim() => return false,
+            _ => (),
+        }
+
+        match (icon.min, number) {
+            (Some(min), Some(n)) if n < min => return false,
+            (Some(_), None) => return false,
+            _ => (),
+        }
+
+        match (icon.max, number) {
+            (Some(max), Some(n)) if n > max => return false,
+            (Some(_), None) => return false,
+            _ => (),
+        }
+
+        return match &icon.when {
+            Some(when) => when.iter().all(|(entry, expected)| {
+                match bus::value(&format!("{}/{}", module, entry)) {
+                    Some(v) => v.trim() == expected,
+                    None => false,
+                }
+            }),
+
+            None => true,
+        };
+    };
+
+    return match icons.iter().find(|i| matches(i)) {
+        Some(i) => i.icon.clone(),
+        None => String::new(),
+    };
+}
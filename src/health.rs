@@ -0,0 +1,95 @@
+use lazy_static::lazy_static;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::time::Instant;
+
+use crate::event_log;
+use crate::sync::Mutex;
+
+const STATUS_DEGRADED: &str = "degraded";
+const STATUS_FAILED: &str = "failed";
+const STATUS_OK: &str = "ok";
+const STATUS_STARTING: &str = "starting";
+const STATUS_STOPPED: &str = "stopped";
+
+/// Result of the last update of a module
+struct Update {
+    error: Option<String>,
+    time: Instant,
+}
+
+lazy_static! {
+    static ref UPDATES: Mutex<HashMap<String, Update>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Function used to record the result of the update of a module
+///
+/// # Arguments
+///
+/// * `module` - The name of the module
+/// * `error` - The error of the update, if any
+pub fn record(module: &str, error: Option<String>) {
+    match UPDATES.lock() {
+        Ok(mut u) => {
+            u.insert(module.to_string(), Update {
+                error: error,
+                time: Instant::now(),
+            });
+        },
+
+        Err(_) => log::error!("Cannot lock health"),
+    }
+}
+
+/// Function used to render the health of the daemon in JSON
+///
+/// A module is failed if it's stopped or if its last update failed, and is
+/// starting until its first update. The daemon is ok if no module is
+/// failed, failed if all of them are and degraded otherwise.
+///
+/// # Arguments
+///
+/// * `modules` - The enabled modules and whether they are running
+pub fn render(modules: &Vec<(String, bool)>) -> String {
+    let updates = match UPDATES.lock() {
+        Ok(u) => u,
+        Err(_) => return json!({"status": STATUS_FAILED}).to_string(),
+    };
+
+    let mut details = Map::new();
+    let mut failed = 0;
+
+    for (name, running) in modules.iter() {
+        let update = updates.get(name);
+
+        let status = match (running, update) {
+            (false, _) => STATUS_STOPPED,
+            (true, None) => STATUS_STARTING,
+            (true, Some(u)) if u.error.is_some() => STATUS_FAILED,
+            (true, Some(_)) => STATUS_OK,
+        };
+
+        if status == STATUS_STOPPED || status == STATUS_FAILED {
+            failed += 1;
+        }
+
+        details.insert(name.clone(), json!({
+            "status": status,
+            "error": update.and_then(|u| u.error.clone()),
+            "last_update_s": update.map(|u| u.time.elapsed().as_secs()),
+        }));
+    }
+
+    let status = match failed {
+        0 => STATUS_OK,
+        f if f == modules.len() => STATUS_FAILED,
+        _ => STATUS_DEGRADED,
+    };
+
+    return json!({
+        "status": status,
+        "timestamp": event_log::timestamp(),
+        "modules": Value::Object(details),
+    }).to_string();
+}
@@ -0,0 +1,153 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::process;
+
+use crate::config;
+use crate::error;
+use crate::sync::Mutex;
+
+/// Program taking the logind inhibitor locks
+const INHIBITOR: &str = "systemd-inhibit";
+
+/// Command run while a lock is held, it exits when its input is closed
+const HOLDER: &str = "cat";
+
+const DEFAULT_MODE: &str = "block";
+const DEFAULT_WHY: &str = "Requested by cerebro";
+const WHO: &str = "cerebro";
+
+const VALUE_OFF: &str = "off";
+const VALUE_ON: &str = "on";
+
+lazy_static! {
+    static ref INHIBITORS: Mutex<HashMap<String, config::InhibitorConfig>> =
+        Mutex::new(HashMap::new());
+
+    static ref LOCKS: Mutex<HashMap<String, process::Child>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Function used to set the inhibitor locks that can be taken
+pub fn init(config: &HashMap<String, config::InhibitorConfig>) {
+    match INHIBITORS.lock() {
+        Ok(mut i) => *i = config.clone(),
+        Err(_) => log::error!("Cannot lock inhibitors"),
+    }
+}
+
+/// Function used to get the names of the locks held, sorted
+#[cfg_attr(not(feature = "dbus"), allow(dead_code))]
+pub fn held() -> Vec<String> {
+    let mut names: Vec<String> = match LOCKS.lock() {
+        Ok(l) => l.keys().cloned().collect(),
+        Err(_) => Vec::new(),
+    };
+
+    names.sort();
+
+    return names;
+}
+
+/// Function used to take an inhibitor lock
+///
+/// The lock is held by a systemd-inhibit process, it is released when the
+/// input of the process is closed, including when cerebro exits.
+///
+/// # Arguments
+///
+/// * `name` - The name of the inhibitor
+pub fn take(name: &str) -> error::Return {
+    let inhibitor = match INHIBITORS.lock() {
+        Ok(i) => match i.get(name) {
+            Some(c) => c.clone(),
+            None => return error!(
+                Config,
+                &format!("Unknown inhibitor `{}`", name)),
+        },
+
+        Err(_) => return error!(Lock, "Cannot lock inhibitors"),
+    };
+
+    let mut locks = match LOCKS.lock() {
+        Ok(l) => l,
+        Err(_) => return error!(Lock, "Cannot lock inhibitor locks"),
+    };
+
+    if locks.contains_key(name) {
+        return success!();
+    }
+
+    let why = match &inhibitor.why {
+        Some(w) => w.as_str(),
+        None => DEFAULT_WHY,
+    };
+
+    let mode = match &inhibitor.mode {
+        Some(m) => m.as_str(),
+        None => DEFAULT_MODE,
+    };
+
+    let child = match process::Command::new(INHIBITOR)
+        .arg(format!("--what={}", inhibitor.what))
+        .arg(format!("--who={}", WHO))
+        .arg(format!("--why={}", why))
+        .arg(format!("--mode={}", mode))
+        .arg(HOLDER)
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::null())
+        .spawn() {
+
+        Ok(c) => c,
+        Err(e) =>
+            return error!(&format!("Cannot execute {}: {:?}", INHIBITOR, e)),
+    };
+
+    log::debug!("Inhibitor `{}` taken", name);
+
+    locks.insert(name.to_string(), child);
+
+    return success!();
+}
+
+/// Function used to release an inhibitor lock
+///
+/// # Arguments
+///
+/// * `name` - The name of the inhibitor
+pub fn release(name: &str) -> error::Return {
+    let mut child = match LOCKS.lock() {
+        Ok(mut l) => match l.remove(name) {
+            Some(c) => c,
+            None => return success!(),
+        },
+
+        Err(_) => return error!(Lock, "Cannot lock inhibitor locks"),
+    };
+
+    // Closing the input stops the holder and releases the lock
+    drop(child.stdin.take());
+
+    match child.wait() {
+        Ok(_) => (),
+        Err(e) => log::error!("Cannot wait for {}: {:?}", INHIBITOR, e),
+    }
+
+    log::debug!("Inhibitor `{}` released", name);
+
+    return success!();
+}
+
+/// Function used to run the inhibit action of a trigger
+///
+/// # Arguments
+///
+/// * `args` - The name of the inhibitor followed by `on` or `off`
+pub fn action(args: &str) -> error::Return {
+    let words: Vec<&str> = args.split_whitespace().collect();
+
+    return match words[..] {
+        [name, VALUE_ON] => take(name),
+        [name, VALUE_OFF] => release(name),
+        _ => error!("Inhibit action must be `@inhibit <name> on|off`"),
+    };
+}
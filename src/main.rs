@@ -1,77 +1,173 @@
 #[macro_use]
 mod error;
 
+mod automations;
+mod bus;
+mod capabilities;
+mod cli;
 mod config;
+mod consumers;
+mod dbus;
+mod debug;
+mod demand;
+mod event_log;
 mod event_manager;
 mod events;
+mod expression;
 mod filesystem;
+mod gauge;
+mod health;
+mod inhibit;
+mod metrics;
+mod mirror;
 mod modules;
+mod mountpoint;
+mod net;
+mod notifications;
+mod notifier;
+mod outbox;
+mod platform;
+mod policy;
+mod polkit;
+mod privileged;
+mod publish;
+mod rrd;
+mod secrets;
+mod schedules;
+mod selftest;
+mod sounds;
+mod stream;
+mod sync;
+mod syslog;
+mod system;
+mod trace;
 mod triggers;
+mod units;
+mod visibility;
 
-use clap;
-use dirs;
+use clap::Parser;
 use env_logger;
 use fuse;
 use log4rs::append::file::FileAppender;
 use log4rs::config::{Appender, Config, Root};
 use std::ffi::OsStr;
 use std::fs;
+use std::path::PathBuf;
+use std::process;
 use std::sync::Arc;
-use std::sync::Mutex;
+use std::time::Duration;
 
-use modules::cpu;
-use modules::battery;
-use modules::brightness;
-use modules::memory;
-use modules::Module;
-use modules::trash;
+use modules::registry;
+
+use crate::sync::Mutex;
 
 fn main() {
     // Command line interface
-    let mut mountpoint: String = "/tmp/cerebro".to_string();
-    let mut log_file: Option<String> = None;
-
-    let app = clap::App::new("NixOS setup")
-        .version("1.0.0")
-        .author("Mathieu H. <mhardy2008@gmail.com>")
-        .about("Monitor system information")
-        .arg(clap::Arg::with_name("mountpoint")
-            .short("m")
-            .long("mountpoint")
-            .help("Path where the filesystem will be mounted")
-            .required(false)
-            .takes_value(true))
-        .arg(clap::Arg::with_name("logfile")
-            .short("l")
-            .long("logfile")
-            .help("Path of a file where the logs should be printed")
-            .required(false)
-            .takes_value(true));
-
-    let matches = app.get_matches();
-
-    for arg in matches.args.iter() {
-        match arg.0 {
-            &"mountpoint" => {
-                match matches.value_of(arg.0) {
-                    Some(s) => mountpoint = s.to_string(),
-                    None => (),
-                }
-            },
+    let cli = cli::Cli::parse();
+    let run = cli.run;
+    let mountpoint = run.mountpoint();
+
+    // Commands that don't need any setup
+    match &cli.command {
+        Some(cli::Command::Completions { shell }) => {
+            cli::print_completions(*shell);
+            return;
+        },
 
-            &"logfile" => {
-                match matches.value_of(arg.0) {
-                    Some(s) => log_file = Some(s.to_string()),
-                    None => (),
-                }
-            },
+        Some(cli::Command::Man) => {
+            match cli::print_man_page() {
+                Ok(_) => (),
+                Err(e) => {
+                    eprintln!("Cannot generate man page: {}", e);
+                    process::exit(e.exit_code());
+                },
+            }
 
-            _ => (),
-        }
+            return;
+        },
+
+        Some(cli::Command::Examples { dir }) => {
+            match cli::install_examples(dir) {
+                Ok(_) => (),
+                Err(e) => {
+                    eprintln!("Cannot install examples: {}", e);
+                    process::exit(e.exit_code());
+                },
+            }
+
+            return;
+        },
+
+        Some(cli::Command::Helper) => {
+            privileged::serve();
+            return;
+        },
+
+        Some(cli::Command::Snapshot) => {
+            match cli::snapshot(&mountpoint) {
+                Ok(_) => (),
+                Err(e) => {
+                    eprintln!("Cannot take snapshot: {}", e);
+                    process::exit(e.exit_code());
+                },
+            }
+
+            return;
+        },
+
+        Some(cli::Command::Get { path }) => {
+            match cli::get(&mountpoint, path) {
+                Ok(_) => (),
+                Err(e) => {
+                    eprintln!("Cannot get value: {}", e);
+                    process::exit(e.exit_code());
+                },
+            }
+
+            return;
+        },
+
+        Some(cli::Command::Set { path, value }) => {
+            match cli::set(&mountpoint, path, value) {
+                Ok(_) => (),
+                Err(e) => {
+                    eprintln!("Cannot set value: {}", e);
+                    process::exit(e.exit_code());
+                },
+            }
+
+            return;
+        },
+
+        Some(cli::Command::Secret {
+            command: cli::SecretCommand::Set { name } }) => {
+
+            match cli::set_secret(name) {
+                Ok(_) => (),
+                Err(e) => {
+                    eprintln!("Cannot set secret: {}", e);
+                    process::exit(e.exit_code());
+                },
+            }
+
+            return;
+        },
+
+        _ => (),
+    }
+
+    // Capabilities of the host, printed instead of the logs
+    if run.print_capabilities {
+        let mut event_manager = event_manager::EventManager::new();
+        let modules = registry::create_all(&mut event_manager, &Vec::new());
+
+        println!("{:#}", capabilities::render(&modules));
+
+        return;
     }
 
     // Configure logs
-    match log_file {
+    match run.logfile {
         Some(l) => {
             let f = FileAppender::builder().build(l).unwrap();
 
@@ -94,92 +190,321 @@ fn main() {
     }
 
     // Load configuration
-    let home_dir = match dirs::home_dir() {
-        Some(path) => path,
-        None => {
-            log::error!("Cannot get home directory");
-            return;
-        }
+    let config_file = match &run.config {
+        Some(c) => PathBuf::from(c),
+        None => match config::find_file() {
+            Some(f) => f,
+            None => {
+                let e = error::CerebroError::Config(
+                    "Cannot find configuration file".to_string());
+
+                log::error!("{}", e);
+                process::exit(e.exit_code());
+            },
+        },
     };
 
-    let config_dir = home_dir.join(".config").join("cerebro");
-    let config_file = config_dir.join("config.json");
+    log::info!("Configuration file is: {}", config_file.display());
 
-    let config = match config::load(config_file) {
+    // Triggers are next to the configuration unless specified
+    let triggers_dir = match &run.triggers_dir {
+        Some(t) => PathBuf::from(t),
+        None => match config_file.parent() {
+            Some(p) => p.to_path_buf(),
+            None => PathBuf::new(),
+        },
+    };
+
+    let mut config = match config::load(&config_file, run.profile.as_deref()) {
         Ok(c) => c,
         Err(e) => {
             log::error!("Error loading configuration: {}", e);
-            return;
+            process::exit(e.exit_code());
         }
     };
 
-    log::info!("{:#?}", config);
+    if run.minimal {
+        config.restrict_to_minimal();
+    }
+
+    if run.system {
+        config.restrict_to_system();
+    }
+
+    log::info!("{:#}", config.redacted());
 
     // Load triggers
-    let triggers = match triggers::load(config_dir) {
+    let triggers = match triggers::load(triggers_dir) {
         Ok(t) => t,
         Err(e) => {
             log::error!("Error loading triggers: {}", e);
-            return;
+            process::exit(e.exit_code());
         },
     };
 
     log::info!("{:#?}", triggers);
 
+    // Load automations
+    let automations = match automations::load(&config) {
+        Ok(a) => a,
+        Err(e) => {
+            log::error!("Error loading automations: {}", e);
+            process::exit(e.exit_code());
+        },
+    };
+
+    // Load schedules
+    let schedules = match schedules::load(&config) {
+        Ok(s) => s,
+        Err(e) => {
+            log::error!("Error loading schedules: {}", e);
+            process::exit(e.exit_code());
+        },
+    };
+
+    // Commands working on the configuration
+    match &cli.command {
+        Some(cli::Command::ValidateConfig) => {
+            println!("Configuration is valid");
+            return;
+        },
+
+        Some(cli::Command::Export { path, since, format, resolution }) => {
+            match cli::export(&config, path, since, *format, resolution) {
+                Ok(_) => (),
+                Err(e) => {
+                    eprintln!("Cannot export values: {}", e);
+                    process::exit(e.exit_code());
+                },
+            }
+
+            return;
+        },
+
+        Some(cli::Command::TestTrigger { path, old_value, new_value }) => {
+            let (module, name) = match cli::split_path(path) {
+                Ok(p) => p,
+                Err(e) => {
+                    eprintln!("Invalid path: {}", e);
+                    process::exit(e.exit_code());
+                },
+            };
+
+            triggers::find_all_and_execute(
+                &triggers,
+                triggers::Kind::Update,
+                &module,
+                &name,
+                old_value,
+                new_value);
+
+            return;
+        },
+
+        _ => (),
+    }
+
+    // The self-test doesn't mount the filesystem
+    let selftest = match &cli.command {
+        Some(cli::Command::Selftest { timeout_s }) => Some(*timeout_s),
+        _ => None,
+    };
+
+    // Check that FUSE is usable before starting anything
+    if cfg!(target_os = "linux") && selftest.is_none() {
+        let fuse_device = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open("/dev/fuse");
+
+        match fuse_device {
+            Ok(_) => (),
+            Err(e) => {
+                log::error!(
+                    "Cannot open /dev/fuse ({}): in a container, it must be \
+                    started with `--device /dev/fuse --cap-add SYS_ADMIN`",
+                    e);
+
+                let e = error::CerebroError::fuse(
+                    "Cannot open /dev/fuse",
+                    e.raw_os_error().unwrap_or(libc::ENODEV));
+
+                process::exit(e.exit_code());
+            },
+        }
+    }
+
+    // Check the mountpoint before starting anything
+    log::info!("Mountpoint is: {}", &mountpoint);
+
+    if selftest.is_none() {
+        match mountpoint::prepare(
+            &mountpoint,
+            run.force,
+            run.force_remount) {
+
+            Ok(_) => (),
+            Err(e) => {
+                log::error!("Invalid mountpoint: {}", e);
+                process::exit(e.exit_code());
+            },
+        }
+    }
+
+    // Filters of the values
+    publish::init(&config);
+
+    // Hidden entries
+    visibility::init(&config);
+
+    // Polling of the modules nobody uses
+    demand::init(&config, &triggers);
+
+    // Privileged helper
+    match &config.helper {
+        Some(c) => privileged::init(c),
+        None => (),
+    }
+
+    // Instrumentation
+    match &config.trace {
+        Some(c) => trace::init(c),
+        None => (),
+    }
+
+    // Event log
+    match &config.event_log {
+        Some(c) => event_log::init(c),
+        None => (),
+    }
+
+    // Mirror of the values
+    match &config.mirror {
+        Some(c) => mirror::init(c),
+        None => (),
+    }
+
+    // Queue of the events of unreachable sinks
+    match &config.outbox {
+        Some(c) => outbox::init(c),
+        None => (),
+    }
+
+    // Forwarding of the triggers
+    match &config.syslog {
+        Some(c) => syslog::init(c),
+        None => (),
+    }
+
+    // Templates of the notifications
+    match &config.notifications {
+        Some(c) => notifications::init(c),
+        None => (),
+    }
+
+    // Sounds played by the triggers
+    match &config.sounds {
+        Some(c) => sounds::init(c),
+        None => (),
+    }
+
+    // Inhibitor locks taken by the triggers
+    match &config.inhibitors {
+        Some(c) => inhibit::init(c),
+        None => (),
+    }
+
+    // Policy of the network requests
+    match &config.net {
+        Some(c) => net::init(c),
+        None => (),
+    }
+
     // Event manager
     let mut event_manager = event_manager::EventManager::new();
 
-    // List of modules
-    let mut modules: Vec<Arc<Mutex<dyn Module>>> = Vec::new();
+    // Triggers write entries through the filesystem
+    triggers::set_event_sender(event_manager.sender());
 
-    modules.push(Arc::new(Mutex::new(cpu::Cpu::new(
+    // List of modules
+    let modules = registry::create_configured(
+        &config,
         &mut event_manager,
-        &triggers))));
+        &triggers);
 
-    modules.push(Arc::new(Mutex::new(battery::Battery::new(
-        &mut event_manager,
-        &triggers))));
+    capabilities::banner(&modules);
 
-    modules.push(Arc::new(Mutex::new(brightness::Brightness::new(
-        &mut event_manager,
-        &triggers))));
+    // Check the modules instead of mounting the filesystem
+    match selftest {
+        Some(t) => {
+            match selftest::run(&modules, &config, Duration::from_secs(t)) {
+                Ok(_) => (),
+                Err(e) => {
+                    eprintln!("Self-test failed: {}", e);
+                    process::exit(e.exit_code());
+                },
+            }
 
-    modules.push(Arc::new(Mutex::new(memory::Memory::new(
-        &mut event_manager,
-        &triggers))));
+            return;
+        },
 
-    modules.push(Arc::new(Mutex::new(trash::Trash::new(
-        &mut event_manager,
-        &triggers))));
+        None => (),
+    }
+
+    // State machines driven by the values
+    automations::start(automations);
+
+    // Commands run at given times
+    schedules::start(schedules);
 
     // Create filesystem
     let fs = Arc::new(Mutex::new(filesystem::Fs::new(
         &modules,
         &config,
+        &config_file,
         &mut event_manager)));
 
     let fs_frontend = filesystem::FsFrontend::new(&fs);
 
-    log::info!("Mountpoint is: {}", &mountpoint);
+    // Every user can access the filesystem of the system service
+    let mount_options = match run.system {
+        true => "fsname=cerebro,allow_other,default_permissions",
+        false => "fsname=cerebro",
+    };
 
-    match fs::create_dir_all(&mountpoint) {
-        Ok(_) => (),
-        Err(_) => {
-            log::error!("Cannot create mountpoint");
-            return;
-        },
-    }
+    let options = ["-o", mount_options];
 
-    let options = ["-o", "fsname=cerebro"]
+    let options = options
         .iter()
         .map(|o| o.as_ref())
         .collect::<Vec<&OsStr>>();
 
+    // The privileged sources have been opened by the modules
+    match &config.system {
+        Some(c) if run.system => match &c.user {
+            Some(u) => match system::drop_privileges(u, &mountpoint) {
+                Ok(_) => log::info!("Running as user: {}", u),
+                Err(e) => {
+                    log::error!("Cannot drop privileges: {}", e);
+                    process::exit(e.exit_code());
+                },
+            },
+
+            None => (),
+        },
+
+        _ => (),
+    }
+
     match fuse::mount(fs_frontend, mountpoint, &options) {
         Ok(_) => (),
-        Err(_) => {
-            log::error!("Cannot mount filesystem");
-            return;
+        Err(e) => {
+            let e = error::CerebroError::fuse(
+                &format!("Cannot mount filesystem: {}", e),
+                e.raw_os_error().unwrap_or(libc::EIO));
+
+            log::error!("{}", e);
+            process::exit(e.exit_code());
         },
     }
 }
@@ -0,0 +1,87 @@
+use lazy_static::lazy_static;
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+pub const TRIGGER_EVALUATIONS: &str = "cerebro_trigger_evaluations_total";
+pub const TRIGGER_EXECUTIONS: &str = "cerebro_trigger_executions_total";
+pub const TRIGGER_FAILURES: &str = "cerebro_trigger_failures_total";
+pub const MODULE_UPDATES: &str = "cerebro_module_updates_total";
+pub const MODULE_UPDATE_ERRORS: &str = "cerebro_module_update_errors_total";
+pub const MODULE_UPDATE_SECONDS: &str = "cerebro_module_update_seconds_total";
+pub const MODULE_PANICS: &str = "cerebro_module_panics_total";
+pub const POISONED_LOCKS: &str = "cerebro_poisoned_locks_total";
+pub const OUTBOX_QUEUED: &str = "cerebro_outbox_queued_total";
+pub const OUTBOX_REPLAYED: &str = "cerebro_outbox_replayed_total";
+pub const OUTBOX_DROPPED: &str = "cerebro_outbox_dropped_total";
+
+/// Description of the metrics (name, help)
+const DESCRIPTIONS: [(&str, &str); 11] = [
+    (TRIGGER_EVALUATIONS, "Number of times a trigger condition was evaluated"),
+    (TRIGGER_EXECUTIONS, "Number of times a trigger command was executed"),
+    (TRIGGER_FAILURES, "Number of trigger commands that failed"),
+    (MODULE_UPDATES, "Number of updates of a module"),
+    (MODULE_UPDATE_ERRORS, "Number of updates of a module that failed"),
+    (MODULE_UPDATE_SECONDS, "Time spent updating a module"),
+    (MODULE_PANICS, "Number of updates of a module that panicked"),
+    (POISONED_LOCKS, "Number of locks recovered after a panic"),
+    (OUTBOX_QUEUED, "Number of events queued while a sink was unreachable"),
+    (OUTBOX_REPLAYED, "Number of queued events sent to a sink"),
+    (OUTBOX_DROPPED, "Number of events lost while a sink was unreachable"),
+];
+
+// A standard mutex is used as the recovery of the poisoned locks is counted
+lazy_static! {
+    static ref COUNTERS: Mutex<BTreeMap<(String, String), f64>> =
+        Mutex::new(BTreeMap::new());
+}
+
+/// Function used to escape a label value
+fn escape(value: &str) -> String {
+    return value
+        .replace("\\", "\\\\")
+        .replace("\"", "\\\"")
+        .replace("\n", "\\n");
+}
+
+/// Function used to add a value to a counter
+pub fn add(name: &str, labels: &[(&str, &str)], value: f64) {
+    let labels = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, escape(v)))
+        .collect::<Vec<String>>()
+        .join(",");
+
+    match COUNTERS.lock() {
+        Ok(mut c) => {
+            *c.entry((name.to_string(), labels)).or_insert(0.0) += value;
+        },
+
+        Err(_) => log::error!("Cannot lock metrics"),
+    }
+}
+
+/// Function used to increment a counter
+pub fn increment(name: &str, labels: &[(&str, &str)]) {
+    add(name, labels, 1.0);
+}
+
+/// Function used to render the counters in the Prometheus text format
+pub fn render() -> String {
+    let counters = match COUNTERS.lock() {
+        Ok(c) => c,
+        Err(_) => return "".to_string(),
+    };
+
+    let mut output = String::new();
+
+    for (name, help) in DESCRIPTIONS.iter() {
+        output += &format!("# HELP {} {}\n", name, help);
+        output += &format!("# TYPE {} counter\n", name);
+
+        for ((_, labels), value) in counters.iter().filter(|c| c.0.0 == *name) {
+            output += &format!("{}{{{}}} {}\n", name, labels, value);
+        }
+    }
+
+    return output;
+}
@@ -0,0 +1,165 @@
+use dirs;
+use lazy_static::lazy_static;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config;
+use crate::error;
+use crate::sync::Mutex;
+use crate::triggers;
+
+/// Name of the default directory, under the runtime directory of the user
+const DEFAULT_DIR: &str = "cerebro-mirror";
+
+/// The structure used to store the state of the mirror
+struct Mirror {
+    path: PathBuf,
+    entries: Option<Vec<String>>,
+}
+
+lazy_static! {
+    static ref MIRROR: Mutex<Option<Mirror>> = Mutex::new(None);
+}
+
+impl Mirror {
+    /// Check if an entry must be mirrored
+    ///
+    /// An entry is mirrored if it or one of its parents is configured.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `path` - The path of the entry (e.g. `memory/used`)
+    fn is_mirrored(&self, path: &str) -> bool {
+        let entries = match &self.entries {
+            Some(e) => e,
+            None => return true,
+        };
+
+        return entries.iter().any(|e| {
+            let e = e.trim_matches('/');
+            path == e || path.starts_with(&format!("{}/", e))
+        });
+    }
+
+    /// Write the value of an entry
+    ///
+    /// The value is written to a temporary file then renamed so that a
+    /// reader never sees a partial value.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `path` - The path of the entry
+    /// * `value` - The value of the entry
+    fn write(&self, path: &str, value: &str) -> error::Return {
+        let file = self.path.join(path);
+
+        let (dir, name) = match (file.parent(), file.file_name()) {
+            (Some(d), Some(n)) => (d, n.to_string_lossy().to_string()),
+            _ => return error!("Invalid mirror path"),
+        };
+
+        match fs::create_dir_all(dir) {
+            Ok(_) => (),
+            Err(_) => return error!("Cannot create mirror directory"),
+        }
+
+        let tmp = dir.join(format!(".{}.tmp", name));
+
+        match fs::write(&tmp, value) {
+            Ok(_) => (),
+            Err(_) => return error!("Cannot write mirror file"),
+        }
+
+        return match fs::rename(&tmp, &file) {
+            Ok(_) => success!(),
+            Err(_) => error!("Cannot write mirror file"),
+        };
+    }
+
+    /// Remove the file of an entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `path` - The path of the entry
+    fn remove(&self, path: &str) {
+        match fs::remove_file(self.path.join(path)) {
+            Ok(_) => (),
+            Err(_) => (),
+        }
+    }
+}
+
+/// Function used to enable the mirror of the values into plain files
+pub fn init(config: &config::MirrorConfig) {
+    match config.enabled {
+        Some(true) => (),
+        _ => return,
+    }
+
+    let path = match &config.path {
+        Some(p) => PathBuf::from(p),
+        None => match dirs::runtime_dir() {
+            Some(d) => d.join(DEFAULT_DIR),
+            None => {
+                log::error!("No runtime directory, set the mirror path");
+                return;
+            },
+        },
+    };
+
+    match fs::create_dir_all(&path) {
+        Ok(_) => (),
+        Err(_) => {
+            log::error!("Cannot create mirror directory: {:?}", path);
+            return;
+        },
+    }
+
+    let mirror = Mirror {
+        path: path,
+        entries: config.entries.clone(),
+    };
+
+    match MIRROR.lock() {
+        Ok(mut m) => *m = Some(mirror),
+        Err(_) => log::error!("Cannot lock mirror"),
+    }
+}
+
+/// Function used to mirror the new value of an entry (if enabled)
+///
+/// # Arguments
+///
+/// * `kind` - The kind of change
+/// * `module` - The name of the module
+/// * `entry` - The path of the entry in the module
+/// * `value` - The new value
+pub fn write(kind: triggers::Kind, module: &str, entry: &str, value: &str) {
+    let mirror = match MIRROR.lock() {
+        Ok(m) => m,
+        Err(_) => return,
+    };
+
+    let mirror = match &*mirror {
+        Some(m) => m,
+        None => return,
+    };
+
+    let path = format!("{}/{}", module, entry);
+
+    if !mirror.is_mirrored(&path) {
+        return;
+    }
+
+    match kind {
+        triggers::Kind::Delete => mirror.remove(&path),
+
+        _ => match mirror.write(&path, value) {
+            Ok(_) => (),
+            Err(e) => log::error!("{}", e),
+        },
+    }
+}
@@ -0,0 +1,344 @@
+use fuse;
+use serde::{Serialize};
+use std::fs;
+use std::path;
+use std::sync::Arc;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::publish;
+use crate::platform;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const VALUE_UNKNOWN: &str = "?";
+
+const ENTRY_LUX: &str = "lux";
+
+/// Information about the ambient light
+#[derive(Serialize)]
+struct AlsData
+{
+    pub lux: String,
+}
+
+impl AlsData {
+    /// AlsData constructor
+    pub fn new() -> Self {
+        Self {
+            lux: VALUE_UNKNOWN.to_string(),
+        }
+    }
+}
+
+/// Ambient light sensor backend that will compute the values
+struct AlsBackend {
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+
+    pub data: AlsData,
+}
+
+impl AlsBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            data: AlsData::new(),
+        }
+    }
+
+    /// Read a floating point attribute of an iio device
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the attribute
+    fn read_attribute(path: &path::Path) -> Option<f64> {
+        return match fs::read_to_string(path) {
+            Ok(v) => v.trim().parse::<f64>().ok(),
+            Err(_) => None,
+        };
+    }
+
+    /// Read the illuminance of the first iio device providing one
+    ///
+    /// A processed `in_illuminance*_input` attribute is used when available,
+    /// otherwise the raw value is converted using its scale and offset.
+    fn illuminance() -> Option<f64> {
+        let root = match platform::current().sysfs("bus/iio/devices") {
+            Some(r) => r,
+            None => return None,
+        };
+
+        let devices = match fs::read_dir(&root) {
+            Ok(d) => d,
+            Err(_) => return None,
+        };
+
+        let mut devices: Vec<path::PathBuf> = devices
+            .filter_map(|d| d.ok())
+            .map(|d| d.path())
+            .collect();
+
+        devices.sort();
+
+        for device in devices {
+            let attributes = match fs::read_dir(&device) {
+                Ok(a) => a,
+                Err(_) => continue,
+            };
+
+            let mut attributes: Vec<String> = attributes
+                .filter_map(|a| a.ok())
+                .filter_map(|a| a.file_name().into_string().ok())
+                .filter(|a| a.starts_with("in_illuminance"))
+                .collect();
+
+            attributes.sort();
+
+            // Processed value
+            match attributes.iter().find(|a| a.ends_with("_input")) {
+                Some(a) => return AlsBackend::read_attribute(&device.join(a)),
+                None => (),
+            }
+
+            // Raw value
+            let raw = match attributes.iter().find(|a| a.ends_with("_raw")) {
+                Some(a) => a,
+                None => continue,
+            };
+
+            let prefix = &raw[..raw.len() - "_raw".len()];
+
+            let value = match AlsBackend::read_attribute(&device.join(raw)) {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let scale = AlsBackend::read_attribute(
+                &device.join(format!("{}_scale", prefix)))
+                .or(AlsBackend::read_attribute(
+                    &device.join("in_illuminance_scale")))
+                .unwrap_or(1.0);
+
+            let offset = AlsBackend::read_attribute(
+                &device.join(format!("{}_offset", prefix)))
+                .or(AlsBackend::read_attribute(
+                    &device.join("in_illuminance_offset")))
+                .unwrap_or(0.0);
+
+            return Some((value + offset) * scale);
+        }
+
+        return None;
+    }
+}
+
+impl module::Data for AlsBackend {
+    /// Update ambient light data
+    ///
+    /// The iio sysfs attributes do not emit inotify events, so the sensor is
+    /// sampled at each update and triggers only fire when the value changes.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let kind = match self.first_update {
+            true => triggers::Kind::Create,
+            false => triggers::Kind::Update,
+        };
+
+        let lux = match AlsBackend::illuminance() {
+            Some(l) => format!("{}", l.round() as i64),
+            None => VALUE_UNKNOWN.to_string(),
+        };
+
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_LUX,
+            &mut self.data.lux,
+            &lux);
+
+        self.first_update = false;
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// Ambient light sensor module structure
+pub struct Als {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    inode_lux: u64,
+    backend: Arc<Mutex<AlsBackend>>,
+    fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl Als {
+    /// Als constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        let lux = filesystem::FsEntry::create_inode();
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            inode_lux: lux,
+            backend: Arc::new(Mutex::new(AlsBackend::new(name, triggers))),
+            fs_entries: vec![
+                filesystem::FsEntry::new(
+                    lux,
+                    fuse::FileType::RegularFile,
+                    ENTRY_LUX,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+                ],
+        }
+    }
+}
+
+impl module::Module for Als {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Check if the system has an ambient light sensor
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn probe(&self) -> Option<bool> {
+        return Some(AlsBackend::illuminance().is_some());
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        return self.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        if inode == self.inode_lux {
+            match self.backend.lock() {
+                Ok(b) => return b.data.lux.clone(),
+                Err(_) => return VALUE_UNKNOWN.to_string(),
+            }
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return format!("lux={}", backend.data.lux).to_string();
+    }
+}
@@ -0,0 +1,692 @@
+use fuse;
+use serde::{Serialize};
+use std::process;
+use std::sync::Arc;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "audio";
+
+const BACKEND_ALSA: &str = "alsa";
+const BACKEND_PULSEAUDIO: &str = "pulseaudio";
+
+/// Control of the ALSA mixer exposed as a sink
+const ALSA_CONTROL: &str = "Master";
+
+const VALUE_FALSE: &str = "false";
+const VALUE_TRUE: &str = "true";
+const VALUE_UNKNOWN: &str = "?";
+
+const ENTRY_DEFAULT: &str = "default";
+const ENTRY_MUTED: &str = "muted";
+const ENTRY_VOLUME_PERCENT: &str = "volume_percent";
+
+const ENTRIES: [&str; 3] = [
+    ENTRY_DEFAULT,
+    ENTRY_MUTED,
+    ENTRY_VOLUME_PERCENT,
+];
+
+/// Information about an audio sink
+#[derive(Clone, Serialize)]
+struct SinkData
+{
+    pub sink: String,
+    pub default: String,
+    pub muted: String,
+    pub volume_percent: String,
+}
+
+impl SinkData {
+    /// Get the value of an entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `name` - The name of the entry
+    fn value(&self, name: &str) -> String {
+        return match name {
+            ENTRY_DEFAULT => self.default.clone(),
+            ENTRY_MUTED => self.muted.clone(),
+            ENTRY_VOLUME_PERCENT => self.volume_percent.clone(),
+            _ => VALUE_UNKNOWN.to_string(),
+        };
+    }
+}
+
+/// Function used to execute a command and get its output
+///
+/// # Arguments
+///
+/// * `program` - The program to be executed
+/// * `args` - The arguments of the program
+fn execute(program: &str, args: &[&str])
+    -> Result<String, error::CerebroError> {
+
+    let output = match process::Command::new(program).args(args).output() {
+        Ok(o) => o,
+        Err(_) => return error!(&format!("Cannot execute {}", program)),
+    };
+
+    if !output.status.success() {
+        return error!(&format!("{} is not successful", program));
+    }
+
+    return Ok(String::from_utf8_lossy(&output.stdout).to_string());
+}
+
+/// Function used to get the first percentage of a text (e.g. `[45%]`)
+///
+/// # Arguments
+///
+/// * `text` - The text to be parsed
+fn first_percent(text: &str) -> Option<String> {
+    return text
+        .split(|c: char| !c.is_ascii_digit() && c != '%')
+        .find(|w| w.len() > 1 && w.ends_with('%'))
+        .map(|w| w.trim_end_matches('%').to_string());
+}
+
+/// Sound server used to read and control the sinks
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mixer {
+    Alsa,
+    PulseAudio,
+}
+
+impl Mixer {
+    /// Find the sound server to be used
+    ///
+    /// PulseAudio (or PipeWire through its PulseAudio server) is preferred
+    /// when it's running.
+    ///
+    /// # Arguments
+    ///
+    /// * `backend` - The backend forced in the configuration (if any)
+    fn find(backend: Option<&str>) -> Result<Mixer, error::CerebroError> {
+        return match backend {
+            Some(BACKEND_ALSA) => Ok(Mixer::Alsa),
+            Some(BACKEND_PULSEAUDIO) => Ok(Mixer::PulseAudio),
+            Some(_) => error!("Unknown audio backend"),
+            None => match execute("pactl", &["info"]) {
+                Ok(_) => Ok(Mixer::PulseAudio),
+                Err(_) => Ok(Mixer::Alsa),
+            },
+        };
+    }
+
+    /// Get the list of sinks
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn sinks(&self) -> Result<Vec<SinkData>, error::CerebroError> {
+        return match self {
+            Mixer::Alsa => Mixer::alsa_sinks(),
+            Mixer::PulseAudio => Mixer::pulseaudio_sinks(),
+        };
+    }
+
+    /// Get the sinks of PulseAudio
+    fn pulseaudio_sinks() -> Result<Vec<SinkData>, error::CerebroError> {
+        let default = execute("pactl", &["get-default-sink"])?;
+        let default = default.trim();
+
+        let output = execute("pactl", &["list", "sinks"])?;
+
+        let mut sinks: Vec<SinkData> = Vec::new();
+
+        for line in output.lines() {
+            let line = line.trim();
+
+            // Each sink starts with its name, the other fields follow
+            if line.starts_with("Name:") {
+                let name = line.trim_start_matches("Name:").trim();
+
+                sinks.push(SinkData {
+                    sink: name.to_string(),
+                    default: match name == default {
+                        true => VALUE_TRUE.to_string(),
+                        false => VALUE_FALSE.to_string(),
+                    },
+                    muted: VALUE_UNKNOWN.to_string(),
+                    volume_percent: VALUE_UNKNOWN.to_string(),
+                });
+
+                continue;
+            }
+
+            let sink = match sinks.last_mut() {
+                Some(s) => s,
+                None => continue,
+            };
+
+            if line.starts_with("Mute:") {
+                sink.muted = match line.trim_start_matches("Mute:").trim() {
+                    "yes" => VALUE_TRUE.to_string(),
+                    "no" => VALUE_FALSE.to_string(),
+                    _ => VALUE_UNKNOWN.to_string(),
+                };
+            }
+            else if line.starts_with("Volume:") {
+                // The volume of the first channel is used
+                sink.volume_percent = match first_percent(line) {
+                    Some(p) => p,
+                    None => VALUE_UNKNOWN.to_string(),
+                };
+            }
+        }
+
+        sinks.sort_by(|a, b| a.sink.cmp(&b.sink));
+
+        return Ok(sinks);
+    }
+
+    /// Get the master control of ALSA as a sink
+    fn alsa_sinks() -> Result<Vec<SinkData>, error::CerebroError> {
+        let output = execute("amixer", &["sget", ALSA_CONTROL])?;
+
+        let muted = match (output.contains("[off]"), output.contains("[on]")) {
+            (true, _) => VALUE_TRUE.to_string(),
+            (false, true) => VALUE_FALSE.to_string(),
+            _ => VALUE_UNKNOWN.to_string(),
+        };
+
+        let volume_percent = match first_percent(&output) {
+            Some(p) => p,
+            None => VALUE_UNKNOWN.to_string(),
+        };
+
+        return Ok(vec![
+            SinkData {
+                sink: ALSA_CONTROL.to_string(),
+                default: VALUE_TRUE.to_string(),
+                muted: muted,
+                volume_percent: volume_percent,
+            },
+        ]);
+    }
+
+    /// Set the volume of a sink
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `sink` - The name of the sink
+    /// * `percent` - The new volume
+    fn set_volume(&self, sink: &str, percent: u64) -> error::Return {
+        let volume = format!("{}%", percent);
+
+        match self {
+            Mixer::Alsa => execute("amixer", &["sset", sink, &volume])?,
+            Mixer::PulseAudio => execute(
+                "pactl",
+                &["set-sink-volume", sink, &volume])?,
+        };
+
+        return success!();
+    }
+
+    /// Mute, unmute or toggle the mute of a sink
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `sink` - The name of the sink
+    /// * `muted` - The new state, None to toggle it
+    fn set_muted(&self, sink: &str, muted: Option<bool>) -> error::Return {
+        match self {
+            Mixer::Alsa => execute("amixer", &["sset", sink, match muted {
+                Some(true) => "mute",
+                Some(false) => "unmute",
+                None => "toggle",
+            }])?,
+
+            Mixer::PulseAudio => execute("pactl", &["set-sink-mute", sink,
+                match muted {
+                    Some(true) => "1",
+                    Some(false) => "0",
+                    None => "toggle",
+                }])?,
+        };
+
+        return success!();
+    }
+}
+
+/// Audio backend that will compute the values
+struct AudioBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    mixer: Option<Mixer>,
+
+    pub data: Vec<SinkData>,
+    pub fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl AudioBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            mixer: None,
+            data: Vec::new(),
+            fs_entries: Vec::new(),
+        }
+    }
+
+    /// Get the sound server, found at the first use
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn mixer(&mut self) -> Result<Mixer, error::CerebroError> {
+        match self.mixer {
+            Some(m) => return Ok(m),
+            None => (),
+        }
+
+        let mixer = Mixer::find(self.config.backend.as_deref())?;
+
+        log::info!("{}: using {:?}", MODULE_NAME, mixer);
+
+        self.mixer = Some(mixer);
+
+        return Ok(mixer);
+    }
+
+    /// Rebuild the sinks data and filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `sinks` - The new list of sinks
+    fn rebuild_filesystem(&mut self, sinks: Vec<SinkData>) {
+        // Deletion triggers
+        for data in self.data.iter() {
+            for name in ENTRIES.iter() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Delete,
+                    &self.name,
+                    &format!("{}/{}", data.sink, name),
+                    "",
+                    "");
+            }
+        }
+
+        self.data = sinks;
+        self.fs_entries.clear();
+
+        for data in self.data.iter() {
+            let entries: Vec<filesystem::FsEntry> = ENTRIES
+                .iter()
+                .map(|name| filesystem::FsEntry::new(
+                    filesystem::FsEntry::create_inode(),
+                    fuse::FileType::RegularFile,
+                    name,
+                    match *name {
+                        ENTRY_DEFAULT => filesystem::Mode::ReadOnly,
+                        _ => filesystem::Mode::ReadWrite,
+                    },
+                    &Vec::new()))
+                .collect();
+
+            self.fs_entries.push(filesystem::FsEntry::new(
+                filesystem::FsEntry::create_inode(),
+                fuse::FileType::Directory,
+                &data.sink,
+                filesystem::Mode::ReadOnly,
+                &entries));
+
+            // Creation triggers
+            for name in ENTRIES.iter() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Create,
+                    &self.name,
+                    &format!("{}/{}", data.sink, name),
+                    "",
+                    &data.value(name));
+            }
+        }
+    }
+
+    /// Find the sink and the name of an entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the entry
+    fn find_entry(&self, inode: u64) -> Option<(String, String)> {
+        for sink_entry in self.fs_entries.iter() {
+            match sink_entry.fs_entries.iter().find(|x| x.inode == inode) {
+                Some(e) => return Some((
+                    sink_entry.name.clone(),
+                    e.name.clone())),
+
+                None => (),
+            }
+        }
+
+        return None;
+    }
+
+    /// Control a sink by writing one of its entries
+    ///
+    /// The volume is given in percent, the mute state is `true`, `false`
+    /// or `toggle`.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the entry written
+    /// * `data` - The data written
+    fn set_value(&mut self, inode: u64, data: &[u8]) -> error::Return {
+        let (sink, name) = match self.find_entry(inode) {
+            Some(e) => e,
+            None => return error!("Unknown audio entry"),
+        };
+
+        let value = String::from_utf8_lossy(data).trim().to_string();
+
+        let mixer = self.mixer()?;
+
+        match name.as_str() {
+            ENTRY_VOLUME_PERCENT => match value.parse::<u64>() {
+                Ok(p) if p <= 100 => mixer.set_volume(&sink, p)?,
+                _ => return error!(&format!(
+                    "Invalid value for {}/{}/{}",
+                    MODULE_NAME,
+                    sink,
+                    name)),
+            },
+
+            ENTRY_MUTED => match value.as_str() {
+                "1" | VALUE_TRUE => mixer.set_muted(&sink, Some(true))?,
+                "0" | VALUE_FALSE => mixer.set_muted(&sink, Some(false))?,
+                "toggle" => mixer.set_muted(&sink, None)?,
+                _ => return error!(&format!(
+                    "Invalid value for {}/{}/{}",
+                    MODULE_NAME,
+                    sink,
+                    name)),
+            },
+
+            _ => return error!("Entry is not writable"),
+        }
+
+        // Read the new values without waiting for the next update, a new
+        // list of sinks is left to the next update
+        let sinks = mixer.sinks()?;
+
+        if !self.is_changed(&sinks) {
+            self.update_values(sinks);
+        }
+
+        return success!();
+    }
+
+    /// Check if the list of sinks has changed
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `sinks` - The new list of sinks
+    fn is_changed(&self, sinks: &Vec<SinkData>) -> bool {
+        return sinks.len() != self.data.len() ||
+            sinks
+                .iter()
+                .zip(self.data.iter())
+                .any(|(a, b)| a.sink != b.sink);
+    }
+
+    /// Update the values of the sinks and call the triggers
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `sinks` - The new values of the sinks, in the same order
+    fn update_values(&mut self, sinks: Vec<SinkData>) {
+        for (data, sink) in self.data.iter_mut().zip(sinks) {
+            for name in ENTRIES.iter() {
+                let old_value = data.value(name);
+                let new_value = sink.value(name);
+
+                if old_value == new_value {
+                    continue;
+                }
+
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Update,
+                    &self.name,
+                    &format!("{}/{}", data.sink, name),
+                    &old_value,
+                    &new_value);
+            }
+
+            *data = sink;
+        }
+    }
+}
+
+impl module::Data for AudioBackend {
+    /// Update audio data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let sinks = self.mixer()?.sinks()?;
+
+        if self.is_changed(&sinks) {
+            self.rebuild_filesystem(sinks);
+
+            return Ok(module::Status::Changed(MODULE_NAME.to_string()));
+        }
+
+        self.update_values(sinks);
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// Audio module structure
+pub struct Audio {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    backend: Arc<Mutex<AudioBackend>>,
+}
+
+impl Audio {
+    /// Audio constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            backend: Arc::new(Mutex::new(AudioBackend::new(name, triggers))),
+        }
+    }
+}
+
+impl module::Module for Audio {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => {
+                b.config = config.clone();
+                b.mixer = None;
+            },
+
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        return backend.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let (sink, name) = match backend.find_entry(inode) {
+            Some(e) => e,
+            None => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match backend.data.iter().find(|x| x.sink == sink) {
+            Some(d) => d.value(&name),
+            None => VALUE_UNKNOWN.to_string(),
+        };
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, inode: u64, data: &[u8]) {
+        let mut backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => {
+                log::error!("Cannot lock backend");
+                return;
+            },
+        };
+
+        match backend.set_value(inode, data) {
+            Ok(_) => (),
+            Err(e) => log::error!("{}", e),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        // Only the default sink is exported
+        return match backend.data
+            .iter().find(|x| x.default == VALUE_TRUE) {
+
+            Some(s) => format!(
+                "sink={} volume_percent={} muted={}",
+                s.sink,
+                s.volume_percent,
+                s.muted),
+
+            None => "sink=".to_string(),
+        };
+    }
+}
@@ -1,25 +1,65 @@
 use fuse;
 use serde::{Serialize};
-use std::sync::{Arc, Mutex};
+use serde_json::Value;
+use std::fs;
+use std::path;
+use std::sync::Arc;
 use systemstat::Platform;
 
 use crate::config;
+use crate::dbus;
 use crate::error;
 use crate::event_manager;
 use crate::filesystem;
+use crate::modules::cooling;
 use crate::modules::module;
+use crate::notifications;
+use crate::platform;
+use crate::publish;
+use crate::sync::Mutex;
 use crate::triggers;
+use crate::units;
 
 const MODULE_NAME: &str = "battery";
 
+const POWER_SUPPLY: &str = "class/power_supply";
+
+const BACKEND_UPOWER: &str = "upower";
+
+const UPOWER_SERVICE: &str = "org.freedesktop.UPower";
+const UPOWER_PATH: &str = "/org/freedesktop/UPower";
+const UPOWER_INTERFACE: &str = "org.freedesktop.UPower";
+const UPOWER_DEVICE_INTERFACE: &str = "org.freedesktop.UPower.Device";
+const UPOWER_DISPLAY_DEVICE: &str =
+    "/org/freedesktop/UPower/devices/DisplayDevice";
+const UPOWER_TYPE_LINE_POWER: u64 = 1;
+
+const MICRO_PER_UNIT: f64 = 1_000_000.0;
+
+/// Percentage under which the battery is low
+const DEFAULT_LOW_PERCENT: u64 = 15;
+
 const VALUE_FALSE: &str = "false";
 const VALUE_TRUE: &str = "true";
 const VALUE_UNKNOWN: &str = "?";
 
+const ENTRY_CAPACITY_HEALTH_PERCENT: &str = "capacity_health_percent";
+const ENTRY_DEVICES: &str = "devices";
+const ENTRY_ENERGY_FULL_DESIGN: &str = "energy_full_design";
+const ENTRY_MODEL: &str = "model";
 const ENTRY_PERCENT: &str = "percent";
 const ENTRY_PLUGGED: &str = "plugged";
 const ENTRY_TIME_REMAINING: &str = "time_remaining";
 
+/// Information about a device powered by a battery (UPower backend only)
+#[derive(Serialize)]
+struct DeviceData
+{
+    pub device: String,
+    pub model: String,
+    pub percent: String,
+}
+
 /// Information about the battery
 #[derive(Serialize)]
 struct BatteryData
@@ -27,6 +67,9 @@ struct BatteryData
     pub plugged: String,
     pub percent: String,
     pub time_remaining: String,
+    pub energy_full_design: String,
+    pub capacity_health_percent: String,
+    pub devices: Vec<DeviceData>,
 }
 
 impl BatteryData {
@@ -36,41 +79,173 @@ impl BatteryData {
             plugged: VALUE_UNKNOWN.to_string(),
             percent: VALUE_UNKNOWN.to_string(),
             time_remaining: VALUE_UNKNOWN.to_string(),
+            energy_full_design: VALUE_UNKNOWN.to_string(),
+            capacity_health_percent: VALUE_UNKNOWN.to_string(),
+            devices: Vec::new(),
         }
     }
 }
 
-/// Battery backend that will compute the values
-struct BatteryBackend {
-    system_stats: systemstat::System,
-    triggers: Vec<triggers::Trigger>,
-    first_update: bool,
+/// Capacity of the batteries of the system (in µWh)
+struct Capacity {
+    pub design: u64,
+    pub full: u64,
+    pub now: u64,
+}
 
-    pub data: BatteryData,
+/// Read a value of a power supply from sysfs
+///
+/// # Arguments
+///
+/// * `supply` - The path of the power supply
+/// * `name` - The name of the value
+fn read_supply(supply: &path::Path, name: &str) -> Option<String> {
+    return match fs::read_to_string(supply.join(name)) {
+        Ok(s) => Some(s.trim().to_string()),
+        Err(_) => None,
+    };
 }
 
-impl BatteryBackend {
-    fn new(triggers: &Vec<triggers::Trigger>) -> Self {
+/// Function used to get the names of the batteries of the system
+///
+/// Batteries of peripherals are ignored.
+pub fn batteries() -> Vec<String> {
+    let mut names: Vec<String> = cooling::list_sysfs(POWER_SUPPLY, "", "")
+        .into_iter()
+        .filter(|s| {
+            read_supply(s, "type").as_deref() == Some("Battery") &&
+                read_supply(s, "scope").as_deref() != Some("Device")
+        })
+        .filter_map(|p| Some(p.file_name()?.to_str()?.to_string()))
+        .collect();
+
+    names.sort();
+
+    return names;
+}
+
+/// Get the capacity of the batteries of the system from sysfs
+///
+/// Batteries reporting a charge (µAh) instead of an energy are converted
+/// using their design voltage. Batteries of peripherals are ignored.
+fn query_capacity() -> Option<Capacity> {
+    let supplies = match fs::read_dir(platform::current()
+        .sysfs(POWER_SUPPLY)?) {
+
+        Ok(s) => s,
+        Err(_) => return None,
+    };
+
+    let mut capacity = Capacity {
+        design: 0,
+        full: 0,
+        now: 0,
+    };
+
+    for supply in supplies.filter_map(|s| s.ok()).map(|s| s.path()) {
+        if read_supply(&supply, "type").as_deref() != Some("Battery") ||
+            read_supply(&supply, "scope").as_deref() == Some("Device") {
+
+            continue;
+        }
+
+        let read = |name: &str| -> Option<u64> {
+            return read_supply(&supply, name)?.parse::<u64>().ok();
+        };
+
+        let (design, full, now) = match (
+            read("energy_full_design"),
+            read("energy_full"),
+            read("energy_now")) {
+
+            (Some(d), Some(f), Some(n)) => (d, f, n),
+
+            _ => {
+                let voltage = read("voltage_min_design")? as f64 /
+                    MICRO_PER_UNIT;
+
+                let energy = |name: &str| -> Option<u64> {
+                    return Some((read(name)? as f64 * voltage) as u64);
+                };
+
+                (
+                    energy("charge_full_design")?,
+                    energy("charge_full")?,
+                    energy("charge_now")?,
+                )
+            },
+        };
+
+        capacity.design += design;
+        capacity.full += full;
+        capacity.now += now;
+    }
+
+    return match capacity.design {
+        0 => None,
+        _ => Some(capacity),
+    };
+}
+
+/// Format a percentage given as a ratio
+///
+/// # Arguments
+///
+/// * `ratio` - The ratio (between 0 and 1)
+fn format_percent(ratio: f64) -> String {
+    return ((ratio * 100.0).ceil() as u8).to_string();
+}
+
+/// Format a duration given in seconds
+///
+/// # Arguments
+///
+/// * `time` - The number of seconds
+fn format_time(time: u64) -> String {
+    return format!("{:0>2}h{:0>2}m", time / 3600, (time / 60) % 60);
+}
+
+/// Get a property of a UPower object
+///
+/// # Arguments
+///
+/// * `path` - The object path
+/// * `interface` - The interface of the property
+/// * `property` - The name of the property
+fn upower_property(path: &str, interface: &str, property: &str)
+    -> Result<Value, error::CerebroError> {
+
+    return dbus::get_property(
+        dbus::Bus::System,
+        UPOWER_SERVICE,
+        path,
+        interface,
+        property);
+}
+
+/// Proxy backend that is only use in the context of the thread
+struct BatteryBackendProxy {
+    backend: Arc<Mutex<BatteryBackend>>,
+    system_stats: systemstat::System,
+    upower_failed: bool,
+}
+
+impl BatteryBackendProxy {
+    fn new(backend: Arc<Mutex<BatteryBackend>>) -> Self {
         Self {
+            backend: backend,
             system_stats: systemstat::System::new(),
-            triggers: triggers.to_vec(),
-            first_update: true,
-            data: BatteryData::new(),
+            upower_failed: false,
         }
     }
-}
 
-impl module::Data for BatteryBackend {
-    /// Update battery data
+    /// Update the values from systemstat
     ///
     /// # Arguments
     ///
     /// * `self` - The instance handle
-    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
-        let kind = match self.first_update {
-            true => triggers::Kind::Create,
-            false => triggers::Kind::Update,
-        };
+    fn update_systemstat(&mut self)
+        -> Result<module::Status, error::CerebroError> {
 
         // Plugged status
         let plugged = match self.system_stats.on_ac_power() {
@@ -82,106 +257,484 @@ impl module::Data for BatteryBackend {
             Err(_) => VALUE_UNKNOWN.to_string(),
         };
 
-        if plugged != self.data.plugged {
-            let old_value = self.data.plugged.clone();
+        // Percent and time remaining
+        let (percent, time_remaining) = match self.system_stats.battery_life() {
+            Ok(battery) => (
+                format_percent(battery.remaining_capacity as f64),
+                format_time(battery.remaining_time.as_secs())),
 
-            self.data.plugged = plugged;
+            Err(_) => (VALUE_UNKNOWN.to_string(), VALUE_UNKNOWN.to_string()),
+        };
 
-            log::debug!("{}: plugged={}", MODULE_NAME, self.data.plugged);
+        let mut backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        };
 
-            triggers::find_all_and_execute(
-                &self.triggers,
-                kind,
-                MODULE_NAME,
-                ENTRY_PLUGGED,
-                &old_value,
-                &self.data.plugged);
+        return Ok(backend.update_values(
+            plugged,
+            percent,
+            time_remaining,
+            query_capacity(),
+            Vec::new()));
+    }
+
+    /// Get the values from UPower
+    ///
+    /// The main values come from the display device, which is the
+    /// composite of the batteries powering the system, the other
+    /// devices (e.g. mouse, headset) are listed separately.
+    fn query_upower()
+        -> Result<(String, String, String, Vec<DeviceData>),
+            error::CerebroError> {
+
+        let plugged = match upower_property(
+            UPOWER_PATH,
+            UPOWER_INTERFACE,
+            "OnBattery")?.as_bool() {
+
+            Some(true) => VALUE_FALSE.to_string(),
+            Some(false) => VALUE_TRUE.to_string(),
+            None => VALUE_UNKNOWN.to_string(),
+        };
+
+        let percent = match upower_property(
+            UPOWER_DISPLAY_DEVICE,
+            UPOWER_DEVICE_INTERFACE,
+            "Percentage")?.as_f64() {
+
+            Some(p) => format_percent(p / 100.0),
+            None => VALUE_UNKNOWN.to_string(),
+        };
+
+        let time_remaining = match upower_property(
+            UPOWER_DISPLAY_DEVICE,
+            UPOWER_DEVICE_INTERFACE,
+            "TimeToEmpty")?.as_u64() {
+
+            Some(t) => format_time(t),
+            None => VALUE_UNKNOWN.to_string(),
+        };
+
+        // Devices
+        let paths = dbus::call(
+            dbus::Bus::System,
+            UPOWER_SERVICE,
+            UPOWER_PATH,
+            UPOWER_INTERFACE,
+            "EnumerateDevices",
+            "",
+            &[])?;
+
+        let mut devices: Vec<DeviceData> = Vec::new();
+
+        for path in paths[0].as_array().unwrap_or(&Vec::new()).iter() {
+            let path = match path.as_str() {
+                Some(p) => p,
+                None => continue,
+            };
+
+            let kind =
+                upower_property(path, UPOWER_DEVICE_INTERFACE, "Type")?;
+
+            if kind.as_u64() == Some(UPOWER_TYPE_LINE_POWER) {
+                continue;
+            }
+
+            let model =
+                upower_property(path, UPOWER_DEVICE_INTERFACE, "Model")?;
+
+            let percent =
+                upower_property(path, UPOWER_DEVICE_INTERFACE, "Percentage")?;
+
+            devices.push(DeviceData {
+                device: path.rsplit('/').next().unwrap_or(path).to_string(),
+                model: match model.as_str() {
+                    Some(m) if !m.is_empty() => m.to_string(),
+                    _ => VALUE_UNKNOWN.to_string(),
+                },
+                percent: match percent.as_f64() {
+                    Some(p) => format_percent(p / 100.0),
+                    None => VALUE_UNKNOWN.to_string(),
+                },
+            });
         }
 
-        // Percent and time remaining
-        let (percent, time_remaining) = match self.system_stats.battery_life() {
-            Ok(battery) => {
-                let capacity = battery.remaining_capacity;
-                let time = battery.remaining_time.as_secs();
+        devices.sort_by(|a, b| a.device.cmp(&b.device));
 
-                (
-                    ((capacity * 100.0).ceil() as u8).to_string(),
-                    format!("{:0>2}h{:0>2}m", time / 3600, time % 60)
-                )
+        return Ok((plugged, percent, time_remaining, devices));
+    }
+
+    /// Update the values from UPower each time it emits a signal
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update_upower(&mut self)
+        -> Result<module::Status, error::CerebroError> {
+
+        // Monitor first so that no signal is missed after the first query
+        let mut monitor =
+            dbus::Monitor::new(dbus::Bus::System, UPOWER_SERVICE)?;
+
+        loop {
+            let (plugged, percent, time_remaining, devices) =
+                BatteryBackendProxy::query_upower()?;
+
+            let capacity = query_capacity();
+
+            let status = match self.backend.lock() {
+                Ok(mut b) => b.update_values(
+                    plugged,
+                    percent,
+                    time_remaining,
+                    capacity,
+                    devices),
+
+                Err(_) => return error!(Lock, "Cannot lock backend"),
+            };
+
+            match status {
+                module::Status::Changed(name) =>
+                    return Ok(module::Status::Changed(name)),
+                _ => (),
+            }
+
+            monitor.next_signal()?;
+        }
+    }
+}
+
+impl module::Data for BatteryBackendProxy {
+    /// Update battery data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let backend_name = match self.backend.lock() {
+            Ok(b) => b.config.backend.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        };
+
+        // UPower is event-driven, polling is only used as a fallback
+        if backend_name.as_deref() == Some(BACKEND_UPOWER) &&
+            !self.upower_failed {
+
+            match self.update_upower() {
+                Ok(s) => return Ok(s),
+                Err(e) => {
+                    log::error!("{}: UPower failed: {}", MODULE_NAME, e);
+                    self.upower_failed = true;
+                },
+            }
+        }
+
+        return self.update_systemstat();
+    }
+
+    /// Update battery data once, using systemstat
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update_once(&mut self)
+        -> Option<Result<module::Status, error::CerebroError>> {
+
+        return Some(self.update_systemstat());
+    }
+}
+
+/// Battery backend that will compute the values
+struct BatteryBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+    low: bool,
+
+    pub data: BatteryData,
+    pub device_fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl BatteryBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            low: false,
+            data: BatteryData::new(),
+            device_fs_entries: Vec::new(),
+        }
+    }
+
+    /// Send the low battery notification when the battery gets low
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn notify_low(&mut self) {
+        let (low_percent, notification) = match &self.config.battery {
+            Some(c) => match &c.low_notification {
+                Some(n) => (
+                    c.low_percent.unwrap_or(DEFAULT_LOW_PERCENT),
+                    n.clone()),
+
+                None => return,
             },
 
-            Err(_) => (VALUE_UNKNOWN.to_string(), VALUE_UNKNOWN.to_string()),
+            None => return,
         };
 
-        if percent != self.data.percent {
-            let old_value = self.data.percent.clone();
+        let low = self.data.plugged == VALUE_FALSE &&
+            match self.data.percent.parse::<u64>() {
+                Ok(p) => p < low_percent,
+                Err(_) => false,
+            };
 
-            self.data.percent = percent;
+        if low && !self.low {
+            match notifications::send(
+                &notification,
+                &[(ENTRY_PERCENT, &self.data.percent)]) {
 
-            log::debug!("{}: percent={}", MODULE_NAME, self.data.percent);
+                Ok(_) => (),
+                Err(e) => log::error!("{}: {}", MODULE_NAME, e),
+            }
+        }
 
-            triggers::find_all_and_execute(
-                &self.triggers,
-                kind,
-                MODULE_NAME,
-                ENTRY_PERCENT,
-                &old_value,
-                &self.data.percent);
+        self.low = low;
+    }
+
+    /// Update the values and call the triggers
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `plugged` - The plugged status
+    /// * `percent` - The percentage of the battery
+    /// * `time_remaining` - The time remaining before the battery is empty
+    /// * `capacity` - The capacity of the batteries, if available
+    /// * `devices` - The devices powered by a battery
+    fn update_values(
+        &mut self,
+        plugged: String,
+        percent: String,
+        time_remaining: String,
+        capacity: Option<Capacity>,
+        devices: Vec<DeviceData>) -> module::Status {
+
+        let kind = match self.first_update {
+            true => triggers::Kind::Create,
+            false => triggers::Kind::Update,
+        };
+
+        let calibrated = match &self.config.battery {
+            Some(c) => c.calibrated == Some(true),
+            None => false,
+        };
+
+        let (percent, energy_full_design, capacity_health_percent) =
+            match capacity {
+                Some(c) => (
+                    match calibrated && c.full > 0 {
+                        true => format_percent(c.now as f64 / c.full as f64),
+                        false => percent,
+                    },
+                    format!("{:.2}", c.design as f64 / MICRO_PER_UNIT),
+                    format!("{}", c.full * 100 / c.design)),
+
+                None => (
+                    percent,
+                    VALUE_UNKNOWN.to_string(),
+                    VALUE_UNKNOWN.to_string()),
+            };
+
+        // Plugged status
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_PLUGGED,
+            &mut self.data.plugged,
+            &plugged);
+
+        // Percent and time remaining
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_PERCENT,
+            &mut self.data.percent,
+            &percent);
+
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_TIME_REMAINING,
+            &mut self.data.time_remaining,
+            &time_remaining);
+
+        // Capacity
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_ENERGY_FULL_DESIGN,
+            &mut self.data.energy_full_design,
+            &energy_full_design);
+
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_CAPACITY_HEALTH_PERCENT,
+            &mut self.data.capacity_health_percent,
+            &capacity_health_percent);
+
+        self.first_update = false;
+
+        self.notify_low();
+
+        // Devices
+        let changed = devices.len() != self.data.devices.len() ||
+            devices
+                .iter()
+                .zip(self.data.devices.iter())
+                .any(|(a, b)| a.device != b.device);
+
+        if changed {
+            self.rebuild_devices(devices);
+
+            return module::Status::Changed(MODULE_NAME.to_string());
         }
 
-        if time_remaining != self.data.time_remaining {
-            let old_value = self.data.time_remaining.clone();
+        for (data, device) in self.data.devices.iter_mut().zip(devices) {
+            if data.percent != device.percent {
+                let old_value = data.percent.clone();
+
+                data.percent = device.percent;
+
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Update,
+                    &self.name,
+                    &format!(
+                        "{}/{}/{}",
+                        ENTRY_DEVICES,
+                        data.device,
+                        ENTRY_PERCENT),
+                    &old_value,
+                    &data.percent);
+            }
 
-            self.data.time_remaining = time_remaining;
+            data.model = device.model;
+        }
 
-            log::debug!(
-                "{}: time_remaining={}",
-                MODULE_NAME,
-                self.data.time_remaining);
+        return module::Status::Ok;
+    }
 
+    /// Rebuild the devices data and filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `devices` - The new list of devices
+    fn rebuild_devices(&mut self, devices: Vec<DeviceData>) {
+        // Deletion triggers
+        for data in self.data.devices.iter() {
             triggers::find_all_and_execute(
                 &self.triggers,
-                kind,
-                MODULE_NAME,
-                ENTRY_TIME_REMAINING,
-                &old_value,
-                &self.data.time_remaining);
+                triggers::Kind::Delete,
+                &self.name,
+                &format!("{}/{}/{}", ENTRY_DEVICES, data.device, ENTRY_PERCENT),
+                "",
+                "");
         }
 
-        self.first_update = false;
-
-        return Ok(module::Status::Ok);
+        self.data.devices = devices;
+        self.device_fs_entries.clear();
+
+        for data in self.data.devices.iter() {
+            self.device_fs_entries.push(filesystem::FsEntry::new(
+                filesystem::FsEntry::create_inode(),
+                fuse::FileType::Directory,
+                &data.device,
+                filesystem::Mode::ReadOnly,
+                &vec![
+                    filesystem::FsEntry::new(
+                        filesystem::FsEntry::create_inode(),
+                        fuse::FileType::RegularFile,
+                        ENTRY_MODEL,
+                        filesystem::Mode::ReadOnly,
+                        &Vec::new()),
+
+                    filesystem::FsEntry::new(
+                        filesystem::FsEntry::create_inode(),
+                        fuse::FileType::RegularFile,
+                        ENTRY_PERCENT,
+                        filesystem::Mode::ReadOnly,
+                        &Vec::new())
+                        .with_unit(units::Unit::Percent),
+                ]));
+
+            // Creation triggers
+            triggers::find_all_and_execute(
+                &self.triggers,
+                triggers::Kind::Create,
+                &self.name,
+                &format!("{}/{}/{}", ENTRY_DEVICES, data.device, ENTRY_PERCENT),
+                "",
+                &data.percent);
+        }
     }
 }
 
 /// Battery module structure
 pub struct Battery {
+    name: String,
     thread: Arc<Mutex<module::Thread>>,
     inode_plugged: u64,
     inode_percent: u64,
     inode_time_remaining: u64,
+    inode_energy_full_design: u64,
+    inode_capacity_health_percent: u64,
+    inode_devices: u64,
     backend: Arc<Mutex<BatteryBackend>>,
+    backend_proxy: Arc<Mutex<BatteryBackendProxy>>,
     fs_entries: Vec<filesystem::FsEntry>,
 }
 
 impl Battery {
     /// Battery constructor
     pub fn new(
+        name: &str,
         event_manager: &mut event_manager::EventManager,
         triggers: &Vec<triggers::Trigger>) -> Self {
 
         let plugged = filesystem::FsEntry::create_inode();
         let percent = filesystem::FsEntry::create_inode();
         let time_remaining = filesystem::FsEntry::create_inode();
+        let energy_full_design = filesystem::FsEntry::create_inode();
+        let capacity_health_percent = filesystem::FsEntry::create_inode();
+        let backend = Arc::new(Mutex::new(BatteryBackend::new(name, triggers)));
 
         Self {
+            name: name.to_string(),
             thread: Arc::new(Mutex::new(
-                module::Thread::new(event_manager.sender()))),
+                module::Thread::new(name, event_manager.sender()))),
 
             inode_plugged: plugged,
             inode_percent: percent,
             inode_time_remaining: time_remaining,
-            backend: Arc::new(Mutex::new(BatteryBackend::new(triggers))),
+            inode_energy_full_design: energy_full_design,
+            inode_capacity_health_percent: capacity_health_percent,
+            inode_devices: filesystem::FsEntry::create_inode(),
+            backend: backend.clone(),
+            backend_proxy: Arc::new(Mutex::new(
+                BatteryBackendProxy::new(backend.clone()))),
             fs_entries: vec![
                 filesystem::FsEntry::new(
                     plugged,
@@ -195,7 +748,8 @@ impl Battery {
                     fuse::FileType::RegularFile,
                     ENTRY_PERCENT,
                     filesystem::Mode::ReadOnly,
-                    &Vec::new()),
+                    &Vec::new())
+                    .with_unit(units::Unit::Percent),
 
                 filesystem::FsEntry::new(
                     time_remaining,
@@ -203,9 +757,78 @@ impl Battery {
                     ENTRY_TIME_REMAINING,
                     filesystem::Mode::ReadOnly,
                     &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    energy_full_design,
+                    fuse::FileType::RegularFile,
+                    ENTRY_ENERGY_FULL_DESIGN,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    capacity_health_percent,
+                    fuse::FileType::RegularFile,
+                    ENTRY_CAPACITY_HEALTH_PERCENT,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new())
+                    .with_unit(units::Unit::Percent),
                 ],
         }
     }
+
+    /// Get the value of a filesystem entry from the locked data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `backend` - The data of the module
+    /// * `inode` - The inode of the filesystem entry
+    fn backend_value(&self, backend: &BatteryBackend, inode: u64) -> String {
+        if inode == self.inode_percent {
+            return backend.data.percent.clone();
+        }
+
+        if inode == self.inode_plugged {
+            return backend.data.plugged.clone();
+        }
+
+        if inode == self.inode_time_remaining {
+            return backend.data.time_remaining.clone();
+        }
+
+        if inode == self.inode_energy_full_design {
+            return backend.data.energy_full_design.clone();
+        }
+
+        if inode == self.inode_capacity_health_percent {
+            return backend.data.capacity_health_percent.clone();
+        }
+
+        // Devices
+        for device_entry in backend.device_fs_entries.iter() {
+            let entry = match device_entry.fs_entries
+                .iter().find(|x| x.inode == inode) {
+
+                Some(e) => e,
+                None => continue,
+            };
+
+            let data = match backend.data.devices
+                .iter().find(|x| x.device == device_entry.name) {
+
+                Some(d) => d,
+                None => return VALUE_UNKNOWN.to_string(),
+            };
+
+            return match entry.name.as_str() {
+                ENTRY_MODEL => data.model.clone(),
+                ENTRY_PERCENT => data.percent.clone(),
+                _ => VALUE_UNKNOWN.to_string(),
+            };
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
 }
 
 impl module::Module for Battery {
@@ -215,7 +838,16 @@ impl module::Module for Battery {
     ///
     /// * `self` - The instance handle
     fn name(&self) -> &str {
-        return MODULE_NAME;
+        return &self.name;
+    }
+
+    /// Check if the system has a battery
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn probe(&self) -> Option<bool> {
+        return Some(!batteries().is_empty());
     }
 
     /// Start the module
@@ -224,12 +856,17 @@ impl module::Module for Battery {
     ///
     /// * `self` - The instance handle
     fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
         let mut thread = match self.thread.lock() {
             Ok(t) => t,
-            Err(_) => return error!("Cannot lock thread"),
+            Err(_) => return error!(Lock, "Cannot lock thread"),
         };
 
-        thread.start(self.backend.clone(), config.timeout_s)?;
+        thread.start(self.backend_proxy.clone(), config)?;
 
         return success!();
     }
@@ -242,7 +879,7 @@ impl module::Module for Battery {
     fn stop(&mut self) -> error::Return {
         let mut thread = match self.thread.lock() {
             Ok(t) => t,
-            Err(_) => return error!("Cannot lock thread"),
+            Err(_) => return error!(Lock, "Cannot lock thread"),
         };
 
         thread.stop()?;
@@ -270,7 +907,23 @@ impl module::Module for Battery {
     ///
     /// * `self` - The instance handle
     fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
-        return self.fs_entries.to_vec();
+        let mut entries = self.fs_entries.to_vec();
+
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return entries,
+        };
+
+        if !backend.device_fs_entries.is_empty() {
+            entries.push(filesystem::FsEntry::new(
+                self.inode_devices,
+                fuse::FileType::Directory,
+                ENTRY_DEVICES,
+                filesystem::Mode::ReadOnly,
+                &backend.device_fs_entries));
+        }
+
+        return entries;
     }
 
     /// Get value to be displayed for a filesystem entry
@@ -280,28 +933,28 @@ impl module::Module for Battery {
     /// * `self` - The instance handle
     /// * `inode` - The inode of the filesystem to be fetched
     fn value(&self, inode: u64) -> String {
-        if inode == self.inode_percent {
-            match self.backend.lock() {
-                Ok(b) => return b.data.percent.clone(),
-                Err(_) => return VALUE_UNKNOWN.to_string(),
-            }
-        }
-
-        if inode == self.inode_plugged {
-            match self.backend.lock() {
-                Ok(b) => return b.data.plugged.clone(),
-                Err(_) => return VALUE_UNKNOWN.to_string(),
-            }
-        }
+        return match self.backend.lock() {
+            Ok(b) => self.backend_value(&b, inode),
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        };
+    }
 
-        if inode == self.inode_time_remaining {
-            match self.backend.lock() {
-                Ok(b) => return b.data.time_remaining.clone(),
-                Err(_) => return VALUE_UNKNOWN.to_string(),
-            }
-        }
+    /// Get the values of several entries from the same update
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inodes` - The inodes of the entries
+    fn values(&self, inodes: &[u64]) -> Vec<String> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return vec![VALUE_UNKNOWN.to_string(); inodes.len()],
+        };
 
-        return VALUE_UNKNOWN.to_string();
+        return inodes
+            .iter()
+            .map(|i| self.backend_value(&backend, *i))
+            .collect();
     }
 
     /// Set value of a filesystem entry
@@ -343,9 +996,12 @@ impl module::Module for Battery {
         };
 
         return format!(
-            "plugged={} percent={} time_remaining={}",
+            "plugged={} percent={} time_remaining={} energy_full_design={} \
+             capacity_health_percent={}",
             backend.data.plugged,
             backend.data.percent,
-            backend.data.time_remaining).to_string();
+            backend.data.time_remaining,
+            backend.data.energy_full_design,
+            backend.data.capacity_health_percent).to_string();
     }
 }
@@ -0,0 +1,687 @@
+use fuse;
+use serde::{Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::config;
+use crate::dbus;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::publish;
+use crate::sync::Mutex;
+use crate::triggers;
+use crate::units;
+
+const MODULE_NAME: &str = "bluetooth";
+
+const BLUEZ_SERVICE: &str = "org.bluez";
+const BLUEZ_ADAPTER_PREFIX: &str = "/org/bluez/hci";
+const BLUEZ_ADAPTER_INTERFACE: &str = "org.bluez.Adapter1";
+const BLUEZ_BATTERY_INTERFACE: &str = "org.bluez.Battery1";
+const BLUEZ_DEVICE_INTERFACE: &str = "org.bluez.Device1";
+const BLUEZ_DEVICE_PREFIX: &str = "dev_";
+
+const VALUE_FALSE: &str = "false";
+const VALUE_TRUE: &str = "true";
+const VALUE_UNKNOWN: &str = "?";
+
+const ENTRY_BATTERY_PERCENT: &str = "battery_percent";
+const ENTRY_CONNECTED: &str = "connected";
+const ENTRY_DEVICES: &str = "devices";
+const ENTRY_POWERED: &str = "powered";
+
+const DEVICE_ENTRIES: [&str; 2] = [
+    ENTRY_BATTERY_PERCENT,
+    ENTRY_CONNECTED,
+];
+
+/// Information about a paired device
+#[derive(Serialize)]
+struct DeviceData
+{
+    pub device: String,
+    pub connected: String,
+    pub battery_percent: String,
+}
+
+impl DeviceData {
+    /// Get the value of an entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `name` - The name of the entry
+    fn value(&self, name: &str) -> String {
+        return match name {
+            ENTRY_BATTERY_PERCENT => self.battery_percent.clone(),
+            ENTRY_CONNECTED => self.connected.clone(),
+            _ => VALUE_UNKNOWN.to_string(),
+        };
+    }
+}
+
+/// Information about Bluetooth
+#[derive(Serialize)]
+struct BluetoothData
+{
+    pub powered: String,
+    pub devices: Vec<DeviceData>,
+}
+
+impl BluetoothData {
+    /// BluetoothData constructor
+    pub fn new() -> Self {
+        Self {
+            powered: VALUE_UNKNOWN.to_string(),
+            devices: Vec::new(),
+        }
+    }
+}
+
+/// Get a property of a BlueZ object
+///
+/// # Arguments
+///
+/// * `path` - The object path
+/// * `interface` - The interface of the property
+/// * `property` - The name of the property
+fn bluez_property(path: &str, interface: &str, property: &str)
+    -> Result<Value, error::CerebroError> {
+
+    return dbus::get_property(
+        dbus::Bus::System,
+        BLUEZ_SERVICE,
+        path,
+        interface,
+        property);
+}
+
+/// Format a boolean property
+///
+/// # Arguments
+///
+/// * `value` - The value of the property
+fn format_bool(value: &Value) -> String {
+    return match value.as_bool() {
+        Some(true) => VALUE_TRUE.to_string(),
+        Some(false) => VALUE_FALSE.to_string(),
+        None => VALUE_UNKNOWN.to_string(),
+    };
+}
+
+/// Proxy backend that is only use in the context of the thread
+struct BluetoothBackendProxy {
+    backend: Arc<Mutex<BluetoothBackend>>,
+}
+
+impl BluetoothBackendProxy {
+    fn new(backend: Arc<Mutex<BluetoothBackend>>) -> Self {
+        Self {
+            backend: backend,
+        }
+    }
+
+    /// Get the values from BlueZ
+    ///
+    /// Only the first adapter and its paired devices are used. Devices are
+    /// named after their alias, the address is appended to the names used
+    /// by several devices.
+    fn query_bluez()
+        -> Result<(String, String, Vec<DeviceData>), error::CerebroError> {
+
+        let paths = dbus::tree(dbus::Bus::System, BLUEZ_SERVICE)?;
+
+        let adapter = match paths.iter().find(|p| {
+            p.starts_with(BLUEZ_ADAPTER_PREFIX) &&
+                !p[BLUEZ_ADAPTER_PREFIX.len()..].contains('/')
+        }) {
+            Some(a) => a.clone(),
+            None => return error!("No Bluetooth adapter"),
+        };
+
+        let powered = format_bool(&bluez_property(
+            &adapter,
+            BLUEZ_ADAPTER_INTERFACE,
+            "Powered")?);
+
+        let prefix = format!("{}/{}", adapter, BLUEZ_DEVICE_PREFIX);
+
+        let mut devices: Vec<(String, DeviceData)> = Vec::new();
+
+        for path in paths.iter() {
+            if !path.starts_with(&prefix) ||
+                path[prefix.len()..].contains('/') {
+
+                continue;
+            }
+
+            let paired =
+                bluez_property(path, BLUEZ_DEVICE_INTERFACE, "Paired")?;
+
+            if paired.as_bool() != Some(true) {
+                continue;
+            }
+
+            let address = path[prefix.len()..].to_string();
+
+            let name = match bluez_property(
+                path,
+                BLUEZ_DEVICE_INTERFACE,
+                "Alias")?.as_str() {
+
+                Some(a) if !a.is_empty() => a.replace('/', "_"),
+                _ => address.clone(),
+            };
+
+            let connected = format_bool(&bluez_property(
+                path,
+                BLUEZ_DEVICE_INTERFACE,
+                "Connected")?);
+
+            // The battery interface only exists for some connected devices
+            let battery_percent = match bluez_property(
+                path,
+                BLUEZ_BATTERY_INTERFACE,
+                "Percentage") {
+
+                Ok(p) => match p.as_u64() {
+                    Some(p) => p.to_string(),
+                    None => VALUE_UNKNOWN.to_string(),
+                },
+
+                Err(_) => VALUE_UNKNOWN.to_string(),
+            };
+
+            devices.push((address, DeviceData {
+                device: name,
+                connected: connected,
+                battery_percent: battery_percent,
+            }));
+        }
+
+        for i in 0..devices.len() {
+            let duplicated = devices
+                .iter()
+                .filter(|(_, d)| d.device == devices[i].1.device)
+                .count() > 1;
+
+            if duplicated {
+                devices[i].1.device =
+                    format!("{}_{}", devices[i].1.device, devices[i].0);
+            }
+        }
+
+        let mut devices: Vec<DeviceData> =
+            devices.into_iter().map(|(_, d)| d).collect();
+
+        devices.sort_by(|a, b| a.device.cmp(&b.device));
+
+        return Ok((adapter, powered, devices));
+    }
+
+    /// Update the values from BlueZ once
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn refresh(&mut self) -> Result<module::Status, error::CerebroError> {
+        let (adapter, powered, devices) =
+            BluetoothBackendProxy::query_bluez()?;
+
+        return match self.backend.lock() {
+            Ok(mut b) => Ok(b.update_values(adapter, powered, devices)),
+            Err(_) => error!(Lock, "Cannot lock backend"),
+        };
+    }
+}
+
+impl module::Data for BluetoothBackendProxy {
+    /// Update Bluetooth data each time BlueZ emits a signal
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        // Monitor first so that no signal is missed after the first query
+        let mut monitor =
+            dbus::Monitor::new(dbus::Bus::System, BLUEZ_SERVICE)?;
+
+        loop {
+            match self.refresh()? {
+                module::Status::Changed(name) =>
+                    return Ok(module::Status::Changed(name)),
+                _ => (),
+            }
+
+            monitor.next_signal()?;
+        }
+    }
+
+    /// Update Bluetooth data once without waiting for any signal
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update_once(&mut self)
+        -> Option<Result<module::Status, error::CerebroError>> {
+
+        return Some(self.refresh());
+    }
+}
+
+/// Bluetooth backend that will compute the values
+struct BluetoothBackend {
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+
+    pub adapter: String,
+    pub data: BluetoothData,
+    pub device_fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl BluetoothBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            adapter: "".to_string(),
+            data: BluetoothData::new(),
+            device_fs_entries: Vec::new(),
+        }
+    }
+
+    /// Update the values and call the triggers
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `adapter` - The object path of the adapter
+    /// * `powered` - The power state of the adapter
+    /// * `devices` - The paired devices
+    fn update_values(
+        &mut self,
+        adapter: String,
+        powered: String,
+        devices: Vec<DeviceData>) -> module::Status {
+
+        let kind = match self.first_update {
+            true => triggers::Kind::Create,
+            false => triggers::Kind::Update,
+        };
+
+        self.adapter = adapter;
+
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_POWERED,
+            &mut self.data.powered,
+            &powered);
+
+        self.first_update = false;
+
+        // Devices
+        let changed = devices.len() != self.data.devices.len() ||
+            devices
+                .iter()
+                .zip(self.data.devices.iter())
+                .any(|(a, b)| a.device != b.device);
+
+        if changed {
+            self.rebuild_devices(devices);
+
+            return module::Status::Changed(MODULE_NAME.to_string());
+        }
+
+        for (data, device) in self.data.devices.iter_mut().zip(devices) {
+            for name in DEVICE_ENTRIES.iter() {
+                let old_value = data.value(name);
+                let new_value = device.value(name);
+
+                if old_value == new_value {
+                    continue;
+                }
+
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Update,
+                    &self.name,
+                    &format!("{}/{}/{}", ENTRY_DEVICES, data.device, name),
+                    &old_value,
+                    &new_value);
+            }
+
+            *data = device;
+        }
+
+        return module::Status::Ok;
+    }
+
+    /// Rebuild the devices data and filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `devices` - The new list of devices
+    fn rebuild_devices(&mut self, devices: Vec<DeviceData>) {
+        // Deletion triggers
+        for data in self.data.devices.iter() {
+            for name in DEVICE_ENTRIES.iter() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Delete,
+                    &self.name,
+                    &format!("{}/{}/{}", ENTRY_DEVICES, data.device, name),
+                    "",
+                    "");
+            }
+        }
+
+        self.data.devices = devices;
+        self.device_fs_entries.clear();
+
+        for data in self.data.devices.iter() {
+            self.device_fs_entries.push(filesystem::FsEntry::new(
+                filesystem::FsEntry::create_inode(),
+                fuse::FileType::Directory,
+                &data.device,
+                filesystem::Mode::ReadOnly,
+                &vec![
+                    filesystem::FsEntry::new(
+                        filesystem::FsEntry::create_inode(),
+                        fuse::FileType::RegularFile,
+                        ENTRY_BATTERY_PERCENT,
+                        filesystem::Mode::ReadOnly,
+                        &Vec::new())
+                        .with_unit(units::Unit::Percent),
+
+                    filesystem::FsEntry::new(
+                        filesystem::FsEntry::create_inode(),
+                        fuse::FileType::RegularFile,
+                        ENTRY_CONNECTED,
+                        filesystem::Mode::ReadOnly,
+                        &Vec::new()),
+                ]));
+
+            // Creation triggers
+            for name in DEVICE_ENTRIES.iter() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Create,
+                    &self.name,
+                    &format!("{}/{}/{}", ENTRY_DEVICES, data.device, name),
+                    "",
+                    &data.value(name));
+            }
+        }
+    }
+
+    /// Power the adapter on or off
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `powered` - The new power state, None to toggle it
+    fn set_powered(&self, powered: Option<bool>) -> error::Return {
+        if self.adapter.is_empty() {
+            return error!("No Bluetooth adapter");
+        }
+
+        let powered = match powered {
+            Some(p) => p,
+            None => self.data.powered != VALUE_TRUE,
+        };
+
+        // The new state is read when BlueZ signals the change
+        return dbus::set_property(
+            dbus::Bus::System,
+            BLUEZ_SERVICE,
+            &self.adapter,
+            BLUEZ_ADAPTER_INTERFACE,
+            "Powered",
+            "b",
+            match powered {
+                true => VALUE_TRUE,
+                false => VALUE_FALSE,
+            });
+    }
+}
+
+/// Bluetooth module structure
+pub struct Bluetooth {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    inode_powered: u64,
+    inode_devices: u64,
+    backend: Arc<Mutex<BluetoothBackend>>,
+    backend_proxy: Arc<Mutex<BluetoothBackendProxy>>,
+}
+
+impl Bluetooth {
+    /// Bluetooth constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        let backend =
+            Arc::new(Mutex::new(BluetoothBackend::new(name, triggers)));
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            inode_powered: filesystem::FsEntry::create_inode(),
+            inode_devices: filesystem::FsEntry::create_inode(),
+            backend: backend.clone(),
+            backend_proxy: Arc::new(Mutex::new(
+                BluetoothBackendProxy::new(backend.clone()))),
+        }
+    }
+}
+
+impl module::Module for Bluetooth {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend_proxy.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        return vec![
+            filesystem::FsEntry::new(
+                self.inode_powered,
+                fuse::FileType::RegularFile,
+                ENTRY_POWERED,
+                filesystem::Mode::ReadWrite,
+                &Vec::new()),
+
+            filesystem::FsEntry::new(
+                self.inode_devices,
+                fuse::FileType::Directory,
+                ENTRY_DEVICES,
+                filesystem::Mode::ReadOnly,
+                &backend.device_fs_entries),
+        ];
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        if inode == self.inode_powered {
+            return backend.data.powered.clone();
+        }
+
+        for device_entry in backend.device_fs_entries.iter() {
+            let entry = match device_entry.fs_entries
+                .iter().find(|x| x.inode == inode) {
+
+                Some(e) => e,
+                None => continue,
+            };
+
+            return match backend.data.devices
+                .iter().find(|x| x.device == device_entry.name) {
+
+                Some(d) => d.value(&entry.name),
+                None => VALUE_UNKNOWN.to_string(),
+            };
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, inode: u64, data: &[u8]) {
+        if inode != self.inode_powered {
+            return;
+        }
+
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => {
+                log::error!("Cannot lock backend");
+                return;
+            },
+        };
+
+        let result = match data {
+            b"1" | b"1\n" | b"true" | b"true\n" =>
+                backend.set_powered(Some(true)),
+
+            b"0" | b"0\n" | b"false" | b"false\n" =>
+                backend.set_powered(Some(false)),
+
+            b"toggle" | b"toggle\n" => backend.set_powered(None),
+
+            _ => error!(&format!(
+                "Invalid value for {}/{}",
+                MODULE_NAME,
+                ENTRY_POWERED)),
+        };
+
+        match result {
+            Ok(_) => (),
+            Err(e) => log::error!("{}", e),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let connected = backend.data.devices
+            .iter()
+            .filter(|d| d.connected == VALUE_TRUE)
+            .count();
+
+        return format!(
+            "powered={} connected={}",
+            backend.data.powered,
+            connected);
+    }
+}
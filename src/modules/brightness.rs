@@ -2,19 +2,23 @@ use fuse;
 use notify::Watcher;
 use serde::{Serialize};
 use std::fs;
-use std::path;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use std::sync::mpsc;
 
 use crate::config;
 use crate::error;
 use crate::event_manager;
 use crate::filesystem;
+use crate::modules::cooling;
 use crate::modules::module;
+use crate::platform;
+use crate::sync::Mutex;
 use crate::triggers;
 
 const MODULE_NAME: &str = "brightness";
 
+const BACKLIGHT: &str = "class/backlight";
+
 const VALUE_UNKNOWN: &str = "?";
 
 const ENTRY_VALUE: &str = "value";
@@ -31,6 +35,18 @@ struct BrightnessData
     pub max_value: String,
 }
 
+/// Function used to get the names of the backlight devices
+pub fn backlights() -> Vec<String> {
+    let mut names: Vec<String> = cooling::list_sysfs(BACKLIGHT, "", "")
+        .into_iter()
+        .filter_map(|p| Some(p.file_name()?.to_str()?.to_string()))
+        .collect();
+
+    names.sort();
+
+    return names;
+}
+
 /// Proxy backend that is only use in the context of the thread
 struct BrightnessBackendProxy {
     backend: Arc<Mutex<BrightnessBackend>>,
@@ -54,7 +70,7 @@ impl module::Data for BrightnessBackendProxy {
         // Check if the fileystem needs to be built
         let status = match self.backend.lock() {
             Ok(mut b) => b.build_filesystem()?,
-            Err(_) => return error!("Cannot lock backend"),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
         };
 
         match status {
@@ -63,17 +79,22 @@ impl module::Data for BrightnessBackendProxy {
         }
 
         // Get entries
-        let root = path::Path::new("/")
-            .join("sys")
-            .join("class")
-            .join("backlight");
+        let root = match platform::current().sysfs(BACKLIGHT) {
+            Some(r) => r,
+            None => return error!("No backlight on this platform"),
+        };
 
-        let devices = fs::read_dir(&root).unwrap();
+        let devices = match fs::read_dir(&root) {
+            Ok(d) => d,
+            Err(_) => return error!("Cannot list backlight devices"),
+        };
 
         // Create watcher
         let (tx, rx) = mpsc::channel();
 
-        let mut w: notify::INotifyWatcher = match notify::Watcher::new_raw(tx) {
+        let mut w: notify::RecommendedWatcher =
+            match notify::Watcher::new_raw(tx) {
+
             Ok(w) => w,
             Err(_) => return error!("Cannot create filesystem watcher"),
         };
@@ -98,7 +119,7 @@ impl module::Data for BrightnessBackendProxy {
         }
 
         loop {
-            let event = match rx.recv() {
+            let event = match module::recv(&rx) {
                 Ok(e) => e,
                 Err(_) => return error!("Error during watching filesystem"),
             };
@@ -127,7 +148,7 @@ impl module::Data for BrightnessBackendProxy {
 
             let mut backend = match self.backend.lock() {
                 Ok(b) => b,
-                Err(_) => return error!("Cannot lock backend"),
+                Err(_) => return error!(Lock, "Cannot lock backend"),
             };
 
             let mut device: String = "".to_string();
@@ -168,17 +189,29 @@ impl module::Data for BrightnessBackendProxy {
                 triggers::find_all_and_execute(
                     &backend.triggers,
                     triggers::Kind::Update,
-                    MODULE_NAME,
+                    &backend.name,
                     &format!("{}/{}", device, ENTRY_VALUE),
                     &old_value,
                     &new_value);
             }
         }
     }
+
+    /// Update brightness data once, not supported as it's event-driven
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update_once(&mut self)
+        -> Option<Result<module::Status, error::CerebroError>> {
+
+        return None;
+    }
 }
 
 /// Brightness backend that will compute the values
 struct BrightnessBackend {
+    name: String,
     triggers: Vec<triggers::Trigger>,
 
     pub data: Vec<BrightnessData>,
@@ -186,8 +219,9 @@ struct BrightnessBackend {
 }
 
 impl BrightnessBackend {
-    fn new(triggers: &Vec<triggers::Trigger>) -> Self {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
         Self {
+            name: name.to_string(),
             triggers: triggers.to_vec(),
             data: Vec::new(),
             fs_entries: Vec::new(),
@@ -201,12 +235,15 @@ impl BrightnessBackend {
             return Ok(module::Status::Ok);
         }
 
-        let root = path::Path::new("/")
-            .join("sys")
-            .join("class")
-            .join("backlight");
+        let root = match platform::current().sysfs(BACKLIGHT) {
+            Some(r) => r,
+            None => return error!("No backlight on this platform"),
+        };
 
-        let devices = fs::read_dir(&root).unwrap();
+        let devices = match fs::read_dir(&root) {
+            Ok(d) => d,
+            Err(_) => return error!("Cannot list backlight devices"),
+        };
 
         // Build data
         self.data.clear();
@@ -294,7 +331,7 @@ impl BrightnessBackend {
             triggers::find_all_and_execute(
                 &self.triggers,
                 triggers::Kind::Create,
-                MODULE_NAME,
+                &self.name,
                 &format!("{}/{}", data.device, ENTRY_VALUE),
                 "",
                 "");
@@ -302,7 +339,7 @@ impl BrightnessBackend {
             triggers::find_all_and_execute(
                 &self.triggers,
                 triggers::Kind::Create,
-                MODULE_NAME,
+                &self.name,
                 &format!("{}/{}", data.device, ENTRY_CURRENT_VALUE),
                 "",
                 "");
@@ -310,7 +347,7 @@ impl BrightnessBackend {
             triggers::find_all_and_execute(
                 &self.triggers,
                 triggers::Kind::Create,
-                MODULE_NAME,
+                &self.name,
                 &format!("{}/{}", data.device, ENTRY_MAX_VALUE),
                 "",
                 "");
@@ -322,6 +359,7 @@ impl BrightnessBackend {
 
 /// Brightness module structure
 pub struct Brightness {
+    name: String,
     thread: Arc<Mutex<module::Thread>>,
     backend: Arc<Mutex<BrightnessBackend>>,
     backend_proxy: Arc<Mutex<BrightnessBackendProxy>>,
@@ -330,14 +368,17 @@ pub struct Brightness {
 impl Brightness {
     /// Brightness constructor
     pub fn new(
+        name: &str,
         event_manager: &mut event_manager::EventManager,
         triggers: &Vec<triggers::Trigger>) -> Self {
 
-        let backend = Arc::new(Mutex::new(BrightnessBackend::new(triggers)));
+        let backend =
+            Arc::new(Mutex::new(BrightnessBackend::new(name, triggers)));
 
         Self {
+            name: name.to_string(),
             thread: Arc::new(Mutex::new(
-                module::Thread::new(event_manager.sender()))),
+                module::Thread::new(name, event_manager.sender()))),
 
             backend: backend.clone(),
             backend_proxy:
@@ -355,7 +396,16 @@ impl module::Module for Brightness {
     ///
     /// * `self` - The instance handle
     fn name(&self) -> &str {
-        return MODULE_NAME;
+        return &self.name;
+    }
+
+    /// Check if the system has a backlight
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn probe(&self) -> Option<bool> {
+        return Some(!backlights().is_empty());
     }
 
     /// Start the module
@@ -366,10 +416,10 @@ impl module::Module for Brightness {
     fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
         let mut thread = match self.thread.lock() {
             Ok(t) => t,
-            Err(_) => return error!("Cannot lock thread"),
+            Err(_) => return error!(Lock, "Cannot lock thread"),
         };
 
-        thread.start(self.backend_proxy.clone(), config.timeout_s)?;
+        thread.start(self.backend_proxy.clone(), config)?;
 
         return success!();
     }
@@ -382,7 +432,7 @@ impl module::Module for Brightness {
     fn stop(&mut self) -> error::Return {
         let mut thread = match self.thread.lock() {
             Ok(t) => t,
-            Err(_) => return error!("Cannot lock thread"),
+            Err(_) => return error!(Lock, "Cannot lock thread"),
         };
 
         thread.stop()?;
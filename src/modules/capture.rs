@@ -0,0 +1,521 @@
+use fuse;
+use serde::{Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+
+use crate::config;
+use crate::dbus;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::publish;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const PORTAL_SERVICE: &str = "org.freedesktop.portal.Desktop";
+const SCREENCAST_INTERFACE: &str = "org.freedesktop.portal.ScreenCast";
+const REQUEST_INTERFACE: &str = "org.freedesktop.portal.Request";
+const SESSION_INTERFACE: &str = "org.freedesktop.portal.Session";
+
+/// Signal of the bus emitted when a client disconnects
+const NAME_OWNER_CHANGED: &str =
+    "type='signal',interface='org.freedesktop.DBus',member='NameOwnerChanged'";
+
+const VALUE_FALSE: &str = "false";
+const VALUE_TRUE: &str = "true";
+const VALUE_UNKNOWN: &str = "?";
+
+const ENTRY_APP_NAME: &str = "app_name";
+const ENTRY_RECORDING: &str = "recording";
+
+/// Information about the screen capture
+#[derive(Serialize)]
+struct CaptureData
+{
+    pub recording: String,
+    pub app_name: String,
+}
+
+impl CaptureData {
+    /// CaptureData constructor
+    pub fn new() -> Self {
+        Self {
+            recording: VALUE_UNKNOWN.to_string(),
+            app_name: VALUE_UNKNOWN.to_string(),
+        }
+    }
+}
+
+/// Screen-cast session being started
+struct Pending {
+    session: String,
+    sender: String,
+}
+
+/// Capture backend that will compute the values
+struct CaptureBackend {
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+
+    pub data: CaptureData,
+}
+
+impl CaptureBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            data: CaptureData::new(),
+        }
+    }
+
+    /// Get the name of the application owning a portal session
+    ///
+    /// # Arguments
+    ///
+    /// * `sender` - The unique name of the application on the bus
+    fn app_name(sender: &str) -> Option<String> {
+        let pid = match dbus::call(
+            dbus::Bus::Session,
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "GetConnectionUnixProcessID",
+            "s",
+            &[sender]) {
+
+            Ok(p) => p.get(0)?.as_u64()?,
+            Err(_) => return None,
+        };
+
+        return match fs::read_to_string(format!("/proc/{}/comm", pid)) {
+            Ok(c) => Some(c.trim().to_string()),
+            Err(_) => None,
+        };
+    }
+
+    /// Update the values from the running screen-casts
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `senders` - The applications of the running screen-casts
+    fn update_values(&mut self, senders: Vec<&String>) {
+        let kind = match self.first_update {
+            true => triggers::Kind::Create,
+            false => triggers::Kind::Update,
+        };
+
+        let recording = match senders.is_empty() {
+            true => VALUE_FALSE.to_string(),
+            false => VALUE_TRUE.to_string(),
+        };
+
+        let app_name = match senders.first() {
+            Some(s) => match CaptureBackend::app_name(s) {
+                Some(n) => n,
+                None => VALUE_UNKNOWN.to_string(),
+            },
+
+            None => "".to_string(),
+        };
+
+        // Recording status
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_RECORDING,
+            &mut self.data.recording,
+            &recording);
+
+        // Application name
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_APP_NAME,
+            &mut self.data.app_name,
+            &app_name);
+
+        self.first_update = false;
+    }
+}
+
+/// Proxy backend that is only use in the context of the thread
+///
+/// The screen-casts are followed through the messages of the ScreenCast
+/// portal: a session is counted once its `Start` request is accepted, until
+/// it's closed or its application disconnects. The other portal sessions
+/// (remote desktop, global shortcuts, ...) are ignored, as well as the
+/// screen-casts started before the module.
+struct CaptureBackendProxy {
+    backend: Arc<Mutex<CaptureBackend>>,
+
+    // Start calls waiting for their reply, by cookie
+    calls: HashMap<u64, Pending>,
+
+    // Start requests waiting for the answer of the user, by object path
+    requests: HashMap<String, Pending>,
+
+    // Applications of the running screen-casts, by session path
+    sessions: HashMap<String, String>,
+}
+
+impl CaptureBackendProxy {
+    fn new(backend: Arc<Mutex<CaptureBackend>>) -> Self {
+        Self {
+            backend: backend,
+            calls: HashMap::new(),
+            requests: HashMap::new(),
+            sessions: HashMap::new(),
+        }
+    }
+
+    /// Handle a message of the bus
+    ///
+    /// Returns true if the running screen-casts have changed.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `message` - The message
+    fn handle(&mut self, message: &Value) -> bool {
+        let path = message["path"].as_str().unwrap_or("");
+        let interface = message["interface"].as_str().unwrap_or("");
+        let member = message["member"].as_str().unwrap_or("");
+        let data = &message["payload"]["data"];
+
+        match (message["type"].as_str(), interface, member) {
+            // A screen-cast is requested by an application
+            (Some("method_call"), SCREENCAST_INTERFACE, "Start") => {
+                let session = match data[0].as_str() {
+                    Some(s) => s.to_string(),
+                    None => return false,
+                };
+
+                match message["cookie"].as_u64() {
+                    Some(c) => {
+                        self.calls.insert(c, Pending {
+                            session: session,
+                            sender: message["sender"]
+                                .as_str()
+                                .unwrap_or("")
+                                .to_string(),
+                        });
+                    },
+
+                    None => (),
+                }
+
+                return false;
+            },
+
+            // The request of the user is created
+            (Some("method_return"), _, _) => {
+                let pending = match message["reply_cookie"].as_u64() {
+                    Some(c) => match self.calls.remove(&c) {
+                        Some(p) => p,
+                        None => return false,
+                    },
+
+                    None => return false,
+                };
+
+                match data[0].as_str() {
+                    Some(r) => {
+                        self.requests.insert(r.to_string(), pending);
+                    },
+
+                    None => (),
+                }
+
+                return false;
+            },
+
+            // The user has answered, 0 means accepted
+            (Some("signal"), REQUEST_INTERFACE, "Response") => {
+                let pending = match self.requests.remove(path) {
+                    Some(p) => p,
+                    None => return false,
+                };
+
+                if data[0].as_u64() != Some(0) {
+                    return false;
+                }
+
+                self.sessions.insert(pending.session, pending.sender);
+
+                return true;
+            },
+
+            // The session is closed by the portal or by the application
+            (Some("signal"), SESSION_INTERFACE, "Closed") |
+            (Some("method_call"), SESSION_INTERFACE, "Close") => {
+                return self.sessions.remove(path).is_some();
+            },
+
+            // The application has disconnected
+            (Some("signal"), "org.freedesktop.DBus", "NameOwnerChanged") => {
+                match (data[0].as_str(), data[2].as_str()) {
+                    (Some(n), Some("")) => {
+                        let count = self.sessions.len();
+
+                        self.sessions.retain(|_, s| s != n);
+                        self.calls.retain(|_, p| p.sender != n);
+                        self.requests.retain(|_, p| p.sender != n);
+
+                        return self.sessions.len() != count;
+                    },
+
+                    _ => return false,
+                }
+            },
+
+            _ => return false,
+        }
+    }
+
+    /// Publish the running screen-casts
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn publish(&mut self) -> error::Return {
+        let mut backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        };
+
+        backend.update_values(self.sessions.values().collect());
+
+        return success!();
+    }
+}
+
+impl module::Data for CaptureBackendProxy {
+    /// Update capture data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let mut monitor = dbus::Monitor::with_matches(
+            dbus::Bus::Session,
+            PORTAL_SERVICE,
+            &[NAME_OWNER_CHANGED])?;
+
+        self.publish()?;
+
+        loop {
+            let message = monitor.next_message()?;
+
+            if self.handle(&message) {
+                self.publish()?;
+            }
+        }
+    }
+
+    /// Update capture data once, not supported as it's event-driven
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update_once(&mut self)
+        -> Option<Result<module::Status, error::CerebroError>> {
+
+        return None;
+    }
+}
+
+/// Capture module structure
+pub struct Capture {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    inode_recording: u64,
+    inode_app_name: u64,
+    backend: Arc<Mutex<CaptureBackend>>,
+    backend_proxy: Arc<Mutex<CaptureBackendProxy>>,
+    fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl Capture {
+    /// Capture constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        let recording = filesystem::FsEntry::create_inode();
+        let app_name = filesystem::FsEntry::create_inode();
+
+        let backend =
+            Arc::new(Mutex::new(CaptureBackend::new(name, triggers)));
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            inode_recording: recording,
+            inode_app_name: app_name,
+            backend: backend.clone(),
+            backend_proxy: Arc::new(Mutex::new(
+                CaptureBackendProxy::new(backend.clone()))),
+            fs_entries: vec![
+                filesystem::FsEntry::new(
+                    recording,
+                    fuse::FileType::RegularFile,
+                    ENTRY_RECORDING,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    app_name,
+                    fuse::FileType::RegularFile,
+                    ENTRY_APP_NAME,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+                ],
+        }
+    }
+}
+
+impl module::Module for Capture {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend_proxy.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        return self.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        if inode == self.inode_recording {
+            match self.backend.lock() {
+                Ok(b) => return b.data.recording.clone(),
+                Err(_) => return VALUE_UNKNOWN.to_string(),
+            }
+        }
+
+        if inode == self.inode_app_name {
+            match self.backend.lock() {
+                Ok(b) => return b.data.app_name.clone(),
+                Err(_) => return VALUE_UNKNOWN.to_string(),
+            }
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return format!(
+            "recording={} app_name={}",
+            backend.data.recording,
+            backend.data.app_name).to_string();
+    }
+}
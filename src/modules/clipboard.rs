@@ -0,0 +1,563 @@
+use fuse;
+use serde::{Serialize};
+use std::env;
+use std::io::{BufRead, BufReader, Read};
+use std::process;
+use std::sync::Arc;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "clipboard";
+
+const DEFAULT_PREVIEW_LENGTH: usize = 64;
+
+const VALUE_UNKNOWN: &str = "?";
+
+const ENTRY_LENGTH: &str = "length";
+const ENTRY_MIME_TYPE: &str = "mime_type";
+const ENTRY_PREVIEW: &str = "preview";
+
+/// Information about the clipboard
+#[derive(Serialize)]
+struct ClipboardData
+{
+    pub length: String,
+    pub mime_type: String,
+    pub preview: String,
+}
+
+impl ClipboardData {
+    /// ClipboardData constructor
+    pub fn new() -> Self {
+        Self {
+            length: VALUE_UNKNOWN.to_string(),
+            mime_type: VALUE_UNKNOWN.to_string(),
+            preview: VALUE_UNKNOWN.to_string(),
+        }
+    }
+}
+
+/// Display server the clipboard belongs to
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Display {
+    Wayland,
+    X11,
+}
+
+impl Display {
+    /// Find the display server of the session
+    fn find() -> Result<Display, error::CerebroError> {
+        if env::var("WAYLAND_DISPLAY").is_ok() {
+            return Ok(Display::Wayland);
+        }
+
+        if env::var("DISPLAY").is_ok() {
+            return Ok(Display::X11);
+        }
+
+        return error!("No display server found");
+    }
+
+    /// Get the command used to list the MIME types of the selection
+    fn types_command(&self) -> process::Command {
+        return match self {
+            Display::Wayland => {
+                let mut c = process::Command::new("wl-paste");
+                c.arg("--list-types");
+                c
+            },
+
+            Display::X11 => {
+                let mut c = process::Command::new("xclip");
+                c.args(&["-selection", "clipboard", "-o", "-t", "TARGETS"]);
+                c
+            },
+        };
+    }
+
+    /// Get the command used to read the selection with a given MIME type
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `mime_type` - The MIME type to be read
+    fn content_command(&self, mime_type: &str) -> process::Command {
+        return match self {
+            Display::Wayland => {
+                let mut c = process::Command::new("wl-paste");
+                c.args(&["--no-newline", "--type", mime_type]);
+                c
+            },
+
+            Display::X11 => {
+                let mut c = process::Command::new("xclip");
+                c.args(&["-selection", "clipboard", "-o", "-t", mime_type]);
+                c
+            },
+        };
+    }
+}
+
+/// Proxy backend that is only use in the context of the thread
+struct ClipboardBackendProxy {
+    backend: Arc<Mutex<ClipboardBackend>>,
+}
+
+impl ClipboardBackendProxy {
+    fn new(backend: Arc<Mutex<ClipboardBackend>>) -> Self {
+        Self {
+            backend: backend,
+        }
+    }
+
+    /// Read the selection and update the data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `display` - The display server
+    fn update_selection(&mut self, display: Display) -> error::Return {
+        let (preview_enabled, preview_length) = match self.backend.lock() {
+            Ok(b) => (b.preview_enabled(), b.preview_length()),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        };
+
+        // The first MIME type is the one preferred by the owner of the
+        // selection, X11 also lists pseudo-targets that must be skipped
+        let types = match display.types_command().output() {
+            Ok(o) => String::from_utf8_lossy(&o.stdout).to_string(),
+            Err(_) => return error!("Cannot list clipboard types"),
+        };
+
+        let mime_type = match types.lines().find(|t| t.contains("/")) {
+            Some(t) => t.trim().to_string(),
+            None => "".to_string(),
+        };
+
+        let content = match mime_type.is_empty() {
+            true => Vec::new(),
+            false => match display.content_command(&mime_type).output() {
+                Ok(o) => o.stdout,
+                Err(_) => return error!("Cannot read clipboard"),
+            },
+        };
+
+        let preview = match preview_enabled && mime_type.starts_with("text/") {
+            true => String::from_utf8_lossy(&content)
+                .chars()
+                .map(|c| if c.is_control() { ' ' } else { c })
+                .take(preview_length)
+                .collect(),
+
+            false => "".to_string(),
+        };
+
+        let mut backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        };
+
+        backend.update_data(content.len().to_string(), mime_type, preview);
+
+        return success!();
+    }
+}
+
+impl module::Data for ClipboardBackendProxy {
+    /// Update clipboard data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let display = Display::find()?;
+
+        self.update_selection(display)?;
+
+        match display {
+            // wl-paste runs the command each time the selection changes
+            Display::Wayland => {
+                let mut child = match process::Command::new("wl-paste")
+                    .args(&["--watch", "echo"])
+                    .stdout(process::Stdio::piped())
+                    .spawn() {
+
+                    Ok(c) => c,
+                    Err(_) => return error!("Cannot watch clipboard"),
+                };
+
+                let stdout = match child.stdout.take() {
+                    Some(s) => s,
+                    None => return error!("Cannot watch clipboard"),
+                };
+
+                // Stopping the module ends the watch
+                let stop = module::kill_on_stop(&child);
+
+                for line in BufReader::new(stdout).lines() {
+                    match line {
+                        Ok(_) => self.update_selection(display)?,
+                        Err(_) => break,
+                    }
+                }
+
+                drop(stop);
+
+                let _ = child.kill();
+                let _ = child.wait();
+
+                return error!("Clipboard watcher has exited");
+            },
+
+            // clipnotify exits each time the selection changes
+            Display::X11 => loop {
+                let mut child = match process::Command::new("clipnotify")
+                    .args(&["-s", "clipboard"])
+                    .stdout(process::Stdio::piped())
+                    .spawn() {
+
+                    Ok(c) => c,
+                    Err(_) => return error!("Cannot watch clipboard"),
+                };
+
+                // Stopping the module ends the watch
+                let stop = module::kill_on_stop(&child);
+
+                // The output is closed when clipnotify exits, the process is
+                // only reaped once the stop cannot kill it anymore
+                let mut output = Vec::new();
+
+                match child.stdout.take() {
+                    Some(mut s) => {
+                        let _ = s.read_to_end(&mut output);
+                    },
+
+                    None => (),
+                }
+
+                drop(stop);
+
+                match child.wait() {
+                    Ok(s) if s.success() => (),
+                    _ => return error!("Cannot watch clipboard"),
+                }
+
+                self.update_selection(display)?;
+            },
+        }
+    }
+
+    /// Update clipboard data once, not supported as it's event-driven
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update_once(&mut self)
+        -> Option<Result<module::Status, error::CerebroError>> {
+
+        return None;
+    }
+}
+
+/// Clipboard backend that will compute the values
+struct ClipboardBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+
+    pub data: ClipboardData,
+}
+
+impl ClipboardBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            data: ClipboardData::new(),
+        }
+    }
+
+    /// Check if the preview of the selection is enabled
+    fn preview_enabled(&self) -> bool {
+        return match &self.config.clipboard {
+            Some(c) => c.preview.unwrap_or(true),
+            None => true,
+        };
+    }
+
+    /// Get the maximum number of characters of the preview
+    fn preview_length(&self) -> usize {
+        return match &self.config.clipboard {
+            Some(c) => c.preview_length.unwrap_or(DEFAULT_PREVIEW_LENGTH),
+            None => DEFAULT_PREVIEW_LENGTH,
+        };
+    }
+
+    /// Update the data and call the triggers
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `length` - The length of the selection in bytes
+    /// * `mime_type` - The MIME type of the selection
+    /// * `preview` - The truncated selection
+    fn update_data(
+        &mut self,
+        length: String,
+        mime_type: String,
+        preview: String) {
+
+        let kind = match self.first_update {
+            true => triggers::Kind::Create,
+            false => triggers::Kind::Update,
+        };
+
+        self.first_update = false;
+
+        let values = vec![
+            (ENTRY_LENGTH, length),
+            (ENTRY_MIME_TYPE, mime_type),
+            (ENTRY_PREVIEW, preview),
+        ];
+
+        for (name, value) in values {
+            let current = match name {
+                ENTRY_LENGTH => &mut self.data.length,
+                ENTRY_MIME_TYPE => &mut self.data.mime_type,
+                _ => &mut self.data.preview,
+            };
+
+            if *current == value {
+                continue;
+            }
+
+            let old_value = current.clone();
+
+            *current = value;
+
+            log::debug!("{}: {}={}", MODULE_NAME, name, current);
+
+            triggers::find_all_and_execute(
+                &self.triggers,
+                kind,
+                &self.name,
+                name,
+                &old_value,
+                current);
+        }
+    }
+}
+
+/// Clipboard module structure
+pub struct Clipboard {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    inode_length: u64,
+    inode_mime_type: u64,
+    inode_preview: u64,
+    backend: Arc<Mutex<ClipboardBackend>>,
+    backend_proxy: Arc<Mutex<ClipboardBackendProxy>>,
+    fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl Clipboard {
+    /// Clipboard constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        let length = filesystem::FsEntry::create_inode();
+        let mime_type = filesystem::FsEntry::create_inode();
+        let preview = filesystem::FsEntry::create_inode();
+        let backend =
+            Arc::new(Mutex::new(ClipboardBackend::new(name, triggers)));
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            inode_length: length,
+            inode_mime_type: mime_type,
+            inode_preview: preview,
+            backend: backend.clone(),
+            backend_proxy: Arc::new(Mutex::new(
+                ClipboardBackendProxy::new(backend.clone()))),
+            fs_entries: vec![
+                filesystem::FsEntry::new(
+                    length,
+                    fuse::FileType::RegularFile,
+                    ENTRY_LENGTH,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    mime_type,
+                    fuse::FileType::RegularFile,
+                    ENTRY_MIME_TYPE,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    preview,
+                    fuse::FileType::RegularFile,
+                    ENTRY_PREVIEW,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+                ],
+        }
+    }
+}
+
+impl module::Module for Clipboard {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend_proxy.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        return self.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        if inode == self.inode_length {
+            return backend.data.length.clone();
+        }
+
+        if inode == self.inode_mime_type {
+            return backend.data.mime_type.clone();
+        }
+
+        if inode == self.inode_preview {
+            return backend.data.preview.clone();
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return format!(
+            "length={} mime_type={} preview={:?}",
+            backend.data.length,
+            backend.data.mime_type,
+            backend.data.preview).to_string();
+    }
+}
@@ -0,0 +1,635 @@
+use fuse;
+use serde::{Serialize};
+use std::fs;
+use std::path;
+use std::sync::Arc;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::notifications;
+use crate::platform;
+use crate::sync::Mutex;
+use crate::triggers;
+use crate::units;
+
+const MODULE_NAME: &str = "cooling";
+
+/// The package temperatures are provided by the CPU module
+const DEPENDENCIES: &[&str] = &["cpu"];
+
+const DEFAULT_WARM_C: u64 = 60;
+const DEFAULT_HOT_C: u64 = 80;
+const DEFAULT_HYSTERESIS_C: u64 = 5;
+
+/// Frequency ratio under which a hot CPU is considered as throttled
+const THROTTLING_FREQUENCY_PERCENT: u64 = 50;
+
+const STATE_NOMINAL: &str = "nominal";
+const STATE_WARM: &str = "warm";
+const STATE_HOT: &str = "hot";
+const STATE_THROTTLING: &str = "throttling";
+
+const VALUE_UNKNOWN: &str = "?";
+
+const ENTRY_FAN_RPM: &str = "fan_rpm";
+const ENTRY_FREQUENCY_PERCENT: &str = "frequency_percent";
+const ENTRY_STATE: &str = "state";
+const ENTRY_TEMPERATURE: &str = "temperature";
+
+/// Information about the cooling of the machine
+#[derive(Serialize)]
+struct CoolingData
+{
+    pub fan_rpm: String,
+    pub frequency_percent: String,
+    pub state: String,
+    pub temperature: String,
+}
+
+impl CoolingData {
+    /// CoolingData constructor
+    pub fn new() -> Self {
+        Self {
+            fan_rpm: VALUE_UNKNOWN.to_string(),
+            frequency_percent: VALUE_UNKNOWN.to_string(),
+            state: VALUE_UNKNOWN.to_string(),
+            temperature: VALUE_UNKNOWN.to_string(),
+        }
+    }
+}
+
+/// Read an integer from a sysfs file
+///
+/// # Arguments
+///
+/// * `path` - The path of the file
+pub fn read_u64(path: &path::Path) -> Option<u64> {
+    return match fs::read_to_string(path) {
+        Ok(s) => s.trim().parse::<u64>().ok(),
+        Err(_) => None,
+    };
+}
+
+/// List the files of a directory whose name matches a prefix and a suffix
+///
+/// # Arguments
+///
+/// * `dir` - The directory to be listed
+/// * `prefix` - The prefix of the names
+/// * `suffix` - The suffix of the names
+fn list(dir: &path::Path, prefix: &str, suffix: &str) -> Vec<path::PathBuf> {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_) => return Vec::new(),
+    };
+
+    return entries
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            name.starts_with(prefix) && name.ends_with(suffix)
+        })
+        .map(|e| e.path())
+        .collect();
+}
+
+/// List the entries of a sysfs directory matching a prefix and a suffix
+///
+/// # Arguments
+///
+/// * `dir` - The path of the directory relatively to the sysfs root
+/// * `prefix` - The prefix of the names of the entries
+/// * `suffix` - The suffix of the names of the entries
+pub fn list_sysfs(dir: &str, prefix: &str, suffix: &str)
+    -> Vec<path::PathBuf> {
+
+    return match platform::current().sysfs(dir) {
+        Some(d) => list(&d, prefix, suffix),
+        None => Vec::new(),
+    };
+}
+
+/// Cooling backend that will compute the values
+struct CoolingBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+    throttle_count: u64,
+    values: module::Values,
+
+    pub data: CoolingData,
+}
+
+impl CoolingBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            throttle_count: 0,
+            values: module::Values::new(name),
+            data: CoolingData::new(),
+        }
+    }
+
+    /// Get the thresholds (warm, hot, hysteresis) in degrees Celsius
+    fn thresholds(&self) -> (u64, u64, u64) {
+        return match &self.config.cooling {
+            Some(c) => (
+                c.warm_c.unwrap_or(DEFAULT_WARM_C),
+                c.hot_c.unwrap_or(DEFAULT_HOT_C),
+                c.hysteresis_c.unwrap_or(DEFAULT_HYSTERESIS_C)),
+
+            None => (DEFAULT_WARM_C, DEFAULT_HOT_C, DEFAULT_HYSTERESIS_C),
+        };
+    }
+
+    /// Get the highest temperature of the thermal zones (in degrees)
+    fn zones_temperature() -> Option<u64> {
+        return list_sysfs("class/thermal", "thermal_zone", "")
+            .iter()
+            .filter_map(|z| read_u64(&z.join("temp")))
+            .map(|t| t / 1000)
+            .max();
+    }
+
+    /// Get the highest temperature of the thermal zones and CPU packages
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn temperature(&self) -> Option<u64> {
+        // Temperatures published by the CPU module
+        let cpu = self.values
+            .list(&format!("{}/physical", DEPENDENCIES[0]))
+            .iter()
+            .filter(|(path, _)| path.ends_with("/temperature"))
+            .filter_map(|(_, value)| value.parse::<u64>().ok())
+            .max();
+
+        return match (CoolingBackend::zones_temperature(), cpu) {
+            (Some(z), Some(c)) => Some(z.max(c)),
+            (z, c) => z.or(c),
+        };
+    }
+
+    /// Get the speed of the fastest fan (in RPM)
+    fn fan_rpm() -> Option<u64> {
+        return list_sysfs("class/hwmon", "hwmon", "")
+            .iter()
+            .flat_map(|h| list(h, "fan", "_input"))
+            .filter_map(|f| read_u64(&f))
+            .max();
+    }
+
+    /// Get the average CPU frequency relatively to its maximum (in percent)
+    fn frequency_percent() -> Option<u64> {
+        let cpus = list_sysfs("devices/system/cpu", "cpu", "");
+
+        let ratios: Vec<u64> = cpus
+            .iter()
+            .filter_map(|c| {
+                let current = read_u64(&c.join("cpufreq/scaling_cur_freq"))?;
+                let max = read_u64(&c.join("cpufreq/cpuinfo_max_freq"))?;
+
+                match max {
+                    0 => None,
+                    _ => Some(current * 100 / max),
+                }
+            })
+            .collect();
+
+        return match ratios.len() {
+            0 => None,
+            n => Some(ratios.iter().sum::<u64>() / n as u64),
+        };
+    }
+
+    /// Get the number of thermal throttling events of the CPU packages
+    ///
+    /// Only available on some platforms (e.g. Intel).
+    fn throttle_count() -> u64 {
+        return list_sysfs("devices/system/cpu", "cpu", "")
+            .iter()
+            .filter_map(|c| {
+                read_u64(&c.join("thermal_throttle/package_throttle_count"))
+            })
+            .sum();
+    }
+
+    /// Compute the new state from the measures
+    ///
+    /// A state is entered when its threshold is reached and left only when
+    /// the temperature drops below the threshold minus the hysteresis, so
+    /// that a temperature oscillating around a threshold doesn't flap.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `temperature` - The highest temperature
+    /// * `throttling` - Whether the CPU is throttled
+    fn state(&self, temperature: u64, throttling: bool) -> &'static str {
+        let (warm, hot, hysteresis) = self.thresholds();
+
+        let (hot, warm) = match self.data.state.as_str() {
+            STATE_THROTTLING | STATE_HOT => (
+                hot.saturating_sub(hysteresis),
+                warm.saturating_sub(hysteresis)),
+
+            STATE_WARM => (hot, warm.saturating_sub(hysteresis)),
+            _ => (hot, warm),
+        };
+
+        if temperature >= hot {
+            return match throttling {
+                true => STATE_THROTTLING,
+                false => STATE_HOT,
+            };
+        }
+
+        if temperature >= warm {
+            return STATE_WARM;
+        }
+
+        return STATE_NOMINAL;
+    }
+
+    /// Send the notification of a hot machine
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `state` - The new state
+    /// * `temperature` - The highest temperature
+    fn notify_hot(&self, state: &str, temperature: u64) {
+        let notification = match &self.config.cooling {
+            Some(c) => match &c.notification {
+                Some(n) => n,
+                None => return,
+            },
+
+            None => return,
+        };
+
+        match notifications::send(notification, &[
+            (ENTRY_STATE, state),
+            (ENTRY_TEMPERATURE, &format!("{}", temperature))]) {
+
+            Ok(_) => (),
+            Err(e) => log::error!("{}: {}", MODULE_NAME, e),
+        }
+    }
+
+    /// Update a value and call the triggers
+    ///
+    /// # Arguments
+    ///
+    /// * `triggers` - The list of triggers
+    /// * `kind` - The kind of trigger to be called
+    /// * `module` - The name of the module
+    /// * `name` - The name of the entry
+    /// * `current` - The current value of the entry
+    /// * `value` - The new value of the entry
+    fn update_value(
+        triggers: &Vec<triggers::Trigger>,
+        kind: triggers::Kind,
+        module: &str,
+        name: &str,
+        current: &mut String,
+        value: String) {
+
+        if value == *current {
+            return;
+        }
+
+        let old_value = current.clone();
+
+        *current = value;
+
+        log::debug!("{}: {}={}", MODULE_NAME, name, current);
+
+        triggers::find_all_and_execute(
+            triggers,
+            kind,
+            module,
+            name,
+            &old_value,
+            current);
+    }
+}
+
+impl module::Data for CoolingBackend {
+    /// Update cooling data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let kind = match self.first_update {
+            true => triggers::Kind::Create,
+            false => triggers::Kind::Update,
+        };
+
+        let temperature = match self.temperature() {
+            Some(t) => t,
+            None => return error!("Cannot get thermal zones temperature"),
+        };
+
+        let fan_rpm = CoolingBackend::fan_rpm();
+        let frequency_percent = CoolingBackend::frequency_percent();
+
+        // Throttled if the kernel has reported new throttling events or if
+        // the frequency has dropped
+        let throttle_count = CoolingBackend::throttle_count();
+
+        let throttling = (!self.first_update &&
+            throttle_count > self.throttle_count) ||
+            match frequency_percent {
+                Some(f) => f < THROTTLING_FREQUENCY_PERCENT,
+                None => false,
+            };
+
+        self.throttle_count = throttle_count;
+
+        let state = self.state(temperature, throttling).to_string();
+
+        CoolingBackend::update_value(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_TEMPERATURE,
+            &mut self.data.temperature,
+            format!("{}", temperature));
+
+        CoolingBackend::update_value(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_FAN_RPM,
+            &mut self.data.fan_rpm,
+            match fan_rpm {
+                Some(f) => format!("{}", f),
+                None => VALUE_UNKNOWN.to_string(),
+            });
+
+        CoolingBackend::update_value(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_FREQUENCY_PERCENT,
+            &mut self.data.frequency_percent,
+            match frequency_percent {
+                Some(f) => format!("{}", f),
+                None => VALUE_UNKNOWN.to_string(),
+            });
+
+        let is_hot = |s: &str| s == STATE_HOT || s == STATE_THROTTLING;
+
+        if is_hot(&state) && !is_hot(&self.data.state) {
+            self.notify_hot(&state, temperature);
+        }
+
+        CoolingBackend::update_value(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_STATE,
+            &mut self.data.state,
+            state);
+
+        self.first_update = false;
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// Cooling module structure
+pub struct Cooling {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    inode_fan_rpm: u64,
+    inode_frequency_percent: u64,
+    inode_state: u64,
+    inode_temperature: u64,
+    backend: Arc<Mutex<CoolingBackend>>,
+    fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl Cooling {
+    /// Cooling constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        let fan_rpm = filesystem::FsEntry::create_inode();
+        let frequency_percent = filesystem::FsEntry::create_inode();
+        let state = filesystem::FsEntry::create_inode();
+        let temperature = filesystem::FsEntry::create_inode();
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            inode_fan_rpm: fan_rpm,
+            inode_frequency_percent: frequency_percent,
+            inode_state: state,
+            inode_temperature: temperature,
+            backend: Arc::new(Mutex::new(CoolingBackend::new(name, triggers))),
+            fs_entries: vec![
+                filesystem::FsEntry::new(
+                    fan_rpm,
+                    fuse::FileType::RegularFile,
+                    ENTRY_FAN_RPM,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    frequency_percent,
+                    fuse::FileType::RegularFile,
+                    ENTRY_FREQUENCY_PERCENT,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new())
+                    .with_unit(units::Unit::Percent),
+
+                filesystem::FsEntry::new(
+                    state,
+                    fuse::FileType::RegularFile,
+                    ENTRY_STATE,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    temperature,
+                    fuse::FileType::RegularFile,
+                    ENTRY_TEMPERATURE,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new())
+                    .with_unit(units::Unit::Celsius),
+                ],
+        }
+    }
+}
+
+impl module::Module for Cooling {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Get the names of the modules consumed by this one
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn dependencies(&self) -> &[&str] {
+        return DEPENDENCIES;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        return self.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        if inode == self.inode_fan_rpm {
+            return backend.data.fan_rpm.clone();
+        }
+
+        if inode == self.inode_frequency_percent {
+            return backend.data.frequency_percent.clone();
+        }
+
+        if inode == self.inode_state {
+            return backend.data.state.clone();
+        }
+
+        if inode == self.inode_temperature {
+            return backend.data.temperature.clone();
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return format!(
+            "fan_rpm={} frequency_percent={} state={} temperature={}",
+            backend.data.fan_rpm,
+            backend.data.frequency_percent,
+            backend.data.state,
+            backend.data.temperature).to_string();
+    }
+}
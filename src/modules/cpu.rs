@@ -1,24 +1,32 @@
 use fuse;
 use regex::Regex;
-use sensors::{FeatureType, Sensors, SubfeatureType};
 use serde::{Serialize};
-use std::sync::{Arc, Mutex};
+use std::fs;
+use std::sync::Arc;
 use std::time::SystemTime;
 use systemstat::{CPULoad, DelayedMeasurement, Platform};
 
+use crate::bus;
 use crate::config;
 use crate::error;
 use crate::event_manager;
 use crate::filesystem;
 use crate::modules::module;
+use crate::platform;
+use crate::publish;
+use crate::sync::Mutex;
 use crate::triggers;
+use crate::units;
 
 const MODULE_NAME: &str = "cpu";
 
 const ENTRY_AVERRAGE: &str = "averrage";
 const ENTRY_COUNT: &str = "count";
 const ENTRY_LOGICAL: &str = "logical";
+const ENTRY_LOGICAL_COUNT: &str = "logical_count";
+const ENTRY_PACKAGE: &str = "package";
 const ENTRY_PHYSICAL: &str = "physical";
+const ENTRY_PHYSICAL_COUNT: &str = "physical_count";
 const ENTRY_TEMPERATURE: &str = "temperature";
 const ENTRY_TIMESTAMP: &str = "timestamp";
 const ENTRY_USAGE: &str = "usage_percent";
@@ -58,6 +66,49 @@ impl PhysicalData {
     }
 }
 
+/// Information of one CPU package (socket)
+#[derive(Debug, PartialEq, Serialize)]
+struct PackageData {
+    pub temperature: String,
+    pub usage_percent: String,
+    pub logical_count: String,
+    pub physical_count: String,
+}
+
+impl PackageData {
+    /// PackageData constructor
+    pub fn new() -> Self {
+        Self {
+            temperature: VALUE_UNKNOWN.to_string(),
+            usage_percent: VALUE_UNKNOWN.to_string(),
+            logical_count: VALUE_UNKNOWN.to_string(),
+            physical_count: VALUE_UNKNOWN.to_string(),
+        }
+    }
+}
+
+/// Get the package of a logical CPU
+///
+/// Defaults to the first package when the topology isn't available.
+///
+/// # Arguments
+///
+/// * `cpu` - The index of the logical CPU
+fn logical_cpu_package(cpu: usize) -> usize {
+    let path = match platform::current().sysfs(&format!(
+        "devices/system/cpu/cpu{}/topology/physical_package_id",
+        cpu)) {
+
+        Some(p) => p,
+        None => return 0,
+    };
+
+    return match fs::read_to_string(path) {
+        Ok(s) => s.trim().parse::<usize>().unwrap_or(0),
+        Err(_) => 0,
+    };
+}
+
 /// Information about the list of CPU
 #[derive(Serialize)]
 struct CpuListData {
@@ -69,6 +120,9 @@ struct CpuListData {
     pub physical_timestamp: String,
     pub physical_count: String,
     pub physical_list: Vec<PhysicalData>,
+
+    pub package_count: String,
+    pub package_list: Vec<PackageData>,
 }
 
 impl CpuListData {
@@ -82,6 +136,8 @@ impl CpuListData {
             physical_timestamp: "0".to_string(),
             physical_count: "0".to_string(),
             physical_list: Vec::new(),
+            package_count: "0".to_string(),
+            package_list: Vec::new(),
         }
     }
 }
@@ -91,7 +147,11 @@ struct CpuBackend {
     config: config::ModuleConfig,
     system_stats: systemstat::System,
     cpu_stats: Option<DelayedMeasurement<Vec<CPULoad>>>,
+    name: String,
     triggers: Vec<triggers::Trigger>,
+    logical_packages: Vec<usize>,
+    package_temperatures: Vec<Vec<u8>>,
+    last_loads: Vec<CPULoad>,
 
     pub inode_logical_timestamp: u64,
     pub inode_physical_timestamp: u64,
@@ -99,15 +159,17 @@ struct CpuBackend {
     pub inode_logical_averrage_usage: u64,
     pub inode_logical_count: u64,
     pub inode_physical_count: u64,
+    pub inode_package_count: u64,
     pub data: CpuListData,
     pub static_fs_entries: Vec<filesystem::FsEntry>,
     pub logical_fs_entries: Vec<filesystem::FsEntry>,
     pub physical_fs_entries: Vec<filesystem::FsEntry>,
+    pub package_fs_entries: Vec<filesystem::FsEntry>,
 }
 
 impl CpuBackend {
     /// CpuBackend constructor
-    fn new(triggers: &Vec<triggers::Trigger>) -> Self {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
         let logical = filesystem::FsEntry::create_inode();
         let logical_averrage = filesystem::FsEntry::create_inode();
         let logical_averrage_usage = filesystem::FsEntry::create_inode();
@@ -116,18 +178,25 @@ impl CpuBackend {
         let physical = filesystem::FsEntry::create_inode();
         let physical_count = filesystem::FsEntry::create_inode();
         let physical_timestamp = filesystem::FsEntry::create_inode();
+        let package = filesystem::FsEntry::create_inode();
+        let package_count = filesystem::FsEntry::create_inode();
 
         Self {
             config: config::ModuleConfig::new(),
             system_stats: systemstat::System::new(),
             cpu_stats: None,
+            name: name.to_string(),
             triggers: triggers.to_vec(),
+            logical_packages: Vec::new(),
+            package_temperatures: Vec::new(),
+            last_loads: Vec::new(),
             inode_logical_timestamp: logical_timestamp,
             inode_physical_timestamp: physical_timestamp,
             inode_logical_averrage: logical_averrage,
             inode_logical_averrage_usage: logical_averrage_usage,
             inode_logical_count: logical_count,
             inode_physical_count: physical_count,
+            inode_package_count: package_count,
             data: CpuListData::new(),
             static_fs_entries: vec![
                 filesystem::FsEntry::new(
@@ -147,7 +216,8 @@ impl CpuBackend {
                                     fuse::FileType::RegularFile,
                                     ENTRY_USAGE,
                                     filesystem::Mode::ReadOnly,
-                                    &Vec::new()),
+                                    &Vec::new())
+                                    .with_unit(units::Unit::Percent),
                             ]),
 
                         filesystem::FsEntry::new(
@@ -185,9 +255,24 @@ impl CpuBackend {
                             filesystem::Mode::ReadOnly,
                             &Vec::new())
                     ]),
+
+                filesystem::FsEntry::new(
+                    package,
+                    fuse::FileType::Directory,
+                    ENTRY_PACKAGE,
+                    filesystem::Mode::ReadOnly,
+                    &vec![
+                        filesystem::FsEntry::new(
+                            package_count,
+                            fuse::FileType::RegularFile,
+                            ENTRY_COUNT,
+                            filesystem::Mode::ReadOnly,
+                            &Vec::new()),
+                    ]),
                 ],
             logical_fs_entries: Vec::new(),
             physical_fs_entries: Vec::new(),
+            package_fs_entries: Vec::new(),
         }
     }
 
@@ -208,7 +293,6 @@ impl CpuBackend {
         log::info!("Update physical CPU data");
 
         let mut status = module::Status::Ok;
-        let mut core_temperatures: Vec<u8> = Vec::new();
 
         let temperature_config = match &self.config.temperature {
             Some(c) => c,
@@ -230,45 +314,11 @@ impl CpuBackend {
             Err(_) => return error!("Cannot build regex"),
         };
 
-        // Get CPU temperatures
-        for chip in Sensors::new() {
-            if chip.prefix() != device {
-                continue;
-            }
+        // Get CPU temperatures, cores are ordered by package
+        self.package_temperatures =
+            platform::current().package_temperatures(device, &re_pattern);
 
-            // Search for a temperature feature
-            for feature in chip {
-                match feature.feature_type() {
-                    FeatureType::SENSORS_FEATURE_TEMP => (),
-                    _ => continue,
-                }
-
-                if ! re_pattern.is_match(feature.name()) {
-                    continue;
-                }
-
-                // Search for a temperature subfeature
-                for subfeature in feature {
-                    match subfeature.subfeature_type() {
-                        SubfeatureType::SENSORS_SUBFEATURE_TEMP_INPUT => (),
-                        _ => continue,
-                    }
-
-                    let value = match subfeature.get_value() {
-                        Ok(v) => v as u8,
-                        Err(_) => continue,
-                    };
-
-                    if value == 0 {
-                        // Not a valid temperature
-                        continue;
-                    }
-
-                    core_temperatures.push(value);
-                    break;
-                }
-            }
-        }
+        let core_temperatures = self.package_temperatures.concat();
 
         // Update CPU count if needed
         let cpu_count = core_temperatures.len();
@@ -283,7 +333,7 @@ impl CpuBackend {
             triggers::find_all_and_execute(
                 &self.triggers,
                 triggers::Kind::Update,
-                MODULE_NAME,
+                &self.name,
                 &format!("{}/{}", ENTRY_PHYSICAL, ENTRY_COUNT),
                 &old_value,
                 &self.data.physical_count);
@@ -292,8 +342,15 @@ impl CpuBackend {
         // Rebuild CPU list
         self.data.physical_list.clear();
 
-        for c in core_temperatures {
-            self.data.physical_list.push(PhysicalData::new(c as i16));
+        for (index, c) in core_temperatures.iter().enumerate() {
+            let data = PhysicalData::new(*c as i16);
+
+            bus::publish(
+                &config::module_path(&self.name),
+                &format!("{}/{}/{}", ENTRY_PHYSICAL, index, ENTRY_TEMPERATURE),
+                &data.temperature);
+
+            self.data.physical_list.push(data);
         }
 
         // Rebuild filesystem entries if needed
@@ -314,7 +371,8 @@ impl CpuBackend {
                                     fuse::FileType::RegularFile,
                                     ENTRY_TEMPERATURE,
                                     filesystem::Mode::ReadOnly,
-                                    &Vec::new()),
+                                    &Vec::new())
+                                    .with_unit(units::Unit::Celsius),
                             ]));
                 }
             },
@@ -341,7 +399,7 @@ impl CpuBackend {
         triggers::find_all_and_execute(
             &self.triggers,
             triggers::Kind::Update,
-            MODULE_NAME,
+            &self.name,
             &format!("{}/{}", ENTRY_PHYSICAL, ENTRY_TIMESTAMP),
             &old_value,
             &self.data.physical_timestamp);
@@ -390,6 +448,8 @@ impl CpuBackend {
         // Restart a monitoring
         self.start_monitoring()?;
 
+        self.last_loads = cpu;
+
         return Ok(status);
     }
 
@@ -407,7 +467,7 @@ impl CpuBackend {
         triggers::find_all_and_execute(
             &self.triggers,
             triggers::Kind::Update,
-            MODULE_NAME,
+            &self.name,
             &format!("{}/{}", ENTRY_LOGICAL, ENTRY_TIMESTAMP),
             &old_value,
             &self.data.logical_timestamp);
@@ -429,25 +489,14 @@ impl CpuBackend {
 
         let averrage = format!("{}", sum / (cpu_count as f32));
 
-        if self.data.logical_averrage_usage == averrage {
-            return success!();
-        }
-
-        // Update data
-        let old_value = self.data.logical_averrage_usage.clone();
-
-        self.data.logical_averrage_usage = format!("{}", averrage);
-
-        log::debug!("CPU usage averrage: {}", averrage);
-
-        // Call triggers if needed
-        triggers::find_all_and_execute(
+        // Update data and call triggers if needed
+        publish::update(
             &self.triggers,
             triggers::Kind::Update,
-            MODULE_NAME,
+            &self.name,
             &format!("{}/{}/{}", ENTRY_LOGICAL, ENTRY_AVERRAGE, ENTRY_USAGE),
-            &old_value,
-            &self.data.logical_averrage_usage);
+            &mut self.data.logical_averrage_usage,
+            &averrage);
 
         return success!();
     }
@@ -473,7 +522,7 @@ impl CpuBackend {
         triggers::find_all_and_execute(
             &self.triggers,
             triggers::Kind::Update,
-            MODULE_NAME,
+            &self.name,
             &format!("{}/{}", ENTRY_LOGICAL, ENTRY_COUNT),
             &old_value,
             &self.data.logical_count);
@@ -490,7 +539,7 @@ impl CpuBackend {
             triggers::find_all_and_execute(
                 &self.triggers,
                 triggers::Kind::Delete,
-                MODULE_NAME,
+                &self.name,
                 &format!("{}/{}/{}", ENTRY_LOGICAL, index, ENTRY_USAGE),
                 "",
                 "");
@@ -503,12 +552,16 @@ impl CpuBackend {
             self.data.logical_list.push(LogicalData::new(c.user));
         }
 
+        self.logical_packages = (0..cpu_list.len())
+            .map(logical_cpu_package)
+            .collect();
+
         // Call create triggers
         for (index, _data) in self.data.logical_list.iter().enumerate() {
             triggers::find_all_and_execute(
                 &self.triggers,
                 triggers::Kind::Create,
-                MODULE_NAME,
+                &self.name,
                 &format!("{}/{}/{}", ENTRY_LOGICAL, index, ENTRY_USAGE),
                 "",
                 "");
@@ -528,27 +581,185 @@ impl CpuBackend {
         for (index, cpu) in cpu_list.iter().enumerate() {
             let data = LogicalData::new(cpu.user);
 
-            if self.data.logical_list[index] == data {
-                continue;
-            }
-
-            let old_value = self.data.logical_list[index].usage_percent.clone();
-
-            self.data.logical_list[index] = data;
-
-            // Call update trigger
-            triggers::find_all_and_execute(
+            // Update data and call triggers if needed
+            publish::update(
                 &self.triggers,
                 triggers::Kind::Update,
-                MODULE_NAME,
+                &self.name,
                 &format!("{}/{}/{}", ENTRY_LOGICAL, index, ENTRY_USAGE),
-                &old_value,
-                &self.data.logical_list[index].usage_percent);
+                &mut self.data.logical_list[index].usage_percent,
+                &data.usage_percent);
         }
 
         return success!();
     }
 
+    /// Update packages data and filesystem
+    ///
+    /// Logical CPUs are associated to their package using the topology and
+    /// the temperatures using the sensor chip of each package.
+    fn update_packages(&mut self)
+        -> Result<module::Status, error::CerebroError> {
+
+        let mut status = module::Status::Ok;
+
+        let package_count = std::cmp::max(
+            self.package_temperatures.len(),
+            match self.logical_packages.iter().max() {
+                Some(p) => p + 1,
+                None => 0,
+            });
+
+        if self.data.package_list.len() != package_count {
+            status = self.rebuild_packages(package_count);
+        }
+
+        let kind = match status {
+            module::Status::Changed(_) => triggers::Kind::Create,
+            _ => triggers::Kind::Update,
+        };
+
+        for index in 0..package_count {
+            let (temperature, physical_count) =
+                match self.package_temperatures.get(index) {
+                    Some(t) => (
+                        match t.iter().max() {
+                            Some(m) => format!("{}", m),
+                            None => VALUE_UNKNOWN.to_string(),
+                        },
+                        format!("{}", t.len())),
+
+                    None => (
+                        VALUE_UNKNOWN.to_string(),
+                        VALUE_UNKNOWN.to_string()),
+                };
+
+            let usages: Vec<f32> = self.data.logical_list
+                .iter()
+                .zip(self.logical_packages.iter())
+                .filter(|(_, p)| **p == index)
+                .filter_map(|(l, _)| l.usage_percent.parse::<f32>().ok())
+                .collect();
+
+            let usage_percent = match usages.len() {
+                0 => VALUE_UNKNOWN.to_string(),
+                n => format!("{}", usages.iter().sum::<f32>() / n as f32),
+            };
+
+            let logical_count = format!("{}", usages.len());
+
+            let data = &mut self.data.package_list[index];
+
+            let values = [
+                (ENTRY_TEMPERATURE, &mut data.temperature, temperature),
+                (ENTRY_USAGE, &mut data.usage_percent, usage_percent),
+                (ENTRY_LOGICAL_COUNT, &mut data.logical_count, logical_count),
+                (ENTRY_PHYSICAL_COUNT, &mut data.physical_count,
+                    physical_count),
+            ];
+
+            for (name, current, value) in values {
+                publish::update(
+                    &self.triggers,
+                    kind,
+                    &self.name,
+                    &format!("{}/{}/{}", ENTRY_PACKAGE, index, name),
+                    current,
+                    &value);
+            }
+        }
+
+        return Ok(status);
+    }
+
+    /// Rebuild packages data and filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `package_count` - The number of packages
+    fn rebuild_packages(&mut self, package_count: usize) -> module::Status {
+        let names = [
+            ENTRY_TEMPERATURE,
+            ENTRY_USAGE,
+            ENTRY_LOGICAL_COUNT,
+            ENTRY_PHYSICAL_COUNT,
+        ];
+
+        // Call delete triggers
+        for index in 0..self.data.package_list.len() {
+            for name in names.iter() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Delete,
+                    &self.name,
+                    &format!("{}/{}/{}", ENTRY_PACKAGE, index, name),
+                    "",
+                    "");
+            }
+        }
+
+        let old_value = self.data.package_count.clone();
+
+        self.data.package_count = format!("{}", package_count);
+
+        triggers::find_all_and_execute(
+            &self.triggers,
+            triggers::Kind::Update,
+            &self.name,
+            &format!("{}/{}", ENTRY_PACKAGE, ENTRY_COUNT),
+            &old_value,
+            &self.data.package_count);
+
+        // Rebuild list and filesystem entries
+        self.data.package_list.clear();
+        self.package_fs_entries.clear();
+
+        for i in 0..package_count {
+            self.data.package_list.push(PackageData::new());
+
+            self.package_fs_entries.push(
+                filesystem::FsEntry::new(
+                    filesystem::FsEntry::create_inode(),
+                    fuse::FileType::Directory,
+                    &format!("{}", i),
+                    filesystem::Mode::ReadOnly,
+                    &vec![
+                        filesystem::FsEntry::new(
+                            filesystem::FsEntry::create_inode(),
+                            fuse::FileType::RegularFile,
+                            ENTRY_TEMPERATURE,
+                            filesystem::Mode::ReadOnly,
+                            &Vec::new())
+                            .with_unit(units::Unit::Celsius),
+
+                        filesystem::FsEntry::new(
+                            filesystem::FsEntry::create_inode(),
+                            fuse::FileType::RegularFile,
+                            ENTRY_USAGE,
+                            filesystem::Mode::ReadOnly,
+                            &Vec::new())
+                            .with_unit(units::Unit::Percent),
+
+                        filesystem::FsEntry::new(
+                            filesystem::FsEntry::create_inode(),
+                            fuse::FileType::RegularFile,
+                            ENTRY_LOGICAL_COUNT,
+                            filesystem::Mode::ReadOnly,
+                            &Vec::new()),
+
+                        filesystem::FsEntry::new(
+                            filesystem::FsEntry::create_inode(),
+                            fuse::FileType::RegularFile,
+                            ENTRY_PHYSICAL_COUNT,
+                            filesystem::Mode::ReadOnly,
+                            &Vec::new()),
+                    ]));
+        }
+
+        return module::Status::Changed(MODULE_NAME.to_string());
+    }
+
     /// Rebuild logical CPU filesystem
     fn rebuild_logical_filesystem(&mut self, cpu_count: usize)
         -> error::Return {
@@ -568,12 +779,111 @@ impl CpuBackend {
                             fuse::FileType::RegularFile,
                             ENTRY_USAGE,
                             filesystem::Mode::ReadOnly,
-                            &Vec::new()),
+                            &Vec::new())
+                            .with_unit(units::Unit::Percent),
                     ]));
         }
 
         return success!();
     }
+
+    /// Get the value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem entry
+    fn value(&self, inode: u64) -> String {
+        if inode == self.inode_logical_timestamp {
+            return self.data.logical_timestamp.clone();
+        }
+
+        if inode == self.inode_logical_count {
+            return self.data.logical_count.clone();
+        }
+
+        if inode == self.inode_physical_timestamp {
+            return self.data.physical_timestamp.clone();
+        }
+
+        if inode == self.inode_physical_count {
+            return self.data.physical_count.clone();
+        }
+
+        if inode == self.inode_package_count {
+            return self.data.package_count.clone();
+        }
+
+        // Search index of entry in logical entries
+        for (index, entry) in self.logical_fs_entries.iter().enumerate() {
+            let entry = match entry.find(inode) {
+                Some(e) => e,
+                None => continue,
+            };
+
+            // Entry found, check if index exists
+            if index >= self.data.logical_list.len() {
+                return VALUE_UNKNOWN.to_string();
+            }
+
+            // Get data
+            let cpu_data = &self.data.logical_list[index];
+
+            match entry.name.as_str() {
+                ENTRY_USAGE => return cpu_data.usage_percent.to_string(),
+                _ => return VALUE_UNKNOWN.to_string(),
+            }
+        }
+
+        // Search index of entry in physical entries
+        for (index, entry) in self.physical_fs_entries.iter().enumerate() {
+            let entry = match entry.find(inode) {
+                Some(e) => e,
+                None => continue,
+            };
+
+            // Entry found, check if index exists
+            if index >= self.data.physical_list.len() {
+                return VALUE_UNKNOWN.to_string();
+            }
+
+            // Get data
+            let cpu_data = &self.data.physical_list[index];
+
+            match entry.name.as_str() {
+                ENTRY_TEMPERATURE => return cpu_data.temperature.to_string(),
+                _ => return VALUE_UNKNOWN.to_string(),
+            }
+        }
+
+        // Search index of entry in package entries
+        for (index, entry) in self.package_fs_entries.iter().enumerate() {
+            let entry = match entry.find(inode) {
+                Some(e) => e,
+                None => continue,
+            };
+
+            // Entry found, check if index exists
+            if index >= self.data.package_list.len() {
+                return VALUE_UNKNOWN.to_string();
+            }
+
+            // Get data
+            let package_data = &self.data.package_list[index];
+
+            match entry.name.as_str() {
+                ENTRY_TEMPERATURE => return package_data.temperature.clone(),
+                ENTRY_USAGE => return package_data.usage_percent.clone(),
+                ENTRY_LOGICAL_COUNT =>
+                    return package_data.logical_count.clone(),
+                ENTRY_PHYSICAL_COUNT =>
+                    return package_data.physical_count.clone(),
+                _ => return VALUE_UNKNOWN.to_string(),
+            }
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
 }
 
 impl module::Data for CpuBackend {
@@ -596,10 +906,23 @@ impl module::Data for CpuBackend {
             _ => (),
         }
 
-        // Physical
-        let status_physical = self.update_physical()?;
+        // Physical, only when temperatures are configured
+        if self.config.temperature.is_some() {
+            let status_physical = self.update_physical()?;
+
+            match status_physical {
+                module::Status::Changed(_) => {
+                    status = module::Status::Changed(MODULE_NAME.to_string())
+                },
+
+                _ => (),
+            }
+        }
 
-        match status_physical {
+        // Packages
+        let status_packages = self.update_packages()?;
+
+        match status_packages {
             module::Status::Changed(_) => {
                 status = module::Status::Changed(MODULE_NAME.to_string())
             },
@@ -609,10 +932,70 @@ impl module::Data for CpuBackend {
 
         return Ok(status);
     }
+
+    /// Update some cpu data at high frequency
+    ///
+    /// The load is measured over the interval between two updates, so
+    /// the logical entries are updated together. Packages are computed from
+    /// both the logical and the physical entries.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `entries` - The paths of the entries to be updated
+    fn update_fast(&mut self, entries: &Vec<String>)
+        -> Option<Result<module::Status, error::CerebroError>> {
+
+        let logical = entries
+            .iter()
+            .any(|e| e.trim_matches('/').starts_with(ENTRY_LOGICAL));
+
+        let physical = entries
+            .iter()
+            .any(|e| e.trim_matches('/').starts_with(ENTRY_PHYSICAL));
+
+        let package = entries
+            .iter()
+            .any(|e| e.trim_matches('/').starts_with(ENTRY_PACKAGE));
+
+        if logical || package {
+            match self.update_logical() {
+                Ok(module::Status::Ok) => (),
+                result => return Some(result),
+            }
+        }
+
+        if (physical || package) && self.config.temperature.is_some() {
+            match self.update_physical() {
+                Ok(module::Status::Ok) => (),
+                result => return Some(result),
+            }
+        }
+
+        if package {
+            return Some(self.update_packages());
+        }
+
+        return Some(Ok(module::Status::Ok));
+    }
+
+    /// Get the loads and the temperatures of the last update
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn raw(&self) -> Option<String> {
+        return Some(format!(
+            "loads: {:#?}
+package_temperatures: {:?}",
+            self.last_loads,
+            self.package_temperatures));
+    }
 }
 
 /// Cpu module structure
 pub struct Cpu {
+    name: String,
     thread: Arc<Mutex<module::Thread>>,
     backend: Arc<Mutex<CpuBackend>>,
 }
@@ -620,14 +1003,16 @@ pub struct Cpu {
 impl Cpu {
     /// Cpu constructor
     pub fn new(
+        name: &str,
         event_manager: &mut event_manager::EventManager,
         triggers: &Vec<triggers::Trigger>) -> Self {
 
         Self {
+            name: name.to_string(),
             thread: Arc::new(Mutex::new(
-                module::Thread::new(event_manager.sender()))),
+                module::Thread::new(name, event_manager.sender()))),
 
-            backend: Arc::new(Mutex::new(CpuBackend::new(triggers))),
+            backend: Arc::new(Mutex::new(CpuBackend::new(name, triggers))),
         }
     }
 }
@@ -639,7 +1024,7 @@ impl module::Module for Cpu {
     ///
     /// * `self` - The instance handle
     fn name(&self) -> &str {
-        return MODULE_NAME;
+        return &self.name;
     }
 
     /// Start the module
@@ -650,17 +1035,17 @@ impl module::Module for Cpu {
     fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
         let mut backend = match self.backend.lock() {
             Ok(b) => b,
-            Err(_) => return error!("Cannot lock backend"),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
         };
 
         backend.config = config.clone();
 
         let mut thread = match self.thread.lock() {
             Ok(t) => t,
-            Err(_) => return error!("Cannot lock thread"),
+            Err(_) => return error!(Lock, "Cannot lock thread"),
         };
 
-        thread.start(self.backend.clone(), config.timeout_s)?;
+        thread.start(self.backend.clone(), config)?;
 
         return success!();
     }
@@ -673,7 +1058,7 @@ impl module::Module for Cpu {
     fn stop(&mut self) -> error::Return {
         let mut thread = match self.thread.lock() {
             Ok(t) => t,
-            Err(_) => return error!("Cannot lock thread"),
+            Err(_) => return error!(Lock, "Cannot lock thread"),
         };
 
         thread.stop()?;
@@ -706,6 +1091,7 @@ impl module::Module for Cpu {
                 let mut entries = b.static_fs_entries.to_vec();
                 entries[0].fs_entries.extend(b.logical_fs_entries.to_vec());
                 entries[1].fs_entries.extend(b.physical_fs_entries.to_vec());
+                entries[2].fs_entries.extend(b.package_fs_entries.to_vec());
                 return entries;
             },
 
@@ -720,70 +1106,25 @@ impl module::Module for Cpu {
     /// * `self` - The instance handle
     /// * `inode` - The inode of the filesystem to be fetched
     fn value(&self, inode: u64) -> String {
+        return match self.backend.lock() {
+            Ok(b) => b.value(inode),
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        };
+    }
+
+    /// Get the values of several entries from the same update
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inodes` - The inodes of the entries
+    fn values(&self, inodes: &[u64]) -> Vec<String> {
         let backend = match self.backend.lock() {
             Ok(b) => b,
-            Err(_) => return VALUE_UNKNOWN.to_string(),
+            Err(_) => return vec![VALUE_UNKNOWN.to_string(); inodes.len()],
         };
 
-        if inode == backend.inode_logical_timestamp {
-            return backend.data.logical_timestamp.clone();
-        }
-
-        if inode == backend.inode_logical_count {
-            return backend.data.logical_count.clone();
-        }
-
-        if inode == backend.inode_physical_timestamp {
-            return backend.data.physical_timestamp.clone();
-        }
-
-        if inode == backend.inode_physical_count {
-            return backend.data.physical_count.clone();
-        }
-
-        // Search index of entry in logical entries
-        for (index, entry) in backend.logical_fs_entries.iter().enumerate() {
-            let entry = match entry.find(inode) {
-                Some(e) => e,
-                None => continue,
-            };
-
-            // Entry found, check if index exists
-            if index >= backend.data.logical_list.len() {
-                return VALUE_UNKNOWN.to_string();
-            }
-
-            // Get data
-            let cpu_data = &backend.data.logical_list[index];
-
-            match entry.name.as_str() {
-                ENTRY_USAGE => return cpu_data.usage_percent.to_string(),
-                _ => return VALUE_UNKNOWN.to_string(),
-            }
-        }
-
-        // Search index of entry in physical entries
-        for (index, entry) in backend.physical_fs_entries.iter().enumerate() {
-            let entry = match entry.find(inode) {
-                Some(e) => e,
-                None => continue,
-            };
-
-            // Entry found, check if index exists
-            if index >= backend.data.physical_list.len() {
-                return VALUE_UNKNOWN.to_string();
-            }
-
-            // Get data
-            let cpu_data = &backend.data.physical_list[index];
-
-            match entry.name.as_str() {
-                ENTRY_TEMPERATURE => return cpu_data.temperature.to_string(),
-                _ => return VALUE_UNKNOWN.to_string(),
-            }
-        }
-
-        return VALUE_UNKNOWN.to_string();
+        return inodes.iter().map(|i| backend.value(*i)).collect();
     }
 
     /// Set value of a filesystem entry
@@ -832,6 +1173,8 @@ impl module::Module for Cpu {
         output +=
             &format!(" physical_cpu_count={}", backend.data.physical_count);
 
+        output += &format!(" package_count={}", backend.data.package_count);
+
         for (index, cpu) in backend.data.logical_list.iter().enumerate() {
             output += &format!(
                 " logical_cpu_{}_usage={}",
@@ -846,6 +1189,15 @@ impl module::Module for Cpu {
                 cpu.temperature);
         }
 
+        for (index, package) in backend.data.package_list.iter().enumerate() {
+            output += &format!(
+                " package_{}_temperature={} package_{}_usage={}",
+                index,
+                package.temperature,
+                index,
+                package.usage_percent);
+        }
+
         return output;
     }
 }
@@ -0,0 +1,542 @@
+use fuse;
+use std::fs;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use crate::bus;
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::files;
+use crate::modules::module;
+use crate::publish;
+use crate::sync::Mutex;
+use crate::triggers;
+use crate::units;
+
+const MODULE_NAME: &str = "deadman";
+
+const ENTRY_AGE_S: &str = "age_s";
+const ENTRY_EXPIRED: &str = "expired";
+const ENTRY_HEARTBEAT: &str = "heartbeat";
+
+const ENTRIES: [&str; 3] = [ENTRY_EXPIRED, ENTRY_AGE_S, ENTRY_HEARTBEAT];
+
+const VALUE_FALSE: &str = "false";
+const VALUE_TRUE: &str = "true";
+const VALUE_UNKNOWN: &str = "?";
+
+/// Function used to get the number of seconds since the epoch of a time
+///
+/// # Arguments
+///
+/// * `time` - The time
+fn epoch_s(time: SystemTime) -> Option<u64> {
+    return match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(d) => Some(d.as_secs()),
+        Err(_) => None,
+    };
+}
+
+/// Thing that must happen regularly
+struct Check {
+    pub name: String,
+    pub config: config::DeadmanCheckConfig,
+    pub expired: String,
+    pub age_s: String,
+    pub heartbeat: String,
+    pub fs_entry: filesystem::FsEntry,
+
+    /// Time of the last change of the entry or of the last heartbeat
+    since: Option<SystemTime>,
+
+    /// Value of the entry at the previous evaluation
+    value: Option<String>,
+}
+
+impl Check {
+    /// Get the value of one of the entries
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `name` - The name of the entry
+    fn value(&self, name: &str) -> String {
+        return match name {
+            ENTRY_EXPIRED => self.expired.clone(),
+            ENTRY_AGE_S => self.age_s.clone(),
+            ENTRY_HEARTBEAT => self.heartbeat.clone(),
+            _ => VALUE_UNKNOWN.to_string(),
+        };
+    }
+
+    /// Get the time of the last activity
+    ///
+    /// This is the most recent of the last change of the entry, of the
+    /// last heartbeat and of the modification time of the file.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn last_activity(&self) -> Option<SystemTime> {
+        let mtime = match &self.config.file {
+            Some(f) => match fs::metadata(files::expand_path(f)) {
+                Ok(m) => m.modified().ok(),
+                Err(_) => None,
+            },
+
+            None => None,
+        };
+
+        return match (self.since, mtime) {
+            (Some(s), Some(m)) => Some(s.max(m)),
+            (Some(s), None) => Some(s),
+            (None, m) => m,
+        };
+    }
+}
+
+/// Dead man's switch backend that will compute the values
+struct DeadmanBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+
+    pub checks: Vec<Check>,
+}
+
+impl DeadmanBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            checks: Vec::new(),
+        }
+    }
+
+    /// Get the configured checks sorted by name
+    fn configured(&self) -> Vec<(String, config::DeadmanCheckConfig)> {
+        let mut checks: Vec<(String, config::DeadmanCheckConfig)> =
+            match &self.config.deadman {
+                Some(c) => match &c.checks {
+                    Some(w) => w.iter()
+                        .map(|(n, c)| (n.clone(), c.clone()))
+                        .collect(),
+
+                    None => Vec::new(),
+                },
+
+                None => Vec::new(),
+            };
+
+        checks.sort_by(|a, b| a.0.cmp(&b.0));
+
+        return checks;
+    }
+
+    /// Rebuild the checks and the filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `configured` - The configured checks
+    fn rebuild_filesystem(
+        &mut self,
+        configured: Vec<(String, config::DeadmanCheckConfig)>) {
+
+        // Deletion triggers
+        for check in self.checks.iter() {
+            for entry in ENTRIES.iter() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Delete,
+                    &self.name,
+                    &format!("{}/{}", check.name, entry),
+                    "",
+                    "");
+            }
+        }
+
+        self.checks.clear();
+
+        let now = SystemTime::now();
+
+        for (name, config) in configured {
+            let fs_entry = filesystem::FsEntry::new(
+                filesystem::FsEntry::create_inode(),
+                fuse::FileType::Directory,
+                &name,
+                filesystem::Mode::ReadOnly,
+                &ENTRIES
+                    .iter()
+                    .map(|e| {
+                        let mode = match *e {
+                            ENTRY_HEARTBEAT => filesystem::Mode::ReadWrite,
+                            _ => filesystem::Mode::ReadOnly,
+                        };
+
+                        let entry = filesystem::FsEntry::new(
+                            filesystem::FsEntry::create_inode(),
+                            fuse::FileType::RegularFile,
+                            e,
+                            mode,
+                            &Vec::new());
+
+                        return match *e {
+                            ENTRY_AGE_S =>
+                                entry.with_unit(units::Unit::Seconds),
+
+                            _ => entry,
+                        };
+                    })
+                    .collect());
+
+            // The time of the last change of an entry is not known, the
+            // delay starts now. A missing file has already expired.
+            let since = match config.file {
+                Some(_) => None,
+                None => Some(now),
+            };
+
+            let value = match &config.entry {
+                Some(e) => bus::value(e),
+                None => None,
+            };
+
+            self.checks.push(Check {
+                name: name,
+                config: config,
+                expired: String::new(),
+                age_s: String::new(),
+                heartbeat: String::new(),
+                fs_entry: fs_entry,
+                since: since,
+                value: value,
+            });
+        }
+    }
+
+    /// Compute the ages of the checks and call the triggers
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `kind` - The kind of trigger to be called
+    fn evaluate(&mut self, kind: triggers::Kind) {
+        let now = SystemTime::now();
+
+        for check in self.checks.iter_mut() {
+            match &check.config.entry {
+                Some(e) => {
+                    let value = bus::value(e);
+
+                    if value.is_some() {
+                        if check.value.is_some() && value != check.value {
+                            check.since = Some(now);
+                        }
+
+                        check.value = value;
+                    }
+                },
+
+                None => (),
+            }
+
+            let last = check.last_activity();
+
+            // A modification time in the future counts as a recent one
+            let age = match last {
+                Some(l) => Some(match now.duration_since(l) {
+                    Ok(d) => d.as_secs(),
+                    Err(_) => 0,
+                }),
+
+                None => None,
+            };
+
+            let expired = match age {
+                Some(a) if a <= check.config.max_age_s => VALUE_FALSE,
+                _ => VALUE_TRUE,
+            };
+
+            let age_s = match age {
+                Some(a) => format!("{}", a),
+                None => VALUE_UNKNOWN.to_string(),
+            };
+
+            let heartbeat = match last {
+                Some(l) => match epoch_s(l) {
+                    Some(s) => format!("{}", s),
+                    None => VALUE_UNKNOWN.to_string(),
+                },
+
+                None => VALUE_UNKNOWN.to_string(),
+            };
+
+            let values = [
+                (ENTRY_EXPIRED, &mut check.expired, expired.to_string()),
+                (ENTRY_AGE_S, &mut check.age_s, age_s),
+                (ENTRY_HEARTBEAT, &mut check.heartbeat, heartbeat),
+            ];
+
+            for (name, current, value) in values {
+                publish::update(
+                    &self.triggers,
+                    kind,
+                    &self.name,
+                    &format!("{}/{}", check.name, name),
+                    current,
+                    &value);
+            }
+        }
+    }
+
+    /// Record a heartbeat for a check
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the heartbeat entry of the check
+    fn heartbeat(&mut self, inode: u64) -> error::Return {
+        let check = self.checks
+            .iter_mut()
+            .find(|c| match c.fs_entry.find(inode) {
+                Some(e) => e.name == ENTRY_HEARTBEAT,
+                None => false,
+            });
+
+        match check {
+            Some(c) => c.since = Some(SystemTime::now()),
+            None => return error!("Not a heartbeat entry"),
+        }
+
+        self.evaluate(triggers::Kind::Update);
+
+        return success!();
+    }
+}
+
+impl module::Data for DeadmanBackend {
+    /// Update dead man's switch data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let configured = self.configured();
+
+        // Check if the configured checks have changed
+        let changed = configured.len() != self.checks.len() ||
+            configured
+                .iter()
+                .zip(self.checks.iter())
+                .any(|((n, c), k)| *n != k.name || *c != k.config);
+
+        if changed {
+            self.rebuild_filesystem(configured);
+            self.evaluate(triggers::Kind::Create);
+
+            return Ok(module::Status::Changed(MODULE_NAME.to_string()));
+        }
+
+        self.evaluate(triggers::Kind::Update);
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// Dead man's switch module structure
+pub struct Deadman {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    backend: Arc<Mutex<DeadmanBackend>>,
+}
+
+impl Deadman {
+    /// Deadman constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            backend: Arc::new(Mutex::new(DeadmanBackend::new(name, triggers))),
+        }
+    }
+}
+
+impl module::Module for Deadman {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        return backend.checks.iter().map(|c| c.fs_entry.clone()).collect();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        for check in backend.checks.iter() {
+            match check.fs_entry.find(inode) {
+                Some(e) => return check.value(&e.name),
+                None => continue,
+            }
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// Any value written to the heartbeat entry of a check resets it.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, inode: u64, _data: &[u8]) {
+        let mut backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => {
+                log::error!("Cannot lock backend");
+                return;
+            },
+        };
+
+        match backend.heartbeat(inode) {
+            Ok(_) => (),
+            Err(e) => log::error!("{}", e),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let values: serde_json::Map<String, serde_json::Value> = backend
+            .checks
+            .iter()
+            .map(|c| (
+                c.name.clone(),
+                serde_json::Value::Object(ENTRIES
+                    .iter()
+                    .map(|e| (e.to_string(), c.value(e).into()))
+                    .collect())))
+            .collect();
+
+        return match serde_json::to_string(&values) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return backend.checks
+            .iter()
+            .flat_map(|c| ENTRIES
+                .iter()
+                .map(move |e| format!("{}_{}={}", c.name, e, c.value(e))))
+            .collect::<Vec<String>>()
+            .join(" ");
+    }
+}
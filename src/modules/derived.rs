@@ -0,0 +1,344 @@
+use fuse;
+use std::sync::Arc;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::expression;
+use crate::filesystem;
+use crate::modules::module;
+use crate::publish;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "derived";
+
+const VALUE_UNKNOWN: &str = "?";
+
+/// Entry computed from the values of other entries
+struct DerivedEntry {
+    pub name: String,
+    pub text: String,
+    pub expression: Option<expression::Expression>,
+    pub inode: u64,
+    pub value: String,
+}
+
+/// Derived backend that will compute the values
+struct DerivedBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    values: module::Values,
+
+    pub entries: Vec<DerivedEntry>,
+    pub fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl DerivedBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            values: module::Values::new(name),
+            entries: Vec::new(),
+            fs_entries: Vec::new(),
+        }
+    }
+
+    /// Get the configured entries sorted by name
+    fn configured(&self) -> Vec<(String, String)> {
+        let mut entries: Vec<(String, String)> = match &self.config.derived {
+            Some(c) => match &c.entries {
+                Some(e) => e.iter()
+                    .map(|(n, t)| (n.clone(), t.clone()))
+                    .collect(),
+
+                None => Vec::new(),
+            },
+
+            None => Vec::new(),
+        };
+
+        entries.sort();
+
+        return entries;
+    }
+
+    /// Rebuild the entries and the filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `configured` - The configured entries
+    fn rebuild_filesystem(&mut self, configured: Vec<(String, String)>) {
+        // Deletion triggers
+        for entry in self.entries.iter() {
+            triggers::find_all_and_execute(
+                &self.triggers,
+                triggers::Kind::Delete,
+                &self.name,
+                &entry.name,
+                "",
+                "");
+        }
+
+        self.entries.clear();
+        self.fs_entries.clear();
+
+        for (name, text) in configured {
+            let expression = match expression::Expression::parse(&text) {
+                Ok(e) => Some(e),
+                Err(e) => {
+                    log::error!("Invalid expression for {}: {}", name, e);
+                    None
+                },
+            };
+
+            let inode = filesystem::FsEntry::create_inode();
+
+            self.fs_entries.push(filesystem::FsEntry::new(
+                inode,
+                fuse::FileType::RegularFile,
+                &name,
+                filesystem::Mode::ReadOnly,
+                &Vec::new()));
+
+            self.entries.push(DerivedEntry {
+                name: name,
+                text: text,
+                expression: expression,
+                inode: inode,
+                value: String::new(),
+            });
+        }
+    }
+
+    /// Compute the values of the entries and call the triggers
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `kind` - The kind of trigger to be called
+    fn evaluate(&mut self, kind: triggers::Kind) {
+        let values = &self.values;
+
+        for entry in self.entries.iter_mut() {
+            let value = match &entry.expression {
+                Some(e) => match e.evaluate(&|path| values.get(path)) {
+                    Some(v) => v.to_string(),
+                    None => VALUE_UNKNOWN.to_string(),
+                },
+
+                None => VALUE_UNKNOWN.to_string(),
+            };
+
+            publish::update(
+                &self.triggers,
+                kind,
+                &self.name,
+                &entry.name,
+                &mut entry.value,
+                &value);
+        }
+    }
+}
+
+impl module::Data for DerivedBackend {
+    /// Update derived data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let configured = self.configured();
+
+        // Check if the configured entries have changed
+        let changed = configured.len() != self.entries.len() ||
+            configured
+                .iter()
+                .zip(self.entries.iter())
+                .any(|((n, t), e)| *n != e.name || *t != e.text);
+
+        if changed {
+            self.rebuild_filesystem(configured);
+            self.evaluate(triggers::Kind::Create);
+
+            return Ok(module::Status::Changed(MODULE_NAME.to_string()));
+        }
+
+        self.evaluate(triggers::Kind::Update);
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// Derived module structure
+pub struct Derived {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    backend: Arc<Mutex<DerivedBackend>>,
+}
+
+impl Derived {
+    /// Derived constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            backend: Arc::new(Mutex::new(DerivedBackend::new(name, triggers))),
+        }
+    }
+}
+
+impl module::Module for Derived {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        return backend.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match backend.entries.iter().find(|e| e.inode == inode) {
+            Some(e) => e.value.clone(),
+            None => VALUE_UNKNOWN.to_string(),
+        };
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let values: serde_json::Map<String, serde_json::Value> = backend
+            .entries
+            .iter()
+            .map(|e| (e.name.clone(), serde_json::Value::from(e.value.clone())))
+            .collect();
+
+        return match serde_json::to_string(&values) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return backend.entries
+            .iter()
+            .map(|e| format!("{}={}", e.name, e.value))
+            .collect::<Vec<String>>()
+            .join(" ");
+    }
+}
@@ -0,0 +1,600 @@
+use fuse;
+use std::env;
+use std::fs;
+use std::path;
+use std::process;
+use std::sync::Arc;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::cooling;
+use crate::modules::module;
+use crate::publish;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "displays";
+
+const BACKEND_DRM: &str = "drm";
+const BACKEND_XRANDR: &str = "xrandr";
+
+const ENTRY_CONNECTED: &str = "connected";
+const ENTRY_PRIMARY: &str = "primary";
+const ENTRY_REFRESH_RATE: &str = "refresh_rate";
+const ENTRY_RESOLUTION: &str = "resolution";
+
+const ENTRIES: [&str; 4] =
+    [ENTRY_CONNECTED, ENTRY_RESOLUTION, ENTRY_REFRESH_RATE, ENTRY_PRIMARY];
+
+/// Directory of the DRM connectors relatively to the sysfs root
+const DRM: &str = "class/drm";
+
+/// Prefix of the DRM connectors (e.g. `card0-eDP-1`)
+const DRM_PREFIX: &str = "card";
+
+/// Status of the outputs where a monitor is plugged
+const STATUS_CONNECTED: &str = "connected";
+const STATUS_DISCONNECTED: &str = "disconnected";
+
+/// Word of the primary output in the list of xrandr
+const XRANDR_PRIMARY: &str = "primary";
+
+const VALUE_FALSE: &str = "false";
+const VALUE_TRUE: &str = "true";
+const VALUE_UNKNOWN: &str = "?";
+
+/// Function used to format a boolean value
+///
+/// # Arguments
+///
+/// * `value` - The value to be formatted
+fn boolean(value: bool) -> String {
+    return match value {
+        true => VALUE_TRUE.to_string(),
+        false => VALUE_FALSE.to_string(),
+    };
+}
+
+/// State of an output as reported by the backend
+struct State {
+    pub name: String,
+    pub connected: String,
+    pub resolution: String,
+    pub refresh_rate: String,
+    pub primary: String,
+}
+
+impl State {
+    /// State constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the output
+    /// * `connected` - Whether a monitor is plugged
+    fn new(name: &str, connected: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            connected: boolean(connected),
+            resolution: String::new(),
+            refresh_rate: String::new(),
+            primary: VALUE_UNKNOWN.to_string(),
+        }
+    }
+}
+
+/// Output of the machine
+struct Output {
+    pub name: String,
+    pub connected: String,
+    pub resolution: String,
+    pub refresh_rate: String,
+    pub primary: String,
+    pub fs_entry: filesystem::FsEntry,
+}
+
+impl Output {
+    /// Get the value of one of the entries
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `name` - The name of the entry
+    fn value(&self, name: &str) -> String {
+        return match name {
+            ENTRY_CONNECTED => self.connected.clone(),
+            ENTRY_PRIMARY => self.primary.clone(),
+            ENTRY_REFRESH_RATE => self.refresh_rate.clone(),
+            ENTRY_RESOLUTION => self.resolution.clone(),
+            _ => VALUE_UNKNOWN.to_string(),
+        };
+    }
+
+    /// Get the name of the entry of an inode, if it belongs to the output
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the entry
+    fn entry(&self, inode: u64) -> Option<&str> {
+        return self.fs_entry.fs_entries
+            .iter()
+            .find(|e| e.inode == inode)
+            .map(|e| e.name.as_str());
+    }
+}
+
+/// Displays backend that will compute the values
+struct DisplaysBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+
+    pub outputs: Vec<Output>,
+}
+
+impl DisplaysBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            outputs: Vec::new(),
+        }
+    }
+
+    /// Get the outputs from the DRM connectors
+    ///
+    /// The resolution is the preferred mode of the monitor, the refresh rate
+    /// and the primary output are not known by the kernel.
+    fn query_drm() -> Result<Vec<State>, error::CerebroError> {
+        let read = |connector: &path::Path, name: &str| -> Option<String> {
+            return fs::read_to_string(connector.join(name)).ok();
+        };
+
+        let mut states: Vec<State> = Vec::new();
+
+        for connector in cooling::list_sysfs(DRM, DRM_PREFIX, "") {
+            let name = match connector.file_name().and_then(|n| n.to_str()) {
+                Some(n) => n.to_string(),
+                None => continue,
+            };
+
+            // Cards themselves have no output in their name
+            let name = match name.split_once('-') {
+                Some((_, o)) => o.to_string(),
+                None => continue,
+            };
+
+            let status = match read(&connector, "status") {
+                Some(s) => s.trim().to_string(),
+                None => continue,
+            };
+
+            let mut state = State::new(&name, status == STATUS_CONNECTED);
+
+            state.refresh_rate = VALUE_UNKNOWN.to_string();
+
+            // The first mode is the preferred one
+            if status == STATUS_CONNECTED {
+                state.resolution = match read(&connector, "modes") {
+                    Some(m) => m.lines().next().unwrap_or("").to_string(),
+                    None => VALUE_UNKNOWN.to_string(),
+                };
+            }
+
+            states.push(state);
+        }
+
+        states.sort_by(|a, b| a.name.cmp(&b.name));
+        states.dedup_by(|a, b| a.name == b.name);
+
+        return Ok(states);
+    }
+
+    /// Get the outputs from xrandr
+    ///
+    /// The resolution is the one of the current mode, which is the one
+    /// whose refresh rate is marked with a star.
+    fn query_xrandr() -> Result<Vec<State>, error::CerebroError> {
+        let output = match process::Command::new(BACKEND_XRANDR)
+            .arg("--query")
+            .output() {
+
+            Ok(o) => o,
+            Err(e) => return error!(
+                &format!("Cannot execute {}: {:?}", BACKEND_XRANDR, e)),
+        };
+
+        if !output.status.success() {
+            return error!("Cannot list the outputs");
+        }
+
+        let mut states: Vec<State> = Vec::new();
+
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let words: Vec<&str> = line.split_whitespace().collect();
+
+            // Modes of the last output are indented (e.g. `1920x1080 60.00*+`)
+            if line.starts_with(char::is_whitespace) {
+                let state = match states.last_mut() {
+                    Some(s) => s,
+                    None => continue,
+                };
+
+                match words.iter().skip(1).find(|w| w.contains('*')) {
+                    Some(r) => {
+                        state.resolution = words[0].to_string();
+                        state.refresh_rate = r
+                            .trim_end_matches(|c| c == '*' || c == '+')
+                            .to_string();
+                    },
+
+                    None => (),
+                }
+
+                continue;
+            }
+
+            // `name connected [primary] [geometry] ...`
+            let connected = match words.get(1) {
+                Some(&STATUS_CONNECTED) => true,
+                Some(&STATUS_DISCONNECTED) => false,
+                _ => continue,
+            };
+
+            let mut state = State::new(words[0], connected);
+
+            state.primary = boolean(words.contains(&XRANDR_PRIMARY));
+
+            states.push(state);
+        }
+
+        states.sort_by(|a, b| a.name.cmp(&b.name));
+
+        return Ok(states);
+    }
+
+    /// Get the outputs of the machine
+    ///
+    /// Without a configured backend, xrandr is used in a graphical session
+    /// and the DRM connectors otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn query(&self) -> Result<Vec<State>, error::CerebroError> {
+        return match self.config.backend.as_deref() {
+            Some(BACKEND_DRM) => DisplaysBackend::query_drm(),
+            Some(BACKEND_XRANDR) => DisplaysBackend::query_xrandr(),
+            Some(b) => error!(
+                Config,
+                &format!("Unknown displays backend `{}`", b)),
+
+            None if env::var_os("DISPLAY").is_some() =>
+                match DisplaysBackend::query_xrandr() {
+                    Ok(s) => Ok(s),
+                    Err(e) => {
+                        log::debug!("{}: {}", MODULE_NAME, e);
+                        DisplaysBackend::query_drm()
+                    },
+                },
+
+            None => DisplaysBackend::query_drm(),
+        };
+    }
+
+    /// Rebuild the outputs and the filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `states` - The states of the outputs
+    fn rebuild_filesystem(&mut self, states: &Vec<State>) {
+        // Deletion triggers
+        for output in self.outputs.iter() {
+            for entry in ENTRIES.iter() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Delete,
+                    &self.name,
+                    &format!("{}/{}", output.name, entry),
+                    "",
+                    "");
+            }
+        }
+
+        self.outputs.clear();
+
+        for state in states.iter() {
+            let fs_entry = filesystem::FsEntry::new(
+                filesystem::FsEntry::create_inode(),
+                fuse::FileType::Directory,
+                &state.name,
+                filesystem::Mode::ReadOnly,
+                &ENTRIES
+                    .iter()
+                    .map(|e| filesystem::FsEntry::new(
+                        filesystem::FsEntry::create_inode(),
+                        fuse::FileType::RegularFile,
+                        e,
+                        filesystem::Mode::ReadOnly,
+                        &Vec::new()))
+                    .collect());
+
+            self.outputs.push(Output {
+                name: state.name.clone(),
+                connected: String::new(),
+                resolution: String::new(),
+                refresh_rate: String::new(),
+                primary: String::new(),
+                fs_entry: fs_entry,
+            });
+        }
+    }
+}
+
+impl module::Data for DisplaysBackend {
+    /// Update outputs data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let states = self.query()?;
+
+        // Check if outputs have been plugged or removed
+        let changed = self.first_update ||
+            states.len() != self.outputs.len() ||
+            states
+                .iter()
+                .zip(self.outputs.iter())
+                .any(|(s, o)| s.name != o.name);
+
+        let mut status = module::Status::Ok;
+        let mut kind = triggers::Kind::Update;
+
+        if changed {
+            self.rebuild_filesystem(&states);
+            self.first_update = false;
+
+            status = module::Status::Changed(MODULE_NAME.to_string());
+            kind = triggers::Kind::Create;
+        }
+
+        for (output, state) in self.outputs.iter_mut().zip(states.iter()) {
+            let values = [
+                (ENTRY_CONNECTED, &mut output.connected, &state.connected),
+                (ENTRY_RESOLUTION, &mut output.resolution, &state.resolution),
+                (
+                    ENTRY_REFRESH_RATE,
+                    &mut output.refresh_rate,
+                    &state.refresh_rate,
+                ),
+                (ENTRY_PRIMARY, &mut output.primary, &state.primary),
+            ];
+
+            for (name, current, new) in values {
+                publish::update(
+                    &self.triggers,
+                    kind,
+                    &self.name,
+                    &format!("{}/{}", output.name, name),
+                    current,
+                    new);
+            }
+        }
+
+        return Ok(status);
+    }
+}
+
+/// Displays module structure
+pub struct Displays {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    backend: Arc<Mutex<DisplaysBackend>>,
+}
+
+impl Displays {
+    /// Displays constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            backend: Arc::new(Mutex::new(DisplaysBackend::new(name, triggers))),
+        }
+    }
+}
+
+impl module::Module for Displays {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Check if the system has outputs
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn probe(&self) -> Option<bool> {
+        if env::var_os("DISPLAY").is_some() {
+            return Some(true);
+        }
+
+        return match DisplaysBackend::query_drm() {
+            Ok(s) => Some(!s.is_empty()),
+            Err(_) => Some(false),
+        };
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        return backend.outputs.iter().map(|o| o.fs_entry.clone()).collect();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        for output in backend.outputs.iter() {
+            match output.entry(inode) {
+                Some(e) => return output.value(e),
+                None => continue,
+            }
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let values: serde_json::Map<String, serde_json::Value> = backend
+            .outputs
+            .iter()
+            .map(|o| (
+                o.name.clone(),
+                serde_json::Value::Object(ENTRIES
+                    .iter()
+                    .map(|e| (e.to_string(), o.value(e).into()))
+                    .collect())))
+            .collect();
+
+        return match serde_json::to_string(&values) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let mut values = Vec::new();
+
+        for output in backend.outputs.iter() {
+            let name: String = output.name
+                .chars()
+                .map(|c| match c.is_ascii_alphanumeric() {
+                    true => c,
+                    false => '_',
+                })
+                .collect();
+
+            for entry in ENTRIES.iter() {
+                values.push(
+                    format!("{}_{}={}", name, entry, output.value(entry)));
+            }
+        }
+
+        return values.join(" ");
+    }
+}
@@ -0,0 +1,401 @@
+use fuse;
+use regex::Regex;
+use std::sync::Arc;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::platform;
+use crate::publish;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "fans";
+
+const ENTRY_COUNT: &str = "count";
+const ENTRY_RPM: &str = "rpm";
+
+/// Pattern of the fans names when not configured
+const DEFAULT_PATTERN: &str = ".*";
+
+const VALUE_UNKNOWN: &str = "?";
+
+/// Fan of a chip
+struct Fan {
+    pub chip: String,
+    pub name: String,
+    pub rpm: String,
+    pub inode_rpm: u64,
+}
+
+/// Fans backend that will compute the values
+struct FansBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+
+    pub count: String,
+    pub fans: Vec<Fan>,
+    pub fs_entries: Vec<filesystem::FsEntry>,
+    pub inode_count: u64,
+}
+
+impl FansBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            count: String::new(),
+            fans: Vec::new(),
+            fs_entries: Vec::new(),
+            inode_count: filesystem::FsEntry::create_inode(),
+        }
+    }
+
+    /// Get the speeds of the configured fans as (chip, fan, rpm)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn query_fans(&self)
+        -> Result<Vec<(String, String, u64)>, error::CerebroError> {
+        let (device, pattern) = match &self.config.fans {
+            Some(c) => (c.device.as_deref(), c.pattern.as_deref()),
+            None => (None, None),
+        };
+
+        let pattern = match Regex::new(pattern.unwrap_or(DEFAULT_PATTERN)) {
+            Ok(r) => r,
+            Err(_) => return error!(Config, "Invalid fans pattern"),
+        };
+
+        return Ok(platform::current().fan_speeds(device, &pattern));
+    }
+
+    /// Rebuild the fans and the filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `fans` - The fans as (chip, fan)
+    fn rebuild_filesystem(&mut self, fans: Vec<(String, String)>) {
+        // Deletion triggers
+        for fan in self.fans.iter() {
+            triggers::find_all_and_execute(
+                &self.triggers,
+                triggers::Kind::Delete,
+                &self.name,
+                &format!("{}/{}/{}", fan.chip, fan.name, ENTRY_RPM),
+                "",
+                "");
+        }
+
+        self.fans.clear();
+
+        self.fs_entries = vec![filesystem::FsEntry::new(
+            self.inode_count,
+            fuse::FileType::RegularFile,
+            ENTRY_COUNT,
+            filesystem::Mode::ReadOnly,
+            &Vec::new())];
+
+        for (chip, name) in fans {
+            let inode_rpm = filesystem::FsEntry::create_inode();
+
+            let fan_entry = filesystem::FsEntry::new(
+                filesystem::FsEntry::create_inode(),
+                fuse::FileType::Directory,
+                &name,
+                filesystem::Mode::ReadOnly,
+                &vec![filesystem::FsEntry::new(
+                    inode_rpm,
+                    fuse::FileType::RegularFile,
+                    ENTRY_RPM,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new())]);
+
+            // The fans of a chip are grouped in its directory
+            match self.fs_entries.iter_mut().find(|e| e.name == chip) {
+                Some(e) => e.fs_entries.push(fan_entry),
+                None => self.fs_entries.push(filesystem::FsEntry::new(
+                    filesystem::FsEntry::create_inode(),
+                    fuse::FileType::Directory,
+                    &chip,
+                    filesystem::Mode::ReadOnly,
+                    &vec![fan_entry])),
+            }
+
+            self.fans.push(Fan {
+                chip: chip,
+                name: name,
+                rpm: String::new(),
+                inode_rpm: inode_rpm,
+            });
+        }
+    }
+}
+
+impl module::Data for FansBackend {
+    /// Update fans data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let speeds = self.query_fans()?;
+
+        // Check if the fans have changed
+        let changed = self.first_update ||
+            speeds.len() != self.fans.len() ||
+            speeds
+                .iter()
+                .zip(self.fans.iter())
+                .any(|((c, n, _), f)| *c != f.chip || *n != f.name);
+
+        let mut status = module::Status::Ok;
+        let mut kind = triggers::Kind::Update;
+
+        if changed {
+            self.rebuild_filesystem(speeds
+                .iter()
+                .map(|(c, n, _)| (c.clone(), n.clone()))
+                .collect());
+
+            self.count = String::new();
+            self.first_update = false;
+
+            status = module::Status::Changed(MODULE_NAME.to_string());
+            kind = triggers::Kind::Create;
+        }
+
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_COUNT,
+            &mut self.count,
+            &format!("{}", speeds.len()));
+
+        for (fan, (_, _, rpm)) in self.fans.iter_mut().zip(speeds.iter()) {
+            publish::update(
+                &self.triggers,
+                kind,
+                &self.name,
+                &format!("{}/{}/{}", fan.chip, fan.name, ENTRY_RPM),
+                &mut fan.rpm,
+                &format!("{}", rpm));
+        }
+
+        return Ok(status);
+    }
+}
+
+/// Fans module structure
+pub struct Fans {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    backend: Arc<Mutex<FansBackend>>,
+}
+
+impl Fans {
+    /// Fans constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            backend: Arc::new(Mutex::new(FansBackend::new(name, triggers))),
+        }
+    }
+}
+
+impl module::Module for Fans {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Check if the system has fans
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn probe(&self) -> Option<bool> {
+        let pattern = match Regex::new(DEFAULT_PATTERN) {
+            Ok(p) => p,
+            Err(_) => return None,
+        };
+
+        return Some(
+            !platform::current().fan_speeds(None, &pattern).is_empty());
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        return backend.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        if inode == backend.inode_count {
+            return backend.count.clone();
+        }
+
+        return match backend.fans.iter().find(|f| f.inode_rpm == inode) {
+            Some(f) => f.rpm.clone(),
+            None => VALUE_UNKNOWN.to_string(),
+        };
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let mut values = serde_json::Map::new();
+
+        values.insert(ENTRY_COUNT.to_string(), backend.count.clone().into());
+
+        for fan in backend.fans.iter() {
+            let chip = values
+                .entry(fan.chip.clone())
+                .or_insert(serde_json::Value::Object(serde_json::Map::new()));
+
+            let mut values = serde_json::Map::new();
+
+            values.insert(ENTRY_RPM.to_string(), fan.rpm.clone().into());
+
+            chip[&fan.name] = serde_json::Value::Object(values);
+        }
+
+        return match serde_json::to_string(&values) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let mut values = vec![format!("{}={}", ENTRY_COUNT, backend.count)];
+
+        for fan in backend.fans.iter() {
+            values.push(format!(
+                "{}_{}_{}={}",
+                fan.chip.replace('-', "_"),
+                fan.name,
+                ENTRY_RPM,
+                fan.rpm));
+        }
+
+        return values.join(" ");
+    }
+}
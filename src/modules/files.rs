@@ -0,0 +1,598 @@
+use dirs;
+use fuse;
+use notify::Watcher;
+use std::fs;
+use std::path;
+use std::sync::Arc;
+use std::sync::mpsc;
+use std::time::SystemTime;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::publish;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "files";
+
+const ENTRY_EXISTS: &str = "exists";
+const ENTRY_LINE_COUNT: &str = "line_count";
+const ENTRY_MTIME: &str = "mtime";
+const ENTRY_SIZE: &str = "size";
+
+const VALUE_FALSE: &str = "false";
+const VALUE_TRUE: &str = "true";
+const VALUE_UNKNOWN: &str = "?";
+
+/// Expand the home directory at the beginning of a path
+///
+/// # Arguments
+///
+/// * `path` - The path as written in the configuration
+pub fn expand_path(path: &str) -> path::PathBuf {
+    match (path.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(p), Some(home)) => return home.join(p),
+        _ => return path::PathBuf::from(path),
+    }
+}
+
+/// File or directory watched by the module
+struct FileWatch {
+    pub name: String,
+    pub text: String,
+    pub path: path::PathBuf,
+    pub line_count: bool,
+    pub exists: String,
+    pub size: String,
+    pub mtime: String,
+    pub lines: String,
+    pub fs_entry: filesystem::FsEntry,
+}
+
+impl FileWatch {
+    /// Get the names of the entries of the watch
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn entries(&self) -> Vec<&'static str> {
+        let mut entries = vec![ENTRY_EXISTS, ENTRY_SIZE, ENTRY_MTIME];
+
+        if self.line_count {
+            entries.push(ENTRY_LINE_COUNT);
+        }
+
+        return entries;
+    }
+
+    /// Get the value of one of the entries
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `name` - The name of the entry
+    fn value(&self, name: &str) -> String {
+        return match name {
+            ENTRY_EXISTS => self.exists.clone(),
+            ENTRY_SIZE => self.size.clone(),
+            ENTRY_MTIME => self.mtime.clone(),
+            ENTRY_LINE_COUNT => self.lines.clone(),
+            _ => VALUE_UNKNOWN.to_string(),
+        };
+    }
+
+    /// Check if an event on a path concerns the watch
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `path` - The path of the event
+    fn concerns(&self, path: &path::Path) -> bool {
+        return path == self.path || path.parent() == Some(&self.path);
+    }
+}
+
+/// Proxy backend that is only use in the context of the thread
+struct FilesBackendProxy {
+    config: config::ModuleConfig,
+    backend: Arc<Mutex<FilesBackend>>,
+}
+
+impl FilesBackendProxy {
+    fn new(backend: Arc<Mutex<FilesBackend>>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            backend: backend,
+        }
+    }
+
+    /// Get the configured watches (name, path, line count) sorted by name
+    fn configured(&self) -> Vec<(String, String, bool)> {
+        let mut watches: Vec<(String, String, bool)> =
+            match &self.config.files {
+                Some(c) => match &c.watches {
+                    Some(w) => w.iter()
+                        .map(|(n, w)| (
+                            n.clone(),
+                            w.path.clone(),
+                            w.line_count == Some(true)))
+                        .collect(),
+
+                    None => Vec::new(),
+                },
+
+                None => Vec::new(),
+            };
+
+        watches.sort();
+
+        return watches;
+    }
+
+    /// Update the watches if the configuration has changed
+    ///
+    /// Returns true if the filesystem has been rebuilt.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update_watches(&mut self) -> Result<bool, error::CerebroError> {
+        let configured = self.configured();
+
+        let mut backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        };
+
+        // Check if the configured watches have changed
+        let changed = configured.len() != backend.watches.len() ||
+            configured
+                .iter()
+                .zip(backend.watches.iter())
+                .any(|((n, p, l), w)|
+                    *n != w.name || *p != w.text || *l != w.line_count);
+
+        if changed {
+            backend.rebuild_filesystem(configured);
+            backend.evaluate(triggers::Kind::Create, None);
+        }
+        else {
+            backend.evaluate(triggers::Kind::Update, None);
+        }
+
+        return Ok(changed);
+    }
+}
+
+impl module::Data for FilesBackendProxy {
+    /// Update files data
+    ///
+    /// The parent directories of the paths are watched, so that files that
+    /// are created or replaced are detected.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        if self.update_watches()? {
+            return Ok(module::Status::Changed(MODULE_NAME.to_string()));
+        }
+
+        // Get paths to watch
+        let mut watch_paths: Vec<path::PathBuf> = match self.backend.lock() {
+            Ok(b) => b.watches
+                .iter()
+                .flat_map(|w| vec![
+                    Some(w.path.clone()),
+                    w.path.parent().map(|p| p.to_path_buf()),
+                ])
+                .filter_map(|p| p)
+                .filter(|p| p.is_dir())
+                .collect(),
+
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        };
+
+        watch_paths.sort();
+        watch_paths.dedup();
+
+        // Create watcher
+        let (tx, rx) = mpsc::channel();
+
+        let mut w: notify::RecommendedWatcher =
+            match notify::Watcher::new_raw(tx) {
+
+            Ok(w) => w,
+            Err(_) => return error!("Cannot create filesystem watcher"),
+        };
+
+        // Add watch paths
+        for path in watch_paths.iter() {
+            match w.watch(path, notify::RecursiveMode::NonRecursive) {
+                Ok(_) => (),
+                Err(_) => log::warn!("Cannot watch path: {:?}", path),
+            }
+        }
+
+        // Wait for events
+        loop {
+            let event = match module::recv(&rx) {
+                Ok(e) => e,
+                Err(_) => return error!("Error during watching filesystem"),
+            };
+
+            let path = match event.path {
+                Some(p) => p,
+                None => continue,
+            };
+
+            match self.backend.lock() {
+                Ok(mut b) => b.evaluate(triggers::Kind::Update, Some(&path)),
+                Err(_) => return error!(Lock, "Cannot lock backend"),
+            }
+        }
+    }
+
+    /// Update files data once, without watching the paths
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update_once(&mut self)
+        -> Option<Result<module::Status, error::CerebroError>> {
+
+        return match self.update_watches() {
+            Ok(true) =>
+                Some(Ok(module::Status::Changed(MODULE_NAME.to_string()))),
+
+            Ok(false) => Some(Ok(module::Status::Ok)),
+            Err(e) => Some(Err(e)),
+        };
+    }
+}
+
+/// Files backend that will compute the values
+struct FilesBackend {
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+
+    pub watches: Vec<FileWatch>,
+}
+
+impl FilesBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            watches: Vec::new(),
+        }
+    }
+
+    /// Rebuild the watches and the filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `configured` - The configured watches
+    fn rebuild_filesystem(&mut self, configured: Vec<(String, String, bool)>) {
+        // Deletion triggers
+        for watch in self.watches.iter() {
+            for entry in watch.entries() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Delete,
+                    &self.name,
+                    &format!("{}/{}", watch.name, entry),
+                    "",
+                    "");
+            }
+        }
+
+        self.watches.clear();
+
+        for (name, text, line_count) in configured {
+            let mut watch = FileWatch {
+                name: name.clone(),
+                path: expand_path(&text),
+                text: text,
+                line_count: line_count,
+                exists: String::new(),
+                size: String::new(),
+                mtime: String::new(),
+                lines: String::new(),
+                fs_entry: filesystem::FsEntry::new(
+                    filesystem::FsEntry::create_inode(),
+                    fuse::FileType::Directory,
+                    &name,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+            };
+
+            watch.fs_entry.fs_entries = watch
+                .entries()
+                .iter()
+                .map(|e| filesystem::FsEntry::new(
+                    filesystem::FsEntry::create_inode(),
+                    fuse::FileType::RegularFile,
+                    e,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()))
+                .collect();
+
+            self.watches.push(watch);
+        }
+    }
+
+    /// Read the paths and call the triggers
+    ///
+    /// The size of a directory is its number of entries.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `kind` - The kind of trigger to be called
+    /// * `event_path` - The path that has changed, all if none
+    fn evaluate(
+        &mut self,
+        kind: triggers::Kind,
+        event_path: Option<&path::Path>) {
+
+        for watch in self.watches.iter_mut() {
+            match event_path {
+                Some(p) if !watch.concerns(p) => continue,
+                _ => (),
+            }
+
+            let metadata = fs::metadata(&watch.path).ok();
+
+            let exists = match metadata {
+                Some(_) => VALUE_TRUE.to_string(),
+                None => VALUE_FALSE.to_string(),
+            };
+
+            let size = match &metadata {
+                Some(m) if m.is_dir() => match fs::read_dir(&watch.path) {
+                    Ok(e) => format!("{}", e.count()),
+                    Err(_) => VALUE_UNKNOWN.to_string(),
+                },
+
+                Some(m) => format!("{}", m.len()),
+                None => VALUE_UNKNOWN.to_string(),
+            };
+
+            let mtime = match metadata.as_ref().map(|m| m.modified()) {
+                Some(Ok(t)) => match t.duration_since(SystemTime::UNIX_EPOCH) {
+                    Ok(d) => format!("{}", d.as_secs()),
+                    Err(_) => VALUE_UNKNOWN.to_string(),
+                },
+
+                _ => VALUE_UNKNOWN.to_string(),
+            };
+
+            let lines = match (&metadata, watch.line_count) {
+                (Some(m), true) if m.is_file() => {
+                    match fs::read(&watch.path) {
+                        Ok(c) => format!(
+                            "{}",
+                            c.iter().filter(|b| **b == b'\n').count()),
+
+                        Err(_) => VALUE_UNKNOWN.to_string(),
+                    }
+                },
+
+                _ => VALUE_UNKNOWN.to_string(),
+            };
+
+            let values = [
+                (ENTRY_EXISTS, &mut watch.exists, exists),
+                (ENTRY_SIZE, &mut watch.size, size),
+                (ENTRY_MTIME, &mut watch.mtime, mtime),
+                (ENTRY_LINE_COUNT, &mut watch.lines, lines),
+            ];
+
+            for (name, current, value) in values {
+                if name == ENTRY_LINE_COUNT && !watch.line_count {
+                    continue;
+                }
+
+                publish::update(
+                    &self.triggers,
+                    kind,
+                    &self.name,
+                    &format!("{}/{}", watch.name, name),
+                    current,
+                    &value);
+            }
+        }
+    }
+}
+
+/// Files module structure
+pub struct Files {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    backend: Arc<Mutex<FilesBackend>>,
+    backend_proxy: Arc<Mutex<FilesBackendProxy>>,
+}
+
+impl Files {
+    /// Files constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        let backend = Arc::new(Mutex::new(FilesBackend::new(name, triggers)));
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            backend: backend.clone(),
+            backend_proxy:
+                Arc::new(Mutex::new(FilesBackendProxy::new(backend.clone()))),
+        }
+    }
+}
+
+impl module::Module for Files {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend_proxy.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend_proxy.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        return backend.watches.iter().map(|w| w.fs_entry.clone()).collect();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        for watch in backend.watches.iter() {
+            match watch.fs_entry.find(inode) {
+                Some(e) => return watch.value(&e.name),
+                None => continue,
+            }
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let values: serde_json::Map<String, serde_json::Value> = backend
+            .watches
+            .iter()
+            .map(|w| (
+                w.name.clone(),
+                serde_json::Value::Object(w
+                    .entries()
+                    .iter()
+                    .map(|e| (e.to_string(), w.value(e).into()))
+                    .collect())))
+            .collect();
+
+        return match serde_json::to_string(&values) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return backend.watches
+            .iter()
+            .flat_map(|w| w
+                .entries()
+                .into_iter()
+                .map(move |e| format!("{}_{}={}", w.name, e, w.value(e))))
+            .collect::<Vec<String>>()
+            .join(" ");
+    }
+}
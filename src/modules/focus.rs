@@ -0,0 +1,285 @@
+use fuse;
+use serde::{Serialize};
+use std::sync::Arc;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "focus";
+
+const VALUE_FALSE: &str = "false";
+const VALUE_TRUE: &str = "true";
+const VALUE_UNKNOWN: &str = "?";
+
+const ENTRY_ENABLED: &str = "enabled";
+
+/// Information about the focus mode
+#[derive(Serialize)]
+struct FocusData
+{
+    pub enabled: String,
+}
+
+impl FocusData {
+    /// FocusData constructor
+    pub fn new() -> Self {
+        Self {
+            enabled: VALUE_FALSE.to_string(),
+        }
+    }
+}
+
+/// Focus backend that stores the state
+struct FocusBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+
+    pub data: FocusData,
+}
+
+impl FocusBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            data: FocusData::new(),
+        }
+    }
+
+    /// Get the groups of triggers to be suppressed in focus mode
+    fn groups(&self) -> Vec<String> {
+        return match &self.config.focus {
+            Some(c) => match &c.groups {
+                Some(g) => g.to_vec(),
+                None => Vec::new(),
+            },
+
+            None => Vec::new(),
+        };
+    }
+
+    /// Enable or disable the focus mode
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `enabled` - The new state of the focus mode
+    fn set_enabled(&mut self, enabled: bool) {
+        let enabled = match enabled {
+            true => VALUE_TRUE.to_string(),
+            false => VALUE_FALSE.to_string(),
+        };
+
+        if enabled == self.data.enabled {
+            return;
+        }
+
+        let old_value = self.data.enabled.clone();
+
+        self.data.enabled = enabled;
+
+        log::debug!("{}: enabled={}", MODULE_NAME, self.data.enabled);
+
+        // Suppress the groups before calling the triggers so that a disabled
+        // group is not reached by this very change
+        match self.data.enabled.as_str() {
+            VALUE_TRUE => triggers::set_suppressed_groups(&self.groups()),
+            _ => triggers::set_suppressed_groups(&Vec::new()),
+        }
+
+        triggers::find_all_and_execute(
+            &self.triggers,
+            triggers::Kind::Update,
+            &self.name,
+            ENTRY_ENABLED,
+            &old_value,
+            &self.data.enabled);
+    }
+}
+
+/// Focus module structure
+pub struct Focus {
+    name: String,
+    running: bool,
+    inode_enabled: u64,
+    backend: Arc<Mutex<FocusBackend>>,
+    fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl Focus {
+    /// Focus constructor
+    pub fn new(
+        name: &str,
+        _event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        let enabled = filesystem::FsEntry::create_inode();
+
+        Self {
+            name: name.to_string(),
+            running: false,
+            inode_enabled: enabled,
+            backend: Arc::new(Mutex::new(FocusBackend::new(name, triggers))),
+            fs_entries: vec![
+                filesystem::FsEntry::new(
+                    enabled,
+                    fuse::FileType::RegularFile,
+                    ENTRY_ENABLED,
+                    filesystem::Mode::ReadWrite,
+                    &Vec::new()),
+                ],
+        }
+    }
+}
+
+impl module::Module for Focus {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// The focus mode has nothing to poll: it only stores the configuration
+    /// that is needed when the state is written.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        let mut backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        };
+
+        backend.config = config.clone();
+
+        self.running = true;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        self.running = false;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        return self.running;
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        return self.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        if inode == self.inode_enabled {
+            match self.backend.lock() {
+                Ok(b) => return b.data.enabled.clone(),
+                Err(_) => return VALUE_UNKNOWN.to_string(),
+            }
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, inode: u64, data: &[u8]) {
+        if inode != self.inode_enabled {
+            return;
+        }
+
+        let mut backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => {
+                log::error!("Cannot lock backend");
+                return;
+            },
+        };
+
+        match data {
+            b"1" | b"1\n" | b"true" | b"true\n" => backend.set_enabled(true),
+            b"0" | b"0\n" | b"false" | b"false\n" => backend.set_enabled(false),
+
+            b"toggle" | b"toggle\n" => {
+                let enabled = backend.data.enabled == VALUE_TRUE;
+                backend.set_enabled(!enabled);
+            },
+
+            _ => log::error!(
+                "Invalid value for {}/{}",
+                MODULE_NAME,
+                ENTRY_ENABLED),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return format!("enabled={}", backend.data.enabled).to_string();
+    }
+}
@@ -0,0 +1,529 @@
+use fuse;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::bus;
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::rrd;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "history";
+
+const ENTRY_1D: &str = "1d";
+const ENTRY_1H: &str = "1h";
+
+/// Period shown by the entries and resolution of the points, in seconds
+const WINDOWS: [(&str, i64, i64); 2] = [
+    (ENTRY_1H, 3600, 60),
+    (ENTRY_1D, 86400, 3600),
+];
+
+const DEFAULT_SAVE_INTERVAL_S: u64 = 60;
+
+const VALUE_UNKNOWN: &str = "?";
+
+/// Function used to get the number of seconds since the epoch
+fn now() -> i64 {
+    return unsafe { libc::time(std::ptr::null_mut()) } as i64;
+}
+
+/// Function used to format the value of a point
+///
+/// # Arguments
+///
+/// * `value` - The value
+fn format_value(value: f64) -> String {
+    return format!("{}", (value * 100.0).round() / 100.0);
+}
+
+/// Function used to add a directory of entries to a tree
+///
+/// The parent directories are created if needed.
+///
+/// # Arguments
+///
+/// * `fs_entries` - The entries of the tree
+/// * `names` - The names of the directory and of its parents
+/// * `children` - The entries of the directory
+fn insert(
+    fs_entries: &mut Vec<filesystem::FsEntry>,
+    names: &[&str],
+    children: &Vec<filesystem::FsEntry>) {
+
+    let (name, names) = match names.split_first() {
+        Some(n) => n,
+        None => return,
+    };
+
+    let index = match fs_entries.iter().position(|e| e.name == *name) {
+        Some(i) => i,
+        None => {
+            fs_entries.push(filesystem::FsEntry::new(
+                filesystem::FsEntry::create_inode(),
+                fuse::FileType::Directory,
+                name,
+                filesystem::Mode::ReadOnly,
+                &Vec::new()));
+
+            fs_entries.len() - 1
+        },
+    };
+
+    match names.is_empty() {
+        true => fs_entries[index].fs_entries.extend(children.iter().cloned()),
+        false => insert(&mut fs_entries[index].fs_entries, names, children),
+    }
+}
+
+/// Entry whose values are recorded
+struct Record {
+    pub entry: String,
+    pub series: rrd::Series,
+    pub fs_entry: filesystem::FsEntry,
+}
+
+impl Record {
+    /// Get the points of one of the windows
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `name` - The name of the window
+    fn points(&self, name: &str) -> Vec<(i64, f64)> {
+        return match WINDOWS.iter().find(|(n, _, _)| *n == name) {
+            Some((_, period_s, step_s)) =>
+                match self.series.points(*step_s, Some(now() - period_s)) {
+                    Some(p) => p,
+                    None => Vec::new(),
+                },
+
+            None => Vec::new(),
+        };
+    }
+
+    /// Get the value of one of the entries
+    ///
+    /// There's one `<timestamp> <value>` line per point.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `name` - The name of the entry
+    fn value(&self, name: &str) -> String {
+        return self.points(name)
+            .iter()
+            .map(|(t, v)| format!("{} {}\n", t, format_value(*v)))
+            .collect();
+    }
+
+    /// Get the average of the values of one of the windows
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `name` - The name of the window
+    fn average(&self, name: &str) -> String {
+        let points = self.points(name);
+
+        if points.is_empty() {
+            return VALUE_UNKNOWN.to_string();
+        }
+
+        let sum: f64 = points.iter().map(|(_, v)| v).sum();
+
+        return format_value(sum / points.len() as f64);
+    }
+}
+
+/// History backend that will record the values
+struct HistoryBackend {
+    config: config::ModuleConfig,
+    dir: Option<PathBuf>,
+    last_save: Instant,
+
+    pub records: Vec<Record>,
+    pub fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl HistoryBackend {
+    fn new() -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            dir: None,
+            last_save: Instant::now(),
+            records: Vec::new(),
+            fs_entries: Vec::new(),
+        }
+    }
+
+    /// Get the configured entries sorted by path
+    fn configured(&self) -> Vec<String> {
+        let mut entries: Vec<String> = match &self.config.history {
+            Some(c) => match &c.entries {
+                Some(e) => e.iter()
+                    .map(|p| p.trim_matches('/').to_string())
+                    .collect(),
+
+                None => Vec::new(),
+            },
+
+            None => Vec::new(),
+        };
+
+        entries.sort();
+        entries.dedup();
+
+        return entries;
+    }
+
+    /// Get the interval between two writes of the series
+    fn save_interval(&self) -> Duration {
+        return Duration::from_secs(match &self.config.history {
+            Some(c) => match c.save_interval_s {
+                Some(s) => s,
+                None => DEFAULT_SAVE_INTERVAL_S,
+            },
+
+            None => DEFAULT_SAVE_INTERVAL_S,
+        });
+    }
+
+    /// Write the series
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn save(&mut self) {
+        self.last_save = Instant::now();
+
+        let dir = match &self.dir {
+            Some(d) => d,
+            None => return,
+        };
+
+        for record in self.records.iter() {
+            match rrd::save(dir, &record.entry, &record.series) {
+                Ok(_) => (),
+                Err(e) => log::error!("{}: {}", record.entry, e),
+            }
+        }
+    }
+
+    /// Rebuild the records and the filesystem
+    ///
+    /// The series of the records are read from the disk.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `configured` - The configured entries
+    fn rebuild_filesystem(&mut self, configured: Vec<String>) {
+        // The series of the removed entries are kept up to date
+        self.save();
+
+        self.dir = match &self.config.history {
+            Some(c) => rrd::directory(c),
+            None => None,
+        };
+
+        if self.dir.is_none() {
+            log::error!("Cannot find history directory");
+        }
+
+        self.records.clear();
+        self.fs_entries.clear();
+
+        for entry in configured {
+            let series = match &self.dir {
+                Some(d) => match rrd::load(d, &entry) {
+                    Ok(s) => s,
+                    Err(e) => {
+                        log::error!("{}", e);
+                        rrd::Series::new()
+                    },
+                },
+
+                None => rrd::Series::new(),
+            };
+
+            let fs_entry = filesystem::FsEntry::new(
+                filesystem::FsEntry::create_inode(),
+                fuse::FileType::Directory,
+                &entry,
+                filesystem::Mode::ReadOnly,
+                &WINDOWS
+                    .iter()
+                    .map(|(n, _, _)| filesystem::FsEntry::new(
+                        filesystem::FsEntry::create_inode(),
+                        fuse::FileType::RegularFile,
+                        n,
+                        filesystem::Mode::ReadOnly,
+                        &Vec::new()))
+                    .collect());
+
+            let names: Vec<&str> = entry.split('/').collect();
+
+            insert(&mut self.fs_entries, &names, &fs_entry.fs_entries);
+
+            self.records.push(Record {
+                entry: entry,
+                series: series,
+                fs_entry: fs_entry,
+            });
+        }
+    }
+
+    /// Add the current values of the entries to their series
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn sample(&mut self) {
+        let time = now();
+
+        for record in self.records.iter_mut() {
+            match bus::value(&record.entry) {
+                Some(v) => match v.trim().parse::<f64>() {
+                    Ok(v) => record.series.add(time, v),
+                    Err(_) => (),
+                },
+
+                None => (),
+            }
+        }
+
+        if self.last_save.elapsed() >= self.save_interval() {
+            self.save();
+        }
+    }
+}
+
+impl module::Data for HistoryBackend {
+    /// Update history data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let configured = self.configured();
+
+        // Check if the configured entries have changed
+        let changed = configured.len() != self.records.len() ||
+            configured
+                .iter()
+                .zip(self.records.iter())
+                .any(|(e, r)| *e != r.entry);
+
+        if changed {
+            self.rebuild_filesystem(configured);
+            self.sample();
+
+            return Ok(module::Status::Changed(MODULE_NAME.to_string()));
+        }
+
+        self.sample();
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// History module structure
+pub struct History {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    backend: Arc<Mutex<HistoryBackend>>,
+}
+
+impl History {
+    /// History constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        _triggers: &Vec<triggers::Trigger>) -> Self {
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            backend: Arc::new(Mutex::new(HistoryBackend::new())),
+        }
+    }
+}
+
+impl module::Module for History {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// The series are written before stopping.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        match self.backend.lock() {
+            Ok(mut b) => b.save(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        return backend.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        for record in backend.records.iter() {
+            match record.fs_entry.find(inode) {
+                Some(e) => return record.value(&e.name),
+                None => continue,
+            }
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let values: serde_json::Map<String, serde_json::Value> = backend
+            .records
+            .iter()
+            .map(|r| (
+                r.entry.clone(),
+                serde_json::Value::Object(WINDOWS
+                    .iter()
+                    .map(|(n, _, _)| (
+                        n.to_string(),
+                        serde_json::json!(r.points(n))))
+                    .collect())))
+            .collect();
+
+        return match serde_json::to_string(&values) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// The averages of the windows are given.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return backend.records
+            .iter()
+            .flat_map(|r| WINDOWS
+                .iter()
+                .map(move |(n, _, _)| format!(
+                    "{}_{}={}",
+                    r.entry.replace('/', "_"),
+                    n,
+                    r.average(n))))
+            .collect::<Vec<String>>()
+            .join(" ");
+    }
+}
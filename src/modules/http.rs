@@ -0,0 +1,618 @@
+use fuse;
+use std::sync::Arc;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::net;
+use crate::publish;
+use crate::secrets;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "http";
+
+const ENTRY_LATENCY_MS: &str = "latency_ms";
+const ENTRY_STATUS_CODE: &str = "status_code";
+const ENTRY_UP: &str = "up";
+const ENTRY_VALUES: &str = "values";
+
+const DEFAULT_METHOD: &str = "GET";
+const DEFAULT_TIMEOUT_S: u64 = 10;
+
+const VALUE_FALSE: &str = "false";
+const VALUE_TRUE: &str = "true";
+const VALUE_UNKNOWN: &str = "?";
+
+/// Response to a request
+struct Response {
+    pub status_code: u16,
+    pub latency_ms: u64,
+    pub body: String,
+}
+
+/// Send a request using curl
+///
+/// # Arguments
+///
+/// * `check` - The configuration of the check
+fn request(check: &config::HttpCheckConfig) -> Option<Response> {
+    let timeout_s = check.timeout_s.unwrap_or(DEFAULT_TIMEOUT_S);
+
+    // The URL and the headers may hold secrets, they're not given as
+    // arguments
+    let mut options = vec![("url", secrets::resolve(&check.url)?)];
+
+    match &check.headers {
+        Some(headers) => for (name, value) in headers.iter() {
+            options.push((
+                "header",
+                format!("{}: {}", name, secrets::resolve(value)?)));
+        },
+
+        None => (),
+    }
+
+    // The status and the time are written on the last line, after the body
+    let args = vec![
+        "--silent".to_string(),
+        "--request".to_string(),
+        check.method.as_deref().unwrap_or(DEFAULT_METHOD).to_string(),
+        "--max-time".to_string(),
+        format!("{}", timeout_s),
+        "--write-out".to_string(),
+        "\n%{http_code} %{time_total}".to_string(),
+    ];
+
+    let output = match secrets::curl(&args, &options) {
+        Ok(o) => o,
+        Err(_) => return None,
+    };
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    let (body, last_line) = stdout.rsplit_once('\n')?;
+
+    let mut parts = last_line.split_whitespace();
+    let status_code = parts.next()?.parse::<u16>().ok()?;
+    let time_s = parts.next()?.replace(',', ".").parse::<f64>().ok()?;
+
+    // No response received
+    if status_code == 0 {
+        return None;
+    }
+
+    return Some(Response {
+        status_code: status_code,
+        latency_ms: (time_s * 1000.0) as u64,
+        body: body.to_string(),
+    });
+}
+
+/// Value extracted from the body of the responses
+struct Extracted {
+    pub name: String,
+    pub pointer: String,
+    pub inode: u64,
+    pub value: String,
+}
+
+/// Endpoint checked by the module
+struct Check {
+    pub name: String,
+    pub config: config::HttpCheckConfig,
+    pub up: String,
+    pub status_code: String,
+    pub latency_ms: String,
+    pub values: Vec<Extracted>,
+    pub fs_entry: filesystem::FsEntry,
+    pub last_response: Option<Response>,
+}
+
+impl Check {
+    /// Get the value of an entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `entry` - The filesystem entry
+    fn value(&self, entry: &filesystem::FsEntry) -> String {
+        match self.values.iter().find(|v| v.inode == entry.inode) {
+            Some(v) => return v.value.clone(),
+            None => (),
+        }
+
+        return match entry.name.as_str() {
+            ENTRY_UP => self.up.clone(),
+            ENTRY_STATUS_CODE => self.status_code.clone(),
+            ENTRY_LATENCY_MS => self.latency_ms.clone(),
+            _ => VALUE_UNKNOWN.to_string(),
+        };
+    }
+
+    /// Get the paths and values of the entries
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn values(&self) -> Vec<(String, String)> {
+        let mut values = vec![
+            (ENTRY_UP.to_string(), self.up.clone()),
+            (ENTRY_STATUS_CODE.to_string(), self.status_code.clone()),
+            (ENTRY_LATENCY_MS.to_string(), self.latency_ms.clone()),
+        ];
+
+        for v in self.values.iter() {
+            values.push((
+                format!("{}/{}", ENTRY_VALUES, v.name),
+                v.value.clone()));
+        }
+
+        return values;
+    }
+}
+
+/// HTTP backend that will compute the values
+struct HttpBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+
+    pub checks: Vec<Check>,
+}
+
+impl HttpBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            checks: Vec::new(),
+        }
+    }
+
+    /// Get the configured checks sorted by name
+    fn configured(&self) -> Vec<(String, config::HttpCheckConfig)> {
+        let mut checks: Vec<(String, config::HttpCheckConfig)> =
+            match &self.config.http {
+                Some(c) => match &c.checks {
+                    Some(checks) => checks.iter()
+                        .map(|(n, c)| (n.clone(), c.clone()))
+                        .collect(),
+
+                    None => Vec::new(),
+                },
+
+                None => Vec::new(),
+            };
+
+        checks.sort_by(|a, b| a.0.cmp(&b.0));
+
+        return checks;
+    }
+
+    /// Rebuild the checks and the filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `configured` - The configured checks
+    fn rebuild_filesystem(
+        &mut self,
+        configured: Vec<(String, config::HttpCheckConfig)>) {
+
+        // Deletion triggers
+        for check in self.checks.iter() {
+            for (path, _) in check.values() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Delete,
+                    &self.name,
+                    &format!("{}/{}", check.name, path),
+                    "",
+                    "");
+            }
+        }
+
+        self.checks.clear();
+
+        for (name, config) in configured {
+            let mut pointers: Vec<(String, String)> = match &config.extract {
+                Some(e) => e.iter()
+                    .map(|(n, p)| (n.clone(), p.clone()))
+                    .collect(),
+
+                None => Vec::new(),
+            };
+
+            pointers.sort();
+
+            let values: Vec<Extracted> = pointers
+                .into_iter()
+                .map(|(name, pointer)| Extracted {
+                    name: name,
+                    pointer: pointer,
+                    inode: filesystem::FsEntry::create_inode(),
+                    value: String::new(),
+                })
+                .collect();
+
+            let mut entries: Vec<filesystem::FsEntry> =
+                [ENTRY_UP, ENTRY_STATUS_CODE, ENTRY_LATENCY_MS]
+                    .iter()
+                    .map(|e| filesystem::FsEntry::new(
+                        filesystem::FsEntry::create_inode(),
+                        fuse::FileType::RegularFile,
+                        e,
+                        filesystem::Mode::ReadOnly,
+                        &Vec::new()))
+                    .collect();
+
+            if !values.is_empty() {
+                entries.push(filesystem::FsEntry::new(
+                    filesystem::FsEntry::create_inode(),
+                    fuse::FileType::Directory,
+                    ENTRY_VALUES,
+                    filesystem::Mode::ReadOnly,
+                    &values
+                        .iter()
+                        .map(|v| filesystem::FsEntry::new(
+                            v.inode,
+                            fuse::FileType::RegularFile,
+                            &v.name,
+                            filesystem::Mode::ReadOnly,
+                            &Vec::new()))
+                        .collect()));
+            }
+
+            self.checks.push(Check {
+                fs_entry: filesystem::FsEntry::new(
+                    filesystem::FsEntry::create_inode(),
+                    fuse::FileType::Directory,
+                    &name,
+                    filesystem::Mode::ReadOnly,
+                    &entries),
+                name: name,
+                config: config,
+                up: String::new(),
+                status_code: String::new(),
+                latency_ms: String::new(),
+                values: values,
+                last_response: None,
+            });
+        }
+    }
+
+    /// Send the requests and call the triggers
+    ///
+    /// An endpoint is up when it responds with the expected status, or with
+    /// a success status if none is expected. The values are kept while the
+    /// host is offline or in backoff after failures.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `kind` - The kind of trigger to be called
+    fn evaluate(&mut self, kind: triggers::Kind) {
+        for check in self.checks.iter_mut() {
+            let host = net::host(&check.config.url);
+
+            if !net::acquire(&host) {
+                continue;
+            }
+
+            let response = request(&check.config);
+
+            net::report(&host, response.is_some());
+
+            let (up, status_code, latency_ms) = match &response {
+                Some(r) => (
+                    match check.config.expected_status {
+                        Some(s) => r.status_code == s,
+                        None => r.status_code >= 200 && r.status_code < 300,
+                    },
+                    format!("{}", r.status_code),
+                    format!("{}", r.latency_ms)),
+
+                None => (
+                    false,
+                    VALUE_UNKNOWN.to_string(),
+                    VALUE_UNKNOWN.to_string()),
+            };
+
+            let up = match up {
+                true => VALUE_TRUE.to_string(),
+                false => VALUE_FALSE.to_string(),
+            };
+
+            let json: Option<serde_json::Value> = match &response {
+                Some(r) if !check.values.is_empty() =>
+                    serde_json::from_str(&r.body).ok(),
+
+                _ => None,
+            };
+
+            let values = [
+                (ENTRY_UP, &mut check.up, up),
+                (ENTRY_STATUS_CODE, &mut check.status_code, status_code),
+                (ENTRY_LATENCY_MS, &mut check.latency_ms, latency_ms),
+            ];
+
+            for (name, current, value) in values {
+                publish::update(
+                    &self.triggers,
+                    kind,
+                    &self.name,
+                    &format!("{}/{}", check.name, name),
+                    current,
+                    &value);
+            }
+
+            // Extracted values, strings are given without quotes
+            for extracted in check.values.iter_mut() {
+                let value = match json
+                    .as_ref()
+                    .and_then(|j| j.pointer(&extracted.pointer)) {
+
+                    Some(serde_json::Value::String(s)) => s.clone(),
+                    Some(v) => v.to_string(),
+                    None => VALUE_UNKNOWN.to_string(),
+                };
+
+                publish::update(
+                    &self.triggers,
+                    kind,
+                    &self.name,
+                    &format!(
+                        "{}/{}/{}",
+                        check.name,
+                        ENTRY_VALUES,
+                        extracted.name),
+                    &mut extracted.value,
+                    &value);
+            }
+
+            check.last_response = response;
+        }
+    }
+}
+
+impl module::Data for HttpBackend {
+    /// Update HTTP data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let configured = self.configured();
+
+        // Check if the configured checks have changed
+        let changed = configured.len() != self.checks.len() ||
+            configured
+                .iter()
+                .zip(self.checks.iter())
+                .any(|((n, c), check)|
+                    *n != check.name || *c != check.config);
+
+        if changed {
+            self.rebuild_filesystem(configured);
+            self.evaluate(triggers::Kind::Create);
+
+            return Ok(module::Status::Changed(MODULE_NAME.to_string()));
+        }
+
+        self.evaluate(triggers::Kind::Update);
+
+        return Ok(module::Status::Ok);
+    }
+
+    /// Get the responses of the last requests
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn raw(&self) -> Option<String> {
+        let raw: Vec<String> = self.checks
+            .iter()
+            .map(|c| match &c.last_response {
+                Some(r) => format!(
+                    "{}: {} in {} ms\n{}",
+                    c.name,
+                    r.status_code,
+                    r.latency_ms,
+                    r.body),
+
+                None => format!("{}: no response", c.name),
+            })
+            .collect();
+
+        return Some(raw.join("\n\n"));
+    }
+}
+
+/// HTTP module structure
+pub struct Http {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    backend: Arc<Mutex<HttpBackend>>,
+}
+
+impl Http {
+    /// Http constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            backend: Arc::new(Mutex::new(HttpBackend::new(name, triggers))),
+        }
+    }
+}
+
+impl module::Module for Http {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        return backend.checks.iter().map(|c| c.fs_entry.clone()).collect();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        for check in backend.checks.iter() {
+            match check.fs_entry.find(inode) {
+                Some(e) => return check.value(e),
+                None => continue,
+            }
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let values: serde_json::Map<String, serde_json::Value> = backend
+            .checks
+            .iter()
+            .map(|c| (
+                c.name.clone(),
+                serde_json::Value::Object(c
+                    .values()
+                    .into_iter()
+                    .map(|(p, v)| (p, v.into()))
+                    .collect())))
+            .collect();
+
+        return match serde_json::to_string(&values) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return backend.checks
+            .iter()
+            .flat_map(|c| c
+                .values()
+                .into_iter()
+                .map(move |(p, v)| format!(
+                    "{}_{}={}",
+                    c.name,
+                    p.replace('/', "_"),
+                    v)))
+            .collect::<Vec<String>>()
+            .join(" ");
+    }
+}
@@ -0,0 +1,323 @@
+use fuse;
+use serde::Serialize;
+use std::sync::Arc;
+
+use crate::config;
+use crate::dbus;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::inhibit;
+use crate::modules::module;
+use crate::publish;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const LOGIN1_SERVICE: &str = "org.freedesktop.login1";
+const LOGIN1_PATH: &str = "/org/freedesktop/login1";
+const LOGIN1_MANAGER: &str = "org.freedesktop.login1.Manager";
+
+const ENTRY_COUNT: &str = "count";
+const ENTRY_HELD: &str = "held";
+const ENTRY_LIST: &str = "list";
+
+const VALUE_UNKNOWN: &str = "?";
+
+/// Information about the inhibitor locks
+#[derive(Serialize)]
+struct InhibitorsData {
+    pub count: String,
+    pub held: String,
+    pub list: String,
+}
+
+impl InhibitorsData {
+    /// InhibitorsData constructor
+    pub fn new() -> Self {
+        Self {
+            count: VALUE_UNKNOWN.to_string(),
+            held: VALUE_UNKNOWN.to_string(),
+            list: VALUE_UNKNOWN.to_string(),
+        }
+    }
+}
+
+/// Inhibitors backend that will compute the values
+struct InhibitorsBackend {
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+
+    pub data: InhibitorsData,
+}
+
+impl InhibitorsBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            data: InhibitorsData::new(),
+        }
+    }
+
+    /// Get the inhibitor locks of logind as `who: what (why)` lines
+    fn query() -> Result<Vec<String>, error::CerebroError> {
+        let reply = dbus::call(
+            dbus::Bus::System,
+            LOGIN1_SERVICE,
+            LOGIN1_PATH,
+            LOGIN1_MANAGER,
+            "ListInhibitors",
+            "",
+            &[])?;
+
+        // Each lock is a (what, who, why, mode, uid, pid) structure
+        let list = match reply[0].as_array() {
+            Some(l) => l.to_vec(),
+            None => return error!("Invalid list of inhibitors"),
+        };
+
+        return Ok(list
+            .iter()
+            .map(|i| format!(
+                "{}: {} ({})",
+                i[1].as_str().unwrap_or(VALUE_UNKNOWN),
+                i[0].as_str().unwrap_or(VALUE_UNKNOWN),
+                i[2].as_str().unwrap_or(VALUE_UNKNOWN)))
+            .collect());
+    }
+}
+
+impl module::Data for InhibitorsBackend {
+    /// Update the inhibitor locks
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let list = InhibitorsBackend::query()?;
+        let held = inhibit::held();
+
+        let kind = match self.first_update {
+            true => triggers::Kind::Create,
+            false => triggers::Kind::Update,
+        };
+
+        self.first_update = false;
+
+        let values = [
+            (ENTRY_COUNT, &mut self.data.count, format!("{}", list.len())),
+            (ENTRY_HELD, &mut self.data.held, held.join("\n")),
+            (ENTRY_LIST, &mut self.data.list, list.join("\n")),
+        ];
+
+        for (name, current, value) in values {
+            publish::update(
+                &self.triggers,
+                kind,
+                &self.name,
+                name,
+                current,
+                &value);
+        }
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// Inhibitors module structure
+pub struct Inhibitors {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    inode_count: u64,
+    inode_held: u64,
+    inode_list: u64,
+    backend: Arc<Mutex<InhibitorsBackend>>,
+    fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl Inhibitors {
+    /// Inhibitors constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        let count = filesystem::FsEntry::create_inode();
+        let held = filesystem::FsEntry::create_inode();
+        let list = filesystem::FsEntry::create_inode();
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            inode_count: count,
+            inode_held: held,
+            inode_list: list,
+            backend:
+                Arc::new(Mutex::new(InhibitorsBackend::new(name, triggers))),
+            fs_entries: vec![
+                filesystem::FsEntry::new(
+                    count,
+                    fuse::FileType::RegularFile,
+                    ENTRY_COUNT,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    held,
+                    fuse::FileType::RegularFile,
+                    ENTRY_HELD,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    list,
+                    fuse::FileType::RegularFile,
+                    ENTRY_LIST,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+            ],
+        }
+    }
+}
+
+impl module::Module for Inhibitors {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        return self.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        if inode == self.inode_count {
+            return backend.data.count.clone();
+        }
+
+        if inode == self.inode_held {
+            return backend.data.held.clone();
+        }
+
+        if inode == self.inode_list {
+            return backend.data.list.clone();
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return format!(
+            "count={} held={:?} list={:?}",
+            backend.data.count,
+            backend.data.held.replace('\n', " "),
+            backend.data.list.replace('\n', " "));
+    }
+}
@@ -0,0 +1,550 @@
+use fuse;
+use std::fs;
+use std::path;
+use std::sync::Arc;
+
+use crate::config;
+use crate::demand;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::cooling;
+use crate::modules::module;
+use crate::platform;
+use crate::publish;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "leds";
+
+const ENTRY_BRIGHTNESS: &str = "brightness";
+const ENTRY_MAX_BRIGHTNESS: &str = "max_brightness";
+const ENTRY_TRIGGER: &str = "trigger";
+
+const ENTRIES: [&str; 3] =
+    [ENTRY_BRIGHTNESS, ENTRY_MAX_BRIGHTNESS, ENTRY_TRIGGER];
+
+/// Directory of the LEDs relatively to the sysfs root
+const LEDS: &str = "class/leds";
+
+const VALUE_OFF: &str = "off";
+const VALUE_ON: &str = "on";
+const VALUE_UNKNOWN: &str = "?";
+
+/// Function used to get the directory of a LED
+///
+/// # Arguments
+///
+/// * `name` - The name of the LED (e.g. `input3::capslock`)
+fn led_path(name: &str) -> Result<path::PathBuf, error::CerebroError> {
+    if name.is_empty() || name.contains('/') || name.starts_with('.') {
+        return error!(&format!("Invalid LED `{}`", name));
+    }
+
+    return match platform::current().sysfs(LEDS) {
+        Some(r) => Ok(r.join(name)),
+        None => error!("No LEDs on this platform"),
+    };
+}
+
+/// Function used to read an attribute of a LED
+///
+/// The selected trigger is the one between brackets in the list of the
+/// available ones (e.g. `none [kbd-capslock] timer`).
+///
+/// # Arguments
+///
+/// * `path` - The directory of the LED
+/// * `name` - The name of the attribute
+fn read(path: &path::Path, name: &str) -> String {
+    let value = match fs::read_to_string(path.join(name)) {
+        Ok(v) => v.trim().to_string(),
+        Err(_) => return VALUE_UNKNOWN.to_string(),
+    };
+
+    if name != ENTRY_TRIGGER {
+        return value;
+    }
+
+    return match value
+        .split_whitespace()
+        .find(|t| t.starts_with('[') && t.ends_with(']')) {
+
+        Some(t) => t[1..t.len() - 1].to_string(),
+        None => VALUE_UNKNOWN.to_string(),
+    };
+}
+
+/// Function used to write an attribute of a LED
+///
+/// # Arguments
+///
+/// * `led` - The name of the LED
+/// * `name` - The name of the attribute, `brightness` or `trigger`
+/// * `value` - The value to be written
+fn write(led: &str, name: &str, value: &str) -> error::Return {
+    let path = led_path(led)?.join(name);
+
+    return match fs::write(&path, value) {
+        Ok(_) => success!(),
+        Err(e) => error!(&format!("Cannot write {:?}: {:?}", path, e)),
+    };
+}
+
+/// Function used to run the LED action of a trigger
+///
+/// The value is `on` (maximum brightness), `off`, a brightness or the name
+/// of a kernel trigger (e.g. `timer`).
+///
+/// # Arguments
+///
+/// * `args` - The name of the LED followed by the value
+pub fn action(args: &str) -> error::Return {
+    let mut words = args.split_whitespace();
+
+    let (led, value) = match (words.next(), words.next()) {
+        (Some(l), Some(v)) => (l, v),
+        _ => return error!("LED action must be `@led <name> <value>`"),
+    };
+
+    match value {
+        VALUE_ON => {
+            let max = read(&led_path(led)?, ENTRY_MAX_BRIGHTNESS);
+            write(led, ENTRY_BRIGHTNESS, &max)?;
+        },
+
+        VALUE_OFF => write(led, ENTRY_BRIGHTNESS, "0")?,
+
+        v if v.parse::<u64>().is_ok() => write(led, ENTRY_BRIGHTNESS, v)?,
+
+        v => write(led, ENTRY_TRIGGER, v)?,
+    }
+
+    // Show the new value without waiting for the next update
+    module::refresh(MODULE_NAME);
+    demand::wake(MODULE_NAME);
+
+    return success!();
+}
+
+/// LED of the machine
+struct Led {
+    pub name: String,
+    pub path: path::PathBuf,
+    pub brightness: String,
+    pub max_brightness: String,
+    pub trigger: String,
+    pub fs_entry: filesystem::FsEntry,
+}
+
+impl Led {
+    /// Get the value of one of the entries
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `name` - The name of the entry
+    fn value(&self, name: &str) -> String {
+        return match name {
+            ENTRY_BRIGHTNESS => self.brightness.clone(),
+            ENTRY_MAX_BRIGHTNESS => self.max_brightness.clone(),
+            ENTRY_TRIGGER => self.trigger.clone(),
+            _ => VALUE_UNKNOWN.to_string(),
+        };
+    }
+
+    /// Get the name of the entry of an inode, if it belongs to the LED
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the entry
+    fn entry(&self, inode: u64) -> Option<&str> {
+        return self.fs_entry.fs_entries
+            .iter()
+            .find(|e| e.inode == inode)
+            .map(|e| e.name.as_str());
+    }
+}
+
+/// LEDs backend that will compute the values
+struct LedsBackend {
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+
+    pub leds: Vec<Led>,
+}
+
+impl LedsBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            leds: Vec::new(),
+        }
+    }
+
+    /// Get the names of the LEDs of the machine
+    fn query_leds() -> Result<Vec<String>, error::CerebroError> {
+        let root = match platform::current().sysfs(LEDS) {
+            Some(r) => r,
+            None => return error!("No LEDs on this platform"),
+        };
+
+        let entries = match fs::read_dir(&root) {
+            Ok(e) => e,
+            Err(_) => return error!("Cannot list LEDs"),
+        };
+
+        let mut names: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| e.file_name().into_string().ok())
+            .collect();
+
+        names.sort();
+
+        return Ok(names);
+    }
+
+    /// Rebuild the LEDs and the filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `names` - The names of the LEDs
+    fn rebuild_filesystem(&mut self, names: Vec<String>)
+        -> Result<(), error::CerebroError> {
+
+        // Deletion triggers
+        for led in self.leds.iter() {
+            for entry in ENTRIES.iter() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Delete,
+                    &self.name,
+                    &format!("{}/{}", led.name, entry),
+                    "",
+                    "");
+            }
+        }
+
+        self.leds.clear();
+
+        for name in names {
+            let path = led_path(&name)?;
+
+            let fs_entry = filesystem::FsEntry::new(
+                filesystem::FsEntry::create_inode(),
+                fuse::FileType::Directory,
+                &name,
+                filesystem::Mode::ReadOnly,
+                &ENTRIES
+                    .iter()
+                    .map(|e| filesystem::FsEntry::new(
+                        filesystem::FsEntry::create_inode(),
+                        fuse::FileType::RegularFile,
+                        e,
+                        match *e {
+                            ENTRY_MAX_BRIGHTNESS => filesystem::Mode::ReadOnly,
+                            _ => filesystem::Mode::ReadWrite,
+                        },
+                        &Vec::new()))
+                    .collect());
+
+            self.leds.push(Led {
+                name: name,
+                path: path,
+                brightness: String::new(),
+                max_brightness: String::new(),
+                trigger: String::new(),
+                fs_entry: fs_entry,
+            });
+        }
+
+        return Ok(());
+    }
+}
+
+impl module::Data for LedsBackend {
+    /// Update LEDs data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let names = LedsBackend::query_leds()?;
+
+        // Check if the LEDs have changed
+        let changed = self.first_update ||
+            names.len() != self.leds.len() ||
+            names.iter().zip(self.leds.iter()).any(|(n, l)| *n != l.name);
+
+        let mut status = module::Status::Ok;
+        let mut kind = triggers::Kind::Update;
+
+        if changed {
+            self.rebuild_filesystem(names)?;
+            self.first_update = false;
+
+            status = module::Status::Changed(MODULE_NAME.to_string());
+            kind = triggers::Kind::Create;
+        }
+
+        for led in self.leds.iter_mut() {
+            let values = [
+                (ENTRY_BRIGHTNESS, &mut led.brightness),
+                (ENTRY_MAX_BRIGHTNESS, &mut led.max_brightness),
+                (ENTRY_TRIGGER, &mut led.trigger),
+            ];
+
+            for (name, current) in values {
+                publish::update(
+                    &self.triggers,
+                    kind,
+                    &self.name,
+                    &format!("{}/{}", led.name, name),
+                    current,
+                    &read(&led.path, name));
+            }
+        }
+
+        return Ok(status);
+    }
+}
+
+/// LEDs module structure
+pub struct Leds {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    backend: Arc<Mutex<LedsBackend>>,
+}
+
+impl Leds {
+    /// LEDs constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            backend: Arc::new(Mutex::new(LedsBackend::new(name, triggers))),
+        }
+    }
+}
+
+impl module::Module for Leds {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Check if the system has LEDs
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn probe(&self) -> Option<bool> {
+        return Some(!cooling::list_sysfs(LEDS, "", "").is_empty());
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        return backend.leds.iter().map(|l| l.fs_entry.clone()).collect();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        for led in backend.leds.iter() {
+            match led.entry(inode) {
+                Some(e) => return led.value(e),
+                None => continue,
+            }
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, inode: u64, data: &[u8]) {
+        let mut backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => {
+                log::error!("Cannot lock backend");
+                return;
+            },
+        };
+
+        let value = String::from_utf8_lossy(data).trim().to_string();
+        let triggers = backend.triggers.to_vec();
+
+        for led in backend.leds.iter_mut() {
+            let entry = match led.entry(inode) {
+                Some(e) => e.to_string(),
+                None => continue,
+            };
+
+            match write(&led.name, &entry, &value) {
+                Ok(_) => (),
+                Err(e) => {
+                    log::error!("{}: {}", MODULE_NAME, e);
+                    return;
+                },
+            }
+
+            let current = match entry.as_str() {
+                ENTRY_BRIGHTNESS => &mut led.brightness,
+                ENTRY_TRIGGER => &mut led.trigger,
+                _ => return,
+            };
+
+            publish::update(
+                &triggers,
+                triggers::Kind::Update,
+                &self.name,
+                &format!("{}/{}", led.name, entry),
+                current,
+                &read(&led.path, &entry));
+
+            return;
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let values: serde_json::Map<String, serde_json::Value> = backend
+            .leds
+            .iter()
+            .map(|l| (
+                l.name.clone(),
+                serde_json::Value::Object(ENTRIES
+                    .iter()
+                    .map(|e| (e.to_string(), l.value(e).into()))
+                    .collect())))
+            .collect();
+
+        return match serde_json::to_string(&values) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let mut values = Vec::new();
+
+        for led in backend.leds.iter() {
+            let name: String = led.name
+                .chars()
+                .map(|c| match c.is_ascii_alphanumeric() {
+                    true => c,
+                    false => '_',
+                })
+                .collect();
+
+            for entry in ENTRIES.iter() {
+                values.push(format!("{}_{}={}", name, entry, led.value(entry)));
+            }
+        }
+
+        return values.join(" ");
+    }
+}
@@ -0,0 +1,494 @@
+use fuse;
+use serde::{Serialize};
+use std::process;
+use std::sync::Arc;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "location";
+
+const DEFAULT_COMMAND: &str =
+    "/usr/libexec/geoclue-2.0/demos/where-am-i -t 5";
+
+const VALUE_UNKNOWN: &str = "?";
+
+const ENTRY_ACCURACY: &str = "accuracy";
+const ENTRY_LATITUDE: &str = "latitude";
+const ENTRY_LONGITUDE: &str = "longitude";
+const ENTRY_TIMEZONE: &str = "timezone";
+
+/// Information about the location
+#[derive(Serialize)]
+struct LocationData
+{
+    pub latitude: String,
+    pub longitude: String,
+    pub accuracy: String,
+    pub timezone: String,
+}
+
+impl LocationData {
+    /// LocationData constructor
+    pub fn new() -> Self {
+        Self {
+            latitude: VALUE_UNKNOWN.to_string(),
+            longitude: VALUE_UNKNOWN.to_string(),
+            accuracy: VALUE_UNKNOWN.to_string(),
+            timezone: VALUE_UNKNOWN.to_string(),
+        }
+    }
+}
+
+/// Location backend that will compute the values
+struct LocationBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+
+    pub data: LocationData,
+}
+
+impl LocationBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            data: LocationData::new(),
+        }
+    }
+
+    /// Query GeoClue for the current location
+    ///
+    /// GeoClue drops a client as soon as the D-Bus connection that created it
+    /// is closed, so the location is requested through an agent (the
+    /// `where-am-i` demo by default) that keeps its connection open until it
+    /// gets a fix.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command to be executed
+    fn query(command: &str) -> Result<(f64, f64, f64), error::CerebroError> {
+        let mut parsed_command = match shellwords::split(command) {
+            Ok(w) => w,
+            Err(e) => return error!(&format!("Cannot split command: {:?}", e)),
+        };
+
+        if parsed_command.is_empty() {
+            return error!("Empty location command");
+        }
+
+        let args = parsed_command.split_off(1);
+
+        let output = match process::Command::new(&parsed_command[0])
+            .args(args).output() {
+
+            Ok(o) => o,
+            Err(e) =>
+                return error!(&format!("Cannot execute command: {:?}", e)),
+        };
+
+        let output = String::from_utf8_lossy(&output.stdout);
+
+        let mut latitude: Option<f64> = None;
+        let mut longitude: Option<f64> = None;
+        let mut accuracy: Option<f64> = None;
+
+        // Keep the last fix printed by the agent
+        for line in output.lines() {
+            let mut parts = line.splitn(2, ':');
+
+            let key = match parts.next() {
+                Some(k) => k.trim(),
+                None => continue,
+            };
+
+            let value = match parts.next() {
+                Some(v) => v
+                    .trim()
+                    .trim_end_matches("meters")
+                    .trim_end_matches('°')
+                    .trim()
+                    .parse::<f64>()
+                    .ok(),
+                None => continue,
+            };
+
+            match key {
+                "Latitude" => latitude = value,
+                "Longitude" => longitude = value,
+                "Accuracy" => accuracy = value,
+                _ => (),
+            }
+        }
+
+        return match (latitude, longitude, accuracy) {
+            (Some(lat), Some(lon), Some(acc)) => Ok((lat, lon, acc)),
+            _ => error!("No location returned by GeoClue"),
+        };
+    }
+
+    /// Derive the nautical timezone from a longitude
+    ///
+    /// # Arguments
+    ///
+    /// * `longitude` - The longitude in degrees
+    fn timezone(longitude: f64) -> String {
+        let offset = (longitude / 15.0).round() as i64;
+
+        return match offset {
+            0 => "UTC".to_string(),
+            o if o > 0 => format!("UTC+{}", o),
+            o => format!("UTC{}", o),
+        };
+    }
+
+    /// Update a value and call the triggers if it has changed
+    ///
+    /// # Arguments
+    ///
+    /// * `triggers` - The list of triggers
+    /// * `kind` - The kind of trigger to be called
+    /// * `module` - The name of the module
+    /// * `name` - The name of the entry
+    /// * `current` - The current value of the entry
+    /// * `value` - The new value of the entry
+    fn update_value(
+        triggers: &Vec<triggers::Trigger>,
+        kind: triggers::Kind,
+        module: &str,
+        name: &str,
+        current: &mut String,
+        value: String) {
+
+        if value == *current {
+            return;
+        }
+
+        let old_value = current.clone();
+
+        *current = value;
+
+        log::debug!("{}: {}={}", MODULE_NAME, name, current);
+
+        triggers::find_all_and_execute(
+            triggers,
+            kind,
+            module,
+            name,
+            &old_value,
+            current);
+    }
+}
+
+impl module::Data for LocationBackend {
+    /// Update location data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let location_config = match &self.config.location {
+            Some(c) => c,
+            None => return error!("Missing location configuration"),
+        };
+
+        // The location is only requested with an explicit consent
+        match location_config.consent {
+            Some(true) => (),
+            _ => return error!("Location access not consented"),
+        }
+
+        let command = match &location_config.command {
+            Some(c) => c.clone(),
+            None => DEFAULT_COMMAND.to_string(),
+        };
+
+        let kind = match self.first_update {
+            true => triggers::Kind::Create,
+            false => triggers::Kind::Update,
+        };
+
+        let (latitude, longitude, accuracy, timezone) =
+            match LocationBackend::query(&command) {
+
+            Ok((lat, lon, acc)) => (
+                format!("{:.6}", lat),
+                format!("{:.6}", lon),
+                format!("{}", acc.round() as i64),
+                LocationBackend::timezone(lon)),
+
+            Err(e) => {
+                log::error!("Cannot get location: {}", e);
+
+                (
+                    VALUE_UNKNOWN.to_string(),
+                    VALUE_UNKNOWN.to_string(),
+                    VALUE_UNKNOWN.to_string(),
+                    VALUE_UNKNOWN.to_string(),
+                )
+            },
+        };
+
+        LocationBackend::update_value(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_LATITUDE,
+            &mut self.data.latitude,
+            latitude);
+
+        LocationBackend::update_value(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_LONGITUDE,
+            &mut self.data.longitude,
+            longitude);
+
+        LocationBackend::update_value(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_ACCURACY,
+            &mut self.data.accuracy,
+            accuracy);
+
+        LocationBackend::update_value(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_TIMEZONE,
+            &mut self.data.timezone,
+            timezone);
+
+        self.first_update = false;
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// Location module structure
+pub struct Location {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    inode_latitude: u64,
+    inode_longitude: u64,
+    inode_accuracy: u64,
+    inode_timezone: u64,
+    backend: Arc<Mutex<LocationBackend>>,
+    fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl Location {
+    /// Location constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        let latitude = filesystem::FsEntry::create_inode();
+        let longitude = filesystem::FsEntry::create_inode();
+        let accuracy = filesystem::FsEntry::create_inode();
+        let timezone = filesystem::FsEntry::create_inode();
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            inode_latitude: latitude,
+            inode_longitude: longitude,
+            inode_accuracy: accuracy,
+            inode_timezone: timezone,
+            backend: Arc::new(Mutex::new(LocationBackend::new(name, triggers))),
+            fs_entries: vec![
+                filesystem::FsEntry::new(
+                    latitude,
+                    fuse::FileType::RegularFile,
+                    ENTRY_LATITUDE,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    longitude,
+                    fuse::FileType::RegularFile,
+                    ENTRY_LONGITUDE,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    accuracy,
+                    fuse::FileType::RegularFile,
+                    ENTRY_ACCURACY,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    timezone,
+                    fuse::FileType::RegularFile,
+                    ENTRY_TIMEZONE,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+                ],
+        }
+    }
+}
+
+impl module::Module for Location {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        let mut backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        };
+
+        backend.config = config.clone();
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        return self.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        if inode == self.inode_latitude {
+            return backend.data.latitude.clone();
+        }
+
+        if inode == self.inode_longitude {
+            return backend.data.longitude.clone();
+        }
+
+        if inode == self.inode_accuracy {
+            return backend.data.accuracy.clone();
+        }
+
+        if inode == self.inode_timezone {
+            return backend.data.timezone.clone();
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return format!(
+            "latitude={} longitude={} accuracy={} timezone={}",
+            backend.data.latitude,
+            backend.data.longitude,
+            backend.data.accuracy,
+            backend.data.timezone).to_string();
+    }
+}
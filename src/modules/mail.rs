@@ -0,0 +1,563 @@
+use fuse;
+use notify::Watcher;
+use std::fs;
+use std::path;
+use std::sync::Arc;
+use std::sync::mpsc;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::files;
+use crate::modules::module;
+use crate::publish;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "mail";
+
+const ENTRY_TOTAL_UNREAD: &str = "total_unread";
+const ENTRY_UNREAD: &str = "unread";
+
+/// Subdirectories of a Maildir holding the messages
+const MAILDIR_CUR: &str = "cur";
+const MAILDIR_NEW: &str = "new";
+
+/// Separator of the flags in the name of a message (e.g. `id:2,RS`)
+const FLAGS_SEPARATOR: &str = ":2,";
+
+/// Flag of the messages that have been read
+const FLAG_SEEN: char = 'S';
+
+const VALUE_UNKNOWN: &str = "?";
+
+/// Function used to count the unread messages of a Maildir
+///
+/// The messages of `new` have never been seen by a client, the ones of
+/// `cur` are unread unless they have the seen flag.
+///
+/// # Arguments
+///
+/// * `path` - The path of the Maildir
+fn count_unread(path: &path::Path) -> Option<usize> {
+    let new = fs::read_dir(path.join(MAILDIR_NEW)).ok()?.count();
+
+    let cur = fs::read_dir(path.join(MAILDIR_CUR))
+        .ok()?
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            let name = e.file_name();
+            let name = name.to_string_lossy();
+
+            match name.rfind(FLAGS_SEPARATOR) {
+                Some(i) => !name[i + FLAGS_SEPARATOR.len()..]
+                    .contains(FLAG_SEEN),
+                None => true,
+            }
+        })
+        .count();
+
+    return Some(new + cur);
+}
+
+/// Maildir watched by the module
+struct Account {
+    pub name: String,
+    pub text: String,
+    pub path: path::PathBuf,
+    pub unread: String,
+    pub fs_entry: filesystem::FsEntry,
+}
+
+impl Account {
+    /// Check if an event on a path concerns the account
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `path` - The path of the event
+    fn concerns(&self, path: &path::Path) -> bool {
+        return path.ancestors().take(3).any(|p| p == self.path);
+    }
+}
+
+/// Proxy backend that is only use in the context of the thread
+struct MailBackendProxy {
+    config: config::ModuleConfig,
+    backend: Arc<Mutex<MailBackend>>,
+}
+
+impl MailBackendProxy {
+    fn new(backend: Arc<Mutex<MailBackend>>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            backend: backend,
+        }
+    }
+
+    /// Get the configured accounts (name, path) sorted by name
+    fn configured(&self) -> Vec<(String, String)> {
+        let mut accounts: Vec<(String, String)> = match &self.config.mail {
+            Some(c) => c.accounts
+                .iter()
+                .map(|(n, p)| (n.clone(), p.clone()))
+                .collect(),
+
+            None => Vec::new(),
+        };
+
+        accounts.sort();
+
+        return accounts;
+    }
+
+    /// Update the accounts if the configuration has changed
+    ///
+    /// Returns true if the filesystem has been rebuilt.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update_accounts(&mut self) -> Result<bool, error::CerebroError> {
+        let configured = self.configured();
+
+        let mut backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        };
+
+        // Check if the configured accounts have changed
+        let changed = configured.len() != backend.accounts.len() ||
+            configured
+                .iter()
+                .zip(backend.accounts.iter())
+                .any(|((n, p), a)| *n != a.name || *p != a.text);
+
+        if changed {
+            backend.rebuild_filesystem(configured);
+            backend.evaluate(triggers::Kind::Create, None);
+        }
+        else {
+            backend.evaluate(triggers::Kind::Update, None);
+        }
+
+        return Ok(changed);
+    }
+}
+
+impl module::Data for MailBackendProxy {
+    /// Update mail data
+    ///
+    /// The `new` and `cur` directories of the Maildirs are watched.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        if self.update_accounts()? {
+            return Ok(module::Status::Changed(MODULE_NAME.to_string()));
+        }
+
+        // Get paths to watch
+        let watch_paths: Vec<path::PathBuf> = match self.backend.lock() {
+            Ok(b) => b.accounts
+                .iter()
+                .flat_map(|a| vec![
+                    a.path.join(MAILDIR_NEW),
+                    a.path.join(MAILDIR_CUR),
+                ])
+                .filter(|p| p.is_dir())
+                .collect(),
+
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        };
+
+        // Create watcher
+        let (tx, rx) = mpsc::channel();
+
+        let mut w: notify::RecommendedWatcher =
+            match notify::Watcher::new_raw(tx) {
+
+            Ok(w) => w,
+            Err(_) => return error!("Cannot create filesystem watcher"),
+        };
+
+        // Add watch paths
+        for path in watch_paths.iter() {
+            match w.watch(path, notify::RecursiveMode::NonRecursive) {
+                Ok(_) => (),
+                Err(_) => log::warn!("Cannot watch path: {:?}", path),
+            }
+        }
+
+        // Wait for events
+        loop {
+            let event = match module::recv(&rx) {
+                Ok(e) => e,
+                Err(_) => return error!("Error during watching filesystem"),
+            };
+
+            let path = match event.path {
+                Some(p) => p,
+                None => continue,
+            };
+
+            match self.backend.lock() {
+                Ok(mut b) => b.evaluate(triggers::Kind::Update, Some(&path)),
+                Err(_) => return error!(Lock, "Cannot lock backend"),
+            }
+        }
+    }
+
+    /// Update mail data once, without watching the Maildirs
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update_once(&mut self)
+        -> Option<Result<module::Status, error::CerebroError>> {
+
+        return match self.update_accounts() {
+            Ok(true) =>
+                Some(Ok(module::Status::Changed(MODULE_NAME.to_string()))),
+
+            Ok(false) => Some(Ok(module::Status::Ok)),
+            Err(e) => Some(Err(e)),
+        };
+    }
+}
+
+/// Mail backend that will compute the values
+struct MailBackend {
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+
+    pub accounts: Vec<Account>,
+    pub total_unread: String,
+}
+
+impl MailBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            accounts: Vec::new(),
+            total_unread: VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Rebuild the accounts and the filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `configured` - The configured accounts
+    fn rebuild_filesystem(&mut self, configured: Vec<(String, String)>) {
+        // Deletion triggers
+        for account in self.accounts.iter() {
+            triggers::find_all_and_execute(
+                &self.triggers,
+                triggers::Kind::Delete,
+                &self.name,
+                &format!("{}/{}", account.name, ENTRY_UNREAD),
+                "",
+                "");
+        }
+
+        self.accounts = configured
+            .into_iter()
+            .map(|(name, text)| Account {
+                fs_entry: filesystem::FsEntry::new(
+                    filesystem::FsEntry::create_inode(),
+                    fuse::FileType::Directory,
+                    &name,
+                    filesystem::Mode::ReadOnly,
+                    &vec![
+                        filesystem::FsEntry::new(
+                            filesystem::FsEntry::create_inode(),
+                            fuse::FileType::RegularFile,
+                            ENTRY_UNREAD,
+                            filesystem::Mode::ReadOnly,
+                            &Vec::new()),
+                    ]),
+
+                name: name,
+                path: files::expand_path(&text),
+                text: text,
+                unread: String::new(),
+            })
+            .collect();
+
+        self.total_unread = String::new();
+    }
+
+    /// Count the unread messages and call the triggers
+    ///
+    /// The total is unknown if one of the Maildirs cannot be read.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `kind` - The kind of trigger to be called
+    /// * `event_path` - The path that has changed, all if none
+    fn evaluate(
+        &mut self,
+        kind: triggers::Kind,
+        event_path: Option<&path::Path>) {
+
+        for account in self.accounts.iter_mut() {
+            match event_path {
+                Some(p) if !account.concerns(p) => continue,
+                _ => (),
+            }
+
+            let unread = match count_unread(&account.path) {
+                Some(c) => format!("{}", c),
+                None => VALUE_UNKNOWN.to_string(),
+            };
+
+            publish::update(
+                &self.triggers,
+                kind,
+                &self.name,
+                &format!("{}/{}", account.name, ENTRY_UNREAD),
+                &mut account.unread,
+                &unread);
+        }
+
+        let total: Option<u64> = self.accounts
+            .iter()
+            .map(|a| a.unread.parse::<u64>().ok())
+            .sum();
+
+        let total_unread = match total {
+            Some(t) => format!("{}", t),
+            None => VALUE_UNKNOWN.to_string(),
+        };
+
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_TOTAL_UNREAD,
+            &mut self.total_unread,
+            &total_unread);
+    }
+}
+
+/// Mail module structure
+pub struct Mail {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    inode_total_unread: u64,
+    backend: Arc<Mutex<MailBackend>>,
+    backend_proxy: Arc<Mutex<MailBackendProxy>>,
+}
+
+impl Mail {
+    /// Mail constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        let backend = Arc::new(Mutex::new(MailBackend::new(name, triggers)));
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            inode_total_unread: filesystem::FsEntry::create_inode(),
+            backend: backend.clone(),
+            backend_proxy:
+                Arc::new(Mutex::new(MailBackendProxy::new(backend.clone()))),
+        }
+    }
+}
+
+impl module::Module for Mail {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend_proxy.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend_proxy.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut fs_entries = vec![
+            filesystem::FsEntry::new(
+                self.inode_total_unread,
+                fuse::FileType::RegularFile,
+                ENTRY_TOTAL_UNREAD,
+                filesystem::Mode::ReadOnly,
+                &Vec::new()),
+        ];
+
+        for account in backend.accounts.iter() {
+            fs_entries.push(account.fs_entry.clone());
+        }
+
+        return fs_entries;
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        if inode == self.inode_total_unread {
+            return backend.total_unread.clone();
+        }
+
+        for account in backend.accounts.iter() {
+            match account.fs_entry.find(inode) {
+                Some(_) => return account.unread.clone(),
+                None => continue,
+            }
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let mut values: serde_json::Map<String, serde_json::Value> = backend
+            .accounts
+            .iter()
+            .map(|a| {
+                let mut account = serde_json::Map::new();
+
+                account.insert(
+                    ENTRY_UNREAD.to_string(),
+                    a.unread.clone().into());
+
+                (a.name.clone(), serde_json::Value::Object(account))
+            })
+            .collect();
+
+        values.insert(
+            ENTRY_TOTAL_UNREAD.to_string(),
+            backend.total_unread.clone().into());
+
+        return match serde_json::to_string(&values) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let mut values =
+            vec![format!("{}={}", ENTRY_TOTAL_UNREAD, backend.total_unread)];
+
+        for account in backend.accounts.iter() {
+            values.push(format!(
+                "{}_{}={}",
+                account.name,
+                ENTRY_UNREAD,
+                account.unread));
+        }
+
+        return values.join(" ");
+    }
+}
@@ -0,0 +1,581 @@
+use fuse;
+use serde::{Serialize};
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::config;
+use crate::dbus;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "media";
+
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+const MPRIS_PLAYER_INTERFACE: &str = "org.mpris.MediaPlayer2.Player";
+
+const STATUS_PAUSED: &str = "Paused";
+const STATUS_PLAYING: &str = "Playing";
+
+const VALUE_UNKNOWN: &str = "?";
+
+const ENTRY_ACTIVE: &str = "active";
+const ENTRY_ARTIST: &str = "artist";
+const ENTRY_PLAYERS: &str = "players";
+const ENTRY_STATUS: &str = "status";
+const ENTRY_TITLE: &str = "title";
+
+const ENTRIES: [&str; 3] = [
+    ENTRY_ARTIST,
+    ENTRY_STATUS,
+    ENTRY_TITLE,
+];
+
+/// Information about a media player
+#[derive(Clone, Serialize)]
+struct PlayerData
+{
+    pub player: String,
+    pub artist: String,
+    pub status: String,
+    pub title: String,
+}
+
+impl PlayerData {
+    /// Get the value of an entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `name` - The name of the entry
+    fn value(&self, name: &str) -> String {
+        return match name {
+            ENTRY_ARTIST => self.artist.clone(),
+            ENTRY_STATUS => self.status.clone(),
+            ENTRY_TITLE => self.title.clone(),
+            _ => VALUE_UNKNOWN.to_string(),
+        };
+    }
+}
+
+/// Information about the media players
+#[derive(Serialize)]
+struct MediaData
+{
+    pub active: String,
+    pub players: Vec<PlayerData>,
+}
+
+impl MediaData {
+    /// MediaData constructor
+    pub fn new() -> Self {
+        Self {
+            active: "".to_string(),
+            players: Vec::new(),
+        }
+    }
+}
+
+/// Media backend that will compute the values
+struct MediaBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+
+    pub inode_active: u64,
+    pub inode_players: u64,
+    pub data: MediaData,
+    pub player_fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl MediaBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            inode_active: filesystem::FsEntry::create_inode(),
+            inode_players: filesystem::FsEntry::create_inode(),
+            data: MediaData::new(),
+            player_fs_entries: Vec::new(),
+        }
+    }
+
+    /// Get the players priority list
+    fn priority(&self) -> Vec<String> {
+        return match &self.config.media {
+            Some(c) => match &c.priority {
+                Some(p) => p.to_vec(),
+                None => Vec::new(),
+            },
+
+            None => Vec::new(),
+        };
+    }
+
+    /// Get the rank of a player in the priority list
+    ///
+    /// A player matches an item of the list if its name starts with it, so
+    /// that `firefox` matches `firefox.instance1234`. Players that are not
+    /// listed come last.
+    ///
+    /// # Arguments
+    ///
+    /// * `priority` - The priority list
+    /// * `player` - The name of the player
+    fn rank(priority: &Vec<String>, player: &str) -> usize {
+        return match priority.iter().position(|p| player.starts_with(p)) {
+            Some(r) => r,
+            None => priority.len(),
+        };
+    }
+
+    /// Get the list of MPRIS players
+    fn players() -> Result<Vec<PlayerData>, error::CerebroError> {
+        let names = dbus::call(
+            dbus::Bus::Session,
+            "org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus",
+            "ListNames",
+            "",
+            &[])?;
+
+        let mut players: Vec<PlayerData> = Vec::new();
+
+        for name in names[0].as_array().unwrap_or(&Vec::new()).iter() {
+            let name = match name.as_str() {
+                Some(n) if n.starts_with(MPRIS_PREFIX) => n,
+                _ => continue,
+            };
+
+            let status = match dbus::get_property(
+                dbus::Bus::Session,
+                name,
+                MPRIS_PATH,
+                MPRIS_PLAYER_INTERFACE,
+                "PlaybackStatus") {
+
+                Ok(Value::String(s)) => s,
+                _ => VALUE_UNKNOWN.to_string(),
+            };
+
+            // Metadata is a dictionary of variants
+            let metadata = match dbus::get_property(
+                dbus::Bus::Session,
+                name,
+                MPRIS_PATH,
+                MPRIS_PLAYER_INTERFACE,
+                "Metadata") {
+
+                Ok(m) => m,
+                Err(_) => Value::Null,
+            };
+
+            let title = match metadata["xesam:title"]["data"].as_str() {
+                Some(t) => t.to_string(),
+                None => "".to_string(),
+            };
+
+            let artist = match metadata["xesam:artist"]["data"].as_array() {
+                Some(a) => a
+                    .iter()
+                    .filter_map(|x| x.as_str())
+                    .collect::<Vec<&str>>()
+                    .join(", "),
+
+                None => "".to_string(),
+            };
+
+            players.push(PlayerData {
+                player: name.trim_start_matches(MPRIS_PREFIX).to_string(),
+                artist: artist,
+                status: status,
+                title: title,
+            });
+        }
+
+        players.sort_by(|a, b| a.player.cmp(&b.player));
+
+        return Ok(players);
+    }
+
+    /// Select the active player
+    ///
+    /// A playing player is preferred to a paused one, which is preferred to
+    /// a stopped one. Ties are broken by the priority list.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn active(&self) -> String {
+        let priority = self.priority();
+
+        let state_rank = |status: &str| match status {
+            STATUS_PLAYING => 0,
+            STATUS_PAUSED => 1,
+            _ => 2,
+        };
+
+        return match self.data.players.iter().min_by_key(|p| {
+            (state_rank(&p.status), MediaBackend::rank(&priority, &p.player))
+        }) {
+            Some(p) => p.player.clone(),
+            None => "".to_string(),
+        };
+    }
+
+    /// Rebuild the players data and filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `players` - The new list of players
+    fn rebuild_filesystem(&mut self, players: Vec<PlayerData>) {
+        // Deletion triggers
+        for data in self.data.players.iter() {
+            for name in ENTRIES.iter() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Delete,
+                    &self.name,
+                    &format!("{}/{}/{}", ENTRY_PLAYERS, data.player, name),
+                    "",
+                    "");
+            }
+        }
+
+        self.data.players = players;
+        self.player_fs_entries.clear();
+
+        for data in self.data.players.iter() {
+            let entries: Vec<filesystem::FsEntry> = ENTRIES
+                .iter()
+                .map(|name| filesystem::FsEntry::new(
+                    filesystem::FsEntry::create_inode(),
+                    fuse::FileType::RegularFile,
+                    name,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()))
+                .collect();
+
+            self.player_fs_entries.push(filesystem::FsEntry::new(
+                filesystem::FsEntry::create_inode(),
+                fuse::FileType::Directory,
+                &data.player,
+                filesystem::Mode::ReadOnly,
+                &entries));
+
+            // Creation triggers
+            for name in ENTRIES.iter() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Create,
+                    &self.name,
+                    &format!("{}/{}/{}", ENTRY_PLAYERS, data.player, name),
+                    "",
+                    &data.value(name));
+            }
+        }
+    }
+
+    /// Select the active player and call the triggers
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update_active(&mut self) {
+        let kind = match self.first_update {
+            true => triggers::Kind::Create,
+            false => triggers::Kind::Update,
+        };
+
+        self.first_update = false;
+
+        let active = self.active();
+
+        if active == self.data.active {
+            return;
+        }
+
+        let old_value = self.data.active.clone();
+
+        self.data.active = active;
+
+        log::debug!("{}: active={}", MODULE_NAME, self.data.active);
+
+        triggers::find_all_and_execute(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_ACTIVE,
+            &old_value,
+            &self.data.active);
+    }
+}
+
+impl module::Data for MediaBackend {
+    /// Update media data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let players = MediaBackend::players()?;
+
+        // Check if the list of players has changed
+        let changed = players.len() != self.data.players.len() ||
+            players
+                .iter()
+                .zip(self.data.players.iter())
+                .any(|(a, b)| a.player != b.player);
+
+        if changed {
+            self.rebuild_filesystem(players);
+            self.update_active();
+
+            return Ok(module::Status::Changed(MODULE_NAME.to_string()));
+        }
+
+        // Update values
+        for (data, player) in self.data.players.iter_mut().zip(players) {
+            for name in ENTRIES.iter() {
+                let old_value = data.value(name);
+                let new_value = player.value(name);
+
+                if old_value == new_value {
+                    continue;
+                }
+
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Update,
+                    &self.name,
+                    &format!("{}/{}/{}", ENTRY_PLAYERS, data.player, name),
+                    &old_value,
+                    &new_value);
+            }
+
+            *data = player;
+        }
+
+        self.update_active();
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// Media module structure
+pub struct Media {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    backend: Arc<Mutex<MediaBackend>>,
+}
+
+impl Media {
+    /// Media constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            backend: Arc::new(Mutex::new(MediaBackend::new(name, triggers))),
+        }
+    }
+}
+
+impl module::Module for Media {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut entries = vec![
+            filesystem::FsEntry::new(
+                backend.inode_players,
+                fuse::FileType::Directory,
+                ENTRY_PLAYERS,
+                filesystem::Mode::ReadOnly,
+                &backend.player_fs_entries),
+        ];
+
+        // The active player is only linked if there's one
+        if !backend.data.players.is_empty() {
+            entries.push(filesystem::FsEntry::new(
+                backend.inode_active,
+                fuse::FileType::Symlink,
+                ENTRY_ACTIVE,
+                filesystem::Mode::ReadOnly,
+                &Vec::new()));
+        }
+
+        return entries;
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        // Target of the symbolic link
+        if inode == backend.inode_active {
+            return format!("{}/{}", ENTRY_PLAYERS, backend.data.active);
+        }
+
+        for player_entry in backend.player_fs_entries.iter() {
+            let entry = match player_entry.fs_entries
+                .iter().find(|x| x.inode == inode) {
+
+                Some(e) => e,
+                None => continue,
+            };
+
+            // Find corresponding data
+            return match backend.data.players
+                .iter().find(|x| x.player == player_entry.name) {
+
+                Some(d) => d.value(&entry.name),
+                None => VALUE_UNKNOWN.to_string(),
+            };
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        // Only the active player is exported
+        return match backend.data.players
+            .iter().find(|x| x.player == backend.data.active) {
+
+            Some(p) => format!(
+                "active={} status={} title={:?} artist={:?}",
+                p.player,
+                p.status,
+                p.title,
+                p.artist),
+
+            None => "active=".to_string(),
+        };
+    }
+}
@@ -1,6 +1,7 @@
 use fuse;
 use serde::{Serialize};
-use std::sync::{Arc, Mutex};
+use std::fs;
+use std::sync::Arc;
 use systemstat::Platform;
 
 use crate::config;
@@ -8,16 +9,49 @@ use crate::error;
 use crate::event_manager;
 use crate::filesystem;
 use crate::modules::module;
+use crate::platform;
+use crate::publish;
+use crate::sync::Mutex;
 use crate::triggers;
+use crate::units;
 
 const MODULE_NAME: &str = "memory";
 
 const VALUE_UNKNOWN: &str = "?";
 
 const ENTRY_FREE: &str = "free";
+const ENTRY_NODE: &str = "node";
+const ENTRY_SWAP_FREE: &str = "swap_free";
+const ENTRY_SWAP_TOTAL: &str = "swap_total";
+const ENTRY_SWAP_USED: &str = "swap_used";
+const ENTRY_SWAP_USED_PERCENT: &str = "swap_used_percent";
 const ENTRY_TOTAL: &str = "total";
 const ENTRY_USED: &str = "used";
 
+const BYTES_PER_KIB: u64 = 1024;
+
+const PROC_MEMINFO: &str = "/proc/meminfo";
+
+/// Information about the memory of a NUMA node
+#[derive(Serialize)]
+struct NodeData
+{
+    pub free: String,
+    pub total: String,
+    pub used: String,
+}
+
+impl NodeData {
+    /// NodeData constructor
+    pub fn new() -> Self {
+        Self {
+            free: VALUE_UNKNOWN.to_string(),
+            total: VALUE_UNKNOWN.to_string(),
+            used: VALUE_UNKNOWN.to_string(),
+        }
+    }
+}
+
 /// Information about the memory
 #[derive(Serialize)]
 struct MemoryData
@@ -25,6 +59,11 @@ struct MemoryData
     pub free: String,
     pub total: String,
     pub used: String,
+    pub swap_free: String,
+    pub swap_total: String,
+    pub swap_used: String,
+    pub swap_used_percent: String,
+    pub nodes: Vec<NodeData>,
 }
 
 impl MemoryData {
@@ -34,28 +73,276 @@ impl MemoryData {
             free: VALUE_UNKNOWN.to_string(),
             total: VALUE_UNKNOWN.to_string(),
             used: VALUE_UNKNOWN.to_string(),
+            swap_free: VALUE_UNKNOWN.to_string(),
+            swap_total: VALUE_UNKNOWN.to_string(),
+            swap_used: VALUE_UNKNOWN.to_string(),
+            swap_used_percent: VALUE_UNKNOWN.to_string(),
+            nodes: Vec::new(),
         }
     }
 }
 
+/// Get the memory of the NUMA nodes as (total, free) in bytes
+///
+/// Returns an empty list if the platform doesn't expose the nodes.
+fn query_nodes() -> Vec<(u64, u64)> {
+    let path = match platform::current().sysfs("devices/system/node") {
+        Some(p) => p,
+        None => return Vec::new(),
+    };
+
+    let entries = match fs::read_dir(path) {
+        Ok(e) => e,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut indexes: Vec<(u64, std::path::PathBuf)> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            let index = name.strip_prefix("node")?.parse::<u64>().ok()?;
+            return Some((index, e.path()));
+        })
+        .collect();
+
+    indexes.sort();
+
+    // Lines are formatted like `Node 0 MemTotal:       32768000 kB`
+    return indexes
+        .iter()
+        .map(|(_, path)| {
+            let meminfo = fs::read_to_string(path.join("meminfo"))
+                .unwrap_or_default();
+
+            let value = |name: &str| -> u64 {
+                return meminfo
+                    .lines()
+                    .filter_map(|l| {
+                        let mut parts = l.splitn(2, ':');
+                        let key = parts.next()?;
+
+                        if !key.ends_with(name) {
+                            return None;
+                        }
+
+                        return parts
+                            .next()?
+                            .split_whitespace()
+                            .next()?
+                            .parse::<u64>()
+                            .ok();
+                    })
+                    .next()
+                    .unwrap_or(0) * BYTES_PER_KIB;
+            };
+
+            return (value(" MemTotal"), value(" MemFree"));
+        })
+        .collect();
+}
+
+/// Get the swap as (total, free) in bytes
+///
+/// Returns `None` if the platform doesn't expose it.
+fn query_swap() -> Option<(u64, u64)> {
+    let meminfo = fs::read_to_string(PROC_MEMINFO).ok()?;
+
+    // Lines are formatted like `SwapTotal:       8388604 kB`
+    let value = |name: &str| -> Option<u64> {
+        return meminfo
+            .lines()
+            .find_map(|l| l.strip_prefix(name))?
+            .split_whitespace()
+            .next()?
+            .parse::<u64>()
+            .ok()
+            .map(|v| v * BYTES_PER_KIB);
+    };
+
+    return Some((value("SwapTotal:")?, value("SwapFree:")?));
+}
+
 /// Memory backend that will compute the values
 struct MemoryBackend {
     system_stats: systemstat::System,
+    name: String,
     triggers: Vec<triggers::Trigger>,
     first_update: bool,
+    last_memory: Option<systemstat::Memory>,
+    last_nodes: Vec<(u64, u64)>,
+    last_swap: Option<(u64, u64)>,
 
     pub data: MemoryData,
+    pub node_fs_entries: Vec<filesystem::FsEntry>,
 }
 
 impl MemoryBackend {
-    fn new(triggers: &Vec<triggers::Trigger>) -> Self {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
         Self {
             system_stats: systemstat::System::new(),
+            name: name.to_string(),
             triggers: triggers.to_vec(),
             first_update: true,
+            last_memory: None,
+            last_nodes: Vec::new(),
+            last_swap: None,
             data: MemoryData::new(),
+            node_fs_entries: Vec::new(),
         }
     }
+
+    /// Rebuild NUMA nodes data and filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `node_count` - The number of nodes
+    fn rebuild_nodes(&mut self, node_count: usize) {
+        // Call delete triggers
+        for index in 0..self.data.nodes.len() {
+            for name in [ENTRY_FREE, ENTRY_TOTAL, ENTRY_USED].iter() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Delete,
+                    &self.name,
+                    &format!("{}/{}/{}", ENTRY_NODE, index, name),
+                    "",
+                    "");
+            }
+        }
+
+        self.data.nodes.clear();
+        self.node_fs_entries.clear();
+
+        for i in 0..node_count {
+            self.data.nodes.push(NodeData::new());
+
+            self.node_fs_entries.push(
+                filesystem::FsEntry::new(
+                    filesystem::FsEntry::create_inode(),
+                    fuse::FileType::Directory,
+                    &format!("{}", i),
+                    filesystem::Mode::ReadOnly,
+                    &[ENTRY_FREE, ENTRY_TOTAL, ENTRY_USED]
+                        .iter()
+                        .map(|name| filesystem::FsEntry::new(
+                            filesystem::FsEntry::create_inode(),
+                            fuse::FileType::RegularFile,
+                            name,
+                            filesystem::Mode::ReadOnly,
+                            &Vec::new())
+                            .with_unit(units::Unit::Bytes))
+                        .collect()));
+        }
+    }
+
+    /// Update NUMA nodes data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update_nodes(&mut self) -> module::Status {
+        let nodes = query_nodes();
+
+        let mut status = module::Status::Ok;
+
+        if nodes.len() != self.data.nodes.len() {
+            self.rebuild_nodes(nodes.len());
+            status = module::Status::Changed(MODULE_NAME.to_string());
+        }
+
+        let kind = match (&status, self.first_update) {
+            (module::Status::Changed(_), _) | (_, true) =>
+                triggers::Kind::Create,
+
+            _ => triggers::Kind::Update,
+        };
+
+        for (index, (total, free)) in nodes.iter().enumerate() {
+            let data = &mut self.data.nodes[index];
+
+            let values = [
+                (ENTRY_FREE, &mut data.free, format!("{}", free)),
+                (ENTRY_TOTAL, &mut data.total, format!("{}", total)),
+                (ENTRY_USED, &mut data.used,
+                    format!("{}", total.saturating_sub(*free))),
+            ];
+
+            for (name, current, value) in values {
+                publish::update(
+                    &self.triggers,
+                    kind,
+                    &self.name,
+                    &format!("{}/{}/{}", ENTRY_NODE, index, name),
+                    current,
+                    &value);
+            }
+        }
+
+        self.last_nodes = nodes;
+
+        return status;
+    }
+
+    /// Update swap data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `kind` - The kind of trigger to be called
+    fn update_swap(&mut self, kind: triggers::Kind) {
+        let swap = query_swap();
+
+        let (free, total, used, used_percent) = match swap {
+            Some((total, free)) => {
+                let used = total.saturating_sub(free);
+
+                let used_percent = match total {
+                    0 => 0.0,
+                    _ => used as f64 * 100.0 / total as f64,
+                };
+
+                (
+                    format!("{}", free),
+                    format!("{}", total),
+                    format!("{}", used),
+                    format!("{:.1}", used_percent),
+                )
+            },
+
+            None => (
+                VALUE_UNKNOWN.to_string(),
+                VALUE_UNKNOWN.to_string(),
+                VALUE_UNKNOWN.to_string(),
+                VALUE_UNKNOWN.to_string(),
+            ),
+        };
+
+        let data = &mut self.data;
+
+        let values = [
+            (ENTRY_SWAP_FREE, &mut data.swap_free, free),
+            (ENTRY_SWAP_TOTAL, &mut data.swap_total, total),
+            (ENTRY_SWAP_USED, &mut data.swap_used, used),
+            (
+                ENTRY_SWAP_USED_PERCENT,
+                &mut data.swap_used_percent,
+                used_percent,
+            ),
+        ];
+
+        for (name, current, value) in values {
+            publish::update(
+                &self.triggers,
+                kind,
+                &self.name,
+                name,
+                current,
+                &value);
+        }
+
+        self.last_swap = swap;
+    }
 }
 
 impl module::Data for MemoryBackend {
@@ -79,69 +366,84 @@ impl module::Data for MemoryBackend {
         let total = format!("{}", memory.total.as_u64());
         let used = format!("{}", memory.total.as_u64() - memory.free.as_u64());
 
-        // Free status
-        if free != self.data.free {
-            let old_value = self.data.free.clone();
-
-            self.data.free = free;
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_FREE,
+            &mut self.data.free,
+            &free);
 
-            log::debug!("{}: free={}", MODULE_NAME, self.data.free);
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_TOTAL,
+            &mut self.data.total,
+            &total);
 
-            triggers::find_all_and_execute(
-                &self.triggers,
-                kind,
-                MODULE_NAME,
-                ENTRY_FREE,
-                &old_value,
-                &self.data.free);
-        }
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_USED,
+            &mut self.data.used,
+            &used);
 
-        // Total status
-        if total != self.data.total {
-            let old_value = self.data.total.clone();
+        self.last_memory = Some(memory);
 
-            self.data.total = total;
+        self.update_swap(kind);
 
-            log::debug!("{}: total={}", MODULE_NAME, self.data.total);
+        let status = self.update_nodes();
 
-            triggers::find_all_and_execute(
-                &self.triggers,
-                kind,
-                MODULE_NAME,
-                ENTRY_TOTAL,
-                &old_value,
-                &self.data.total);
-        }
-
-        // Used status
-        if used != self.data.used {
-            let old_value = self.data.used.clone();
-
-            self.data.used = used;
+        self.first_update = false;
 
-            log::debug!("{}: used={}", MODULE_NAME, self.data.used);
+        return Ok(status);
+    }
 
-            triggers::find_all_and_execute(
-                &self.triggers,
-                kind,
-                MODULE_NAME,
-                ENTRY_USED,
-                &old_value,
-                &self.data.used);
-        }
+    /// Update some memory data at high frequency
+    ///
+    /// All the values come from the same query, so they are all updated.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `_entries` - The paths of the entries to be updated
+    fn update_fast(&mut self, _entries: &Vec<String>)
+        -> Option<Result<module::Status, error::CerebroError>> {
 
-        self.first_update = false;
+        return Some(self.update());
+    }
 
-        return Ok(module::Status::Ok);
+    /// Get the statistics, the swap and the nodes (total, free) of the last
+    /// update
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn raw(&self) -> Option<String> {
+        return Some(format!(
+            "memory: {:#?}
+swap: {:?}
+nodes: {:?}",
+            self.last_memory,
+            self.last_swap,
+            self.last_nodes));
     }
 }
 
 /// Memory module structure
 pub struct Memory {
+    name: String,
     thread: Arc<Mutex<module::Thread>>,
     inode_free: u64,
     inode_total: u64,
     inode_used: u64,
+    inode_swap_free: u64,
+    inode_swap_total: u64,
+    inode_swap_used: u64,
+    inode_swap_used_percent: u64,
+    inode_node: u64,
     backend: Arc<Mutex<MemoryBackend>>,
     fs_entries: Vec<filesystem::FsEntry>,
 }
@@ -149,45 +451,161 @@ pub struct Memory {
 impl Memory {
     /// Memory constructor
     pub fn new(
+        name: &str,
         event_manager: &mut event_manager::EventManager,
         triggers: &Vec<triggers::Trigger>) -> Self {
 
         let free = filesystem::FsEntry::create_inode();
         let total = filesystem::FsEntry::create_inode();
         let used = filesystem::FsEntry::create_inode();
+        let swap_free = filesystem::FsEntry::create_inode();
+        let swap_total = filesystem::FsEntry::create_inode();
+        let swap_used = filesystem::FsEntry::create_inode();
+        let swap_used_percent = filesystem::FsEntry::create_inode();
+        let node = filesystem::FsEntry::create_inode();
 
         Self {
+            name: name.to_string(),
             thread: Arc::new(Mutex::new(
-                module::Thread::new(event_manager.sender()))),
+                module::Thread::new(name, event_manager.sender()))),
 
             inode_free: free,
             inode_total: total,
             inode_used: used,
-            backend: Arc::new(Mutex::new(MemoryBackend::new(triggers))),
+            inode_swap_free: swap_free,
+            inode_swap_total: swap_total,
+            inode_swap_used: swap_used,
+            inode_swap_used_percent: swap_used_percent,
+            inode_node: node,
+            backend: Arc::new(Mutex::new(MemoryBackend::new(name, triggers))),
             fs_entries: vec![
                 filesystem::FsEntry::new(
                     free,
                     fuse::FileType::RegularFile,
                     ENTRY_FREE,
                     filesystem::Mode::ReadOnly,
-                    &Vec::new()),
+                    &Vec::new())
+                    .with_unit(units::Unit::Bytes),
 
                 filesystem::FsEntry::new(
                     total,
                     fuse::FileType::RegularFile,
                     ENTRY_TOTAL,
                     filesystem::Mode::ReadOnly,
-                    &Vec::new()),
+                    &Vec::new())
+                    .with_unit(units::Unit::Bytes),
 
                 filesystem::FsEntry::new(
                     used,
                     fuse::FileType::RegularFile,
                     ENTRY_USED,
                     filesystem::Mode::ReadOnly,
+                    &Vec::new())
+                    .with_unit(units::Unit::Bytes),
+
+                filesystem::FsEntry::new(
+                    swap_free,
+                    fuse::FileType::RegularFile,
+                    ENTRY_SWAP_FREE,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new())
+                    .with_unit(units::Unit::Bytes),
+
+                filesystem::FsEntry::new(
+                    swap_total,
+                    fuse::FileType::RegularFile,
+                    ENTRY_SWAP_TOTAL,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new())
+                    .with_unit(units::Unit::Bytes),
+
+                filesystem::FsEntry::new(
+                    swap_used,
+                    fuse::FileType::RegularFile,
+                    ENTRY_SWAP_USED,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new())
+                    .with_unit(units::Unit::Bytes),
+
+                filesystem::FsEntry::new(
+                    swap_used_percent,
+                    fuse::FileType::RegularFile,
+                    ENTRY_SWAP_USED_PERCENT,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new())
+                    .with_unit(units::Unit::Percent),
+
+                filesystem::FsEntry::new(
+                    node,
+                    fuse::FileType::Directory,
+                    ENTRY_NODE,
+                    filesystem::Mode::ReadOnly,
                     &Vec::new()),
                 ],
         }
     }
+
+    /// Get the value of a filesystem entry from the locked data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `backend` - The data of the module
+    /// * `inode` - The inode of the filesystem entry
+    fn backend_value(&self, backend: &MemoryBackend, inode: u64) -> String {
+        if inode == self.inode_free {
+            return backend.data.free.clone();
+        }
+
+        if inode == self.inode_total {
+            return backend.data.total.clone();
+        }
+
+        if inode == self.inode_used {
+            return backend.data.used.clone();
+        }
+
+        if inode == self.inode_swap_free {
+            return backend.data.swap_free.clone();
+        }
+
+        if inode == self.inode_swap_total {
+            return backend.data.swap_total.clone();
+        }
+
+        if inode == self.inode_swap_used {
+            return backend.data.swap_used.clone();
+        }
+
+        if inode == self.inode_swap_used_percent {
+            return backend.data.swap_used_percent.clone();
+        }
+
+        // Search index of entry in nodes entries
+        for (index, entry) in backend.node_fs_entries.iter().enumerate() {
+            let entry = match entry.find(inode) {
+                Some(e) => e,
+                None => continue,
+            };
+
+            // Entry found, check if index exists
+            if index >= backend.data.nodes.len() {
+                return VALUE_UNKNOWN.to_string();
+            }
+
+            // Get data
+            let node_data = &backend.data.nodes[index];
+
+            match entry.name.as_str() {
+                ENTRY_FREE => return node_data.free.clone(),
+                ENTRY_TOTAL => return node_data.total.clone(),
+                ENTRY_USED => return node_data.used.clone(),
+                _ => return VALUE_UNKNOWN.to_string(),
+            }
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
 }
 
 impl module::Module for Memory {
@@ -197,7 +615,7 @@ impl module::Module for Memory {
     ///
     /// * `self` - The instance handle
     fn name(&self) -> &str {
-        return MODULE_NAME;
+        return &self.name;
     }
 
     /// Start the module
@@ -208,10 +626,10 @@ impl module::Module for Memory {
     fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
         let mut thread = match self.thread.lock() {
             Ok(t) => t,
-            Err(_) => return error!("Cannot lock thread"),
+            Err(_) => return error!(Lock, "Cannot lock thread"),
         };
 
-        thread.start(self.backend.clone(), config.timeout_s)?;
+        thread.start(self.backend.clone(), config)?;
 
         return success!();
     }
@@ -224,7 +642,7 @@ impl module::Module for Memory {
     fn stop(&mut self) -> error::Return {
         let mut thread = match self.thread.lock() {
             Ok(t) => t,
-            Err(_) => return error!("Cannot lock thread"),
+            Err(_) => return error!(Lock, "Cannot lock thread"),
         };
 
         thread.stop()?;
@@ -252,7 +670,23 @@ impl module::Module for Memory {
     ///
     /// * `self` - The instance handle
     fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
-        return self.fs_entries.to_vec();
+        let node_fs_entries = match self.backend.lock() {
+            Ok(b) => b.node_fs_entries.to_vec(),
+            Err(_) => Vec::new(),
+        };
+
+        return self.fs_entries
+            .iter()
+            .map(|e| {
+                let mut e = e.clone();
+
+                if e.inode == self.inode_node {
+                    e.fs_entries.extend(node_fs_entries.to_vec());
+                }
+
+                return e;
+            })
+            .collect();
     }
 
     /// Get value to be displayed for a filesystem entry
@@ -262,28 +696,28 @@ impl module::Module for Memory {
     /// * `self` - The instance handle
     /// * `inode` - The inode of the filesystem to be fetched
     fn value(&self, inode: u64) -> String {
-        if inode == self.inode_free {
-            match self.backend.lock() {
-                Ok(b) => return b.data.free.clone(),
-                Err(_) => return VALUE_UNKNOWN.to_string(),
-            }
-        }
-
-        if inode == self.inode_total {
-            match self.backend.lock() {
-                Ok(b) => return b.data.total.clone(),
-                Err(_) => return VALUE_UNKNOWN.to_string(),
-            }
-        }
+        return match self.backend.lock() {
+            Ok(b) => self.backend_value(&b, inode),
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        };
+    }
 
-        if inode == self.inode_used {
-            match self.backend.lock() {
-                Ok(b) => return b.data.used.clone(),
-                Err(_) => return VALUE_UNKNOWN.to_string(),
-            }
-        }
+    /// Get the values of several entries from the same update
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inodes` - The inodes of the entries
+    fn values(&self, inodes: &[u64]) -> Vec<String> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return vec![VALUE_UNKNOWN.to_string(); inodes.len()],
+        };
 
-        return VALUE_UNKNOWN.to_string();
+        return inodes
+            .iter()
+            .map(|i| self.backend_value(&backend, *i))
+            .collect();
     }
 
     /// Set value of a filesystem entry
@@ -324,10 +758,28 @@ impl module::Module for Memory {
             Err(_) => return VALUE_UNKNOWN.to_string(),
         };
 
-        return format!(
-            "free={} total={} used={}",
+        let mut output = format!(
+            "free={} total={} used={} swap_free={} swap_total={} swap_used={} \
+            swap_used_percent={}",
             backend.data.free,
             backend.data.total,
-            backend.data.used).to_string();
+            backend.data.used,
+            backend.data.swap_free,
+            backend.data.swap_total,
+            backend.data.swap_used,
+            backend.data.swap_used_percent);
+
+        for (index, node) in backend.data.nodes.iter().enumerate() {
+            output += &format!(
+                " node_{}_free={} node_{}_total={} node_{}_used={}",
+                index,
+                node.free,
+                index,
+                node.total,
+                index,
+                node.used);
+        }
+
+        return output;
     }
 }
@@ -5,6 +5,48 @@ pub use self::module::Module;
 pub mod cpu;
 pub mod battery;
 pub mod brightness;
+#[cfg(feature = "dbus")]
+pub mod capture;
 pub mod memory;
 pub mod module;
 pub mod trash;
+pub mod peripherals;
+pub mod als;
+pub mod location;
+pub mod focus;
+pub mod wm;
+pub mod clipboard;
+pub mod quota;
+pub mod wireguard;
+#[cfg(feature = "dbus")]
+pub mod sessions;
+pub mod cooling;
+pub mod network;
+#[cfg(feature = "media")]
+pub mod media;
+pub mod derived;
+pub mod watchdog;
+pub mod files;
+#[cfg(feature = "http")]
+pub mod http;
+pub mod portcheck;
+pub mod audio;
+#[cfg(feature = "dbus")]
+pub mod bluetooth;
+pub mod deadman;
+pub mod processes;
+pub mod history;
+pub mod uptime;
+pub mod fans;
+pub mod power;
+pub mod leds;
+#[cfg(feature = "dbus")]
+pub mod systemd;
+pub mod updates;
+pub mod mail;
+#[cfg(feature = "dbus")]
+pub mod inhibitors;
+#[cfg(feature = "weather")]
+pub mod weather;
+pub mod displays;
+pub mod registry;
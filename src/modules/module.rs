@@ -1,13 +1,57 @@
-use std::sync::{Arc, Mutex};
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::{channel, Receiver, Sender, TryRecvError};
+use lazy_static::lazy_static;
+use serde_json::json;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::panic;
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{
+    channel,
+    Receiver,
+    RecvError,
+    RecvTimeoutError,
+    Sender,
+    TryRecvError,
+};
 use std::thread;
 use std::time;
 
+use crate::bus;
 use crate::config;
+use crate::consumers;
+use crate::debug;
+use crate::demand;
 use crate::error;
+use crate::event_log;
 use crate::events;
 use crate::filesystem;
+use crate::health;
+use crate::metrics;
+use crate::sync::Mutex;
+
+/// Shortest interval of the high-frequency mode
+const MIN_FAST_INTERVAL_MS: u64 = 100;
+
+/// Default interval of the high-frequency mode
+pub const DEFAULT_FAST_INTERVAL_MS: u64 = 250;
+
+/// Delay before updating a module again after a panic, doubled at each
+/// consecutive panic
+const PANIC_DELAY_MS: u64 = 1000;
+const MAX_PANIC_DELAY_MS: u64 = 60000;
+
+/// Interval at which a module waiting for messages checks for a stop
+const STOP_POLL_MS: u64 = 500;
+
+lazy_static! {
+    static ref LAZY: Mutex<HashMap<String, Lazy>> = Mutex::new(HashMap::new());
+}
+
+thread_local! {
+    /// Interruption of the module thread running on the current thread
+    static INTERRUPT: RefCell<Option<Arc<Interrupt>>> = RefCell::new(None);
+}
 
 #[derive(Debug, PartialEq)]
 pub enum Status
@@ -30,30 +74,475 @@ pub trait Module: Send {
 
     fn value(&self, inode: u64) -> String;
 
+    /// Get the values of several entries at once
+    ///
+    /// Modules whose data is protected by a single lock read all the values
+    /// under one acquisition, so that they come from the same update. By
+    /// default, the values are read one after the other.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inodes` - The inodes of the entries
+    fn values(&self, inodes: &[u64]) -> Vec<String> {
+        return inodes.iter().map(|i| self.value(*i)).collect();
+    }
+
     fn set_value(&mut self, inode:u64, data: &[u8]);
 
     fn json(&self) -> String;
 
     fn shell(&self) -> String;
+
+    /// Names of the modules whose data is consumed by this one
+    ///
+    /// The dependencies are started before the module.
+    fn dependencies(&self) -> &[&str] {
+        return &[];
+    }
+
+    /// Check if the hardware read by the module is present on the host
+    ///
+    /// Returns `None` when the module doesn't depend on any hardware or
+    /// cannot tell without being started.
+    fn probe(&self) -> Option<bool> {
+        return None;
+    }
 }
 
 pub trait Data: Send {
     fn update(&mut self) -> Result<Status, error::CerebroError>;
+
+    /// Update the data once without waiting for any event
+    ///
+    /// This is used when the module is refreshed on access. Data that can
+    /// only be updated by waiting for events return `None`.
+    fn update_once(&mut self)
+        -> Option<Result<Status, error::CerebroError>> {
+
+        return Some(self.update());
+    }
+
+    /// Update only some entries of the data
+    ///
+    /// This is used by the high-frequency mode. Data that cannot update its
+    /// entries separately return `None`.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `_entries` - The paths of the entries to be updated
+    fn update_fast(&mut self, _entries: &Vec<String>)
+        -> Option<Result<Status, error::CerebroError>> {
+
+        return None;
+    }
+
+    /// Raw data gathered by the last update, before formatting
+    ///
+    /// This is shown in the `.debug/last_update` entry of the module.
+    fn raw(&self) -> Option<String> {
+        return None;
+    }
+}
+
+/// Handle used by a module to read the values of the other modules
+///
+/// The values are the last ones published by the modules, no module is
+/// locked to get them.
+#[derive(Debug, Clone)]
+pub struct Values {
+    owner: String,
+}
+
+impl Values {
+    /// Values constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `owner` - The name of the module reading the values
+    pub fn new(owner: &str) -> Self {
+        Self {
+            owner: owner.to_string(),
+        }
+    }
+
+    /// Get the current value of an entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `path` - The path of the entry (e.g. `memory/used`)
+    pub fn get(&self, path: &str) -> Option<String> {
+        let value = bus::value(path);
+
+        if value.is_none() {
+            log::debug!("{}: no value for {}", self.owner, path);
+        }
+
+        return value;
+    }
+
+    /// Get the current values of the entries under a path
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `prefix` - The path of the parent entry (e.g. `cpu/physical`)
+    pub fn list(&self, prefix: &str) -> Vec<(String, String)> {
+        return bus::values(prefix);
+    }
+}
+
+/// Module refreshed when its entries are accessed instead of being polled
+struct Lazy {
+    data: Arc<Mutex<dyn Data>>,
+    event_sender: Arc<Mutex<Sender<events::Events>>>,
+    min_interval: time::Duration,
+    last_update: time::Instant,
+    debug: bool,
+}
+
+/// Function used to check if the debug entries of a module are enabled
+fn debug_enabled(config: &config::ModuleConfig) -> bool {
+    return match &config.debug {
+        Some(c) => c.enabled == Some(true),
+        None => false,
+    };
+}
+
+/// Function used to run an update of a module, catching its panics
+///
+/// The data is not poisoned as its lock is held outside of the update, the
+/// panic is returned as an error so that the module is marked as failed.
+///
+/// # Arguments
+///
+/// * `name` - The name of the module
+/// * `update` - The update to be run
+fn catch<T, F>(name: &str, update: F) -> Result<T, error::CerebroError>
+    where F: FnOnce() -> T {
+
+    let payload = match panic::catch_unwind(panic::AssertUnwindSafe(update)) {
+        Ok(r) => return Ok(r),
+        Err(p) => p,
+    };
+
+    let message = match payload.downcast_ref::<&str>() {
+        Some(m) => m.to_string(),
+        None => match payload.downcast_ref::<String>() {
+            Some(m) => m.clone(),
+            None => "unknown panic".to_string(),
+        },
+    };
+
+    metrics::increment(metrics::MODULE_PANICS, &[("module", name)]);
+
+    return error!(&format!("Module panicked: {}", message));
+}
+
+/// Interruption of the waits of a module thread
+///
+/// Event-driven modules wait for their events inside the update, which never
+/// returns. A stop runs the closers registered by these waits (killing the
+/// watcher process, shutting down the socket, ...) so that the update returns
+/// and the thread can be joined.
+struct Interrupt {
+    stopping: AtomicBool,
+    next_id: AtomicUsize,
+    closers: Mutex<HashMap<usize, Box<dyn Fn() + Send>>>,
+}
+
+impl Interrupt {
+    fn new() -> Self {
+        Self {
+            stopping: AtomicBool::new(false),
+            next_id: AtomicUsize::new(0),
+            closers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Interrupt the waits of the thread
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&self) {
+        let closers = match self.closers.lock() {
+            Ok(c) => c,
+            Err(_) => return,
+        };
+
+        self.stopping.store(true, Ordering::SeqCst);
+
+        for closer in closers.values() {
+            closer();
+        }
+    }
+}
+
+/// Registration of a closer, removed when dropped
+pub struct OnStop {
+    interrupt: Option<Arc<Interrupt>>,
+    id: usize,
+}
+
+impl Drop for OnStop {
+    fn drop(&mut self) {
+        let interrupt = match &self.interrupt {
+            Some(i) => i,
+            None => return,
+        };
+
+        match interrupt.closers.lock() {
+            Ok(mut c) => {
+                c.remove(&self.id);
+            },
+
+            Err(_) => (),
+        }
+    }
+}
+
+/// Function used to register a closer run when the module thread is stopped
+///
+/// The closer is run at once if the thread is already stopping. Nothing is
+/// registered outside of a module thread (lazy or one-shot updates).
+///
+/// # Arguments
+///
+/// * `closer` - The closer interrupting the wait of the caller
+pub fn on_stop<F>(closer: F) -> OnStop
+    where F: Fn() + Send + 'static {
+
+    let interrupt = INTERRUPT.with(|i| i.borrow().clone());
+
+    let id = match &interrupt {
+        Some(i) => {
+            let id = i.next_id.fetch_add(1, Ordering::SeqCst);
+
+            match i.closers.lock() {
+                Ok(mut c) => match i.stopping.load(Ordering::SeqCst) {
+                    true => closer(),
+                    false => {
+                        c.insert(id, Box::new(closer));
+                    },
+                },
+
+                Err(_) => (),
+            }
+
+            id
+        },
+
+        None => 0,
+    };
+
+    return OnStop {
+        interrupt: interrupt,
+        id: id,
+    };
+}
+
+/// Function used to kill a child process when the module thread is stopped
+///
+/// The registration must be dropped before the child is waited for, as the
+/// identifier of a reaped process can be reused.
+///
+/// # Arguments
+///
+/// * `child` - The process the module is waiting for
+pub fn kill_on_stop(child: &process::Child) -> OnStop {
+    let pid = child.id() as libc::pid_t;
+
+    return on_stop(move || {
+        unsafe { libc::kill(pid, libc::SIGKILL) };
+    });
+}
+
+/// Function used to know if the module thread of the caller is stopping
+pub fn stopping() -> bool {
+    return INTERRUPT.with(|i| match &*i.borrow() {
+        Some(i) => i.stopping.load(Ordering::SeqCst),
+        None => false,
+    });
+}
+
+/// Function used to wait for a message, giving up when the module thread is
+/// stopped
+///
+/// # Arguments
+///
+/// * `rx` - The receiver of the messages
+pub fn recv<T>(rx: &Receiver<T>) -> Result<T, RecvError> {
+    let interval = time::Duration::from_millis(STOP_POLL_MS);
+
+    loop {
+        match rx.recv_timeout(interval) {
+            Ok(m) => return Ok(m),
+            Err(RecvTimeoutError::Disconnected) => return Err(RecvError),
+            Err(RecvTimeoutError::Timeout) => match stopping() {
+                true => return Err(RecvError),
+                false => (),
+            },
+        }
+    }
+}
+
+/// Function used to record the result of an update
+///
+/// # Arguments
+///
+/// * `name` - The name of the module
+/// * `result` - The result of the update
+/// * `start` - The time the update started
+/// * `debug` - The data of the module, if its debug entries are enabled
+fn record(
+    name: &str,
+    result: Result<Status, error::CerebroError>,
+    start: time::Instant,
+    debug: Option<&dyn Data>) -> Status {
+
+    // An update interrupted by a stop is not an error
+    if stopping() {
+        return Status::Ok;
+    }
+
+    let error = match &result {
+        Ok(_) => None,
+        Err(e) => Some(e.to_string()),
+    };
+
+    match debug {
+        Some(d) => debug::record(name, start.elapsed(), error.clone(), d.raw()),
+        None => (),
+    }
+
+    health::record(name, error);
+
+    let status = match result {
+        Ok(s) => s,
+        Err(e) => {
+            event_log::write(
+                "error",
+                json!({
+                    "module": name,
+                    "error": e.to_string(),
+                }));
+            log::error!(
+                "Cannot update module {}",
+                error::CerebroError::module(name, e));
+            metrics::increment(
+                metrics::MODULE_UPDATE_ERRORS,
+                &[("module", name)]);
+            Status::Error
+        },
+    };
+
+    metrics::increment(
+        metrics::MODULE_UPDATES,
+        &[("module", name)]);
+
+    metrics::add(
+        metrics::MODULE_UPDATE_SECONDS,
+        &[("module", name)],
+        start.elapsed().as_secs_f64());
+
+    return status;
+}
+
+/// Function used to signal that the filesystem of a module has changed
+fn send_changed(sender: &Arc<Mutex<Sender<events::Events>>>, name: String) {
+    log::info!("module `{}` has changed", name);
+
+    let sender = match sender.lock() {
+        Ok(s) => s,
+        Err(_) => {
+            log::error!("Cannot lock event sender");
+            return;
+        },
+    };
+
+    match sender.send(events::Events::ModuleUpdated(name)) {
+        Ok(_) => (),
+        Err(_) => log::error!("Cannot send event"),
+    }
+}
+
+/// Function used to refresh a lazy module before its entries are read
+///
+/// Nothing is done if the module is not lazy or if it has been refreshed
+/// less than its minimum interval ago.
+pub fn refresh(name: &str) {
+    let (data, sender, debug) = {
+        let mut lazy = match LAZY.lock() {
+            Ok(l) => l,
+            Err(_) => return,
+        };
+
+        let entry = match lazy.get_mut(name) {
+            Some(e) => e,
+            None => return,
+        };
+
+        if entry.last_update.elapsed() < entry.min_interval {
+            return;
+        }
+
+        entry.last_update = time::Instant::now();
+
+        (entry.data.clone(), entry.event_sender.clone(), entry.debug)
+    };
+
+    let _span = tracing::trace_span!("module.refresh", module = name).entered();
+
+    let mut data = match data.lock() {
+        Ok(d) => d,
+        Err(_) => {
+            log::error!("Cannot lock module's data");
+            return;
+        },
+    };
+
+    let start = time::Instant::now();
+
+    let result = match catch(name, || data.update_once()) {
+        Ok(Some(r)) => r,
+        Ok(None) => return,
+        Err(e) => Err(e),
+    };
+
+    let debug = match debug {
+        true => Some(&*data),
+        false => None,
+    };
+
+    match record(name, result, start, debug) {
+        Status::Changed(_) => send_changed(&sender, name.to_string()),
+        _ => (),
+    }
 }
 
 pub struct Thread {
+    name: String,
     running: Arc<AtomicBool>,
     handle: Option<thread::JoinHandle<()>>,
     stopper: Option<Mutex<Sender<()>>>,
+    interrupt: Option<Arc<Interrupt>>,
     event_sender: Arc<Mutex<Sender<events::Events>>>,
 }
 
 impl Thread {
-    pub fn new(event_sender: Arc<Mutex<Sender<events::Events>>>) -> Self {
+    pub fn new(
+        name: &str,
+        event_sender: Arc<Mutex<Sender<events::Events>>>) -> Self {
+
         Self {
+            name: name.to_string(),
             running: Arc::new(AtomicBool::new(false)),
             handle: None,
             stopper: None,
+            interrupt: None,
             event_sender: event_sender,
         }
     }
@@ -61,7 +550,7 @@ impl Thread {
     pub fn start(
         &mut self,
         data: Arc<Mutex<dyn Data>>,
-        timeout_s: Option<u64>) -> error::Return {
+        config: &config::ModuleConfig) -> error::Return {
 
         // Check status
         if self.running.load(Ordering::SeqCst) {
@@ -71,22 +560,76 @@ impl Thread {
         self.running.store(true, Ordering::SeqCst);
 
         // Check timeout
-        let timeout_s = match timeout_s {
+        let timeout_s = match config.timeout_s {
             Some(t) => t,
             None => return error!("No timeout given to the thread"),
         };
 
+        let debug = debug_enabled(config);
+
+        // Lazy modules are only updated when accessed
+        match &config.lazy {
+            Some(c) if c.enabled == Some(true) => {
+                let min_interval_s = match c.min_interval_s {
+                    Some(i) => i,
+                    None => timeout_s,
+                };
+
+                if self.start_lazy(data.clone(), min_interval_s, debug)? {
+                    return success!();
+                }
+
+                log::warn!(
+                    "module `{}` cannot be lazy, polling instead",
+                    self.name);
+            },
+
+            _ => (),
+        }
+
+        // Some entries may be updated between two full updates
+        let timeout = time::Duration::from_secs(timeout_s);
+
+        let (fast_entries, tick, idle) = match &config.fast {
+            Some(c) => {
+                let interval_ms = c.interval_ms
+                    .unwrap_or(DEFAULT_FAST_INTERVAL_MS)
+                    .max(MIN_FAST_INTERVAL_MS);
+
+                (
+                    c.entries.clone().unwrap_or(Vec::new()),
+                    time::Duration::from_millis(interval_ms).min(timeout),
+                    c.idle_s.map(|i| time::Duration::from_secs(i)),
+                )
+            },
+
+            None => (Vec::new(), timeout, None),
+        };
+
         // Get handle to stop the thread
         let (tx, rx): (Sender<()>, Receiver<()>) = channel();
         let sender = self.event_sender.clone();
+        let name = self.name.clone();
+
+        let mut last_update: Option<time::Instant> = None;
+        let mut panics: u32 = 0;
 
         self.stopper = Some(Mutex::new(tx));
 
-        // Spawn the thread
-        self.handle = Some(thread::spawn(move || loop {
+        // Get handle to interrupt the waits of the thread
+        let interrupt = Arc::new(Interrupt::new());
+
+        self.interrupt = Some(interrupt.clone());
+
+        let mut run = move || loop {
             let status: Status;
+            let mut panicked = false;
 
             {
+                let _span = tracing::trace_span!(
+                    "module.update",
+                    module = name.as_str()).entered();
+
                 // Call update on the module's data
                 let mut data = match data.lock() {
                     Ok(d) => d,
@@ -96,34 +639,76 @@ impl Thread {
                     },
                 };
 
-                status = match data.update() {
-                    Ok(s) => s,
-                    Err(e) => {
-                        log::error!("Cannot update module: {}", e);
-                        Status::Error
+                let start = time::Instant::now();
+
+                let full = match last_update {
+                    Some(l) => l.elapsed() >= timeout,
+                    None => true,
+                };
+
+                // Entries nobody reads are left to the full updates
+                let entries: Vec<String> = match idle {
+                    Some(i) => fast_entries
+                        .iter()
+                        .filter(|e| {
+                            let path = format!(
+                                "{}/{}",
+                                config::module_path(&name),
+                                e.trim_matches('/'));
+
+                            match consumers::last_read(&path) {
+                                Some(l) => l < i,
+                                None => false,
+                            }
+                        })
+                        .cloned()
+                        .collect(),
+
+                    None => fast_entries.clone(),
+                };
+
+                let result = match full {
+                    true => None,
+                    false if entries.is_empty() => None,
+                    false => catch(&name, || data.update_fast(&entries))
+                        .unwrap_or_else(|e| {
+                            panicked = true;
+                            Some(Err(e))
+                        }),
+                };
+
+                status = match result {
+                    Some(r) => record(&name, r, start, None),
+
+                    // Data cannot be partially updated: full update
+                    None if full || tick == timeout => {
+                        last_update = Some(start);
+
+                        let result = match catch(&name, || data.update()) {
+                            Ok(r) => r,
+                            Err(e) => {
+                                panicked = true;
+                                Err(e)
+                            },
+                        };
+
+                        let debug = match debug {
+                            true => Some(&*data),
+                            false => None,
+                        };
+
+                        record(&name, result, start, debug)
                     },
+
+                    None => Status::Ok,
                 };
             }
 
             // Check if the module has changed (then the thread needs to be
             // stopped)
             match status {
-                Status::Changed(name) => {
-                    log::info!("module `{}` has changed", name);
-
-                    let sender = match sender.lock() {
-                        Ok(s) => s,
-                        Err(_) => {
-                            log::error!("Cannot lock event sender");
-                            break;
-                        },
-                    };
-
-                    match sender.send(events::Events::ModuleUpdated(name)) {
-                        Ok(_) => (),
-                        Err(_) => log::error!("Cannot send event"),
-                    }
-
+                Status::Changed(_) => {
+                    send_changed(&sender, name.clone());
                     break;
                 },
 
@@ -139,14 +724,110 @@ impl Thread {
                 Err(TryRecvError::Empty) => (),
             }
 
-            // Wait a moment
-            thread::sleep(time::Duration::from_secs(timeout_s));
+            // Wait a moment, longer after consecutive panics so that a module
+            // failing at each update doesn't flood the logs
+            let delay = match panicked {
+                true => {
+                    panics = panics.saturating_add(1);
+
+                    let delay = PANIC_DELAY_MS
+                        .saturating_mul(1 << (panics - 1).min(16))
+                        .min(MAX_PANIC_DELAY_MS);
+
+                    log::warn!(
+                        "module `{}` restarted in {} ms",
+                        name,
+                        delay);
+
+                    time::Duration::from_millis(delay).max(tick)
+                },
+
+                false => {
+                    panics = 0;
+                    tick
+                },
+            };
+
+            // Modules nobody uses wait longer
+            demand::sleep(&name, delay);
+        };
+
+        // Spawn the thread
+        self.handle = Some(thread::spawn(move || {
+            INTERRUPT.with(|i| *i.borrow_mut() = Some(interrupt));
+            run()
         }));
 
         return success!();
     }
 
+    /// Register the data to be updated on access
+    ///
+    /// Returns false if the data cannot be updated without waiting for
+    /// events.
+    fn start_lazy(
+        &mut self,
+        data: Arc<Mutex<dyn Data>>,
+        min_interval_s: u64,
+        debug: bool) -> Result<bool, error::CerebroError> {
+
+        // First update, also checks that the data support it
+        let start = time::Instant::now();
+
+        let status = match data.lock() {
+            Ok(mut d) => match catch(&self.name, || d.update_once()) {
+                Ok(Some(r)) => record(
+                    &self.name,
+                    r,
+                    start,
+                    match debug {
+                        true => Some(&*d),
+                        false => None,
+                    }),
+
+                Ok(None) => return Ok(false),
+                Err(e) => record(&self.name, Err(e), start, None),
+            },
+
+            Err(_) => return error!(Lock, "Cannot lock module's data"),
+        };
+
+        match status {
+            Status::Changed(_) =>
+                send_changed(&self.event_sender, self.name.clone()),
+            _ => (),
+        }
+
+        let mut lazy = match LAZY.lock() {
+            Ok(l) => l,
+            Err(_) => return error!(Lock, "Cannot lock lazy modules"),
+        };
+
+        lazy.insert(self.name.clone(), Lazy {
+            data: data,
+            event_sender: self.event_sender.clone(),
+            min_interval: time::Duration::from_secs(min_interval_s),
+            last_update: time::Instant::now(),
+            debug: debug,
+        });
+
+        return Ok(true);
+    }
+
     pub fn stop(&mut self) -> error::Return {
+        // Unregister lazy data
+        match LAZY.lock() {
+            Ok(mut l) => {
+                l.remove(&self.name);
+            },
+
+            Err(_) => return error!(Lock, "Cannot lock lazy modules"),
+        }
+
+        if self.handle.is_none() {
+            self.running.store(false, Ordering::SeqCst);
+        }
+
         // Send stop signal to the thread
         let stopper = match &self.stopper {
             Some(s) => s,
@@ -155,7 +836,7 @@ impl Thread {
 
         let stopper = match stopper.lock() {
             Ok(s) => s,
-            Err(_) => return error!("Cannot lock stopper"),
+            Err(_) => return error!(Lock, "Cannot lock stopper"),
         };
 
         match stopper.send(()) {
@@ -164,6 +845,14 @@ impl Thread {
                           // is already stopped
         }
 
+        // The thread may be waiting for its entries to be read or for events
+        demand::wake(&self.name);
+
+        match &self.interrupt {
+            Some(i) => i.stop(),
+            None => (),
+        }
+
         // Wait the thread to finish
         let handle = match self.handle.take() {
             Some(h) => h,
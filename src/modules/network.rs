@@ -0,0 +1,626 @@
+use fuse;
+use serde::{Serialize};
+use serde_json::Value;
+use std::fs;
+use std::process;
+use std::sync::Arc;
+
+use crate::config;
+use crate::dbus;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::cooling;
+use crate::modules::module;
+use crate::publish;
+use crate::platform;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "network";
+
+const NET: &str = "class/net";
+
+const BACKEND_NETWORKMANAGER: &str = "networkmanager";
+
+const NM_SERVICE: &str = "org.freedesktop.NetworkManager";
+const NM_PATH: &str = "/org/freedesktop/NetworkManager";
+const NM_INTERFACE: &str = "org.freedesktop.NetworkManager";
+const NM_ACTIVE_INTERFACE: &str =
+    "org.freedesktop.NetworkManager.Connection.Active";
+const NM_DEVICE_INTERFACE: &str = "org.freedesktop.NetworkManager.Device";
+const NM_AP_INTERFACE: &str = "org.freedesktop.NetworkManager.AccessPoint";
+
+const STATE_CONNECTED: &str = "connected";
+const STATE_CONNECTING: &str = "connecting";
+const STATE_DISCONNECTED: &str = "disconnected";
+
+const VALUE_UNKNOWN: &str = "?";
+
+const ENTRY_INTERFACE: &str = "interface";
+const ENTRY_SSID: &str = "ssid";
+const ENTRY_STATE: &str = "state";
+
+/// Function used to get the names of the network interfaces
+pub fn interfaces() -> Vec<String> {
+    let mut names: Vec<String> = cooling::list_sysfs(NET, "", "")
+        .into_iter()
+        .filter_map(|p| Some(p.file_name()?.to_str()?.to_string()))
+        .collect();
+
+    names.sort();
+
+    return names;
+}
+
+/// Information about the network
+#[derive(Serialize)]
+struct NetworkData
+{
+    pub interface: String,
+    pub ssid: String,
+    pub state: String,
+}
+
+impl NetworkData {
+    /// NetworkData constructor
+    pub fn new() -> Self {
+        Self {
+            interface: VALUE_UNKNOWN.to_string(),
+            ssid: VALUE_UNKNOWN.to_string(),
+            state: VALUE_UNKNOWN.to_string(),
+        }
+    }
+}
+
+/// Get a property of a NetworkManager object
+///
+/// # Arguments
+///
+/// * `path` - The object path
+/// * `interface` - The interface of the property
+/// * `property` - The name of the property
+fn nm_property(path: &str, interface: &str, property: &str)
+    -> Result<Value, error::CerebroError> {
+
+    return dbus::get_property(
+        dbus::Bus::System,
+        NM_SERVICE,
+        path,
+        interface,
+        property);
+}
+
+/// Proxy backend that is only use in the context of the thread
+struct NetworkBackendProxy {
+    backend: Arc<Mutex<NetworkBackend>>,
+    networkmanager_failed: bool,
+}
+
+impl NetworkBackendProxy {
+    fn new(backend: Arc<Mutex<NetworkBackend>>) -> Self {
+        Self {
+            backend: backend,
+            networkmanager_failed: false,
+        }
+    }
+
+    /// Get the values (interface, SSID, state) from NetworkManager
+    fn query_networkmanager()
+        -> Result<(String, String, String), error::CerebroError> {
+
+        // See NMState in the NetworkManager documentation
+        let state = match nm_property(NM_PATH, NM_INTERFACE, "State")?
+            .as_u64() {
+
+            Some(s) if s >= 50 => STATE_CONNECTED,
+            Some(s) if s >= 40 => STATE_CONNECTING,
+            Some(_) => STATE_DISCONNECTED,
+            None => VALUE_UNKNOWN,
+        };
+
+        let connection =
+            nm_property(NM_PATH, NM_INTERFACE, "PrimaryConnection")?;
+
+        let connection = match connection.as_str() {
+            Some(c) if c != "/" => c.to_string(),
+            _ => return Ok((
+                "".to_string(),
+                "".to_string(),
+                state.to_string())),
+        };
+
+        // Interface of the primary connection
+        let devices =
+            nm_property(&connection, NM_ACTIVE_INTERFACE, "Devices")?;
+
+        let interface = match devices[0].as_str() {
+            Some(d) => match nm_property(d, NM_DEVICE_INTERFACE, "Interface")?
+                .as_str() {
+
+                Some(i) => i.to_string(),
+                None => "".to_string(),
+            },
+
+            None => "".to_string(),
+        };
+
+        // SSID of the access point (if it's a wireless connection)
+        let access_point =
+            nm_property(&connection, NM_ACTIVE_INTERFACE, "SpecificObject")?;
+
+        let ssid = match access_point.as_str() {
+            Some(a) if a.contains("/AccessPoint/") => {
+                let bytes: Vec<u8> = nm_property(a, NM_AP_INTERFACE, "Ssid")?
+                    .as_array()
+                    .unwrap_or(&Vec::new())
+                    .iter()
+                    .filter_map(|b| b.as_u64())
+                    .map(|b| b as u8)
+                    .collect();
+
+                String::from_utf8_lossy(&bytes).to_string()
+            },
+
+            _ => "".to_string(),
+        };
+
+        return Ok((interface, ssid, state.to_string()));
+    }
+
+    /// Get the values (interface, SSID, state) from sysfs
+    ///
+    /// The primary interface is the one of the default route.
+    fn query_sysfs() -> Result<(String, String, String), error::CerebroError> {
+        let routes = match fs::read_to_string("/proc/net/route") {
+            Ok(r) => r,
+            Err(_) => return error!("Cannot read routes"),
+        };
+
+        let interface = routes
+            .lines()
+            .skip(1)
+            .map(|l| l.split_whitespace().collect::<Vec<&str>>())
+            .find(|f| f.len() > 1 && f[1] == "00000000")
+            .map(|f| f[0].to_string());
+
+        let interface = match interface {
+            Some(i) => i,
+            None => return Ok((
+                "".to_string(),
+                "".to_string(),
+                STATE_DISCONNECTED.to_string())),
+        };
+
+        let net = match platform::current().sysfs(NET) {
+            Some(n) => n,
+            None => return error!("No network interfaces on this platform"),
+        };
+
+        let operstate = net.join(&interface).join("operstate");
+
+        let state = match fs::read_to_string(operstate) {
+            Ok(s) if s.trim() == "up" => STATE_CONNECTED,
+            Ok(_) => STATE_DISCONNECTED,
+            Err(_) => VALUE_UNKNOWN,
+        };
+
+        // Only wireless interfaces have a `wireless` directory
+        let wireless = net.join(&interface).join("wireless");
+
+        let ssid = match wireless.exists() {
+            true => match process::Command::new("iw")
+                .args(&["dev", &interface, "link"])
+                .output() {
+
+                Ok(o) => String::from_utf8_lossy(&o.stdout)
+                    .lines()
+                    .map(|l| l.trim())
+                    .find(|l| l.starts_with("SSID: "))
+                    .map(|l| l.trim_start_matches("SSID: ").to_string())
+                    .unwrap_or("".to_string()),
+
+                Err(_) => VALUE_UNKNOWN.to_string(),
+            },
+
+            false => "".to_string(),
+        };
+
+        return Ok((interface, ssid, state.to_string()));
+    }
+
+    /// Update the values
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `values` - The values (interface, SSID, state)
+    fn update_values(&mut self, values: (String, String, String))
+        -> error::Return {
+
+        let (interface, ssid, state) = values;
+
+        let mut backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        };
+
+        backend.update_values(interface, ssid, state);
+
+        return success!();
+    }
+
+    /// Update the values from NetworkManager each time it emits a signal
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update_networkmanager(&mut self)
+        -> Result<module::Status, error::CerebroError> {
+
+        // Monitor first so that no signal is missed after the first query
+        let mut monitor = dbus::Monitor::new(dbus::Bus::System, NM_SERVICE)?;
+
+        loop {
+            self.update_values(NetworkBackendProxy::query_networkmanager()?)?;
+
+            monitor.next_signal()?;
+        }
+    }
+}
+
+impl module::Data for NetworkBackendProxy {
+    /// Update network data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let backend_name = match self.backend.lock() {
+            Ok(b) => b.config.backend.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        };
+
+        // NetworkManager is event-driven, sysfs is polled as a fallback
+        if backend_name.as_deref() == Some(BACKEND_NETWORKMANAGER) &&
+            !self.networkmanager_failed {
+
+            match self.update_networkmanager() {
+                Ok(s) => return Ok(s),
+                Err(e) => {
+                    log::error!(
+                        "{}: NetworkManager failed: {}",
+                        MODULE_NAME,
+                        e);
+
+                    self.networkmanager_failed = true;
+                },
+            }
+        }
+
+        self.update_values(NetworkBackendProxy::query_sysfs()?)?;
+
+        return Ok(module::Status::Ok);
+    }
+
+    /// Update network data once, using sysfs
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update_once(&mut self)
+        -> Option<Result<module::Status, error::CerebroError>> {
+
+        let values = match NetworkBackendProxy::query_sysfs() {
+            Ok(v) => v,
+            Err(e) => return Some(Err(e)),
+        };
+
+        return match self.update_values(values) {
+            Ok(_) => Some(Ok(module::Status::Ok)),
+            Err(e) => Some(Err(e)),
+        };
+    }
+}
+
+/// Network backend that will compute the values
+struct NetworkBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+
+    pub data: NetworkData,
+}
+
+impl NetworkBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            data: NetworkData::new(),
+        }
+    }
+
+    /// Update a value and call the triggers
+    ///
+    /// # Arguments
+    ///
+    /// * `triggers` - The list of triggers
+    /// * `kind` - The kind of trigger to be called
+    /// * `module` - The name of the module
+    /// * `name` - The name of the entry
+    /// * `current` - The current value of the entry
+    /// * `value` - The new value of the entry
+    fn update_value(
+        triggers: &Vec<triggers::Trigger>,
+        kind: triggers::Kind,
+        module: &str,
+        name: &str,
+        current: &mut String,
+        value: String) {
+
+        publish::update(triggers, kind, module, name, current, &value);
+    }
+
+    /// Update the values and call the triggers
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `interface` - The primary interface
+    /// * `ssid` - The SSID of the wireless network
+    /// * `state` - The connection state
+    fn update_values(
+        &mut self,
+        interface: String,
+        ssid: String,
+        state: String) {
+
+        let kind = match self.first_update {
+            true => triggers::Kind::Create,
+            false => triggers::Kind::Update,
+        };
+
+        NetworkBackend::update_value(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_INTERFACE,
+            &mut self.data.interface,
+            interface);
+
+        NetworkBackend::update_value(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_SSID,
+            &mut self.data.ssid,
+            ssid);
+
+        NetworkBackend::update_value(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_STATE,
+            &mut self.data.state,
+            state);
+
+        self.first_update = false;
+    }
+}
+
+/// Network module structure
+pub struct Network {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    inode_interface: u64,
+    inode_ssid: u64,
+    inode_state: u64,
+    backend: Arc<Mutex<NetworkBackend>>,
+    backend_proxy: Arc<Mutex<NetworkBackendProxy>>,
+    fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl Network {
+    /// Network constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        let interface = filesystem::FsEntry::create_inode();
+        let ssid = filesystem::FsEntry::create_inode();
+        let state = filesystem::FsEntry::create_inode();
+        let backend = Arc::new(Mutex::new(NetworkBackend::new(name, triggers)));
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            inode_interface: interface,
+            inode_ssid: ssid,
+            inode_state: state,
+            backend: backend.clone(),
+            backend_proxy: Arc::new(Mutex::new(
+                NetworkBackendProxy::new(backend.clone()))),
+            fs_entries: vec![
+                filesystem::FsEntry::new(
+                    interface,
+                    fuse::FileType::RegularFile,
+                    ENTRY_INTERFACE,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    ssid,
+                    fuse::FileType::RegularFile,
+                    ENTRY_SSID,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    state,
+                    fuse::FileType::RegularFile,
+                    ENTRY_STATE,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+                ],
+        }
+    }
+}
+
+impl module::Module for Network {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Check if the system has a network interface
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn probe(&self) -> Option<bool> {
+        return Some(!interfaces().is_empty());
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend_proxy.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        return self.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        if inode == self.inode_interface {
+            return backend.data.interface.clone();
+        }
+
+        if inode == self.inode_ssid {
+            return backend.data.ssid.clone();
+        }
+
+        if inode == self.inode_state {
+            return backend.data.state.clone();
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return format!(
+            "interface={} ssid={:?} state={}",
+            backend.data.interface,
+            backend.data.ssid,
+            backend.data.state).to_string();
+    }
+}
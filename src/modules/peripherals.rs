@@ -0,0 +1,419 @@
+use fuse;
+use serde::{Serialize};
+use std::fs;
+use std::path;
+use std::sync::Arc;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::platform;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "peripherals";
+
+const VALUE_UNKNOWN: &str = "?";
+
+const ENTRY_CAPACITY: &str = "capacity";
+const ENTRY_MODEL: &str = "model";
+
+/// Information about a peripheral
+#[derive(Serialize)]
+struct PeripheralData
+{
+    pub device: String,
+    pub capacity: String,
+    pub model: String,
+}
+
+/// Peripherals backend that will compute the values
+struct PeripheralsBackend {
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+
+    pub data: Vec<PeripheralData>,
+    pub fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl PeripheralsBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            data: Vec::new(),
+            fs_entries: Vec::new(),
+        }
+    }
+
+    /// Read a power supply attribute
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - The path of the power supply device
+    /// * `attribute` - The name of the attribute
+    fn read_attribute(device: &path::Path, attribute: &str) -> Option<String> {
+        return match fs::read_to_string(device.join(attribute)) {
+            Ok(v) => Some(v.trim().to_string()),
+            Err(_) => None,
+        };
+    }
+
+    /// Get the list of peripherals currently connected
+    ///
+    /// Peripherals are power supplies with a `Device` scope, which excludes
+    /// the main batteries and the AC adapters.
+    fn peripherals() -> Vec<PeripheralData> {
+        let mut peripherals: Vec<PeripheralData> = Vec::new();
+
+        let root = match platform::current().sysfs("class/power_supply") {
+            Some(r) => r,
+            None => return peripherals,
+        };
+
+        let devices = match fs::read_dir(&root) {
+            Ok(d) => d,
+            Err(_) => return peripherals,
+        };
+
+        for device in devices {
+            let name = match device {
+                Ok(d) => d.file_name(),
+                Err(_) => continue,
+            };
+
+            let name = match name.into_string() {
+                Ok(n) => n,
+                Err(_) => continue,
+            };
+
+            let device_path = root.join(&name);
+
+            match PeripheralsBackend::read_attribute(&device_path, "scope") {
+                Some(s) if s == "Device" => (),
+                _ => continue,
+            }
+
+            let capacity =
+                match PeripheralsBackend::read_attribute(
+                    &device_path,
+                    "capacity") {
+
+                Some(c) => c,
+                None => VALUE_UNKNOWN.to_string(),
+            };
+
+            let model =
+                match PeripheralsBackend::read_attribute(
+                    &device_path,
+                    "model_name") {
+
+                Some(m) => m,
+                None => VALUE_UNKNOWN.to_string(),
+            };
+
+            peripherals.push(PeripheralData {
+                device: name,
+                capacity: capacity,
+                model: model,
+            });
+        }
+
+        peripherals.sort_by(|a, b| a.device.cmp(&b.device));
+
+        return peripherals;
+    }
+
+    /// Rebuild the filesystem from a new list of peripherals
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `peripherals` - The list of peripherals now connected
+    fn rebuild_filesystem(&mut self, peripherals: Vec<PeripheralData>) {
+        // Deletion triggers
+        for data in self.data.iter() {
+            triggers::find_all_and_execute(
+                &self.triggers,
+                triggers::Kind::Delete,
+                &self.name,
+                &format!("{}/{}", data.device, ENTRY_CAPACITY),
+                "",
+                "");
+        }
+
+        self.data = peripherals;
+        self.fs_entries.clear();
+
+        for data in self.data.iter() {
+            self.fs_entries.push(filesystem::FsEntry::new(
+                filesystem::FsEntry::create_inode(),
+                fuse::FileType::Directory,
+                &data.device,
+                filesystem::Mode::ReadOnly,
+                &vec![
+                    filesystem::FsEntry::new(
+                        filesystem::FsEntry::create_inode(),
+                        fuse::FileType::RegularFile,
+                        ENTRY_CAPACITY,
+                        filesystem::Mode::ReadOnly,
+                        &Vec::new()),
+
+                    filesystem::FsEntry::new(
+                        filesystem::FsEntry::create_inode(),
+                        fuse::FileType::RegularFile,
+                        ENTRY_MODEL,
+                        filesystem::Mode::ReadOnly,
+                        &Vec::new()),
+                ]));
+
+            // Creation triggers
+            triggers::find_all_and_execute(
+                &self.triggers,
+                triggers::Kind::Create,
+                &self.name,
+                &format!("{}/{}", data.device, ENTRY_CAPACITY),
+                "",
+                &data.capacity);
+        }
+    }
+}
+
+impl module::Data for PeripheralsBackend {
+    /// Update peripherals data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let peripherals = PeripheralsBackend::peripherals();
+
+        // Check if the list of devices has changed
+        let changed = peripherals.len() != self.data.len() ||
+            peripherals
+                .iter()
+                .zip(self.data.iter())
+                .any(|(a, b)| a.device != b.device);
+
+        if changed {
+            self.rebuild_filesystem(peripherals);
+
+            return Ok(module::Status::Changed(MODULE_NAME.to_string()));
+        }
+
+        // Update values
+        for (data, peripheral) in self.data.iter_mut().zip(peripherals) {
+            if data.capacity != peripheral.capacity {
+                let old_value = data.capacity.clone();
+
+                data.capacity = peripheral.capacity;
+
+                log::debug!(
+                    "{}: {} capacity={}",
+                    MODULE_NAME,
+                    data.device,
+                    data.capacity);
+
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Update,
+                    &self.name,
+                    &format!("{}/{}", data.device, ENTRY_CAPACITY),
+                    &old_value,
+                    &data.capacity);
+            }
+
+            data.model = peripheral.model;
+        }
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// Peripherals module structure
+pub struct Peripherals {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    backend: Arc<Mutex<PeripheralsBackend>>,
+}
+
+impl Peripherals {
+    /// Peripherals constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            backend:
+                Arc::new(Mutex::new(PeripheralsBackend::new(name, triggers))),
+        }
+    }
+}
+
+impl module::Module for Peripherals {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        return backend.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        // Find filesystem entry
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        for device_entry in backend.fs_entries.iter() {
+            let entry = match device_entry.fs_entries
+                .iter().find(|x| x.inode == inode) {
+
+                Some(e) => e,
+                None => continue,
+            };
+
+            // Find corresponding data
+            let data =
+                match backend.data
+                .iter().find(|x| x.device == device_entry.name) {
+
+                Some(d) => d,
+                None => return VALUE_UNKNOWN.to_string(),
+            };
+
+            return match entry.name.as_str() {
+                ENTRY_CAPACITY => data.capacity.clone(),
+                ENTRY_MODEL => data.model.clone(),
+                _ => VALUE_UNKNOWN.to_string(),
+            }
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return backend.data
+            .iter()
+            .map(|d| format!(
+                "{}_capacity={} {}_model={}",
+                d.device,
+                d.capacity,
+                d.device,
+                d.model))
+            .collect::<Vec<String>>()
+            .join(" ");
+    }
+}
@@ -0,0 +1,509 @@
+use fuse;
+use std::io;
+use std::net;
+use std::net::ToSocketAddrs;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::publish;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "portcheck";
+
+const ENTRY_LATENCY_MS: &str = "latency_ms";
+const ENTRY_OPEN: &str = "open";
+
+const ENTRIES: [&str; 2] = [ENTRY_OPEN, ENTRY_LATENCY_MS];
+
+const DEFAULT_TIMEOUT_MS: u64 = 3000;
+
+const PROTOCOL_TCP: &str = "tcp";
+const PROTOCOL_UDP: &str = "udp";
+
+const VALUE_FALSE: &str = "false";
+const VALUE_TRUE: &str = "true";
+const VALUE_UNKNOWN: &str = "?";
+
+/// Probe a TCP port
+///
+/// Returns the time needed to connect, or None if the port is closed.
+///
+/// # Arguments
+///
+/// * `address` - The address of the port
+/// * `timeout` - The maximum time to wait for the connection
+fn probe_tcp(address: &net::SocketAddr, timeout: Duration)
+    -> Option<Duration> {
+
+    let start = Instant::now();
+
+    return match net::TcpStream::connect_timeout(address, timeout) {
+        Ok(_) => Some(start.elapsed()),
+        Err(_) => None,
+    };
+}
+
+/// Probe a UDP port
+///
+/// An empty datagram is sent. The port is closed if it's refused (ICMP port
+/// unreachable) and open otherwise. The latency is only known if the
+/// service answers.
+///
+/// # Arguments
+///
+/// * `address` - The address of the port
+/// * `timeout` - The maximum time to wait for an answer
+fn probe_udp(address: &net::SocketAddr, timeout: Duration)
+    -> Option<Option<Duration>> {
+
+    let local = match address {
+        net::SocketAddr::V4(_) => "0.0.0.0:0",
+        net::SocketAddr::V6(_) => "[::]:0",
+    };
+
+    let socket = match net::UdpSocket::bind(local) {
+        Ok(s) => s,
+        Err(_) => return None,
+    };
+
+    if socket.connect(address).is_err() ||
+        socket.set_read_timeout(Some(timeout)).is_err() {
+
+        return None;
+    }
+
+    let start = Instant::now();
+
+    if socket.send(&[]).is_err() {
+        return None;
+    }
+
+    let mut buffer = [0; 1];
+
+    return match socket.recv(&mut buffer) {
+        Ok(_) => Some(Some(start.elapsed())),
+
+        Err(e) => match e.kind() {
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => Some(None),
+            _ => None,
+        },
+    };
+}
+
+/// Port probed by the module
+struct Probe {
+    pub name: String,
+    pub config: config::ProbeConfig,
+    pub open: String,
+    pub latency_ms: String,
+    pub fs_entry: filesystem::FsEntry,
+}
+
+impl Probe {
+    /// Get the value of one of the entries
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `name` - The name of the entry
+    fn value(&self, name: &str) -> String {
+        return match name {
+            ENTRY_OPEN => self.open.clone(),
+            ENTRY_LATENCY_MS => self.latency_ms.clone(),
+            _ => VALUE_UNKNOWN.to_string(),
+        };
+    }
+
+    /// Probe the port
+    ///
+    /// Returns if the port is open and the latency if known.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn probe(&self) -> (bool, Option<Duration>) {
+        let timeout = Duration::from_millis(
+            self.config.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+
+        let address = match self.config.address.to_socket_addrs() {
+            Ok(mut a) => match a.next() {
+                Some(a) => a,
+                None => return (false, None),
+            },
+
+            Err(_) => return (false, None),
+        };
+
+        let protocol = self.config.protocol.as_deref().unwrap_or(PROTOCOL_TCP);
+
+        return match protocol {
+            PROTOCOL_UDP => match probe_udp(&address, timeout) {
+                Some(latency) => (true, latency),
+                None => (false, None),
+            },
+
+            _ => match probe_tcp(&address, timeout) {
+                Some(latency) => (true, Some(latency)),
+                None => (false, None),
+            },
+        };
+    }
+}
+
+/// Port check backend that will compute the values
+struct PortcheckBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+
+    pub probes: Vec<Probe>,
+}
+
+impl PortcheckBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            probes: Vec::new(),
+        }
+    }
+
+    /// Get the configured probes sorted by name
+    fn configured(&self) -> Vec<(String, config::ProbeConfig)> {
+        let mut probes: Vec<(String, config::ProbeConfig)> =
+            match &self.config.portcheck {
+                Some(c) => match &c.probes {
+                    Some(p) => p.iter()
+                        .map(|(n, c)| (n.clone(), c.clone()))
+                        .collect(),
+
+                    None => Vec::new(),
+                },
+
+                None => Vec::new(),
+            };
+
+        probes.sort_by(|a, b| a.0.cmp(&b.0));
+
+        return probes;
+    }
+
+    /// Rebuild the probes and the filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `configured` - The configured probes
+    fn rebuild_filesystem(
+        &mut self,
+        configured: Vec<(String, config::ProbeConfig)>) {
+
+        // Deletion triggers
+        for probe in self.probes.iter() {
+            for entry in ENTRIES.iter() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Delete,
+                    &self.name,
+                    &format!("{}/{}", probe.name, entry),
+                    "",
+                    "");
+            }
+        }
+
+        self.probes.clear();
+
+        for (name, config) in configured {
+            match config.protocol.as_deref() {
+                None | Some(PROTOCOL_TCP) | Some(PROTOCOL_UDP) => (),
+                Some(p) => log::error!("Unknown protocol for {}: {}", name, p),
+            }
+
+            self.probes.push(Probe {
+                fs_entry: filesystem::FsEntry::new(
+                    filesystem::FsEntry::create_inode(),
+                    fuse::FileType::Directory,
+                    &name,
+                    filesystem::Mode::ReadOnly,
+                    &ENTRIES
+                        .iter()
+                        .map(|e| filesystem::FsEntry::new(
+                            filesystem::FsEntry::create_inode(),
+                            fuse::FileType::RegularFile,
+                            e,
+                            filesystem::Mode::ReadOnly,
+                            &Vec::new()))
+                        .collect()),
+                name: name,
+                config: config,
+                open: String::new(),
+                latency_ms: String::new(),
+            });
+        }
+    }
+
+    /// Probe the ports and call the triggers
+    ///
+    /// The values are kept while the host is offline or in backoff after
+    /// failures.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `kind` - The kind of trigger to be called
+    fn evaluate(&mut self, kind: triggers::Kind) {
+        for probe in self.probes.iter_mut() {
+            let host = crate::net::host(&probe.config.address);
+
+            if !crate::net::acquire(&host) {
+                continue;
+            }
+
+            let (open, latency) = probe.probe();
+
+            crate::net::report(&host, open);
+
+            let open = match open {
+                true => VALUE_TRUE.to_string(),
+                false => VALUE_FALSE.to_string(),
+            };
+
+            let latency_ms = match latency {
+                Some(l) => format!("{}", l.as_millis()),
+                None => VALUE_UNKNOWN.to_string(),
+            };
+
+            let values = [
+                (ENTRY_OPEN, &mut probe.open, open),
+                (ENTRY_LATENCY_MS, &mut probe.latency_ms, latency_ms),
+            ];
+
+            for (name, current, value) in values {
+                publish::update(
+                    &self.triggers,
+                    kind,
+                    &self.name,
+                    &format!("{}/{}", probe.name, name),
+                    current,
+                    &value);
+            }
+        }
+    }
+}
+
+impl module::Data for PortcheckBackend {
+    /// Update port check data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let configured = self.configured();
+
+        // Check if the configured probes have changed
+        let changed = configured.len() != self.probes.len() ||
+            configured
+                .iter()
+                .zip(self.probes.iter())
+                .any(|((n, c), p)| *n != p.name || *c != p.config);
+
+        if changed {
+            self.rebuild_filesystem(configured);
+            self.evaluate(triggers::Kind::Create);
+
+            return Ok(module::Status::Changed(MODULE_NAME.to_string()));
+        }
+
+        self.evaluate(triggers::Kind::Update);
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// Port check module structure
+pub struct Portcheck {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    backend: Arc<Mutex<PortcheckBackend>>,
+}
+
+impl Portcheck {
+    /// Portcheck constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            backend:
+                Arc::new(Mutex::new(PortcheckBackend::new(name, triggers))),
+        }
+    }
+}
+
+impl module::Module for Portcheck {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        return backend.probes.iter().map(|p| p.fs_entry.clone()).collect();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        for probe in backend.probes.iter() {
+            match probe.fs_entry.find(inode) {
+                Some(e) => return probe.value(&e.name),
+                None => continue,
+            }
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let values: serde_json::Map<String, serde_json::Value> = backend
+            .probes
+            .iter()
+            .map(|p| (
+                p.name.clone(),
+                serde_json::Value::Object(ENTRIES
+                    .iter()
+                    .map(|e| (e.to_string(), p.value(e).into()))
+                    .collect())))
+            .collect();
+
+        return match serde_json::to_string(&values) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return backend.probes
+            .iter()
+            .flat_map(|p| ENTRIES
+                .iter()
+                .map(move |e| format!("{}_{}={}", p.name, e, p.value(e))))
+            .collect::<Vec<String>>()
+            .join(" ");
+    }
+}
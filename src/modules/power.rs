@@ -0,0 +1,452 @@
+use fuse;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::cooling;
+use crate::modules::module;
+use crate::privileged;
+use crate::publish;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "power";
+
+const ENTRY_DRAM_WATTS: &str = "dram_watts";
+const ENTRY_ENERGY_JOULES: &str = "energy_joules";
+const ENTRY_PACKAGE_WATTS: &str = "package_watts";
+
+/// Directory of the RAPL zones relatively to the sysfs root
+const POWERCAP: &str = "class/powercap";
+
+/// Prefix of the RAPL zones and subzones (e.g. `intel-rapl:0:1`)
+const RAPL_PREFIX: &str = "intel-rapl:";
+
+/// Source of the privileged helper reading the RAPL counters
+const SOURCE_RAPL: &str = "rapl";
+
+const ZONE_DRAM: &str = "dram";
+const ZONE_PACKAGE: &str = "package";
+
+const VALUE_UNKNOWN: &str = "?";
+
+/// Information about the power consumption of the machine
+#[derive(Serialize)]
+struct PowerData {
+    pub package_watts: String,
+    pub dram_watts: String,
+    pub energy_joules: String,
+}
+
+impl PowerData {
+    /// PowerData constructor
+    pub fn new() -> Self {
+        Self {
+            package_watts: VALUE_UNKNOWN.to_string(),
+            dram_watts: VALUE_UNKNOWN.to_string(),
+            energy_joules: VALUE_UNKNOWN.to_string(),
+        }
+    }
+}
+
+/// Counter of a RAPL zone
+struct Zone {
+    pub path: path::PathBuf,
+    pub name: String,
+    pub energy_uj: u64,
+    pub max_energy_uj: u64,
+}
+
+/// Power backend that will compute the values
+struct PowerBackend {
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+    last_poll: Option<Instant>,
+    counters: HashMap<path::PathBuf, u64>,
+    energy_uj: u64,
+
+    pub data: PowerData,
+}
+
+impl PowerBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            last_poll: None,
+            counters: HashMap::new(),
+            energy_uj: 0,
+            data: PowerData::new(),
+        }
+    }
+
+    /// Get the energy counter of a RAPL zone (in µJ)
+    ///
+    /// The counters are only readable by root on recent kernels, they're
+    /// read by the privileged helper otherwise.
+    ///
+    /// # Arguments
+    ///
+    /// * `zone` - The path of the zone
+    fn query_energy(zone: &path::Path) -> Option<u64> {
+        match cooling::read_u64(&zone.join("energy_uj")) {
+            Some(e) => return Some(e),
+            None => (),
+        }
+
+        let name = zone.file_name()?.to_str()?;
+
+        return match privileged::read(SOURCE_RAPL, name) {
+            Ok(v) => v.parse::<u64>().ok(),
+            Err(e) => {
+                log::debug!("{}: {}", MODULE_NAME, e);
+                None
+            },
+        };
+    }
+
+    /// Get the counters of the RAPL zones
+    fn query_zones() -> Vec<Zone> {
+        return cooling::list_sysfs(POWERCAP, RAPL_PREFIX, "")
+            .into_iter()
+            .filter_map(|z| {
+                let name = fs::read_to_string(z.join("name")).ok()?;
+                let energy_uj = PowerBackend::query_energy(&z)?;
+
+                let max_energy_uj =
+                    cooling::read_u64(&z.join("max_energy_range_uj"))?;
+
+                Some(Zone {
+                    path: z,
+                    name: name.trim().to_string(),
+                    energy_uj: energy_uj,
+                    max_energy_uj: max_energy_uj,
+                })
+            })
+            .collect();
+    }
+
+    /// Get the energy consumed by a zone since the last poll (in µJ)
+    ///
+    /// The counters wrap around when they reach their maximum.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `zone` - The zone
+    fn consumed(&mut self, zone: &Zone) -> u64 {
+        let consumed = match self.counters.get(&zone.path) {
+            Some(p) if zone.energy_uj >= *p => zone.energy_uj - p,
+            Some(p) => zone.max_energy_uj.saturating_sub(*p) + zone.energy_uj,
+            None => 0,
+        };
+
+        self.counters.insert(zone.path.clone(), zone.energy_uj);
+
+        return consumed;
+    }
+}
+
+impl module::Data for PowerBackend {
+    /// Update power data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let zones = PowerBackend::query_zones();
+
+        if zones.is_empty() {
+            return error!("Cannot read RAPL energy counters");
+        }
+
+        let now = Instant::now();
+
+        let mut package_uj = 0;
+        let mut dram_uj = None;
+
+        for zone in zones.iter() {
+            let consumed = self.consumed(zone);
+
+            if zone.name.starts_with(ZONE_PACKAGE) {
+                package_uj += consumed;
+            }
+            else if zone.name == ZONE_DRAM {
+                dram_uj = Some(dram_uj.unwrap_or(0) + consumed);
+            }
+        }
+
+        self.energy_uj += package_uj;
+
+        // The rates need two polls
+        let elapsed = match self.last_poll {
+            Some(t) => now.duration_since(t).as_secs_f64(),
+            None => 0.0,
+        };
+
+        self.last_poll = Some(now);
+
+        let watts = |uj: u64| {
+            return match elapsed > 0.0 {
+                true => format!("{:.2}", uj as f64 / 1_000_000.0 / elapsed),
+                false => VALUE_UNKNOWN.to_string(),
+            };
+        };
+
+        let package_watts = watts(package_uj);
+
+        let dram_watts = match dram_uj {
+            Some(uj) => watts(uj),
+            None => VALUE_UNKNOWN.to_string(),
+        };
+
+        let energy_joules = format!("{}", self.energy_uj / 1_000_000);
+
+        let kind = match self.first_update {
+            true => triggers::Kind::Create,
+            false => triggers::Kind::Update,
+        };
+
+        self.first_update = false;
+
+        let values = [
+            (
+                ENTRY_PACKAGE_WATTS,
+                &mut self.data.package_watts,
+                package_watts,
+            ),
+            (ENTRY_DRAM_WATTS, &mut self.data.dram_watts, dram_watts),
+            (
+                ENTRY_ENERGY_JOULES,
+                &mut self.data.energy_joules,
+                energy_joules,
+            ),
+        ];
+
+        for (name, current, value) in values {
+            publish::update(
+                &self.triggers,
+                kind,
+                &self.name,
+                name,
+                current,
+                &value);
+        }
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// Power module structure
+pub struct Power {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    inode_dram_watts: u64,
+    inode_energy_joules: u64,
+    inode_package_watts: u64,
+    backend: Arc<Mutex<PowerBackend>>,
+    fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl Power {
+    /// Power constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        let dram_watts = filesystem::FsEntry::create_inode();
+        let energy_joules = filesystem::FsEntry::create_inode();
+        let package_watts = filesystem::FsEntry::create_inode();
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            inode_dram_watts: dram_watts,
+            inode_energy_joules: energy_joules,
+            inode_package_watts: package_watts,
+            backend: Arc::new(Mutex::new(PowerBackend::new(name, triggers))),
+            fs_entries: vec![
+                filesystem::FsEntry::new(
+                    package_watts,
+                    fuse::FileType::RegularFile,
+                    ENTRY_PACKAGE_WATTS,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    dram_watts,
+                    fuse::FileType::RegularFile,
+                    ENTRY_DRAM_WATTS,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    energy_joules,
+                    fuse::FileType::RegularFile,
+                    ENTRY_ENERGY_JOULES,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+            ],
+        }
+    }
+}
+
+impl module::Module for Power {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Check if the system has RAPL zones
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn probe(&self) -> Option<bool> {
+        return Some(
+            !cooling::list_sysfs(POWERCAP, RAPL_PREFIX, "").is_empty());
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        return self.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        if inode == self.inode_package_watts {
+            return backend.data.package_watts.clone();
+        }
+
+        if inode == self.inode_dram_watts {
+            return backend.data.dram_watts.clone();
+        }
+
+        if inode == self.inode_energy_joules {
+            return backend.data.energy_joules.clone();
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return format!(
+            "package_watts={} dram_watts={} energy_joules={}",
+            backend.data.package_watts,
+            backend.data.dram_watts,
+            backend.data.energy_joules);
+    }
+}
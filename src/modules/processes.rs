@@ -0,0 +1,618 @@
+use fuse;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::modules::watchdog;
+use crate::publish;
+use crate::sync::Mutex;
+use crate::triggers;
+use crate::units;
+
+const MODULE_NAME: &str = "processes";
+
+const ENTRY_COUNT: &str = "count";
+const ENTRY_CPU_PERCENT: &str = "cpu_percent";
+const ENTRY_MEM_RSS: &str = "mem_rss";
+const ENTRY_NAME: &str = "name";
+const ENTRY_PID: &str = "pid";
+const ENTRY_TOP_CPU: &str = "top_cpu";
+const ENTRY_TOP_MEMORY: &str = "top_memory";
+
+/// Number of processes listed when not configured
+const DEFAULT_TOP: usize = 5;
+
+const VALUE_UNKNOWN: &str = "?";
+
+/// Usage of a process
+struct Usage {
+    pid: u64,
+    name: String,
+    cpu_percent: Option<f64>,
+    mem_rss: u64,
+}
+
+/// Process listed at a rank of a top
+struct Slot {
+    pub pid: String,
+    pub name: String,
+    pub value: String,
+    pub metric: &'static str,
+    pub fs_entry: filesystem::FsEntry,
+}
+
+impl Slot {
+    /// Slot constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `rank` - The rank of the slot in the top
+    /// * `metric` - The name of the entry used to rank the processes
+    fn new(rank: usize, metric: &'static str) -> Self {
+        let unit = match metric {
+            ENTRY_CPU_PERCENT => units::Unit::Percent,
+            _ => units::Unit::Bytes,
+        };
+
+        let fs_entry = filesystem::FsEntry::new(
+            filesystem::FsEntry::create_inode(),
+            fuse::FileType::Directory,
+            &format!("{}", rank),
+            filesystem::Mode::ReadOnly,
+            &[ENTRY_PID, ENTRY_NAME, metric]
+                .iter()
+                .map(|e| {
+                    let entry = filesystem::FsEntry::new(
+                        filesystem::FsEntry::create_inode(),
+                        fuse::FileType::RegularFile,
+                        e,
+                        filesystem::Mode::ReadOnly,
+                        &Vec::new());
+
+                    return match *e == metric {
+                        true => entry.with_unit(unit),
+                        false => entry,
+                    };
+                })
+                .collect());
+
+        return Self {
+            pid: String::new(),
+            name: String::new(),
+            value: String::new(),
+            metric: metric,
+            fs_entry: fs_entry,
+        };
+    }
+
+    /// Get the names of the entries
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn entries(&self) -> [&'static str; 3] {
+        return [ENTRY_PID, ENTRY_NAME, self.metric];
+    }
+
+    /// Get the value of one of the entries
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `name` - The name of the entry
+    fn value(&self, name: &str) -> String {
+        return match name {
+            ENTRY_PID => self.pid.clone(),
+            ENTRY_NAME => self.name.clone(),
+            _ if name == self.metric => self.value.clone(),
+            _ => VALUE_UNKNOWN.to_string(),
+        };
+    }
+}
+
+/// Processes backend that will compute the values
+struct ProcessesBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    clock_ticks: u64,
+
+    /// CPU ticks of the processes and time of the previous measure
+    last_ticks: HashMap<u64, u64>,
+    last_time: Option<Instant>,
+
+    /// Number of processes listed in the tops of the filesystem
+    top: Option<usize>,
+
+    pub count: String,
+    pub top_cpu: Vec<Slot>,
+    pub top_memory: Vec<Slot>,
+    pub fs_entries: Vec<filesystem::FsEntry>,
+    pub inode_count: u64,
+}
+
+impl ProcessesBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            clock_ticks: unsafe { libc::sysconf(libc::_SC_CLK_TCK) } as u64,
+            last_ticks: HashMap::new(),
+            last_time: None,
+            top: None,
+            count: String::new(),
+            top_cpu: Vec::new(),
+            top_memory: Vec::new(),
+            fs_entries: Vec::new(),
+            inode_count: filesystem::FsEntry::create_inode(),
+        }
+    }
+
+    /// Get the configured number of processes listed
+    fn configured(&self) -> usize {
+        return match &self.config.processes {
+            Some(c) => match c.top {
+                Some(t) => t,
+                None => DEFAULT_TOP,
+            },
+
+            None => DEFAULT_TOP,
+        };
+    }
+
+    /// Rebuild the tops and the filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `top` - The number of processes listed
+    fn rebuild_filesystem(&mut self, top: usize) {
+        // Deletion triggers
+        for slot in self.top_cpu.iter().chain(self.top_memory.iter()) {
+            let parent = match slot.metric {
+                ENTRY_CPU_PERCENT => ENTRY_TOP_CPU,
+                _ => ENTRY_TOP_MEMORY,
+            };
+
+            for entry in slot.entries().iter() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Delete,
+                    &self.name,
+                    &format!("{}/{}/{}", parent, slot.fs_entry.name, entry),
+                    "",
+                    "");
+            }
+        }
+
+        self.top_cpu = (0..top).map(|i| Slot::new(i, ENTRY_CPU_PERCENT))
+            .collect();
+
+        self.top_memory = (0..top).map(|i| Slot::new(i, ENTRY_MEM_RSS))
+            .collect();
+
+        self.fs_entries = vec![
+            filesystem::FsEntry::new(
+                self.inode_count,
+                fuse::FileType::RegularFile,
+                ENTRY_COUNT,
+                filesystem::Mode::ReadOnly,
+                &Vec::new()),
+
+            filesystem::FsEntry::new(
+                filesystem::FsEntry::create_inode(),
+                fuse::FileType::Directory,
+                ENTRY_TOP_CPU,
+                filesystem::Mode::ReadOnly,
+                &self.top_cpu.iter().map(|s| s.fs_entry.clone()).collect()),
+
+            filesystem::FsEntry::new(
+                filesystem::FsEntry::create_inode(),
+                fuse::FileType::Directory,
+                ENTRY_TOP_MEMORY,
+                filesystem::Mode::ReadOnly,
+                &self.top_memory
+                    .iter()
+                    .map(|s| s.fs_entry.clone())
+                    .collect()),
+        ];
+
+        self.top = Some(top);
+    }
+
+    /// Measure the usage of the processes
+    ///
+    /// The CPU usage is the one since the previous measure, it's not known
+    /// for the processes started since then.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn measure(&mut self) -> Vec<Usage> {
+        let now = Instant::now();
+
+        let elapsed = match self.last_time {
+            Some(t) => now.duration_since(t).as_secs_f64(),
+            None => 0.0,
+        };
+
+        let mut ticks = HashMap::new();
+        let mut usages = Vec::new();
+
+        for process in watchdog::query_processes() {
+            let (t, rss) = match watchdog::query_usage(process.pid) {
+                Some(u) => u,
+                None => continue,
+            };
+
+            let known = elapsed > 0.0 && self.clock_ticks > 0;
+
+            let cpu_percent = match self.last_ticks.get(&process.pid) {
+                Some(l) if known && t >= *l => Some(
+                    (t - l) as f64 / self.clock_ticks as f64 /
+                        elapsed * 100.0),
+
+                _ => None,
+            };
+
+            ticks.insert(process.pid, t);
+
+            usages.push(Usage {
+                pid: process.pid,
+                name: process.name,
+                cpu_percent: cpu_percent,
+                mem_rss: rss,
+            });
+        }
+
+        self.last_ticks = ticks;
+        self.last_time = Some(now);
+
+        return usages;
+    }
+
+    /// Measure the processes and call the triggers
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `kind` - The kind of trigger to be called
+    fn evaluate(&mut self, kind: triggers::Kind) {
+        let mut usages = self.measure();
+
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_COUNT,
+            &mut self.count,
+            &format!("{}", usages.len()));
+
+        // Unknown usages come last, ties are sorted by PID
+        usages.sort_by(|a, b| {
+            let a_cpu = a.cpu_percent.unwrap_or(-1.0);
+            let b_cpu = b.cpu_percent.unwrap_or(-1.0);
+
+            return b_cpu.partial_cmp(&a_cpu)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(a.pid.cmp(&b.pid));
+        });
+
+        let triggers = &self.triggers;
+
+        for (i, slot) in self.top_cpu.iter_mut().enumerate() {
+            let value = match usages.get(i) {
+                Some(u) => match u.cpu_percent {
+                    Some(c) => format!("{:.1}", c),
+                    None => VALUE_UNKNOWN.to_string(),
+                },
+
+                None => VALUE_UNKNOWN.to_string(),
+            };
+
+            update_slot(
+                triggers,
+                kind,
+                &self.name,
+                ENTRY_TOP_CPU,
+                slot,
+                usages.get(i),
+                value);
+        }
+
+        usages.sort_by(|a, b| b.mem_rss.cmp(&a.mem_rss)
+            .then(a.pid.cmp(&b.pid)));
+
+        for (i, slot) in self.top_memory.iter_mut().enumerate() {
+            let value = match usages.get(i) {
+                Some(u) => format!("{}", u.mem_rss),
+                None => VALUE_UNKNOWN.to_string(),
+            };
+
+            update_slot(
+                triggers,
+                kind,
+                &self.name,
+                ENTRY_TOP_MEMORY,
+                slot,
+                usages.get(i),
+                value);
+        }
+    }
+}
+
+/// Function used to update the values of a slot and call the triggers
+///
+/// # Arguments
+///
+/// * `triggers` - The triggers
+/// * `kind` - The kind of trigger to be called
+/// * `module` - The name of the module
+/// * `parent` - The name of the top of the slot
+/// * `slot` - The slot
+/// * `usage` - The process listed in the slot, if any
+/// * `value` - The new value of the metric of the slot
+fn update_slot(
+    triggers: &Vec<triggers::Trigger>,
+    kind: triggers::Kind,
+    module: &str,
+    parent: &str,
+    slot: &mut Slot,
+    usage: Option<&Usage>,
+    value: String) {
+
+    let (pid, name) = match usage {
+        Some(u) => (format!("{}", u.pid), u.name.clone()),
+        None => (VALUE_UNKNOWN.to_string(), VALUE_UNKNOWN.to_string()),
+    };
+
+    let path = format!("{}/{}", parent, slot.fs_entry.name);
+    let metric = slot.metric;
+
+    let values = [
+        (ENTRY_PID, &mut slot.pid, pid),
+        (ENTRY_NAME, &mut slot.name, name),
+        (metric, &mut slot.value, value),
+    ];
+
+    for (entry, current, value) in values {
+        publish::update(
+            triggers,
+            kind,
+            module,
+            &format!("{}/{}", path, entry),
+            current,
+            &value);
+    }
+}
+
+impl module::Data for ProcessesBackend {
+    /// Update processes data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let top = self.configured();
+
+        if self.top != Some(top) {
+            self.rebuild_filesystem(top);
+            self.evaluate(triggers::Kind::Create);
+
+            return Ok(module::Status::Changed(MODULE_NAME.to_string()));
+        }
+
+        self.evaluate(triggers::Kind::Update);
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// Processes module structure
+pub struct Processes {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    backend: Arc<Mutex<ProcessesBackend>>,
+}
+
+impl Processes {
+    /// Processes constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            backend:
+                Arc::new(Mutex::new(ProcessesBackend::new(name, triggers))),
+        }
+    }
+}
+
+impl module::Module for Processes {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        return backend.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        if inode == backend.inode_count {
+            return backend.count.clone();
+        }
+
+        for slot in backend.top_cpu.iter().chain(backend.top_memory.iter()) {
+            match slot.fs_entry.find(inode) {
+                Some(e) => return slot.value(&e.name),
+                None => continue,
+            }
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let top = |slots: &Vec<Slot>| serde_json::Value::Array(slots
+            .iter()
+            .map(|s| serde_json::Value::Object(s.entries()
+                .iter()
+                .map(|e| (e.to_string(), s.value(e).into()))
+                .collect()))
+            .collect());
+
+        let mut values = serde_json::Map::new();
+
+        values.insert(ENTRY_COUNT.to_string(), backend.count.clone().into());
+        values.insert(ENTRY_TOP_CPU.to_string(), top(&backend.top_cpu));
+
+        values.insert(
+            ENTRY_TOP_MEMORY.to_string(),
+            top(&backend.top_memory));
+
+        return match serde_json::to_string(&values) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let mut values = vec![format!("{}={}", ENTRY_COUNT, backend.count)];
+
+        for (parent, slots) in [
+            (ENTRY_TOP_CPU, &backend.top_cpu),
+            (ENTRY_TOP_MEMORY, &backend.top_memory)] {
+
+            for slot in slots.iter() {
+                for e in slot.entries().iter() {
+                    values.push(format!(
+                        "{}_{}_{}={}",
+                        parent,
+                        slot.fs_entry.name,
+                        e,
+                        slot.value(e)));
+                }
+            }
+        }
+
+        return values.join(" ");
+    }
+}
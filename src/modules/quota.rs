@@ -0,0 +1,581 @@
+use dirs;
+use fuse;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path;
+use std::sync::Arc;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::platform;
+use crate::sync::Mutex;
+use crate::triggers;
+use crate::units;
+
+const MODULE_NAME: &str = "quota";
+
+const BYTES_PER_GB: f64 = 1_000_000_000.0;
+
+const VALUE_UNKNOWN: &str = "?";
+
+const ENTRY_MONTH_RX_GB: &str = "month_rx_gb";
+const ENTRY_MONTH_TX_GB: &str = "month_tx_gb";
+const ENTRY_QUOTA_PERCENT: &str = "quota_percent";
+
+/// Information about the bandwidth quota
+#[derive(Serialize)]
+struct QuotaData
+{
+    pub month_rx_gb: String,
+    pub month_tx_gb: String,
+    pub quota_percent: String,
+}
+
+impl QuotaData {
+    /// QuotaData constructor
+    pub fn new() -> Self {
+        Self {
+            month_rx_gb: VALUE_UNKNOWN.to_string(),
+            month_tx_gb: VALUE_UNKNOWN.to_string(),
+            quota_percent: VALUE_UNKNOWN.to_string(),
+        }
+    }
+}
+
+/// Counters of an interface, persisted across restarts
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct InterfaceCounters {
+    /// Bytes received during the month
+    pub rx: u64,
+
+    /// Bytes transmitted during the month
+    pub tx: u64,
+
+    /// Last value read from the kernel (used to compute the increase)
+    pub last_rx: u64,
+
+    /// Last value read from the kernel (used to compute the increase)
+    pub last_tx: u64,
+}
+
+/// Accounting state, persisted across restarts
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+struct QuotaState {
+    pub month: String,
+    pub interfaces: HashMap<String, InterfaceCounters>,
+}
+
+impl QuotaState {
+    /// Get the path of the file used to persist the state
+    fn path() -> Option<path::PathBuf> {
+        return match dirs::data_dir() {
+            Some(d) => Some(d.join("cerebro").join("quota.json")),
+            None => None,
+        };
+    }
+
+    /// Load the state from disk (or an empty state if there's none)
+    fn load() -> QuotaState {
+        let path = match QuotaState::path() {
+            Some(p) => p,
+            None => return QuotaState::default(),
+        };
+
+        let content = match fs::read_to_string(&path) {
+            Ok(c) => c,
+            Err(_) => return QuotaState::default(),
+        };
+
+        return match serde_json::from_str(&content) {
+            Ok(s) => s,
+            Err(_) => {
+                log::error!("Cannot parse quota state: {:?}", path);
+                QuotaState::default()
+            },
+        };
+    }
+
+    /// Save the state to disk
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn save(&self) -> error::Return {
+        let path = match QuotaState::path() {
+            Some(p) => p,
+            None => return error!("Cannot get data directory"),
+        };
+
+        match path.parent() {
+            Some(d) => match fs::create_dir_all(d) {
+                Ok(_) => (),
+                Err(_) => return error!("Cannot create data directory"),
+            },
+
+            None => (),
+        }
+
+        let content = match serde_json::to_string(self) {
+            Ok(c) => c,
+            Err(_) => return error!("Cannot serialize quota state"),
+        };
+
+        // Write then rename so that a crash never leaves a truncated file
+        let tmp = path.with_extension("json.tmp");
+
+        match fs::write(&tmp, content) {
+            Ok(_) => (),
+            Err(_) => return error!("Cannot write quota state"),
+        }
+
+        return match fs::rename(&tmp, &path) {
+            Ok(_) => success!(),
+            Err(_) => error!("Cannot write quota state"),
+        };
+    }
+}
+
+/// Get the current month in the local timezone (e.g. `2021-07`)
+fn current_month() -> String {
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+
+    unsafe {
+        let now = libc::time(std::ptr::null_mut());
+        libc::localtime_r(&now, &mut tm);
+    }
+
+    return format!("{:04}-{:02}", tm.tm_year + 1900, tm.tm_mon + 1);
+}
+
+/// Read a statistic counter of a network interface
+///
+/// # Arguments
+///
+/// * `interface` - The name of the interface
+/// * `name` - The name of the statistic
+fn read_statistic(interface: &str, name: &str) -> Option<u64> {
+    let path = platform::current()
+        .sysfs("class/net")?
+        .join(interface)
+        .join("statistics")
+        .join(name);
+
+    return match fs::read_to_string(path) {
+        Ok(s) => s.trim().parse::<u64>().ok(),
+        Err(_) => None,
+    };
+}
+
+/// Quota backend that will compute the values
+struct QuotaBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+    state: QuotaState,
+
+    pub data: QuotaData,
+}
+
+impl QuotaBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            state: QuotaState::load(),
+            data: QuotaData::new(),
+        }
+    }
+
+    /// Get the interfaces to be accounted
+    ///
+    /// Uses the configured list or all interfaces except the loopback.
+    fn interfaces(&self) -> Vec<String> {
+        match &self.config.quota {
+            Some(c) => match &c.interfaces {
+                Some(i) => return i.to_vec(),
+                None => (),
+            },
+
+            None => (),
+        }
+
+        let net = match platform::current().sysfs("class/net") {
+            Some(n) => n,
+            None => return Vec::new(),
+        };
+
+        let entries = match fs::read_dir(net) {
+            Ok(e) => e,
+            Err(_) => return Vec::new(),
+        };
+
+        return entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|n| n != "lo")
+            .collect();
+    }
+
+    /// Get the monthly cap in gigabytes
+    fn cap_gb(&self) -> Option<f64> {
+        return match &self.config.quota {
+            Some(c) => c.cap_gb,
+            None => None,
+        };
+    }
+
+    /// Update a value and call the triggers
+    ///
+    /// # Arguments
+    ///
+    /// * `triggers` - The list of triggers
+    /// * `kind` - The kind of trigger to be called
+    /// * `module` - The name of the module
+    /// * `name` - The name of the entry
+    /// * `current` - The value to be updated
+    /// * `value` - The new value
+    fn update_value(
+        triggers: &Vec<triggers::Trigger>,
+        kind: triggers::Kind,
+        module: &str,
+        name: &str,
+        current: &mut String,
+        value: String) {
+
+        if *current == value {
+            return;
+        }
+
+        let old_value = current.clone();
+
+        *current = value;
+
+        log::debug!("{}: {}={}", MODULE_NAME, name, current);
+
+        triggers::find_all_and_execute(
+            triggers,
+            kind,
+            module,
+            name,
+            &old_value,
+            current);
+    }
+}
+
+impl module::Data for QuotaBackend {
+    /// Update quota data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let kind = match self.first_update {
+            true => triggers::Kind::Create,
+            false => triggers::Kind::Update,
+        };
+
+        // Start a new accounting period
+        let month = current_month();
+
+        if month != self.state.month {
+            for counters in self.state.interfaces.values_mut() {
+                counters.rx = 0;
+                counters.tx = 0;
+            }
+
+            self.state.month = month;
+        }
+
+        for interface in self.interfaces() {
+            let rx = match read_statistic(&interface, "rx_bytes") {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let tx = match read_statistic(&interface, "tx_bytes") {
+                Some(v) => v,
+                None => continue,
+            };
+
+            let counters = self.state.interfaces
+                .entry(interface)
+                .or_insert(InterfaceCounters {
+                    rx: 0,
+                    tx: 0,
+                    last_rx: rx,
+                    last_tx: tx,
+                });
+
+            // Kernel counters are reset on reboot or when the interface is
+            // recreated, then the whole value is the increase
+            counters.rx += match rx >= counters.last_rx {
+                true => rx - counters.last_rx,
+                false => rx,
+            };
+
+            counters.tx += match tx >= counters.last_tx {
+                true => tx - counters.last_tx,
+                false => tx,
+            };
+
+            counters.last_rx = rx;
+            counters.last_tx = tx;
+        }
+
+        match self.state.save() {
+            Ok(_) => (),
+            Err(e) => log::error!("{}", e),
+        }
+
+        let rx: u64 = self.state.interfaces.values().map(|c| c.rx).sum();
+        let tx: u64 = self.state.interfaces.values().map(|c| c.tx).sum();
+
+        let month_rx_gb = format!("{:.2}", rx as f64 / BYTES_PER_GB);
+        let month_tx_gb = format!("{:.2}", tx as f64 / BYTES_PER_GB);
+
+        // Integer value so that it can be compared by the triggers
+        let quota_percent = match self.cap_gb() {
+            Some(cap) if cap > 0.0 => format!(
+                "{}",
+                ((rx + tx) as f64 / BYTES_PER_GB * 100.0 / cap) as u64),
+
+            _ => VALUE_UNKNOWN.to_string(),
+        };
+
+        QuotaBackend::update_value(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_MONTH_RX_GB,
+            &mut self.data.month_rx_gb,
+            month_rx_gb);
+
+        QuotaBackend::update_value(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_MONTH_TX_GB,
+            &mut self.data.month_tx_gb,
+            month_tx_gb);
+
+        QuotaBackend::update_value(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_QUOTA_PERCENT,
+            &mut self.data.quota_percent,
+            quota_percent);
+
+        self.first_update = false;
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// Quota module structure
+pub struct Quota {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    inode_month_rx_gb: u64,
+    inode_month_tx_gb: u64,
+    inode_quota_percent: u64,
+    backend: Arc<Mutex<QuotaBackend>>,
+    fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl Quota {
+    /// Quota constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        let month_rx_gb = filesystem::FsEntry::create_inode();
+        let month_tx_gb = filesystem::FsEntry::create_inode();
+        let quota_percent = filesystem::FsEntry::create_inode();
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            inode_month_rx_gb: month_rx_gb,
+            inode_month_tx_gb: month_tx_gb,
+            inode_quota_percent: quota_percent,
+            backend: Arc::new(Mutex::new(QuotaBackend::new(name, triggers))),
+            fs_entries: vec![
+                filesystem::FsEntry::new(
+                    month_rx_gb,
+                    fuse::FileType::RegularFile,
+                    ENTRY_MONTH_RX_GB,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    month_tx_gb,
+                    fuse::FileType::RegularFile,
+                    ENTRY_MONTH_TX_GB,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    quota_percent,
+                    fuse::FileType::RegularFile,
+                    ENTRY_QUOTA_PERCENT,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new())
+                    .with_unit(units::Unit::Percent),
+                ],
+        }
+    }
+}
+
+impl module::Module for Quota {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        return self.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        if inode == self.inode_month_rx_gb {
+            return backend.data.month_rx_gb.clone();
+        }
+
+        if inode == self.inode_month_tx_gb {
+            return backend.data.month_tx_gb.clone();
+        }
+
+        if inode == self.inode_quota_percent {
+            return backend.data.quota_percent.clone();
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return format!(
+            "month_rx_gb={} month_tx_gb={} quota_percent={}",
+            backend.data.month_rx_gb,
+            backend.data.month_tx_gb,
+            backend.data.quota_percent).to_string();
+    }
+}
@@ -0,0 +1,203 @@
+use std::sync::Arc;
+
+use crate::config;
+use crate::event_manager;
+use crate::modules::als;
+use crate::modules::audio;
+use crate::modules::battery;
+#[cfg(feature = "dbus")]
+use crate::modules::bluetooth;
+use crate::modules::brightness;
+#[cfg(feature = "dbus")]
+use crate::modules::capture;
+use crate::modules::clipboard;
+use crate::modules::cooling;
+use crate::modules::cpu;
+use crate::modules::deadman;
+use crate::modules::derived;
+use crate::modules::displays;
+use crate::modules::fans;
+use crate::modules::files;
+use crate::modules::focus;
+use crate::modules::history;
+#[cfg(feature = "http")]
+use crate::modules::http;
+#[cfg(feature = "dbus")]
+use crate::modules::inhibitors;
+use crate::modules::leds;
+use crate::modules::location;
+use crate::modules::mail;
+#[cfg(feature = "media")]
+use crate::modules::media;
+use crate::modules::memory;
+use crate::modules::network;
+use crate::modules::peripherals;
+use crate::modules::portcheck;
+use crate::modules::power;
+use crate::modules::processes;
+use crate::modules::quota;
+#[cfg(feature = "dbus")]
+use crate::modules::sessions;
+#[cfg(feature = "dbus")]
+use crate::modules::systemd;
+use crate::modules::trash;
+use crate::modules::updates;
+use crate::modules::uptime;
+use crate::modules::watchdog;
+#[cfg(feature = "weather")]
+use crate::modules::weather;
+use crate::modules::wireguard;
+use crate::modules::wm;
+use crate::modules::module;
+use crate::sync::Mutex;
+use crate::triggers;
+
+/// Function creating a module under a name
+type Constructor = fn(
+    &str,
+    &mut event_manager::EventManager,
+    &Vec<triggers::Trigger>) -> Arc<Mutex<dyn module::Module>>;
+
+/// Function used to share a module between the threads
+///
+/// # Arguments
+///
+/// * `module` - The module
+fn shared<M>(module: M) -> Arc<Mutex<dyn module::Module>>
+    where M: module::Module + 'static {
+
+    return Arc::new(Mutex::new(module));
+}
+
+/// Modules compiled in, by type
+///
+/// Optional modules are only available when their cargo feature is enabled.
+const REGISTRY: &[(&str, Constructor)] = &[
+    ("cpu", |n, e, t| shared(cpu::Cpu::new(n, e, t))),
+    ("battery", |n, e, t| shared(battery::Battery::new(n, e, t))),
+    ("brightness", |n, e, t| shared(brightness::Brightness::new(n, e, t))),
+    #[cfg(feature = "dbus")]
+    ("capture", |n, e, t| shared(capture::Capture::new(n, e, t))),
+    ("memory", |n, e, t| shared(memory::Memory::new(n, e, t))),
+    ("trash", |n, e, t| shared(trash::Trash::new(n, e, t))),
+    ("peripherals", |n, e, t| shared(peripherals::Peripherals::new(n, e, t))),
+    ("als", |n, e, t| shared(als::Als::new(n, e, t))),
+    ("location", |n, e, t| shared(location::Location::new(n, e, t))),
+    ("focus", |n, e, t| shared(focus::Focus::new(n, e, t))),
+    ("wm", |n, e, t| shared(wm::Wm::new(n, e, t))),
+    ("clipboard", |n, e, t| shared(clipboard::Clipboard::new(n, e, t))),
+    ("quota", |n, e, t| shared(quota::Quota::new(n, e, t))),
+    ("wireguard", |n, e, t| shared(wireguard::Wireguard::new(n, e, t))),
+    #[cfg(feature = "dbus")]
+    ("sessions", |n, e, t| shared(sessions::Sessions::new(n, e, t))),
+    ("cooling", |n, e, t| shared(cooling::Cooling::new(n, e, t))),
+    ("network", |n, e, t| shared(network::Network::new(n, e, t))),
+    #[cfg(feature = "media")]
+    ("media", |n, e, t| shared(media::Media::new(n, e, t))),
+    ("derived", |n, e, t| shared(derived::Derived::new(n, e, t))),
+    ("watchdog", |n, e, t| shared(watchdog::Watchdog::new(n, e, t))),
+    ("files", |n, e, t| shared(files::Files::new(n, e, t))),
+    #[cfg(feature = "http")]
+    ("http", |n, e, t| shared(http::Http::new(n, e, t))),
+    ("portcheck", |n, e, t| shared(portcheck::Portcheck::new(n, e, t))),
+    ("audio", |n, e, t| shared(audio::Audio::new(n, e, t))),
+    #[cfg(feature = "dbus")]
+    ("bluetooth", |n, e, t| shared(bluetooth::Bluetooth::new(n, e, t))),
+    ("deadman", |n, e, t| shared(deadman::Deadman::new(n, e, t))),
+    ("processes", |n, e, t| shared(processes::Processes::new(n, e, t))),
+    ("history", |n, e, t| shared(history::History::new(n, e, t))),
+    ("uptime", |n, e, t| shared(uptime::Uptime::new(n, e, t))),
+    ("fans", |n, e, t| shared(fans::Fans::new(n, e, t))),
+    ("power", |n, e, t| shared(power::Power::new(n, e, t))),
+    ("leds", |n, e, t| shared(leds::Leds::new(n, e, t))),
+    #[cfg(feature = "dbus")]
+    ("systemd", |n, e, t| shared(systemd::Systemd::new(n, e, t))),
+    ("updates", |n, e, t| shared(updates::Updates::new(n, e, t))),
+    ("mail", |n, e, t| shared(mail::Mail::new(n, e, t))),
+    #[cfg(feature = "dbus")]
+    ("inhibitors", |n, e, t| shared(inhibitors::Inhibitors::new(n, e, t))),
+    #[cfg(feature = "weather")]
+    ("weather", |n, e, t| shared(weather::Weather::new(n, e, t))),
+    ("displays", |n, e, t| shared(displays::Displays::new(n, e, t))),
+];
+
+/// Function used to create a module
+///
+/// Returns `None` if the type of the module is not compiled in.
+///
+/// # Arguments
+///
+/// * `name` - The name of the instance
+/// * `kind` - The type of the module (e.g. `files`)
+/// * `event_manager` - The event manager
+/// * `triggers` - The triggers of the values
+pub fn create(
+    name: &str,
+    kind: &str,
+    event_manager: &mut event_manager::EventManager,
+    triggers: &Vec<triggers::Trigger>)
+    -> Option<Arc<Mutex<dyn module::Module>>> {
+
+    let constructor = match REGISTRY.iter().find(|(k, _)| *k == kind) {
+        Some((_, c)) => c,
+        None => return None,
+    };
+
+    return Some(constructor(name, event_manager, triggers));
+}
+
+/// Function used to create the modules of the configuration
+///
+/// The modules are created in the order of the configuration, each name
+/// being an instance of the type given by its prefix (e.g. `ping:home`), if
+/// any. An instance whose type is also configured as a module is ignored,
+/// its directory would replace the one of the module.
+///
+/// # Arguments
+///
+/// * `config` - The configuration
+/// * `event_manager` - The event manager
+/// * `triggers` - The triggers of the values
+pub fn create_configured(
+    config: &config::Config,
+    event_manager: &mut event_manager::EventManager,
+    triggers: &Vec<triggers::Trigger>)
+    -> Vec<Arc<Mutex<dyn module::Module>>> {
+
+    let mut modules: Vec<Arc<Mutex<dyn module::Module>>> = Vec::new();
+
+    for name in config.module_names() {
+        let kind = config::module_kind(&name);
+
+        if kind != name && config.modules.contains_key(kind) {
+            log::error!("Module `{}` conflicts with `{}`", name, kind);
+            continue;
+        }
+
+        match create(&name, kind, event_manager, triggers) {
+            Some(m) => modules.push(m),
+            None if config.modules[&name].enabled == Some(true) =>
+                log::warn!("Module `{}` is not available in this build", kind),
+            None => (),
+        }
+    }
+
+    return modules;
+}
+
+/// Function used to create one module of each type compiled in
+///
+/// # Arguments
+///
+/// * `event_manager` - The event manager
+/// * `triggers` - The triggers of the values
+pub fn create_all(
+    event_manager: &mut event_manager::EventManager,
+    triggers: &Vec<triggers::Trigger>)
+    -> Vec<Arc<Mutex<dyn module::Module>>> {
+
+    return REGISTRY
+        .iter()
+        .map(|(kind, constructor)| constructor(kind, event_manager, triggers))
+        .collect();
+}
@@ -0,0 +1,540 @@
+use fuse;
+use serde::{Serialize};
+use std::process;
+use std::sync::Arc;
+
+use crate::config;
+use crate::dbus;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::publish;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "sessions";
+
+const LOGIN1_SERVICE: &str = "org.freedesktop.login1";
+const LOGIN1_PATH: &str = "/org/freedesktop/login1";
+const LOGIN1_MANAGER: &str = "org.freedesktop.login1.Manager";
+const LOGIN1_SESSION: &str = "org.freedesktop.login1.Session";
+
+const VALUE_FALSE: &str = "false";
+const VALUE_TRUE: &str = "true";
+const VALUE_UNKNOWN: &str = "?";
+
+const ENTRY_ACTIVE_COUNT: &str = "active_count";
+const ENTRY_REMOTE: &str = "remote";
+const ENTRY_REMOTE_HOST: &str = "remote_host";
+const ENTRY_TTY: &str = "tty";
+const ENTRY_USER: &str = "user";
+
+const ENTRIES: [&str; 4] = [
+    ENTRY_REMOTE,
+    ENTRY_REMOTE_HOST,
+    ENTRY_TTY,
+    ENTRY_USER,
+];
+
+/// Information about a login session
+#[derive(Clone, Serialize)]
+struct SessionData
+{
+    pub id: String,
+    pub remote: String,
+    pub remote_host: String,
+    pub tty: String,
+    pub user: String,
+}
+
+impl SessionData {
+    /// Get the value of an entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `name` - The name of the entry
+    fn value(&self, name: &str) -> String {
+        return match name {
+            ENTRY_REMOTE => self.remote.clone(),
+            ENTRY_REMOTE_HOST => self.remote_host.clone(),
+            ENTRY_TTY => self.tty.clone(),
+            ENTRY_USER => self.user.clone(),
+            _ => VALUE_UNKNOWN.to_string(),
+        };
+    }
+}
+
+/// Information about the sessions
+#[derive(Serialize)]
+struct SessionsData
+{
+    pub active_count: String,
+    pub sessions: Vec<SessionData>,
+}
+
+impl SessionsData {
+    /// SessionsData constructor
+    pub fn new() -> Self {
+        Self {
+            active_count: VALUE_UNKNOWN.to_string(),
+            sessions: Vec::new(),
+        }
+    }
+}
+
+/// Sessions backend that will compute the values
+struct SessionsBackend {
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+
+    pub inode_active_count: u64,
+    pub data: SessionsData,
+    pub session_fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl SessionsBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            inode_active_count: filesystem::FsEntry::create_inode(),
+            data: SessionsData::new(),
+            session_fs_entries: Vec::new(),
+        }
+    }
+
+    /// Get a string property of a logind session
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The object path of the session
+    /// * `property` - The name of the property
+    fn session_property(path: &str, property: &str) -> String {
+        let value = dbus::get_property(
+            dbus::Bus::System,
+            LOGIN1_SERVICE,
+            path,
+            LOGIN1_SESSION,
+            property);
+
+        return match value {
+            Ok(serde_json::Value::String(s)) => s,
+            Ok(serde_json::Value::Bool(true)) => VALUE_TRUE.to_string(),
+            Ok(serde_json::Value::Bool(false)) => VALUE_FALSE.to_string(),
+            _ => VALUE_UNKNOWN.to_string(),
+        };
+    }
+
+    /// Get the list of sessions from logind
+    fn logind_sessions() -> Result<Vec<SessionData>, error::CerebroError> {
+        let reply = dbus::call(
+            dbus::Bus::System,
+            LOGIN1_SERVICE,
+            LOGIN1_PATH,
+            LOGIN1_MANAGER,
+            "ListSessions",
+            "",
+            &[])?;
+
+        let mut sessions: Vec<SessionData> = Vec::new();
+
+        // Each session is a (id, uid, user, seat, path) structure
+        let list = match reply[0].as_array() {
+            Some(l) => l.to_vec(),
+            None => return error!("Invalid list of sessions"),
+        };
+
+        for session in list.iter() {
+            let id = match session[0].as_str() {
+                Some(i) => i.to_string(),
+                None => continue,
+            };
+
+            let path = match session[4].as_str() {
+                Some(p) => p.to_string(),
+                None => continue,
+            };
+
+            sessions.push(SessionData {
+                id: id,
+                remote: SessionsBackend::session_property(&path, "Remote"),
+                remote_host:
+                    SessionsBackend::session_property(&path, "RemoteHost"),
+                tty: SessionsBackend::session_property(&path, "TTY"),
+                user: match session[2].as_str() {
+                    Some(u) => u.to_string(),
+                    None => VALUE_UNKNOWN.to_string(),
+                },
+            });
+        }
+
+        return Ok(sessions);
+    }
+
+    /// Get the list of sessions from utmp
+    ///
+    /// Used on systems without logind, the terminal is used as identifier.
+    fn utmp_sessions() -> Result<Vec<SessionData>, error::CerebroError> {
+        let output = match process::Command::new("who").output() {
+            Ok(o) => o,
+            Err(_) => return error!("Cannot execute who"),
+        };
+
+        let mut sessions: Vec<SessionData> = Vec::new();
+
+        // Lines look like `user pts/0 2021-07-14 09:12 (192.168.1.2)`
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+
+            if fields.len() < 2 {
+                continue;
+            }
+
+            let remote_host = match fields.last() {
+                Some(f) if f.starts_with("(") && f.ends_with(")") =>
+                    f.trim_matches(|c| c == '(' || c == ')').to_string(),
+
+                _ => "".to_string(),
+            };
+
+            // Local X displays are also reported between parentheses
+            let remote = match remote_host.is_empty() ||
+                remote_host.starts_with(":") {
+
+                true => VALUE_FALSE,
+                false => VALUE_TRUE,
+            };
+
+            sessions.push(SessionData {
+                id: fields[1].replace("/", "_"),
+                remote: remote.to_string(),
+                remote_host: remote_host,
+                tty: fields[1].to_string(),
+                user: fields[0].to_string(),
+            });
+        }
+
+        return Ok(sessions);
+    }
+
+    /// Get the list of sessions
+    fn sessions() -> Result<Vec<SessionData>, error::CerebroError> {
+        let mut sessions = match SessionsBackend::logind_sessions() {
+            Ok(s) => s,
+            Err(_) => SessionsBackend::utmp_sessions()?,
+        };
+
+        sessions.sort_by(|a, b| a.id.cmp(&b.id));
+
+        return Ok(sessions);
+    }
+
+    /// Rebuild the sessions data and filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `sessions` - The new list of sessions
+    fn rebuild_filesystem(&mut self, sessions: Vec<SessionData>) {
+        // Deletion triggers
+        for data in self.data.sessions.iter() {
+            for name in ENTRIES.iter() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Delete,
+                    &self.name,
+                    &format!("{}/{}", data.id, name),
+                    "",
+                    "");
+            }
+        }
+
+        self.data.sessions = sessions;
+        self.session_fs_entries.clear();
+
+        for data in self.data.sessions.iter() {
+            let entries: Vec<filesystem::FsEntry> = ENTRIES
+                .iter()
+                .map(|name| filesystem::FsEntry::new(
+                    filesystem::FsEntry::create_inode(),
+                    fuse::FileType::RegularFile,
+                    name,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()))
+                .collect();
+
+            self.session_fs_entries.push(filesystem::FsEntry::new(
+                filesystem::FsEntry::create_inode(),
+                fuse::FileType::Directory,
+                &data.id,
+                filesystem::Mode::ReadOnly,
+                &entries));
+
+            // Creation triggers (e.g. `C /sessions/.*/remote == true`)
+            for name in ENTRIES.iter() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Create,
+                    &self.name,
+                    &format!("{}/{}", data.id, name),
+                    "",
+                    &data.value(name));
+            }
+        }
+    }
+}
+
+impl module::Data for SessionsBackend {
+    /// Update sessions data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let kind = match self.first_update {
+            true => triggers::Kind::Create,
+            false => triggers::Kind::Update,
+        };
+
+        self.first_update = false;
+
+        let sessions = SessionsBackend::sessions()?;
+
+        // Active count
+        let active_count = format!("{}", sessions.len());
+
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_ACTIVE_COUNT,
+            &mut self.data.active_count,
+            &active_count);
+
+        // Check if the list of sessions has changed
+        let changed = sessions.len() != self.data.sessions.len() ||
+            sessions
+                .iter()
+                .zip(self.data.sessions.iter())
+                .any(|(a, b)| a.id != b.id);
+
+        if changed {
+            self.rebuild_filesystem(sessions);
+
+            return Ok(module::Status::Changed(MODULE_NAME.to_string()));
+        }
+
+        // Update values
+        for (data, session) in self.data.sessions.iter_mut().zip(sessions) {
+            for name in ENTRIES.iter() {
+                let old_value = data.value(name);
+                let new_value = session.value(name);
+
+                if old_value == new_value {
+                    continue;
+                }
+
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Update,
+                    &self.name,
+                    &format!("{}/{}", data.id, name),
+                    &old_value,
+                    &new_value);
+            }
+
+            *data = session;
+        }
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// Sessions module structure
+pub struct Sessions {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    backend: Arc<Mutex<SessionsBackend>>,
+}
+
+impl Sessions {
+    /// Sessions constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            backend: Arc::new(Mutex::new(SessionsBackend::new(name, triggers))),
+        }
+    }
+}
+
+impl module::Module for Sessions {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        let mut entries = vec![
+            filesystem::FsEntry::new(
+                backend.inode_active_count,
+                fuse::FileType::RegularFile,
+                ENTRY_ACTIVE_COUNT,
+                filesystem::Mode::ReadOnly,
+                &Vec::new()),
+        ];
+
+        entries.extend(backend.session_fs_entries.to_vec());
+
+        return entries;
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        if inode == backend.inode_active_count {
+            return backend.data.active_count.clone();
+        }
+
+        for session_entry in backend.session_fs_entries.iter() {
+            let entry = match session_entry.fs_entries
+                .iter().find(|x| x.inode == inode) {
+
+                Some(e) => e,
+                None => continue,
+            };
+
+            // Find corresponding data
+            return match backend.data.sessions
+                .iter().find(|x| x.id == session_entry.name) {
+
+                Some(d) => d.value(&entry.name),
+                None => VALUE_UNKNOWN.to_string(),
+            };
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return format!(
+            "active_count={}",
+            backend.data.active_count).to_string();
+    }
+}
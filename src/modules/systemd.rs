@@ -0,0 +1,594 @@
+use fuse;
+use std::sync::Arc;
+
+use crate::config;
+use crate::dbus;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::publish;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "systemd";
+
+const SYSTEMD_SERVICE: &str = "org.freedesktop.systemd1";
+const SYSTEMD_PATH: &str = "/org/freedesktop/systemd1";
+const SYSTEMD_MANAGER_INTERFACE: &str = "org.freedesktop.systemd1.Manager";
+const SYSTEMD_UNIT_INTERFACE: &str = "org.freedesktop.systemd1.Unit";
+
+const ENTRY_ACTIVE_STATE: &str = "active_state";
+const ENTRY_SINCE_TIMESTAMP: &str = "since_timestamp";
+const ENTRY_SUB_STATE: &str = "sub_state";
+
+const ENTRIES: [&str; 3] =
+    [ENTRY_ACTIVE_STATE, ENTRY_SUB_STATE, ENTRY_SINCE_TIMESTAMP];
+
+const MICRO_PER_UNIT: u64 = 1_000_000;
+
+const VALUE_UNKNOWN: &str = "?";
+
+/// State of a unit as (object path, active state, sub state, since)
+type UnitState = (String, String, String, String);
+
+/// Function used to get a property of a unit
+///
+/// # Arguments
+///
+/// * `bus` - The bus of the manager of the unit
+/// * `path` - The object path of the unit
+/// * `property` - The name of the property
+fn unit_property(bus: dbus::Bus, path: &str, property: &str)
+    -> Result<serde_json::Value, error::CerebroError> {
+
+    return dbus::get_property(
+        bus,
+        SYSTEMD_SERVICE,
+        path,
+        SYSTEMD_UNIT_INTERFACE,
+        property);
+}
+
+/// Function used to get the state of a unit
+///
+/// # Arguments
+///
+/// * `bus` - The bus of the manager of the unit
+/// * `name` - The name of the unit (e.g. `backup.service`)
+fn query_unit(bus: dbus::Bus, name: &str)
+    -> Result<UnitState, error::CerebroError> {
+
+    // Loading gives the path of units that are not active
+    let path = dbus::call(
+        bus,
+        SYSTEMD_SERVICE,
+        SYSTEMD_PATH,
+        SYSTEMD_MANAGER_INTERFACE,
+        "LoadUnit",
+        "s",
+        &[name])?;
+
+    let path = match path[0].as_str() {
+        Some(p) => p.to_string(),
+        None => return error!("Invalid unit path"),
+    };
+
+    let active_state = match unit_property(bus, &path, "ActiveState")?
+        .as_str() {
+
+        Some(s) => s.to_string(),
+        None => VALUE_UNKNOWN.to_string(),
+    };
+
+    let sub_state = match unit_property(bus, &path, "SubState")?.as_str() {
+        Some(s) => s.to_string(),
+        None => VALUE_UNKNOWN.to_string(),
+    };
+
+    // Microseconds since the epoch, zero if the state has never changed
+    let since_timestamp = match unit_property(
+        bus,
+        &path,
+        "StateChangeTimestamp")?.as_u64() {
+
+        Some(t) if t > 0 => format!("{}", t / MICRO_PER_UNIT),
+        _ => VALUE_UNKNOWN.to_string(),
+    };
+
+    return Ok((path, active_state, sub_state, since_timestamp));
+}
+
+/// Unit watched by the module
+struct Unit {
+    pub name: String,
+    pub path: String,
+    pub active_state: String,
+    pub sub_state: String,
+    pub since_timestamp: String,
+    pub fs_entry: filesystem::FsEntry,
+}
+
+impl Unit {
+    /// Get the value of one of the entries
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `name` - The name of the entry
+    fn value(&self, name: &str) -> String {
+        return match name {
+            ENTRY_ACTIVE_STATE => self.active_state.clone(),
+            ENTRY_SUB_STATE => self.sub_state.clone(),
+            ENTRY_SINCE_TIMESTAMP => self.since_timestamp.clone(),
+            _ => VALUE_UNKNOWN.to_string(),
+        };
+    }
+}
+
+/// Proxy backend that is only use in the context of the thread
+struct SystemdBackendProxy {
+    backend: Arc<Mutex<SystemdBackend>>,
+}
+
+impl SystemdBackendProxy {
+    fn new(backend: Arc<Mutex<SystemdBackend>>) -> Self {
+        Self {
+            backend: backend,
+        }
+    }
+
+    /// Get the states of the units and update the values
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `bus` - The bus of the manager of the units
+    /// * `names` - The names of the units
+    fn update_units(&mut self, bus: dbus::Bus, names: &Vec<String>)
+        -> error::Return {
+
+        // Queried without holding the lock, a unit that cannot be loaded
+        // has unknown values
+        let states: Vec<Option<UnitState>> = names
+            .iter()
+            .map(|n| match query_unit(bus, n) {
+                Ok(s) => Some(s),
+                Err(e) => {
+                    log::debug!("{}: {}: {}", MODULE_NAME, n, e);
+                    None
+                },
+            })
+            .collect();
+
+        return match self.backend.lock() {
+            Ok(mut b) => {
+                b.update_values(states);
+                success!()
+            },
+
+            Err(_) => error!(Lock, "Cannot lock backend"),
+        };
+    }
+}
+
+impl module::Data for SystemdBackendProxy {
+    /// Update the units each time the manager emits a signal about them
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let (bus, names, changed) = match self.backend.lock() {
+            Ok(mut b) => {
+                let changed = b.configure();
+                (b.bus(), b.names(), changed)
+            },
+
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        };
+
+        if changed {
+            self.update_units(bus, &names)?;
+
+            return Ok(module::Status::Changed(MODULE_NAME.to_string()));
+        }
+
+        // Monitor first so that no signal is missed after the first query
+        let mut monitor = dbus::Monitor::new(bus, SYSTEMD_SERVICE)?;
+
+        let paths: Vec<String> = match self.backend.lock() {
+            Ok(b) => b.units.iter().map(|u| u.path.clone()).collect(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        };
+
+        loop {
+            self.update_units(bus, &names)?;
+
+            // Wait for a signal about one of the units
+            loop {
+                let signal = monitor.next_signal()?;
+
+                match signal["path"].as_str() {
+                    Some(p) if paths.iter().any(|x| x == p) => break,
+                    _ => continue,
+                }
+            }
+        }
+    }
+
+    /// Update systemd data once, without waiting for signals
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update_once(&mut self)
+        -> Option<Result<module::Status, error::CerebroError>> {
+
+        let (bus, names) = match self.backend.lock() {
+            Ok(b) => (b.bus(), b.names()),
+            Err(_) => return Some(error!(Lock, "Cannot lock backend")),
+        };
+
+        return Some(match self.update_units(bus, &names) {
+            Ok(_) => Ok(module::Status::Ok),
+            Err(e) => Err(e),
+        });
+    }
+}
+
+/// Systemd backend that will compute the values
+struct SystemdBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+
+    pub units: Vec<Unit>,
+}
+
+impl SystemdBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            units: Vec::new(),
+        }
+    }
+
+    /// Get the bus of the manager of the units
+    fn bus(&self) -> dbus::Bus {
+        return match &self.config.systemd {
+            Some(c) if c.user == Some(true) => dbus::Bus::Session,
+            _ => dbus::Bus::System,
+        };
+    }
+
+    /// Get the names of the units
+    fn names(&self) -> Vec<String> {
+        return self.units.iter().map(|u| u.name.clone()).collect();
+    }
+
+    /// Get the configured units sorted by name
+    fn configured(&self) -> Vec<String> {
+        let mut units = match &self.config.systemd {
+            Some(c) => c.units.to_vec(),
+            None => Vec::new(),
+        };
+
+        units.sort();
+        units.dedup();
+
+        return units;
+    }
+
+    /// Rebuild the units and the filesystem if the configuration changed
+    ///
+    /// Returns whether the filesystem has been rebuilt.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn configure(&mut self) -> bool {
+        let configured = self.configured();
+
+        let changed = self.first_update || configured != self.names();
+
+        if !changed {
+            return false;
+        }
+
+        // Deletion triggers
+        for unit in self.units.iter() {
+            for entry in ENTRIES.iter() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Delete,
+                    &self.name,
+                    &format!("{}/{}", unit.name, entry),
+                    "",
+                    "");
+            }
+        }
+
+        self.units = configured
+            .into_iter()
+            .map(|name| Unit {
+                fs_entry: filesystem::FsEntry::new(
+                    filesystem::FsEntry::create_inode(),
+                    fuse::FileType::Directory,
+                    &name,
+                    filesystem::Mode::ReadOnly,
+                    &ENTRIES
+                        .iter()
+                        .map(|e| filesystem::FsEntry::new(
+                            filesystem::FsEntry::create_inode(),
+                            fuse::FileType::RegularFile,
+                            e,
+                            filesystem::Mode::ReadOnly,
+                            &Vec::new()))
+                        .collect()),
+
+                name: name,
+                path: String::new(),
+                active_state: String::new(),
+                sub_state: String::new(),
+                since_timestamp: String::new(),
+            })
+            .collect();
+
+        self.first_update = true;
+
+        return true;
+    }
+
+    /// Update the values and call the triggers
+    ///
+    /// The triggers of a failing unit are called with the `failed` active
+    /// state.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `states` - The states of the units, in the order of the units
+    fn update_values(&mut self, states: Vec<Option<UnitState>>) {
+        let kind = match self.first_update {
+            true => triggers::Kind::Create,
+            false => triggers::Kind::Update,
+        };
+
+        self.first_update = false;
+
+        for (unit, state) in self.units.iter_mut().zip(states.into_iter()) {
+            let (path, active_state, sub_state, since_timestamp) =
+                match state {
+                    Some(s) => s,
+                    None => (
+                        unit.path.clone(),
+                        VALUE_UNKNOWN.to_string(),
+                        VALUE_UNKNOWN.to_string(),
+                        VALUE_UNKNOWN.to_string()),
+                };
+
+            unit.path = path;
+
+            let values = [
+                (ENTRY_ACTIVE_STATE, &mut unit.active_state, active_state),
+                (ENTRY_SUB_STATE, &mut unit.sub_state, sub_state),
+                (
+                    ENTRY_SINCE_TIMESTAMP,
+                    &mut unit.since_timestamp,
+                    since_timestamp,
+                ),
+            ];
+
+            for (name, current, value) in values {
+                publish::update(
+                    &self.triggers,
+                    kind,
+                    &self.name,
+                    &format!("{}/{}", unit.name, name),
+                    current,
+                    &value);
+            }
+        }
+    }
+}
+
+/// Systemd module structure
+pub struct Systemd {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    backend: Arc<Mutex<SystemdBackend>>,
+    backend_proxy: Arc<Mutex<SystemdBackendProxy>>,
+}
+
+impl Systemd {
+    /// Systemd constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        let backend = Arc::new(Mutex::new(SystemdBackend::new(name, triggers)));
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            backend: backend.clone(),
+            backend_proxy: Arc::new(Mutex::new(
+                SystemdBackendProxy::new(backend.clone()))),
+        }
+    }
+}
+
+impl module::Module for Systemd {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend_proxy.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        return backend.units.iter().map(|u| u.fs_entry.clone()).collect();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        for unit in backend.units.iter() {
+            match unit.fs_entry.find(inode) {
+                Some(e) => return unit.value(&e.name),
+                None => continue,
+            }
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let values: serde_json::Map<String, serde_json::Value> = backend
+            .units
+            .iter()
+            .map(|u| (
+                u.name.clone(),
+                serde_json::Value::Object(ENTRIES
+                    .iter()
+                    .map(|e| (e.to_string(), u.value(e).into()))
+                    .collect())))
+            .collect();
+
+        return match serde_json::to_string(&values) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let mut values = Vec::new();
+
+        for unit in backend.units.iter() {
+            let name: String = unit.name
+                .chars()
+                .map(|c| match c.is_ascii_alphanumeric() {
+                    true => c,
+                    false => '_',
+                })
+                .collect();
+
+            for entry in ENTRIES.iter() {
+                values.push(
+                    format!("{}_{}={}", name, entry, unit.value(entry)));
+            }
+        }
+
+        return values.join(" ");
+    }
+}
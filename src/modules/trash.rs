@@ -5,7 +5,7 @@ use serde::{Serialize};
 use std::fs;
 use std::io;
 use std::path;
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use std::sync::mpsc;
 use walkdir;
 
@@ -14,6 +14,7 @@ use crate::error;
 use crate::event_manager;
 use crate::filesystem;
 use crate::modules::module;
+use crate::sync::Mutex;
 use crate::triggers;
 
 const MODULE_NAME: &str = "trash";
@@ -73,7 +74,7 @@ impl TrashBackendProxy {
         // Lock backend
         let mut backend = match self.backend.lock() {
             Ok(b) => b,
-            Err(_) => return error!("Cannot lock backend"),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
         };
 
         if count != backend.data.count {
@@ -87,7 +88,7 @@ impl TrashBackendProxy {
                 triggers::find_all_and_execute(
                     &backend.triggers,
                     triggers::Kind::Update,
-                    MODULE_NAME,
+                    &backend.name,
                     ENTRY_COUNT,
                     &old_value,
                     &backend.data.count);
@@ -118,7 +119,9 @@ impl module::Data for TrashBackendProxy {
         // Create watcher
         let (tx, rx) = mpsc::channel();
 
-        let mut w: notify::INotifyWatcher = match notify::Watcher::new_raw(tx) {
+        let mut w: notify::RecommendedWatcher =
+            match notify::Watcher::new_raw(tx) {
+
             Ok(w) => w,
             Err(_) => return error!("Cannot create filesystem watcher"),
         };
@@ -133,7 +136,7 @@ impl module::Data for TrashBackendProxy {
         self.update_count()?;
 
         loop {
-            let event = match rx.recv() {
+            let event = match module::recv(&rx) {
                 Ok(e) => e,
                 Err(_) => return error!("Error during watching filesystem"),
             };
@@ -151,18 +154,34 @@ impl module::Data for TrashBackendProxy {
             self.update_count()?;
         }
     }
+
+    /// Update trash data once, without watching the trash directory
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update_once(&mut self)
+        -> Option<Result<module::Status, error::CerebroError>> {
+
+        return match self.update_count() {
+            Ok(_) => Some(Ok(module::Status::Ok)),
+            Err(e) => Some(Err(e)),
+        };
+    }
 }
 
 /// Trash backend that will compute the values
 struct TrashBackend {
+    name: String,
     triggers: Vec<triggers::Trigger>,
 
     pub data: TrashData,
 }
 
 impl TrashBackend {
-    fn new(triggers: &Vec<triggers::Trigger>) -> Self {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
         Self {
+            name: name.to_string(),
             triggers: triggers.to_vec(),
             data: TrashData::new(),
         }
@@ -171,6 +190,7 @@ impl TrashBackend {
 
 /// Trash module structure
 pub struct Trash {
+    name: String,
     thread: Arc<Mutex<module::Thread>>,
     inode_count: u64,
     inode_empty: u64,
@@ -182,16 +202,18 @@ pub struct Trash {
 impl Trash {
     /// Trash constructor
     pub fn new(
+        name: &str,
         event_manager: &mut event_manager::EventManager,
         triggers: &Vec<triggers::Trigger>) -> Self {
 
         let count = filesystem::FsEntry::create_inode();
         let empty = filesystem::FsEntry::create_inode();
-        let backend = Arc::new(Mutex::new(TrashBackend::new(triggers)));
+        let backend = Arc::new(Mutex::new(TrashBackend::new(name, triggers)));
 
         Self {
+            name: name.to_string(),
             thread: Arc::new(Mutex::new(
-                module::Thread::new(event_manager.sender()))),
+                module::Thread::new(name, event_manager.sender()))),
 
             inode_count: count,
             inode_empty: empty,
@@ -240,7 +262,7 @@ impl module::Module for Trash {
     ///
     /// * `self` - The instance handle
     fn name(&self) -> &str {
-        return MODULE_NAME;
+        return &self.name;
     }
 
     /// Start the module
@@ -251,10 +273,10 @@ impl module::Module for Trash {
     fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
         let mut thread = match self.thread.lock() {
             Ok(t) => t,
-            Err(_) => return error!("Cannot lock thread"),
+            Err(_) => return error!(Lock, "Cannot lock thread"),
         };
 
-        thread.start(self.backend_proxy.clone(), config.timeout_s)?;
+        thread.start(self.backend_proxy.clone(), config)?;
 
         return success!();
     }
@@ -267,7 +289,7 @@ impl module::Module for Trash {
     fn stop(&mut self) -> error::Return {
         let mut thread = match self.thread.lock() {
             Ok(t) => t,
-            Err(_) => return error!("Cannot lock thread"),
+            Err(_) => return error!(Lock, "Cannot lock thread"),
         };
 
         thread.stop()?;
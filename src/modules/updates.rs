@@ -0,0 +1,449 @@
+use fuse;
+use serde::Serialize;
+use std::env;
+use std::process;
+use std::sync::Arc;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::publish;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const ENTRY_COUNT: &str = "count";
+const ENTRY_LIST: &str = "list";
+
+const BACKEND_APT: &str = "apt";
+const BACKEND_DNF: &str = "dnf";
+const BACKEND_NIX: &str = "nix";
+const BACKEND_PACMAN: &str = "pacman";
+
+/// Commands listing the pending updates of each backend, in the order they
+/// are looked for when no backend is configured
+const BACKENDS: [(&str, &[&str]); 4] = [
+    (BACKEND_APT, &["apt", "list", "--upgradable"]),
+    // Provided by pacman-contrib, it doesn't need root
+    (BACKEND_PACMAN, &["checkupdates"]),
+    (BACKEND_DNF, &["dnf", "check-update", "--quiet"]),
+    (BACKEND_NIX, &["nix-env", "--upgrade", "--dry-run"]),
+];
+
+/// Exit code of dnf when there are updates
+const DNF_UPDATES_AVAILABLE: i32 = 100;
+
+/// Exit code of checkupdates when there are no updates
+const PACMAN_NO_UPDATES: i32 = 2;
+
+const VALUE_UNKNOWN: &str = "?";
+
+/// Function used to check whether a program is installed
+///
+/// # Arguments
+///
+/// * `program` - The name of the program
+fn installed(program: &str) -> bool {
+    let path = match env::var_os("PATH") {
+        Some(p) => p,
+        None => return false,
+    };
+
+    return env::split_paths(&path).any(|d| d.join(program).is_file());
+}
+
+/// Function used to get the name of the package of a line of output
+///
+/// # Arguments
+///
+/// * `backend` - The name of the backend, `None` for a custom command
+/// * `line` - The line printed by the command
+fn package(backend: Option<&str>, line: &str) -> Option<String> {
+    let line = line.trim();
+
+    let name = match backend {
+        // `name/suite version arch [upgradable from: version]`
+        Some(BACKEND_APT) => match line.find('/') {
+            Some(i) => &line[..i],
+            None => return None,
+        },
+
+        // `name.arch version repository`
+        Some(BACKEND_DNF) => {
+            let word = line.split_whitespace().next()?;
+
+            // Obsoleted packages are listed after a title
+            if word.ends_with(':') || !line.contains(' ') {
+                return None;
+            }
+
+            match word.rfind('.') {
+                Some(i) => &word[..i],
+                None => word,
+            }
+        },
+
+        // `upgrading 'name-version' to 'name-version'`
+        Some(BACKEND_NIX) => line
+            .strip_prefix("upgrading '")?
+            .split('\'')
+            .next()?,
+
+        // `name version -> version` or one name per line
+        _ => line.split_whitespace().next()?,
+    };
+
+    return match name.is_empty() {
+        true => None,
+        false => Some(name.to_string()),
+    };
+}
+
+/// Information about the pending updates
+#[derive(Serialize)]
+struct UpdatesData {
+    pub count: String,
+    pub list: String,
+}
+
+impl UpdatesData {
+    /// UpdatesData constructor
+    pub fn new() -> Self {
+        Self {
+            count: VALUE_UNKNOWN.to_string(),
+            list: VALUE_UNKNOWN.to_string(),
+        }
+    }
+}
+
+/// Updates backend that will compute the values
+struct UpdatesBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+
+    pub data: UpdatesData,
+}
+
+impl UpdatesBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            data: UpdatesData::new(),
+        }
+    }
+
+    /// Get the backend and the command listing the pending updates
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn command(&self)
+        -> Result<(Option<&'static str>, Vec<String>), error::CerebroError> {
+
+        // Custom command
+        match &self.config.updates {
+            Some(config::UpdatesConfig { command: Some(c) }) => {
+                return match shellwords::split(c) {
+                    Ok(w) if !w.is_empty() => Ok((None, w)),
+                    Ok(_) => error!(Config, "Empty updates command"),
+                    Err(e) => error!(
+                        Config,
+                        &format!("Cannot split command: {:?}", e)),
+                };
+            },
+
+            _ => (),
+        }
+
+        let backend = match &self.config.backend {
+            Some(b) => match BACKENDS.iter().find(|(n, _)| n == b) {
+                Some(b) => b,
+                None => return error!(
+                    Config,
+                    &format!("Unknown updates backend `{}`", b)),
+            },
+
+            None => match BACKENDS.iter().find(|(_, c)| installed(c[0])) {
+                Some(b) => b,
+                None => return error!("No package manager found"),
+            },
+        };
+
+        return Ok((
+            Some(backend.0),
+            backend.1.iter().map(|a| a.to_string()).collect()));
+    }
+
+    /// Get the names of the packages that can be updated
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn query(&self) -> Result<Vec<String>, error::CerebroError> {
+        let (backend, mut command) = self.command()?;
+
+        let args = command.split_off(1);
+
+        let output = match process::Command::new(&command[0])
+            .args(args)
+            .output() {
+
+            Ok(o) => o,
+            Err(e) =>
+                return error!(&format!("Cannot execute command: {:?}", e)),
+        };
+
+        let success = output.status.success() ||
+            (backend == Some(BACKEND_DNF) &&
+                output.status.code() == Some(DNF_UPDATES_AVAILABLE)) ||
+            (backend == Some(BACKEND_PACMAN) &&
+                output.status.code() == Some(PACMAN_NO_UPDATES));
+
+        if !success {
+            return error!("Cannot list the pending updates");
+        }
+
+        // nix-env prints the upgrades on the error output
+        let text = match backend {
+            Some(BACKEND_NIX) => String::from_utf8_lossy(&output.stderr),
+            _ => String::from_utf8_lossy(&output.stdout),
+        };
+
+        let mut packages: Vec<String> = text
+            .lines()
+            .filter_map(|l| package(backend, l))
+            .collect();
+
+        packages.sort();
+        packages.dedup();
+
+        return Ok(packages);
+    }
+}
+
+impl module::Data for UpdatesBackend {
+    /// Update the pending updates
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let packages = self.query()?;
+
+        let kind = match self.first_update {
+            true => triggers::Kind::Create,
+            false => triggers::Kind::Update,
+        };
+
+        self.first_update = false;
+
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_COUNT,
+            &mut self.data.count,
+            &format!("{}", packages.len()));
+
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_LIST,
+            &mut self.data.list,
+            &packages.join("\n"));
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// Updates module structure
+pub struct Updates {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    inode_count: u64,
+    inode_list: u64,
+    backend: Arc<Mutex<UpdatesBackend>>,
+    fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl Updates {
+    /// Updates constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        let count = filesystem::FsEntry::create_inode();
+        let list = filesystem::FsEntry::create_inode();
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            inode_count: count,
+            inode_list: list,
+            backend: Arc::new(Mutex::new(UpdatesBackend::new(name, triggers))),
+            fs_entries: vec![
+                filesystem::FsEntry::new(
+                    count,
+                    fuse::FileType::RegularFile,
+                    ENTRY_COUNT,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    list,
+                    fuse::FileType::RegularFile,
+                    ENTRY_LIST,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+            ],
+        }
+    }
+}
+
+impl module::Module for Updates {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        return self.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        if inode == self.inode_count {
+            return backend.data.count.clone();
+        }
+
+        if inode == self.inode_list {
+            return backend.data.list.clone();
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return format!(
+            "count={} list={:?}",
+            backend.data.count,
+            backend.data.list.replace('\n', " "));
+    }
+}
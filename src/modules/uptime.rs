@@ -0,0 +1,344 @@
+use fuse;
+use serde::Serialize;
+use std::fs;
+use std::sync::Arc;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::publish;
+use crate::sync::Mutex;
+use crate::triggers;
+use crate::units;
+
+const ENTRY_BOOT_TIMESTAMP: &str = "boot_timestamp";
+const ENTRY_PRETTY: &str = "pretty";
+const ENTRY_SECONDS: &str = "seconds";
+
+const PROC_STAT: &str = "/proc/stat";
+const PROC_UPTIME: &str = "/proc/uptime";
+
+const VALUE_UNKNOWN: &str = "?";
+
+/// Function used to format a duration as days, hours and minutes
+///
+/// The leading units that are zero are omitted (e.g. `4h 12m`).
+///
+/// # Arguments
+///
+/// * `seconds` - The duration in seconds
+fn pretty(seconds: u64) -> String {
+    let days = seconds / 86400;
+    let hours = seconds % 86400 / 3600;
+    let minutes = seconds % 3600 / 60;
+
+    return match (days, hours) {
+        (0, 0) => format!("{}m", minutes),
+        (0, _) => format!("{}h {}m", hours, minutes),
+        _ => format!("{}d {}h {}m", days, hours, minutes),
+    };
+}
+
+/// Information about the uptime of the system
+#[derive(Serialize)]
+struct UptimeData {
+    pub seconds: String,
+    pub pretty: String,
+    pub boot_timestamp: String,
+}
+
+impl UptimeData {
+    /// UptimeData constructor
+    pub fn new() -> Self {
+        Self {
+            seconds: VALUE_UNKNOWN.to_string(),
+            pretty: VALUE_UNKNOWN.to_string(),
+            boot_timestamp: VALUE_UNKNOWN.to_string(),
+        }
+    }
+}
+
+/// Uptime backend that will compute the values
+struct UptimeBackend {
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+
+    pub data: UptimeData,
+}
+
+impl UptimeBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            data: UptimeData::new(),
+        }
+    }
+
+    /// Get the number of seconds since the boot
+    fn query_seconds() -> Option<u64> {
+        let content = fs::read_to_string(PROC_UPTIME).ok()?;
+        let seconds = content.split_whitespace().next()?.parse::<f64>().ok()?;
+
+        return Some(seconds as u64);
+    }
+
+    /// Get the number of seconds since the epoch of the boot
+    fn query_boot_timestamp() -> Option<u64> {
+        let content = fs::read_to_string(PROC_STAT).ok()?;
+
+        return content
+            .lines()
+            .find_map(|l| l.strip_prefix("btime "))?
+            .trim()
+            .parse::<u64>()
+            .ok();
+    }
+}
+
+impl module::Data for UptimeBackend {
+    /// Update uptime data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let seconds = match UptimeBackend::query_seconds() {
+            Some(s) => s,
+            None => return error!("Cannot read uptime"),
+        };
+
+        let boot_timestamp = match UptimeBackend::query_boot_timestamp() {
+            Some(b) => format!("{}", b),
+            None => VALUE_UNKNOWN.to_string(),
+        };
+
+        let kind = match self.first_update {
+            true => triggers::Kind::Create,
+            false => triggers::Kind::Update,
+        };
+
+        self.first_update = false;
+
+        let values = [
+            (ENTRY_SECONDS, &mut self.data.seconds, format!("{}", seconds)),
+            (ENTRY_PRETTY, &mut self.data.pretty, pretty(seconds)),
+            (
+                ENTRY_BOOT_TIMESTAMP,
+                &mut self.data.boot_timestamp,
+                boot_timestamp,
+            ),
+        ];
+
+        for (name, current, value) in values {
+            publish::update(
+                &self.triggers,
+                kind,
+                &self.name,
+                name,
+                current,
+                &value);
+        }
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// Uptime module structure
+pub struct Uptime {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    inode_boot_timestamp: u64,
+    inode_pretty: u64,
+    inode_seconds: u64,
+    backend: Arc<Mutex<UptimeBackend>>,
+    fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl Uptime {
+    /// Uptime constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        let boot_timestamp = filesystem::FsEntry::create_inode();
+        let pretty = filesystem::FsEntry::create_inode();
+        let seconds = filesystem::FsEntry::create_inode();
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            inode_boot_timestamp: boot_timestamp,
+            inode_pretty: pretty,
+            inode_seconds: seconds,
+            backend: Arc::new(Mutex::new(UptimeBackend::new(name, triggers))),
+            fs_entries: vec![
+                filesystem::FsEntry::new(
+                    seconds,
+                    fuse::FileType::RegularFile,
+                    ENTRY_SECONDS,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()).with_unit(units::Unit::Seconds),
+
+                filesystem::FsEntry::new(
+                    pretty,
+                    fuse::FileType::RegularFile,
+                    ENTRY_PRETTY,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+
+                filesystem::FsEntry::new(
+                    boot_timestamp,
+                    fuse::FileType::RegularFile,
+                    ENTRY_BOOT_TIMESTAMP,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()),
+            ],
+        }
+    }
+}
+
+impl module::Module for Uptime {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        return self.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        if inode == self.inode_seconds {
+            return backend.data.seconds.clone();
+        }
+
+        if inode == self.inode_pretty {
+            return backend.data.pretty.clone();
+        }
+
+        if inode == self.inode_boot_timestamp {
+            return backend.data.boot_timestamp.clone();
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return format!(
+            "seconds={} pretty={:?} boot_timestamp={}",
+            backend.data.seconds,
+            backend.data.pretty,
+            backend.data.boot_timestamp);
+    }
+}
@@ -0,0 +1,561 @@
+use fuse;
+use regex::Regex;
+use std::fs;
+use std::path;
+use std::sync::Arc;
+use std::time::Instant;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::publish;
+use crate::sync::Mutex;
+use crate::triggers;
+use crate::units;
+
+const MODULE_NAME: &str = "watchdog";
+
+const ENTRY_CPU_PERCENT: &str = "cpu_percent";
+const ENTRY_MEM_RSS: &str = "mem_rss";
+const ENTRY_PID: &str = "pid";
+const ENTRY_RUNNING: &str = "running";
+
+const ENTRIES: [&str; 4] = [
+    ENTRY_RUNNING,
+    ENTRY_PID,
+    ENTRY_CPU_PERCENT,
+    ENTRY_MEM_RSS,
+];
+
+const PROC_PATH: &str = "/proc";
+
+const VALUE_FALSE: &str = "false";
+const VALUE_TRUE: &str = "true";
+const VALUE_UNKNOWN: &str = "?";
+
+/// Process running on the system
+pub struct Process {
+    pub pid: u64,
+    pub name: String,
+    pub command: String,
+}
+
+/// Get the list of the processes of the system sorted by PID
+///
+/// Returns an empty list if there's no procfs.
+pub fn query_processes() -> Vec<Process> {
+    let entries = match fs::read_dir(PROC_PATH) {
+        Ok(e) => e,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut processes: Vec<Process> = entries
+        .filter_map(|e| e.ok())
+        .filter_map(|e| {
+            let pid = e.file_name().to_string_lossy().parse::<u64>().ok()?;
+            let path = e.path();
+
+            let name = fs::read_to_string(path.join("comm")).ok()?;
+
+            // Arguments are separated by NUL characters
+            let command = match fs::read(path.join("cmdline")) {
+                Ok(c) => String::from_utf8_lossy(&c)
+                    .split('\0')
+                    .filter(|a| !a.is_empty())
+                    .collect::<Vec<&str>>()
+                    .join(" "),
+
+                Err(_) => String::new(),
+            };
+
+            return Some(Process {
+                pid: pid,
+                name: name.trim().to_string(),
+                command: command,
+            });
+        })
+        .collect();
+
+    processes.sort_by_key(|p| p.pid);
+
+    return processes;
+}
+
+/// Get the CPU time (in clock ticks) and the resident memory (in bytes) of
+/// a process
+///
+/// # Arguments
+///
+/// * `pid` - The PID of the process
+pub fn query_usage(pid: u64) -> Option<(u64, u64)> {
+    let path = path::Path::new(PROC_PATH).join(format!("{}", pid));
+
+    // The name may contain spaces, fields are read after its parenthesis
+    let stat = fs::read_to_string(path.join("stat")).ok()?;
+    let fields: Vec<&str> = stat
+        .get(stat.rfind(')')? + 1..)?
+        .split_whitespace()
+        .collect();
+
+    // utime and stime are the 14th and 15th fields of the line
+    let ticks = fields.get(11)?.parse::<u64>().ok()? +
+        fields.get(12)?.parse::<u64>().ok()?;
+
+    // The resident set size is the second field, in pages
+    let statm = fs::read_to_string(path.join("statm")).ok()?;
+    let pages = statm.split_whitespace().nth(1)?.parse::<u64>().ok()?;
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as u64;
+
+    return Some((ticks, pages * page_size));
+}
+
+/// Process watched by the module
+struct Watch {
+    pub name: String,
+    pub text: String,
+    pub pattern: Option<Regex>,
+    pub running: String,
+    pub pid: String,
+    pub cpu_percent: String,
+    pub mem_rss: String,
+    pub fs_entry: filesystem::FsEntry,
+
+    /// PID, CPU ticks and time of the previous measure
+    last_usage: Option<(u64, u64, Instant)>,
+}
+
+impl Watch {
+    /// Get the value of one of the entries
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `name` - The name of the entry
+    fn value(&self, name: &str) -> String {
+        return match name {
+            ENTRY_RUNNING => self.running.clone(),
+            ENTRY_PID => self.pid.clone(),
+            ENTRY_CPU_PERCENT => self.cpu_percent.clone(),
+            ENTRY_MEM_RSS => self.mem_rss.clone(),
+            _ => VALUE_UNKNOWN.to_string(),
+        };
+    }
+}
+
+/// Watchdog backend that will compute the values
+struct WatchdogBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    clock_ticks: u64,
+
+    pub watches: Vec<Watch>,
+}
+
+impl WatchdogBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            clock_ticks: unsafe { libc::sysconf(libc::_SC_CLK_TCK) } as u64,
+            watches: Vec::new(),
+        }
+    }
+
+    /// Get the configured watches sorted by name
+    fn configured(&self) -> Vec<(String, String)> {
+        let mut watches: Vec<(String, String)> = match &self.config.watchdog {
+            Some(c) => match &c.watches {
+                Some(w) => w.iter()
+                    .map(|(n, p)| (n.clone(), p.clone()))
+                    .collect(),
+
+                None => Vec::new(),
+            },
+
+            None => Vec::new(),
+        };
+
+        watches.sort();
+
+        return watches;
+    }
+
+    /// Rebuild the watches and the filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `configured` - The configured watches
+    fn rebuild_filesystem(&mut self, configured: Vec<(String, String)>) {
+        // Deletion triggers
+        for watch in self.watches.iter() {
+            for entry in ENTRIES.iter() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Delete,
+                    &self.name,
+                    &format!("{}/{}", watch.name, entry),
+                    "",
+                    "");
+            }
+        }
+
+        self.watches.clear();
+
+        for (name, text) in configured {
+            let pattern = match Regex::new(&text) {
+                Ok(r) => Some(r),
+                Err(_) => {
+                    log::error!("Invalid pattern for watch {}", name);
+                    None
+                },
+            };
+
+            let fs_entry = filesystem::FsEntry::new(
+                filesystem::FsEntry::create_inode(),
+                fuse::FileType::Directory,
+                &name,
+                filesystem::Mode::ReadOnly,
+                &ENTRIES
+                    .iter()
+                    .map(|e| {
+                        let entry = filesystem::FsEntry::new(
+                            filesystem::FsEntry::create_inode(),
+                            fuse::FileType::RegularFile,
+                            e,
+                            filesystem::Mode::ReadOnly,
+                            &Vec::new());
+
+                        return match *e {
+                            ENTRY_CPU_PERCENT =>
+                                entry.with_unit(units::Unit::Percent),
+
+                            ENTRY_MEM_RSS =>
+                                entry.with_unit(units::Unit::Bytes),
+
+                            _ => entry,
+                        };
+                    })
+                    .collect());
+
+            self.watches.push(Watch {
+                name: name,
+                text: text,
+                pattern: pattern,
+                running: String::new(),
+                pid: String::new(),
+                cpu_percent: String::new(),
+                mem_rss: String::new(),
+                fs_entry: fs_entry,
+                last_usage: None,
+            });
+        }
+    }
+
+    /// Find the watched processes and call the triggers
+    ///
+    /// The oldest process (lowest PID) matching the pattern of a watch on
+    /// its name or on its command line is the watched one.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `kind` - The kind of trigger to be called
+    fn evaluate(&mut self, kind: triggers::Kind) {
+        let processes = query_processes();
+        let clock_ticks = self.clock_ticks;
+
+        for watch in self.watches.iter_mut() {
+            let process = match &watch.pattern {
+                Some(r) => processes
+                    .iter()
+                    .find(|p| r.is_match(&p.name) || r.is_match(&p.command)),
+
+                None => None,
+            };
+
+            let usage = match process {
+                Some(p) => match query_usage(p.pid) {
+                    Some((ticks, rss)) => Some((p.pid, ticks, rss)),
+                    None => None,
+                },
+
+                None => None,
+            };
+
+            let (running, pid, cpu_percent, mem_rss) = match usage {
+                Some((pid, ticks, rss)) => {
+                    let now = Instant::now();
+
+                    // Usage since the previous measure of the same process
+                    let cpu_percent = match watch.last_usage {
+                        Some((p, t, i)) if p == pid && ticks >= t => {
+                            let elapsed = now.duration_since(i).as_secs_f64();
+
+                            match elapsed > 0.0 && clock_ticks > 0 {
+                                true => format!(
+                                    "{:.1}",
+                                    (ticks - t) as f64 / clock_ticks as f64 /
+                                        elapsed * 100.0),
+
+                                false => VALUE_UNKNOWN.to_string(),
+                            }
+                        },
+
+                        _ => VALUE_UNKNOWN.to_string(),
+                    };
+
+                    watch.last_usage = Some((pid, ticks, now));
+
+                    (
+                        VALUE_TRUE.to_string(),
+                        format!("{}", pid),
+                        cpu_percent,
+                        format!("{}", rss),
+                    )
+                },
+
+                None => {
+                    watch.last_usage = None;
+
+                    (
+                        VALUE_FALSE.to_string(),
+                        VALUE_UNKNOWN.to_string(),
+                        VALUE_UNKNOWN.to_string(),
+                        VALUE_UNKNOWN.to_string(),
+                    )
+                },
+            };
+
+            let values = [
+                (ENTRY_RUNNING, &mut watch.running, running),
+                (ENTRY_PID, &mut watch.pid, pid),
+                (ENTRY_CPU_PERCENT, &mut watch.cpu_percent, cpu_percent),
+                (ENTRY_MEM_RSS, &mut watch.mem_rss, mem_rss),
+            ];
+
+            for (name, current, value) in values {
+                publish::update(
+                    &self.triggers,
+                    kind,
+                    &self.name,
+                    &format!("{}/{}", watch.name, name),
+                    current,
+                    &value);
+            }
+        }
+    }
+}
+
+impl module::Data for WatchdogBackend {
+    /// Update watchdog data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let configured = self.configured();
+
+        // Check if the configured watches have changed
+        let changed = configured.len() != self.watches.len() ||
+            configured
+                .iter()
+                .zip(self.watches.iter())
+                .any(|((n, p), w)| *n != w.name || *p != w.text);
+
+        if changed {
+            self.rebuild_filesystem(configured);
+            self.evaluate(triggers::Kind::Create);
+
+            return Ok(module::Status::Changed(MODULE_NAME.to_string()));
+        }
+
+        self.evaluate(triggers::Kind::Update);
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// Watchdog module structure
+pub struct Watchdog {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    backend: Arc<Mutex<WatchdogBackend>>,
+}
+
+impl Watchdog {
+    /// Watchdog constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            backend: Arc::new(Mutex::new(WatchdogBackend::new(name, triggers))),
+        }
+    }
+}
+
+impl module::Module for Watchdog {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        return backend.watches.iter().map(|w| w.fs_entry.clone()).collect();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        for watch in backend.watches.iter() {
+            match watch.fs_entry.find(inode) {
+                Some(e) => return watch.value(&e.name),
+                None => continue,
+            }
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let values: serde_json::Map<String, serde_json::Value> = backend
+            .watches
+            .iter()
+            .map(|w| (
+                w.name.clone(),
+                serde_json::Value::Object(ENTRIES
+                    .iter()
+                    .map(|e| (e.to_string(), w.value(e).into()))
+                    .collect())))
+            .collect();
+
+        return match serde_json::to_string(&values) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return backend.watches
+            .iter()
+            .flat_map(|w| ENTRIES
+                .iter()
+                .map(move |e| format!("{}_{}={}", w.name, e, w.value(e))))
+            .collect::<Vec<String>>()
+            .join(" ");
+    }
+}
@@ -0,0 +1,511 @@
+use fuse;
+use serde::Serialize;
+use serde_json::Value;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::net;
+use crate::publish;
+use crate::secrets;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const ENTRY_CONDITION: &str = "condition";
+const ENTRY_HUMIDITY: &str = "humidity";
+const ENTRY_TEMPERATURE: &str = "temperature";
+const ENTRY_WIND: &str = "wind";
+
+const PROVIDER_OPEN_METEO: &str = "open-meteo";
+const PROVIDER_OPENWEATHERMAP: &str = "openweathermap";
+
+const OPEN_METEO_URL: &str = "https://api.open-meteo.com/v1/forecast";
+const OPENWEATHERMAP_URL: &str =
+    "https://api.openweathermap.org/data/2.5/weather";
+
+const UNITS_IMPERIAL: &str = "imperial";
+
+/// The providers are not queried more often than this
+const DEFAULT_CACHE_S: u64 = 1800;
+
+const DEFAULT_TIMEOUT_S: u64 = 10;
+
+const VALUE_UNKNOWN: &str = "?";
+
+/// Function used to get the condition of a WMO weather code, as named by
+/// OpenWeatherMap
+///
+/// # Arguments
+///
+/// * `code` - The WMO code (e.g. 61 for slight rain)
+fn wmo_condition(code: u64) -> &'static str {
+    return match code {
+        0 | 1 => "clear",
+        2 | 3 => "clouds",
+        45 | 48 => "fog",
+        51..=57 => "drizzle",
+        61..=67 | 80..=82 => "rain",
+        71..=77 | 85 | 86 => "snow",
+        95..=99 => "thunderstorm",
+        _ => VALUE_UNKNOWN,
+    };
+}
+
+/// Function used to format a number of a response
+///
+/// # Arguments
+///
+/// * `value` - The value of the response
+fn number(value: &Value) -> Option<String> {
+    return value.as_f64().map(|v| format!("{:.1}", v));
+}
+
+/// Information about the weather
+#[derive(Serialize)]
+struct WeatherData {
+    pub temperature: String,
+    pub condition: String,
+    pub humidity: String,
+    pub wind: String,
+}
+
+impl WeatherData {
+    /// WeatherData constructor
+    pub fn new() -> Self {
+        Self {
+            temperature: VALUE_UNKNOWN.to_string(),
+            condition: VALUE_UNKNOWN.to_string(),
+            humidity: VALUE_UNKNOWN.to_string(),
+            wind: VALUE_UNKNOWN.to_string(),
+        }
+    }
+}
+
+/// Weather backend that will compute the values
+struct WeatherBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+    last_fetch: Option<Instant>,
+
+    pub data: WeatherData,
+}
+
+impl WeatherBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            last_fetch: None,
+            data: WeatherData::new(),
+        }
+    }
+
+    /// Get the URL of the current weather
+    ///
+    /// Temperatures are in °C and wind speeds in m/s, or in °F and mph
+    /// with imperial units.
+    ///
+    /// # Arguments
+    ///
+    /// * `weather` - The configuration of the module
+    fn url(weather: &config::WeatherConfig)
+        -> Result<String, error::CerebroError> {
+
+        let imperial = weather.units.as_deref() == Some(UNITS_IMPERIAL);
+
+        match weather.provider.as_deref() {
+            None | Some(PROVIDER_OPEN_METEO) => {
+                let url = weather.url.as_deref().unwrap_or(OPEN_METEO_URL);
+
+                let units = match imperial {
+                    true => "&temperature_unit=fahrenheit&wind_speed_unit=mph",
+                    false => "&wind_speed_unit=ms",
+                };
+
+                return Ok(format!(
+                    "{}?latitude={}&longitude={}&current=temperature_2m,\
+                        relative_humidity_2m,weather_code,wind_speed_10m{}",
+                    url,
+                    weather.latitude,
+                    weather.longitude,
+                    units));
+            },
+
+            Some(PROVIDER_OPENWEATHERMAP) => {
+                let url = weather.url.as_deref().unwrap_or(OPENWEATHERMAP_URL);
+
+                let api_key = match &weather.api_key {
+                    Some(k) => match secrets::resolve(k) {
+                        Some(k) => k,
+                        None => return error!("Cannot resolve API key"),
+                    },
+
+                    None => return error!(
+                        Config,
+                        "An API key is needed by openweathermap"),
+                };
+
+                let units = match imperial {
+                    true => "imperial",
+                    false => "metric",
+                };
+
+                return Ok(format!(
+                    "{}?lat={}&lon={}&appid={}&units={}",
+                    url,
+                    weather.latitude,
+                    weather.longitude,
+                    api_key,
+                    units));
+            },
+
+            Some(p) => return error!(
+                Config,
+                &format!("Unknown weather provider `{}`", p)),
+        }
+    }
+
+    /// Parse the response of a provider
+    ///
+    /// # Arguments
+    ///
+    /// * `provider` - The name of the provider
+    /// * `body` - The body of the response
+    fn parse(provider: Option<&str>, body: &Value) -> Option<WeatherData> {
+        return match provider {
+            Some(PROVIDER_OPENWEATHERMAP) => Some(WeatherData {
+                temperature: number(&body["main"]["temp"])?,
+                condition: body["weather"][0]["main"]
+                    .as_str()?
+                    .to_lowercase(),
+                humidity: number(&body["main"]["humidity"])?,
+                wind: number(&body["wind"]["speed"])?,
+            }),
+
+            _ => {
+                let current = &body["current"];
+
+                Some(WeatherData {
+                    temperature: number(&current["temperature_2m"])?,
+                    condition: wmo_condition(
+                        current["weather_code"].as_u64()?).to_string(),
+                    humidity: number(&current["relative_humidity_2m"])?,
+                    wind: number(&current["wind_speed_10m"])?,
+                })
+            },
+        };
+    }
+
+    /// Fetch the current weather
+    ///
+    /// # Arguments
+    ///
+    /// * `weather` - The configuration of the module
+    fn fetch(weather: &config::WeatherConfig)
+        -> Result<WeatherData, error::CerebroError> {
+
+        let url = WeatherBackend::url(weather)?;
+        let host = net::host(&url);
+
+        if !net::acquire(&host) {
+            return error!("Weather request delayed");
+        }
+
+        // The URL may hold the API key, it's not given as an argument
+        let args = vec![
+            "--silent".to_string(),
+            "--fail".to_string(),
+            "--max-time".to_string(),
+            format!("{}", DEFAULT_TIMEOUT_S),
+        ];
+
+        let output = secrets::curl(&args, &[("url", url)]);
+
+        let body: Option<Value> = match output {
+            Ok(o) if o.status.success() =>
+                serde_json::from_slice(&o.stdout).ok(),
+            _ => None,
+        };
+
+        net::report(&host, body.is_some());
+
+        let body = match body {
+            Some(b) => b,
+            None => return error!("Cannot fetch the weather"),
+        };
+
+        return match WeatherBackend::parse(weather.provider.as_deref(), &body) {
+            Some(d) => Ok(d),
+            None => error!("Invalid weather response"),
+        };
+    }
+}
+
+impl module::Data for WeatherBackend {
+    /// Update weather data
+    ///
+    /// The previous values are kept until the cache expires or when the
+    /// provider cannot be reached.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let weather = match &self.config.weather {
+            Some(w) => w.clone(),
+            None => return error!(Config, "No weather configuration"),
+        };
+
+        let cache = Duration::from_secs(
+            weather.cache_s.unwrap_or(DEFAULT_CACHE_S));
+
+        match self.last_fetch {
+            Some(t) if t.elapsed() < cache => return Ok(module::Status::Ok),
+            _ => (),
+        }
+
+        let data = WeatherBackend::fetch(&weather)?;
+
+        self.last_fetch = Some(Instant::now());
+
+        let kind = match self.first_update {
+            true => triggers::Kind::Create,
+            false => triggers::Kind::Update,
+        };
+
+        self.first_update = false;
+
+        let values = [
+            (
+                ENTRY_TEMPERATURE,
+                &mut self.data.temperature,
+                data.temperature,
+            ),
+            (ENTRY_CONDITION, &mut self.data.condition, data.condition),
+            (ENTRY_HUMIDITY, &mut self.data.humidity, data.humidity),
+            (ENTRY_WIND, &mut self.data.wind, data.wind),
+        ];
+
+        for (name, current, value) in values {
+            publish::update(
+                &self.triggers,
+                kind,
+                &self.name,
+                name,
+                current,
+                &value);
+        }
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// Weather module structure
+pub struct Weather {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    inode_condition: u64,
+    inode_humidity: u64,
+    inode_temperature: u64,
+    inode_wind: u64,
+    backend: Arc<Mutex<WeatherBackend>>,
+    fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl Weather {
+    /// Weather constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        let condition = filesystem::FsEntry::create_inode();
+        let humidity = filesystem::FsEntry::create_inode();
+        let temperature = filesystem::FsEntry::create_inode();
+        let wind = filesystem::FsEntry::create_inode();
+
+        let entries = [
+            (temperature, ENTRY_TEMPERATURE),
+            (condition, ENTRY_CONDITION),
+            (humidity, ENTRY_HUMIDITY),
+            (wind, ENTRY_WIND),
+        ];
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            inode_condition: condition,
+            inode_humidity: humidity,
+            inode_temperature: temperature,
+            inode_wind: wind,
+            backend: Arc::new(Mutex::new(WeatherBackend::new(name, triggers))),
+            fs_entries: entries
+                .iter()
+                .map(|(inode, name)| filesystem::FsEntry::new(
+                    *inode,
+                    fuse::FileType::RegularFile,
+                    name,
+                    filesystem::Mode::ReadOnly,
+                    &Vec::new()))
+                .collect(),
+        }
+    }
+}
+
+impl module::Module for Weather {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        return self.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        if inode == self.inode_temperature {
+            return backend.data.temperature.clone();
+        }
+
+        if inode == self.inode_condition {
+            return backend.data.condition.clone();
+        }
+
+        if inode == self.inode_humidity {
+            return backend.data.humidity.clone();
+        }
+
+        if inode == self.inode_wind {
+            return backend.data.wind.clone();
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return format!(
+            "temperature={} condition={} humidity={} wind={}",
+            backend.data.temperature,
+            backend.data.condition,
+            backend.data.humidity,
+            backend.data.wind);
+    }
+}
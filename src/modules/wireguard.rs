@@ -0,0 +1,498 @@
+use fuse;
+use serde::{Serialize};
+use std::process;
+use std::sync::Arc;
+use std::time;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "wireguard";
+
+/// Age after which WireGuard itself considers a session expired
+const DEFAULT_STALE_S: u64 = 180;
+
+const VALUE_FALSE: &str = "false";
+const VALUE_TRUE: &str = "true";
+const VALUE_UNKNOWN: &str = "?";
+
+const ENTRY_ALLOWED_IPS: &str = "allowed_ips";
+const ENTRY_ENDPOINT: &str = "endpoint";
+const ENTRY_LAST_HANDSHAKE_AGE: &str = "last_handshake_age";
+const ENTRY_STALE: &str = "stale";
+const ENTRY_TRANSFER_RX: &str = "transfer_rx";
+const ENTRY_TRANSFER_TX: &str = "transfer_tx";
+
+const ENTRIES: [&str; 6] = [
+    ENTRY_ALLOWED_IPS,
+    ENTRY_ENDPOINT,
+    ENTRY_LAST_HANDSHAKE_AGE,
+    ENTRY_STALE,
+    ENTRY_TRANSFER_RX,
+    ENTRY_TRANSFER_TX,
+];
+
+/// Information about a WireGuard peer
+#[derive(Clone, Serialize)]
+struct PeerData
+{
+    pub interface: String,
+    pub peer: String,
+    pub allowed_ips: String,
+    pub endpoint: String,
+    pub last_handshake_age: String,
+    pub stale: String,
+    pub transfer_rx: String,
+    pub transfer_tx: String,
+}
+
+impl PeerData {
+    /// Get the value of an entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `name` - The name of the entry
+    fn value(&self, name: &str) -> String {
+        return match name {
+            ENTRY_ALLOWED_IPS => self.allowed_ips.clone(),
+            ENTRY_ENDPOINT => self.endpoint.clone(),
+            ENTRY_LAST_HANDSHAKE_AGE => self.last_handshake_age.clone(),
+            ENTRY_STALE => self.stale.clone(),
+            ENTRY_TRANSFER_RX => self.transfer_rx.clone(),
+            ENTRY_TRANSFER_TX => self.transfer_tx.clone(),
+            _ => VALUE_UNKNOWN.to_string(),
+        };
+    }
+
+    /// Get the path of an entry relatively to the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `name` - The name of the entry
+    fn path(&self, name: &str) -> String {
+        return format!("{}/{}/{}", self.interface, self.peer, name);
+    }
+}
+
+/// WireGuard backend that will compute the values
+struct WireguardBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+
+    pub data: Vec<PeerData>,
+    pub fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl WireguardBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            data: Vec::new(),
+            fs_entries: Vec::new(),
+        }
+    }
+
+    /// Get the handshake age after which a peer is considered as stale
+    fn stale_s(&self) -> u64 {
+        return match &self.config.wireguard {
+            Some(c) => c.stale_s.unwrap_or(DEFAULT_STALE_S),
+            None => DEFAULT_STALE_S,
+        };
+    }
+
+    /// Get the list of peers of all interfaces
+    ///
+    /// The peers are read with `wg show all dump` that queries the kernel
+    /// through the WireGuard netlink API. Public keys are base64 encoded and
+    /// may contain a `/` so they are converted to the URL-safe alphabet to be
+    /// used as directory names.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn peers(&self) -> Result<Vec<PeerData>, error::CerebroError> {
+        let output = match process::Command::new("wg")
+            .args(&["show", "all", "dump"])
+            .output() {
+
+            Ok(o) => o,
+            Err(_) => return error!("Cannot execute wg"),
+        };
+
+        if !output.status.success() {
+            return error!("Cannot get WireGuard peers");
+        }
+
+        let now = match time::SystemTime::now()
+            .duration_since(time::UNIX_EPOCH) {
+
+            Ok(d) => d.as_secs(),
+            Err(_) => return error!("Cannot get current time"),
+        };
+
+        let mut peers: Vec<PeerData> = Vec::new();
+
+        for line in String::from_utf8_lossy(&output.stdout).lines() {
+            let fields: Vec<&str> = line.split('\t').collect();
+
+            // Interface lines have 5 fields, peer lines have 9
+            if fields.len() != 9 {
+                continue;
+            }
+
+            let handshake = fields[5].parse::<u64>().unwrap_or(0);
+
+            let (age, stale) = match handshake {
+                0 => (VALUE_UNKNOWN.to_string(), VALUE_TRUE),
+                h => {
+                    let age = now.saturating_sub(h);
+
+                    (age.to_string(), match age > self.stale_s() {
+                        true => VALUE_TRUE,
+                        false => VALUE_FALSE,
+                    })
+                },
+            };
+
+            peers.push(PeerData {
+                interface: fields[0].to_string(),
+                peer: fields[1].replace("/", "_").replace("+", "-"),
+                endpoint: fields[3].to_string(),
+                allowed_ips: fields[4].to_string(),
+                last_handshake_age: age,
+                stale: stale.to_string(),
+                transfer_rx: fields[6].to_string(),
+                transfer_tx: fields[7].to_string(),
+            });
+        }
+
+        peers.sort_by(|a, b| {
+            (&a.interface, &a.peer).cmp(&(&b.interface, &b.peer))
+        });
+
+        return Ok(peers);
+    }
+
+    /// Rebuild the filesystem from a new list of peers
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `peers` - The list of peers now configured
+    fn rebuild_filesystem(&mut self, peers: Vec<PeerData>) {
+        // Deletion triggers
+        for data in self.data.iter() {
+            for name in ENTRIES.iter() {
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Delete,
+                    &self.name,
+                    &data.path(name),
+                    "",
+                    "");
+            }
+        }
+
+        self.data = peers;
+        self.fs_entries.clear();
+
+        let mut interfaces: Vec<String> =
+            self.data.iter().map(|d| d.interface.clone()).collect();
+
+        interfaces.dedup();
+
+        for interface in interfaces.iter() {
+            let mut peer_entries: Vec<filesystem::FsEntry> = Vec::new();
+
+            for data in self.data.iter().filter(|d| &d.interface == interface) {
+                let entries: Vec<filesystem::FsEntry> = ENTRIES
+                    .iter()
+                    .map(|name| filesystem::FsEntry::new(
+                        filesystem::FsEntry::create_inode(),
+                        fuse::FileType::RegularFile,
+                        name,
+                        filesystem::Mode::ReadOnly,
+                        &Vec::new()))
+                    .collect();
+
+                peer_entries.push(filesystem::FsEntry::new(
+                    filesystem::FsEntry::create_inode(),
+                    fuse::FileType::Directory,
+                    &data.peer,
+                    filesystem::Mode::ReadOnly,
+                    &entries));
+
+                // Creation triggers
+                for name in ENTRIES.iter() {
+                    triggers::find_all_and_execute(
+                        &self.triggers,
+                        triggers::Kind::Create,
+                        &self.name,
+                        &data.path(name),
+                        "",
+                        &data.value(name));
+                }
+            }
+
+            self.fs_entries.push(filesystem::FsEntry::new(
+                filesystem::FsEntry::create_inode(),
+                fuse::FileType::Directory,
+                interface,
+                filesystem::Mode::ReadOnly,
+                &peer_entries));
+        }
+    }
+}
+
+impl module::Data for WireguardBackend {
+    /// Update WireGuard data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let peers = self.peers()?;
+
+        // Check if the list of peers has changed
+        let changed = peers.len() != self.data.len() ||
+            peers
+                .iter()
+                .zip(self.data.iter())
+                .any(|(a, b)| a.interface != b.interface || a.peer != b.peer);
+
+        if changed {
+            self.rebuild_filesystem(peers);
+
+            return Ok(module::Status::Changed(MODULE_NAME.to_string()));
+        }
+
+        // Update values
+        for (data, peer) in self.data.iter_mut().zip(peers) {
+            for name in ENTRIES.iter() {
+                let old_value = data.value(name);
+                let new_value = peer.value(name);
+
+                if old_value == new_value {
+                    continue;
+                }
+
+                log::debug!(
+                    "{}: {}={}",
+                    MODULE_NAME,
+                    data.path(name),
+                    new_value);
+
+                triggers::find_all_and_execute(
+                    &self.triggers,
+                    triggers::Kind::Update,
+                    &self.name,
+                    &data.path(name),
+                    &old_value,
+                    &new_value);
+            }
+
+            *data = peer;
+        }
+
+        return Ok(module::Status::Ok);
+    }
+}
+
+/// WireGuard module structure
+pub struct Wireguard {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    backend: Arc<Mutex<WireguardBackend>>,
+}
+
+impl Wireguard {
+    /// Wireguard constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            backend:
+                Arc::new(Mutex::new(WireguardBackend::new(name, triggers))),
+        }
+    }
+}
+
+impl module::Module for Wireguard {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        return backend.fs_entries.to_vec();
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        // Find filesystem entry
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        for interface_entry in backend.fs_entries.iter() {
+            for peer_entry in interface_entry.fs_entries.iter() {
+                let entry = match peer_entry.fs_entries
+                    .iter().find(|x| x.inode == inode) {
+
+                    Some(e) => e,
+                    None => continue,
+                };
+
+                // Find corresponding data
+                return match backend.data.iter().find(|x| {
+                    x.interface == interface_entry.name &&
+                        x.peer == peer_entry.name
+                }) {
+                    Some(d) => d.value(&entry.name),
+                    None => VALUE_UNKNOWN.to_string(),
+                };
+            }
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        // Public keys are not valid shell names, peers are numbered instead
+        return backend.data
+            .iter()
+            .enumerate()
+            .map(|(i, d)| format!(
+                "{}_{}_endpoint={} {}_{}_last_handshake_age={} \
+                 {}_{}_stale={}",
+                d.interface, i, d.endpoint,
+                d.interface, i, d.last_handshake_age,
+                d.interface, i, d.stale))
+            .collect::<Vec<String>>()
+            .join(" ");
+    }
+}
@@ -0,0 +1,955 @@
+use fuse;
+use serde::{Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::env;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::net::UnixStream;
+use std::path;
+use std::process;
+use std::sync::Arc;
+
+use crate::config;
+use crate::error;
+use crate::event_manager;
+use crate::filesystem;
+use crate::modules::module;
+use crate::publish;
+use crate::sync::Mutex;
+use crate::triggers;
+
+const MODULE_NAME: &str = "wm";
+
+const BACKEND_HYPRLAND: &str = "hyprland";
+const BACKEND_I3: &str = "i3";
+const BACKEND_SWAY: &str = "sway";
+
+const I3_MAGIC: &[u8] = b"i3-ipc";
+const I3_GET_WORKSPACES: u32 = 1;
+const I3_SUBSCRIBE: u32 = 2;
+const I3_GET_TREE: u32 = 4;
+
+const VALUE_FALSE: &str = "false";
+const VALUE_TRUE: &str = "true";
+const VALUE_UNKNOWN: &str = "?";
+
+const ENTRY_FOCUSED_WINDOW_TITLE: &str = "focused_window_title";
+const ENTRY_FOCUSED_WORKSPACE: &str = "focused_workspace";
+const ENTRY_URGENT: &str = "urgent";
+const ENTRY_WORKSPACES: &str = "workspaces";
+
+/// Information about a workspace
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct WorkspaceData {
+    pub name: String,
+    pub urgent: String,
+}
+
+/// Information about the window manager
+#[derive(Serialize)]
+struct WmData
+{
+    pub focused_workspace: String,
+    pub focused_window_title: String,
+    pub workspaces: Vec<WorkspaceData>,
+}
+
+impl WmData {
+    /// WmData constructor
+    pub fn new() -> Self {
+        Self {
+            focused_workspace: VALUE_UNKNOWN.to_string(),
+            focused_window_title: VALUE_UNKNOWN.to_string(),
+            workspaces: Vec::new(),
+        }
+    }
+}
+
+/// IPC endpoint of the window manager
+#[derive(Clone, Debug)]
+enum Ipc {
+    I3(path::PathBuf),
+    Hyprland(path::PathBuf),
+}
+
+impl Ipc {
+    /// Find the IPC endpoint of the running window manager
+    ///
+    /// # Arguments
+    ///
+    /// * `backend` - The backend forced in the configuration (if any)
+    fn find(backend: Option<&str>) -> Result<Ipc, error::CerebroError> {
+        let backend = match backend {
+            Some(b) => b.to_string(),
+            None => {
+                if env::var("HYPRLAND_INSTANCE_SIGNATURE").is_ok() {
+                    BACKEND_HYPRLAND.to_string()
+                }
+                else if env::var("SWAYSOCK").is_ok() {
+                    BACKEND_SWAY.to_string()
+                }
+                else {
+                    BACKEND_I3.to_string()
+                }
+            },
+        };
+
+        return match backend.as_str() {
+            BACKEND_HYPRLAND => Ipc::hyprland(),
+            BACKEND_SWAY => match env::var("SWAYSOCK") {
+                Ok(s) => Ok(Ipc::I3(path::PathBuf::from(s))),
+                Err(_) => error!("SWAYSOCK is not set"),
+            },
+            BACKEND_I3 => Ipc::i3(),
+            _ => error!("Unknown window manager backend"),
+        };
+    }
+
+    /// Find the IPC socket of i3
+    fn i3() -> Result<Ipc, error::CerebroError> {
+        match env::var("I3SOCK") {
+            Ok(s) => return Ok(Ipc::I3(path::PathBuf::from(s))),
+            Err(_) => (),
+        }
+
+        let output = match process::Command::new("i3")
+            .arg("--get-socketpath")
+            .output() {
+
+            Ok(o) => o,
+            Err(_) => return error!("Cannot get i3 socket path"),
+        };
+
+        let socket = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+        if socket.is_empty() {
+            return error!("Cannot get i3 socket path");
+        }
+
+        return Ok(Ipc::I3(path::PathBuf::from(socket)));
+    }
+
+    /// Find the directory of the Hyprland sockets
+    fn hyprland() -> Result<Ipc, error::CerebroError> {
+        let signature = match env::var("HYPRLAND_INSTANCE_SIGNATURE") {
+            Ok(s) => s,
+            Err(_) => return error!("HYPRLAND_INSTANCE_SIGNATURE is not set"),
+        };
+
+        // Recent versions use the runtime directory, older ones use /tmp
+        match env::var("XDG_RUNTIME_DIR") {
+            Ok(d) => {
+                let dir = path::Path::new(&d).join("hypr").join(&signature);
+
+                if dir.exists() {
+                    return Ok(Ipc::Hyprland(dir));
+                }
+            },
+
+            Err(_) => (),
+        }
+
+        return Ok(Ipc::Hyprland(
+            path::Path::new("/tmp").join("hypr").join(&signature)));
+    }
+}
+
+/// Send a message on an i3 IPC socket
+///
+/// # Arguments
+///
+/// * `stream` - The socket
+/// * `kind` - The type of message
+/// * `payload` - The payload of the message
+fn i3_send(stream: &mut UnixStream, kind: u32, payload: &str)
+    -> error::Return {
+
+    let mut message: Vec<u8> = Vec::new();
+
+    message.extend_from_slice(I3_MAGIC);
+    message.extend_from_slice(&(payload.len() as u32).to_ne_bytes());
+    message.extend_from_slice(&kind.to_ne_bytes());
+    message.extend_from_slice(payload.as_bytes());
+
+    return match stream.write_all(&message) {
+        Ok(_) => success!(),
+        Err(_) => error!("Cannot write to i3 socket"),
+    };
+}
+
+/// Receive a message (reply or event) from an i3 IPC socket
+///
+/// # Arguments
+///
+/// * `stream` - The socket
+fn i3_receive(stream: &mut UnixStream)
+    -> Result<Value, error::CerebroError> {
+
+    let mut header = [0u8; 14];
+
+    match stream.read_exact(&mut header) {
+        Ok(_) => (),
+        Err(_) => return error!("Cannot read from i3 socket"),
+    }
+
+    if &header[0..6] != I3_MAGIC {
+        return error!("Invalid i3 message");
+    }
+
+    let mut length = [0u8; 4];
+    length.copy_from_slice(&header[6..10]);
+
+    let mut payload = vec![0u8; u32::from_ne_bytes(length) as usize];
+
+    match stream.read_exact(&mut payload) {
+        Ok(_) => (),
+        Err(_) => return error!("Cannot read from i3 socket"),
+    }
+
+    return match serde_json::from_slice(&payload) {
+        Ok(v) => Ok(v),
+        Err(_) => error!("Cannot parse i3 message"),
+    };
+}
+
+/// Send a request to i3 and wait for the reply
+///
+/// # Arguments
+///
+/// * `socket` - The path of the IPC socket
+/// * `kind` - The type of message
+fn i3_request(socket: &path::Path, kind: u32)
+    -> Result<Value, error::CerebroError> {
+
+    let mut stream = match UnixStream::connect(socket) {
+        Ok(s) => s,
+        Err(_) => return error!("Cannot connect to i3 socket"),
+    };
+
+    i3_send(&mut stream, kind, "")?;
+
+    return i3_receive(&mut stream);
+}
+
+/// Find the title of the focused window in an i3 tree
+///
+/// # Arguments
+///
+/// * `node` - The node of the tree to search in
+fn i3_focused_title(node: &Value) -> Option<String> {
+    if node["focused"].as_bool() == Some(true) {
+        return Some(match node["name"].as_str() {
+            Some(n) => n.to_string(),
+            None => "".to_string(),
+        });
+    }
+
+    for children in ["nodes", "floating_nodes"].iter() {
+        match node[*children].as_array() {
+            Some(nodes) => {
+                for n in nodes.iter() {
+                    match i3_focused_title(n) {
+                        Some(t) => return Some(t),
+                        None => (),
+                    }
+                }
+            },
+
+            None => (),
+        }
+    }
+
+    return None;
+}
+
+/// Send a request to Hyprland and wait for the reply
+///
+/// # Arguments
+///
+/// * `dir` - The directory of the Hyprland sockets
+/// * `request` - The request to be sent
+fn hyprland_request(dir: &path::Path, request: &str)
+    -> Result<Value, error::CerebroError> {
+
+    let mut stream = match UnixStream::connect(dir.join(".socket.sock")) {
+        Ok(s) => s,
+        Err(_) => return error!("Cannot connect to Hyprland socket"),
+    };
+
+    match stream.write_all(request.as_bytes()) {
+        Ok(_) => (),
+        Err(_) => return error!("Cannot write to Hyprland socket"),
+    }
+
+    let mut reply = String::new();
+
+    match stream.read_to_string(&mut reply) {
+        Ok(_) => (),
+        Err(_) => return error!("Cannot read from Hyprland socket"),
+    }
+
+    return match serde_json::from_str(&reply) {
+        Ok(v) => Ok(v),
+        Err(_) => error!("Cannot parse Hyprland reply"),
+    };
+}
+
+/// Stream of events sent by the window manager
+enum EventStream {
+    I3(UnixStream),
+    Hyprland(BufReader<UnixStream>),
+}
+
+impl EventStream {
+    /// Subscribe to the events of the window manager
+    ///
+    /// # Arguments
+    ///
+    /// * `ipc` - The IPC endpoint of the window manager
+    fn subscribe(ipc: &Ipc) -> Result<EventStream, error::CerebroError> {
+        return match ipc {
+            Ipc::I3(socket) => {
+                let mut stream = match UnixStream::connect(socket) {
+                    Ok(s) => s,
+                    Err(_) => return error!("Cannot connect to i3 socket"),
+                };
+
+                let events = r#"["workspace","window"]"#;
+
+                i3_send(&mut stream, I3_SUBSCRIBE, events)?;
+
+                match i3_receive(&mut stream)?["success"].as_bool() {
+                    Some(true) => Ok(EventStream::I3(stream)),
+                    _ => error!("Cannot subscribe to i3 events"),
+                }
+            },
+
+            Ipc::Hyprland(dir) => {
+                match UnixStream::connect(dir.join(".socket2.sock")) {
+                    Ok(s) => Ok(EventStream::Hyprland(BufReader::new(s))),
+                    Err(_) => error!("Cannot connect to Hyprland socket"),
+                }
+            },
+        };
+    }
+
+    /// Shut down the socket when the module is stopped
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn close_on_stop(&self) -> Result<module::OnStop, error::CerebroError> {
+        let stream = match self {
+            EventStream::I3(stream) => stream,
+            EventStream::Hyprland(reader) => reader.get_ref(),
+        };
+
+        let stream = match stream.try_clone() {
+            Ok(s) => s,
+            Err(_) => return error!("Cannot clone window manager socket"),
+        };
+
+        return Ok(module::on_stop(move || {
+            let _ = stream.shutdown(Shutdown::Both);
+        }));
+    }
+
+    /// Wait for the next event
+    ///
+    /// Returns the raw Hyprland event line (empty for i3 as the state is
+    /// fully queried again).
+    fn next(&mut self) -> Result<String, error::CerebroError> {
+        return match self {
+            EventStream::I3(stream) => {
+                i3_receive(stream)?;
+                Ok("".to_string())
+            },
+
+            EventStream::Hyprland(reader) => {
+                let mut line = String::new();
+
+                match reader.read_line(&mut line) {
+                    Ok(0) | Err(_) => error!("Hyprland event stream closed"),
+                    Ok(_) => Ok(line.trim().to_string()),
+                }
+            },
+        };
+    }
+}
+
+/// Proxy backend that is only use in the context of the thread
+struct WmBackendProxy {
+    backend: Arc<Mutex<WmBackend>>,
+    urgent_windows: HashSet<String>,
+}
+
+impl WmBackendProxy {
+    fn new(backend: Arc<Mutex<WmBackend>>) -> Self {
+        Self {
+            backend: backend,
+            urgent_windows: HashSet::new(),
+        }
+    }
+
+    /// Query the current state of the window manager
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `ipc` - The IPC endpoint of the window manager
+    fn query(&self, ipc: &Ipc)
+        -> Result<(String, String, Vec<WorkspaceData>), error::CerebroError> {
+
+        let mut focused_workspace = "".to_string();
+        let mut workspaces: Vec<WorkspaceData> = Vec::new();
+
+        match ipc {
+            Ipc::I3(socket) => {
+                let list = i3_request(socket, I3_GET_WORKSPACES)?;
+
+                for w in list.as_array().unwrap_or(&Vec::new()).iter() {
+                    let name = w["name"].as_str().unwrap_or("").to_string();
+
+                    if w["focused"].as_bool() == Some(true) {
+                        focused_workspace = name.clone();
+                    }
+
+                    workspaces.push(WorkspaceData {
+                        name: name,
+                        urgent: match w["urgent"].as_bool() {
+                            Some(true) => VALUE_TRUE.to_string(),
+                            _ => VALUE_FALSE.to_string(),
+                        },
+                    });
+                }
+
+                let tree = i3_request(socket, I3_GET_TREE)?;
+
+                let title = match i3_focused_title(&tree) {
+                    Some(t) => t,
+                    None => "".to_string(),
+                };
+
+                return Ok((focused_workspace, title, workspaces));
+            },
+
+            Ipc::Hyprland(dir) => {
+                let active = hyprland_request(dir, "j/activeworkspace")?;
+
+                focused_workspace =
+                    active["name"].as_str().unwrap_or("").to_string();
+
+                // Hyprland only reports urgency of windows through events
+                let clients = hyprland_request(dir, "j/clients")?;
+                let mut urgent_workspaces: HashSet<String> = HashSet::new();
+
+                for c in clients.as_array().unwrap_or(&Vec::new()).iter() {
+                    let address = c["address"].as_str().unwrap_or("");
+
+                    if self.urgent_windows.contains(address) {
+                        urgent_workspaces.insert(
+                            c["workspace"]["name"]
+                                .as_str()
+                                .unwrap_or("")
+                                .to_string());
+                    }
+                }
+
+                let list = hyprland_request(dir, "j/workspaces")?;
+
+                for w in list.as_array().unwrap_or(&Vec::new()).iter() {
+                    let name = w["name"].as_str().unwrap_or("").to_string();
+
+                    workspaces.push(WorkspaceData {
+                        urgent: match urgent_workspaces.contains(&name) {
+                            true => VALUE_TRUE.to_string(),
+                            false => VALUE_FALSE.to_string(),
+                        },
+                        name: name,
+                    });
+                }
+
+                workspaces.sort_by(|a, b| a.name.cmp(&b.name));
+
+                let window = hyprland_request(dir, "j/activewindow")?;
+
+                let title =
+                    window["title"].as_str().unwrap_or("").to_string();
+
+                return Ok((focused_workspace, title, workspaces));
+            },
+        }
+    }
+
+    /// Track urgent windows from Hyprland events
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `event` - The raw event line
+    fn handle_hyprland_event(&mut self, event: &str) {
+        let mut parts = event.splitn(2, ">>");
+
+        let name = parts.next().unwrap_or("");
+        let data = parts.next().unwrap_or("");
+
+        match name {
+            "urgent" => {
+                self.urgent_windows.insert(format!("0x{}", data));
+            },
+
+            "activewindowv2" | "closewindow" => {
+                self.urgent_windows.remove(&format!("0x{}", data));
+            },
+
+            _ => (),
+        }
+    }
+
+    /// Query the window manager and update the data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `ipc` - The IPC endpoint of the window manager
+    fn refresh(&mut self, ipc: &Ipc)
+        -> Result<module::Status, error::CerebroError> {
+
+        let (focused_workspace, focused_window_title, workspaces) =
+            self.query(ipc)?;
+
+        let mut backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        };
+
+        return backend.update_data(
+            focused_workspace,
+            focused_window_title,
+            workspaces);
+    }
+}
+
+impl module::Data for WmBackendProxy {
+    /// Update window manager data
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update(&mut self) -> Result<module::Status, error::CerebroError> {
+        let backend_name = match self.backend.lock() {
+            Ok(b) => b.config.backend.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        };
+
+        let ipc = Ipc::find(backend_name.as_ref().map(|b| b.as_str()))?;
+
+        // Subscribe first so that no event is missed after the first query
+        let mut events = EventStream::subscribe(&ipc)?;
+        let _stop = events.close_on_stop()?;
+
+        match self.refresh(&ipc)? {
+            module::Status::Changed(name) =>
+                return Ok(module::Status::Changed(name)),
+            _ => (),
+        }
+
+        loop {
+            let event = events.next()?;
+
+            match ipc {
+                Ipc::Hyprland(_) => self.handle_hyprland_event(&event),
+                _ => (),
+            }
+
+            match self.refresh(&ipc)? {
+                module::Status::Changed(name) =>
+                    return Ok(module::Status::Changed(name)),
+                _ => (),
+            }
+        }
+    }
+
+    /// Update window manager data once, not supported as it's event-driven
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn update_once(&mut self)
+        -> Option<Result<module::Status, error::CerebroError>> {
+
+        return None;
+    }
+}
+
+/// Window manager backend that will compute the values
+struct WmBackend {
+    config: config::ModuleConfig,
+    name: String,
+    triggers: Vec<triggers::Trigger>,
+    first_update: bool,
+
+    pub inode_focused_workspace: u64,
+    pub inode_focused_window_title: u64,
+    pub inode_workspaces: u64,
+    pub data: WmData,
+    pub workspace_fs_entries: Vec<filesystem::FsEntry>,
+}
+
+impl WmBackend {
+    fn new(name: &str, triggers: &Vec<triggers::Trigger>) -> Self {
+        Self {
+            config: config::ModuleConfig::new(),
+            name: name.to_string(),
+            triggers: triggers.to_vec(),
+            first_update: true,
+            inode_focused_workspace: filesystem::FsEntry::create_inode(),
+            inode_focused_window_title: filesystem::FsEntry::create_inode(),
+            inode_workspaces: filesystem::FsEntry::create_inode(),
+            data: WmData::new(),
+            workspace_fs_entries: Vec::new(),
+        }
+    }
+
+    /// Update the data and call the triggers
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `focused_workspace` - The name of the focused workspace
+    /// * `focused_window_title` - The title of the focused window
+    /// * `workspaces` - The list of workspaces
+    fn update_data(
+        &mut self,
+        focused_workspace: String,
+        focused_window_title: String,
+        workspaces: Vec<WorkspaceData>)
+        -> Result<module::Status, error::CerebroError> {
+
+        let kind = match self.first_update {
+            true => triggers::Kind::Create,
+            false => triggers::Kind::Update,
+        };
+
+        self.first_update = false;
+
+        // Focused workspace
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_FOCUSED_WORKSPACE,
+            &mut self.data.focused_workspace,
+            &focused_workspace);
+
+        // Focused window
+        publish::update(
+            &self.triggers,
+            kind,
+            &self.name,
+            ENTRY_FOCUSED_WINDOW_TITLE,
+            &mut self.data.focused_window_title,
+            &focused_window_title);
+
+        // Rebuild the filesystem if the list of workspaces has changed
+        let changed = workspaces.len() != self.data.workspaces.len() ||
+            workspaces
+                .iter()
+                .zip(self.data.workspaces.iter())
+                .any(|(a, b)| a.name != b.name);
+
+        if changed {
+            self.rebuild_workspaces(workspaces);
+
+            return Ok(module::Status::Changed(MODULE_NAME.to_string()));
+        }
+
+        // Urgent flags
+        for (data, workspace) in
+            self.data.workspaces.iter_mut().zip(workspaces) {
+
+            if data.urgent == workspace.urgent {
+                continue;
+            }
+
+            let old_value = data.urgent.clone();
+
+            data.urgent = workspace.urgent;
+
+            triggers::find_all_and_execute(
+                &self.triggers,
+                triggers::Kind::Update,
+                &self.name,
+                &format!("{}/{}/{}", ENTRY_WORKSPACES, data.name, ENTRY_URGENT),
+                &old_value,
+                &data.urgent);
+        }
+
+        return Ok(module::Status::Ok);
+    }
+
+    /// Rebuild the workspaces data and filesystem
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `workspaces` - The new list of workspaces
+    fn rebuild_workspaces(&mut self, workspaces: Vec<WorkspaceData>) {
+        // Deletion triggers
+        for data in self.data.workspaces.iter() {
+            triggers::find_all_and_execute(
+                &self.triggers,
+                triggers::Kind::Delete,
+                &self.name,
+                &format!("{}/{}/{}", ENTRY_WORKSPACES, data.name, ENTRY_URGENT),
+                "",
+                "");
+        }
+
+        self.data.workspaces = workspaces;
+        self.workspace_fs_entries.clear();
+
+        for data in self.data.workspaces.iter() {
+            self.workspace_fs_entries.push(filesystem::FsEntry::new(
+                filesystem::FsEntry::create_inode(),
+                fuse::FileType::Directory,
+                &data.name,
+                filesystem::Mode::ReadOnly,
+                &vec![
+                    filesystem::FsEntry::new(
+                        filesystem::FsEntry::create_inode(),
+                        fuse::FileType::RegularFile,
+                        ENTRY_URGENT,
+                        filesystem::Mode::ReadOnly,
+                        &Vec::new()),
+                ]));
+
+            // Creation triggers
+            triggers::find_all_and_execute(
+                &self.triggers,
+                triggers::Kind::Create,
+                &self.name,
+                &format!("{}/{}/{}", ENTRY_WORKSPACES, data.name, ENTRY_URGENT),
+                "",
+                &data.urgent);
+        }
+    }
+}
+
+/// Window manager module structure
+pub struct Wm {
+    name: String,
+    thread: Arc<Mutex<module::Thread>>,
+    backend: Arc<Mutex<WmBackend>>,
+    backend_proxy: Arc<Mutex<WmBackendProxy>>,
+}
+
+impl Wm {
+    /// Wm constructor
+    pub fn new(
+        name: &str,
+        event_manager: &mut event_manager::EventManager,
+        triggers: &Vec<triggers::Trigger>) -> Self {
+
+        let backend = Arc::new(Mutex::new(WmBackend::new(name, triggers)));
+
+        Self {
+            name: name.to_string(),
+            thread: Arc::new(Mutex::new(
+                module::Thread::new(name, event_manager.sender()))),
+
+            backend: backend.clone(),
+            backend_proxy:
+                Arc::new(Mutex::new(WmBackendProxy::new(backend.clone()))),
+        }
+    }
+}
+
+impl module::Module for Wm {
+    /// Get name of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn name(&self) -> &str {
+        return &self.name;
+    }
+
+    /// Start the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn start(&mut self, config: &config::ModuleConfig) -> error::Return {
+        match self.backend.lock() {
+            Ok(mut b) => b.config = config.clone(),
+            Err(_) => return error!(Lock, "Cannot lock backend"),
+        }
+
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.start(self.backend_proxy.clone(), config)?;
+
+        return success!();
+    }
+
+    /// Stop the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn stop(&mut self) -> error::Return {
+        let mut thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return error!(Lock, "Cannot lock thread"),
+        };
+
+        thread.stop()?;
+
+        return success!();
+    }
+
+    /// Check if module is running
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn is_running(&self) -> bool {
+        let thread = match self.thread.lock() {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        return thread.is_running();
+    }
+
+    /// Get filesystem entries of the module
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn fs_entries(&self) -> Vec<filesystem::FsEntry> {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return Vec::new(),
+        };
+
+        return vec![
+            filesystem::FsEntry::new(
+                backend.inode_focused_workspace,
+                fuse::FileType::RegularFile,
+                ENTRY_FOCUSED_WORKSPACE,
+                filesystem::Mode::ReadOnly,
+                &Vec::new()),
+
+            filesystem::FsEntry::new(
+                backend.inode_focused_window_title,
+                fuse::FileType::RegularFile,
+                ENTRY_FOCUSED_WINDOW_TITLE,
+                filesystem::Mode::ReadOnly,
+                &Vec::new()),
+
+            filesystem::FsEntry::new(
+                backend.inode_workspaces,
+                fuse::FileType::Directory,
+                ENTRY_WORKSPACES,
+                filesystem::Mode::ReadOnly,
+                &backend.workspace_fs_entries),
+        ];
+    }
+
+    /// Get value to be displayed for a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be fetched
+    fn value(&self, inode: u64) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        if inode == backend.inode_focused_workspace {
+            return backend.data.focused_workspace.clone();
+        }
+
+        if inode == backend.inode_focused_window_title {
+            return backend.data.focused_window_title.clone();
+        }
+
+        for workspace_entry in backend.workspace_fs_entries.iter() {
+            match workspace_entry.fs_entries.iter().find(|x| x.inode == inode) {
+                Some(_) => (),
+                None => continue,
+            }
+
+            return match backend.data.workspaces
+                .iter()
+                .find(|x| x.name == workspace_entry.name) {
+
+                Some(d) => d.urgent.clone(),
+                None => VALUE_UNKNOWN.to_string(),
+            };
+        }
+
+        return VALUE_UNKNOWN.to_string();
+    }
+
+    /// Set value of a filesystem entry
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `inode` - The inode of the filesystem to be written
+    /// * `data` - The data to be written
+    fn set_value(&mut self, _inode: u64, _data: &[u8]) {
+    }
+
+    /// Get value to be displayed for a filesystem entry (in JSON format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn json(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        return match serde_json::to_string(&backend.data) {
+            Ok(json) => json,
+            Err(_) => VALUE_UNKNOWN.to_string(),
+        }
+    }
+
+    /// Get value to be displayed for a filesystem entry (in shell format)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn shell(&self) -> String {
+        let backend = match self.backend.lock() {
+            Ok(b) => b,
+            Err(_) => return VALUE_UNKNOWN.to_string(),
+        };
+
+        let mut output = format!(
+            "focused_workspace={:?} focused_window_title={:?}",
+            backend.data.focused_workspace,
+            backend.data.focused_window_title);
+
+        for workspace in backend.data.workspaces.iter() {
+            output += &format!(
+                " workspace_{}_urgent={}",
+                workspace.name,
+                workspace.urgent);
+        }
+
+        return output;
+    }
+}
@@ -0,0 +1,112 @@
+use std::fs;
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::process;
+
+use crate::error;
+
+/// Function used to unmount a filesystem
+///
+/// A lazy unmount is used so that a stale mount whose process has died
+/// can be removed.
+fn unmount(path: &Path) -> error::Return {
+    let mut command = match cfg!(target_os = "linux") {
+        true => process::Command::new("fusermount"),
+        false => process::Command::new("umount"),
+    };
+
+    match cfg!(target_os = "linux") {
+        true => command.arg("-uz"),
+        false => command.arg("-f"),
+    };
+
+    let status = match command.arg(path).status() {
+        Ok(s) => s,
+        Err(_) => return error!("Cannot execute unmount command"),
+    };
+
+    if !status.success() {
+        return error!("Cannot unmount previous mount");
+    }
+
+    log::info!("Previous mount has been removed: {}", path.display());
+
+    return success!();
+}
+
+/// Function used to check if a directory is a mountpoint
+fn is_mounted(path: &Path, metadata: &fs::Metadata) -> bool {
+    let parent = match path.parent() {
+        Some(p) => p,
+        None => return false,
+    };
+
+    return match fs::metadata(parent) {
+        Ok(m) => m.dev() != metadata.dev(),
+        Err(_) => false,
+    };
+}
+
+/// Function used to check and prepare the mountpoint before mounting
+///
+/// # Arguments
+///
+/// * `path` - The path of the mountpoint
+/// * `force` - Mount even if the directory is not empty
+/// * `force_remount` - Unmount a previous mount found at the mountpoint
+pub fn prepare(path: &str, force: bool, force_remount: bool)
+    -> error::Return {
+
+    let path = Path::new(path);
+
+    match fs::metadata(path) {
+        // A mount whose process has died can't be accessed anymore
+        Err(e) if e.raw_os_error() == Some(libc::ENOTCONN) => {
+            if !force_remount {
+                return error!(
+                    "Stale mount found at the mountpoint, use \
+                    --force-remount to remove it");
+            }
+
+            unmount(path)?;
+        },
+
+        Err(e) if e.kind() == io::ErrorKind::NotFound => (),
+
+        Err(_) => return error!("Cannot access mountpoint"),
+
+        Ok(m) => {
+            if !m.is_dir() {
+                return error!("Mountpoint is not a directory");
+            }
+
+            if is_mounted(path, &m) {
+                if !force_remount {
+                    return error!(
+                        "A filesystem is already mounted at the mountpoint, \
+                        use --force-remount to replace it");
+                }
+
+                unmount(path)?;
+            }
+            else {
+                let empty = match fs::read_dir(path) {
+                    Ok(mut entries) => entries.next().is_none(),
+                    Err(_) => return error!("Cannot list mountpoint"),
+                };
+
+                if !empty && !force {
+                    return error!(
+                        "Mountpoint is not empty, use --force to mount over \
+                        it anyway");
+                }
+            }
+        },
+    }
+
+    return match fs::create_dir_all(path) {
+        Ok(_) => success!(),
+        Err(_) => error!("Cannot create mountpoint"),
+    };
+}
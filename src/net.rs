@@ -0,0 +1,251 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::fs;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::config;
+use crate::sync::Mutex;
+
+const DEFAULT_MIN_INTERVAL_MS: u64 = 1000;
+const DEFAULT_BACKOFF_BASE_S: u64 = 5;
+const DEFAULT_BACKOFF_MAX_S: u64 = 600;
+
+/// Routing table of the kernel, used to detect that the host is offline
+const ROUTES_PATH: &str = "/proc/net/route";
+const ROUTES_IPV6_PATH: &str = "/proc/net/ipv6_route";
+
+/// Policy applied to the requests
+struct Policy {
+    min_interval: Duration,
+    backoff_base: Duration,
+    backoff_max: Duration,
+}
+
+/// State of the requests sent to a host
+struct Host {
+    last_request: Option<Instant>,
+    failures: u32,
+    retry_at: Option<Instant>,
+}
+
+lazy_static! {
+    static ref POLICY: Mutex<Policy> = Mutex::new(Policy {
+        min_interval: Duration::from_millis(DEFAULT_MIN_INTERVAL_MS),
+        backoff_base: Duration::from_secs(DEFAULT_BACKOFF_BASE_S),
+        backoff_max: Duration::from_secs(DEFAULT_BACKOFF_MAX_S),
+    });
+
+    static ref HOSTS: Mutex<HashMap<String, Host>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Function used to get a pseudo-random number between 0 and `max`
+///
+/// This is only used to spread the retries of the hosts.
+///
+/// # Arguments
+///
+/// * `max` - The maximum value
+fn jitter(max: u64) -> u64 {
+    if max == 0 {
+        return 0;
+    }
+
+    let seed = match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(d) => d.subsec_nanos() as u64 ^ d.as_secs(),
+        Err(_) => 0,
+    };
+
+    // xorshift
+    let mut x = seed | 1;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+
+    return x % (max + 1);
+}
+
+/// Function used to configure the policy of the requests
+pub fn init(config: &config::NetConfig) {
+    match POLICY.lock() {
+        Ok(mut p) => {
+            p.min_interval = Duration::from_millis(
+                config.min_interval_ms.unwrap_or(DEFAULT_MIN_INTERVAL_MS));
+
+            p.backoff_base = Duration::from_secs(
+                config.backoff_base_s.unwrap_or(DEFAULT_BACKOFF_BASE_S));
+
+            p.backoff_max = Duration::from_secs(
+                config.backoff_max_s.unwrap_or(DEFAULT_BACKOFF_MAX_S));
+        },
+
+        Err(_) => log::error!("Cannot lock network policy"),
+    }
+}
+
+/// Function used to check if the host has a default route
+///
+/// The host is considered online when the routing table is not available.
+pub fn is_online() -> bool {
+    let routes = match fs::read_to_string(ROUTES_PATH) {
+        Ok(r) => r,
+        Err(_) => return true,
+    };
+
+    // The destination of the default route is 0.0.0.0
+    let ipv4 = routes
+        .lines()
+        .skip(1)
+        .any(|l| l.split_whitespace().nth(1) == Some("00000000"));
+
+    // The destination of the default route is ::/0, not on loopback
+    let ipv6 = match fs::read_to_string(ROUTES_IPV6_PATH) {
+        Ok(r) => r.lines().any(|l| {
+            let fields: Vec<&str> = l.split_whitespace().collect();
+
+            fields.len() >= 10 &&
+                fields[0] == "00000000000000000000000000000000" &&
+                fields[1] == "00" &&
+                fields[9] != "lo"
+        }),
+
+        Err(_) => false,
+    };
+
+    return ipv4 || ipv6;
+}
+
+/// Function used to get the host of a URL or of an address
+///
+/// # Arguments
+///
+/// * `target` - The URL (e.g. `https://example.com/api`) or the address
+///   (e.g. `example.com:22`)
+pub fn host(target: &str) -> String {
+    let target = match target.find("://") {
+        Some(i) => &target[i + 3..],
+        None => target,
+    };
+
+    let target = target.split('/').next().unwrap_or(target);
+
+    // Remove the credentials
+    let target = match target.rfind('@') {
+        Some(i) => &target[i + 1..],
+        None => target,
+    };
+
+    // Remove the port, IPv6 addresses are between brackets
+    let host = match target.rfind(':') {
+        Some(i) if !target[i..].contains(']') => &target[..i],
+        _ => target,
+    };
+
+    return host.trim_matches(&['[', ']'][..]).to_string();
+}
+
+/// Function used to check if a request can be sent to a host
+///
+/// A request is refused while the host is offline or while the host is in
+/// backoff after failures. When accepted, the function waits until the
+/// minimum interval since the previous request to the host has elapsed.
+///
+/// # Arguments
+///
+/// * `host` - The host the request is sent to
+pub fn acquire(host: &str) -> bool {
+    if !is_online() {
+        log::debug!("Offline, request to {} delayed", host);
+        return false;
+    }
+
+    let min_interval = match POLICY.lock() {
+        Ok(p) => p.min_interval,
+        Err(_) => return true,
+    };
+
+    let slot = {
+        let mut hosts = match HOSTS.lock() {
+            Ok(h) => h,
+            Err(_) => return true,
+        };
+
+        let state = hosts.entry(host.to_string()).or_insert(Host {
+            last_request: None,
+            failures: 0,
+            retry_at: None,
+        });
+
+        let now = Instant::now();
+
+        match state.retry_at {
+            Some(r) if now < r => return false,
+            _ => (),
+        }
+
+        // Reserve the next slot, the lock is released while waiting
+        let slot = match state.last_request {
+            Some(l) if l + min_interval > now => l + min_interval,
+            _ => now,
+        };
+
+        state.last_request = Some(slot);
+
+        slot
+    };
+
+    let now = Instant::now();
+
+    if slot > now {
+        thread::sleep(slot - now);
+    }
+
+    return true;
+}
+
+/// Function used to report the result of a request to a host
+///
+/// After a failure, the requests are delayed exponentially with a random
+/// jitter so that the hosts are not all retried at the same time.
+///
+/// # Arguments
+///
+/// * `host` - The host the request was sent to
+/// * `success` - Whether the request was successful
+pub fn report(host: &str, success: bool) {
+    let (base, max) = match POLICY.lock() {
+        Ok(p) => (p.backoff_base, p.backoff_max),
+        Err(_) => return,
+    };
+
+    let mut hosts = match HOSTS.lock() {
+        Ok(h) => h,
+        Err(_) => return,
+    };
+
+    let state = match hosts.get_mut(host) {
+        Some(s) => s,
+        None => return,
+    };
+
+    if success {
+        state.failures = 0;
+        state.retry_at = None;
+        return;
+    }
+
+    state.failures = state.failures.saturating_add(1);
+
+    let delay = base
+        .checked_mul(1 << state.failures.min(16).saturating_sub(1))
+        .unwrap_or(max)
+        .min(max);
+
+    let delay = delay + Duration::from_millis(
+        jitter(delay.as_millis() as u64 / 2));
+
+    log::debug!("Request to {} failed, retry in {:?}", host, delay);
+
+    state.retry_at = Some(Instant::now() + delay);
+}
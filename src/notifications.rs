@@ -0,0 +1,166 @@
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+
+use crate::bus;
+use crate::config;
+use crate::dbus;
+use crate::error;
+use crate::sounds;
+use crate::sync::Mutex;
+
+const APP_NAME: &str = "cerebro";
+
+const SERVICE: &str = "org.freedesktop.Notifications";
+const OBJECT: &str = "/org/freedesktop/Notifications";
+const INTERFACE: &str = "org.freedesktop.Notifications";
+const METHOD: &str = "Notify";
+
+/// Arguments: application, replaced id, icon, title, body, actions, hints
+/// and timeout
+const SIGNATURE: &str = "susssasa{sv}i";
+
+/// Timeout chosen by the notification server
+const DEFAULT_TIMEOUT_MS: i32 = -1;
+
+const URGENCIES: [(&str, &str); 3] =
+    [("low", "0"), ("normal", "1"), ("critical", "2")];
+
+const VALUE_UNKNOWN: &str = "?";
+
+lazy_static! {
+    static ref TEMPLATES: Mutex<HashMap<String, config::NotificationConfig>> =
+        Mutex::new(HashMap::new());
+
+    /// Identifiers of the last notification of each template
+    static ref IDS: Mutex<HashMap<String, u32>> = Mutex::new(HashMap::new());
+}
+
+/// Function used to set the templates of the notifications
+pub fn init(config: &HashMap<String, config::NotificationConfig>) {
+    match TEMPLATES.lock() {
+        Ok(mut t) => *t = config.clone(),
+        Err(_) => log::error!("Cannot lock notification templates"),
+    }
+}
+
+/// Function used to replace the placeholders of a text
+///
+/// # Arguments
+///
+/// * `text` - The text
+/// * `values` - The values of the placeholders that are not entries
+fn expand(text: &str, values: &[(&str, &str)]) -> String {
+    let re = match Regex::new(r"\{([^{}]+)\}") {
+        Ok(r) => r,
+        Err(_) => return text.to_string(),
+    };
+
+    return re
+        .replace_all(text, |c: &Captures| {
+            let name = &c[1];
+
+            match values.iter().find(|(n, _)| *n == name) {
+                Some((_, v)) => return v.to_string(),
+                None => (),
+            }
+
+            return match bus::value(name) {
+                Some(v) => v,
+                None => VALUE_UNKNOWN.to_string(),
+            };
+        })
+        .to_string();
+}
+
+/// Function used to send a notification
+///
+/// # Arguments
+///
+/// * `name` - The name of the template
+/// * `values` - The values of the placeholders that are not entries
+pub fn send(name: &str, values: &[(&str, &str)]) -> error::Return {
+    let template = match TEMPLATES.lock() {
+        Ok(t) => match t.get(name) {
+            Some(t) => t.clone(),
+            None => return error!(
+                Config,
+                &format!("Unknown notification `{}`", name)),
+        },
+
+        Err(_) => return error!(Lock, "Cannot lock notification templates"),
+    };
+
+    let urgency = match &template.urgency {
+        Some(u) => match URGENCIES.iter().find(|(n, _)| n == u) {
+            Some((_, level)) => Some(*level),
+            None => return error!(
+                Config,
+                &format!("Unknown notification urgency `{}`", u)),
+        },
+
+        None => None,
+    };
+
+    let mut ids = match IDS.lock() {
+        Ok(i) => i,
+        Err(_) => return error!(Lock, "Cannot lock notification ids"),
+    };
+
+    let replaced = match template.replace {
+        Some(true) => *ids.get(name).unwrap_or(&0),
+        _ => 0,
+    };
+
+    let replaced = format!("{}", replaced);
+    let title = expand(&template.title, values);
+
+    let body = match &template.body {
+        Some(b) => expand(b, values),
+        None => String::new(),
+    };
+
+    let timeout = format!(
+        "{}",
+        template.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+
+    let mut args = vec![
+        APP_NAME,
+        replaced.as_str(),
+        template.icon.as_deref().unwrap_or(""),
+        title.as_str(),
+        body.as_str(),
+        "0",
+    ];
+
+    match urgency {
+        Some(level) => args.extend_from_slice(&["1", "urgency", "y", level]),
+        None => args.push("0"),
+    }
+
+    args.push(timeout.as_str());
+
+    let reply = dbus::call(
+        dbus::Bus::Session,
+        SERVICE,
+        OBJECT,
+        INTERFACE,
+        METHOD,
+        SIGNATURE,
+        &args)?;
+
+    match reply[0].as_u64() {
+        Some(id) => {
+            ids.insert(name.to_string(), id as u32);
+        },
+
+        None => log::debug!("No identifier for notification `{}`", name),
+    }
+
+    match &template.sound {
+        Some(s) => sounds::play(s, None)?,
+        None => (),
+    }
+
+    return success!();
+}
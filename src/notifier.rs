@@ -0,0 +1,78 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::sync::Condvar;
+use std::time::{Duration, Instant};
+
+use crate::sync::Mutex;
+
+/// Longest wait for a change, the current value is given back afterwards so
+/// that a reader that is gone doesn't hold a thread forever
+const WAIT_TIMEOUT_S: u64 = 60;
+
+lazy_static! {
+    static ref GENERATIONS: Mutex<HashMap<String, u64>> =
+        Mutex::new(HashMap::new());
+
+    static ref CHANGED: Condvar = Condvar::new();
+}
+
+/// Function used to signal that the value of an entry has changed
+///
+/// # Arguments
+///
+/// * `path` - The path of the entry
+pub fn notify(path: &str) {
+    let mut generations = match GENERATIONS.lock() {
+        Ok(g) => g,
+        Err(_) => return,
+    };
+
+    *generations.entry(path.to_string()).or_insert(0) += 1;
+
+    CHANGED.notify_all();
+}
+
+/// Function used to get the number of changes of an entry
+///
+/// # Arguments
+///
+/// * `path` - The path of the entry
+pub fn generation(path: &str) -> u64 {
+    return match GENERATIONS.lock() {
+        Ok(g) => *g.get(path).unwrap_or(&0),
+        Err(_) => 0,
+    };
+}
+
+/// Function used to wait until the value of an entry changes
+///
+/// The generation is the one read before waiting, so that a change that
+/// happens in between is not missed. The wait gives up after a while, the
+/// reader then gets the current value.
+///
+/// # Arguments
+///
+/// * `path` - The path of the entry
+/// * `generation` - The generation of the entry before waiting
+pub fn wait(path: &str, generation: u64) {
+    let deadline = Instant::now() + Duration::from_secs(WAIT_TIMEOUT_S);
+
+    let mut generations = match GENERATIONS.lock() {
+        Ok(g) => g,
+        Err(_) => return,
+    };
+
+    while *generations.get(path).unwrap_or(&0) == generation {
+        let now = Instant::now();
+
+        if now >= deadline {
+            log::debug!("{}: no change after {} s", path, WAIT_TIMEOUT_S);
+            return;
+        }
+
+        generations = match CHANGED.wait_timeout(generations, deadline - now) {
+            Ok((g, _)) => g,
+            Err(_) => return,
+        };
+    }
+}
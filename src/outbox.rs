@@ -0,0 +1,245 @@
+use dirs;
+use lazy_static::lazy_static;
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::config;
+use crate::error;
+use crate::metrics;
+use crate::sync::Mutex;
+
+/// Name of the default directory, under the cache directory of the user
+const DEFAULT_DIR: &str = "cerebro/outbox";
+
+const DEFAULT_MAX_SIZE_KB: u64 = 1024;
+
+/// The structure used to store the state of the outbox
+struct Outbox {
+    path: PathBuf,
+    max_size: usize,
+}
+
+lazy_static! {
+    static ref OUTBOX: Mutex<Option<Outbox>> = Mutex::new(None);
+}
+
+impl Outbox {
+    /// Get the path of the file queueing the events of a sink
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `sink` - The name of the sink
+    fn sink_path(&self, sink: &str) -> PathBuf {
+        return self.path.join(format!("{}.queue", sink));
+    }
+
+    /// Read the events queued for a sink
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `sink` - The name of the sink
+    fn read(&self, sink: &str) -> Vec<String> {
+        return match fs::read_to_string(self.sink_path(sink)) {
+            Ok(c) => c.lines().map(|l| l.to_string()).collect(),
+            Err(_) => Vec::new(),
+        };
+    }
+
+    /// Replace the events queued for a sink
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `sink` - The name of the sink
+    /// * `events` - The events to be queued
+    fn write(&self, sink: &str, events: &[String]) -> error::Return {
+        let path = self.sink_path(sink);
+
+        if events.is_empty() {
+            return match fs::remove_file(&path) {
+                Ok(_) => success!(),
+                Err(_) => error!("Cannot remove outbox"),
+            };
+        }
+
+        let mut content = events.join("\n");
+        content.push('\n');
+
+        return match fs::write(&path, content) {
+            Ok(_) => success!(),
+            Err(_) => error!("Cannot write outbox"),
+        };
+    }
+
+    /// Queue an event for a sink
+    ///
+    /// The oldest events are dropped if the queue exceeds the maximum size.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `sink` - The name of the sink
+    /// * `event` - The event to be queued
+    fn push(&self, sink: &str, event: &str) -> error::Return {
+        let path = self.sink_path(sink);
+
+        let size = match fs::metadata(&path) {
+            Ok(m) => m.len() as usize,
+            Err(_) => 0,
+        };
+
+        // Fast path, the event is appended
+        if size + event.len() + 1 <= self.max_size {
+            let mut file = match fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path) {
+
+                Ok(f) => f,
+                Err(_) => return error!("Cannot open outbox"),
+            };
+
+            return match writeln!(file, "{}", event) {
+                Ok(_) => success!(),
+                Err(_) => error!("Cannot write outbox"),
+            };
+        }
+
+        let mut events = self.read(sink);
+        events.push(event.to_string());
+
+        let mut size: usize = events.iter().map(|e| e.len() + 1).sum();
+        let mut dropped = 0;
+
+        while size > self.max_size && dropped < events.len() {
+            size -= events[dropped].len() + 1;
+            dropped += 1;
+        }
+
+        if dropped > 0 {
+            log::debug!("Outbox of {} full, {} events dropped", sink, dropped);
+
+            metrics::add(
+                metrics::OUTBOX_DROPPED,
+                &[("sink", sink)],
+                dropped as f64);
+        }
+
+        return self.write(sink, &events[dropped..]);
+    }
+}
+
+/// Function used to enable the queueing of the events on disk
+pub fn init(config: &config::OutboxConfig) {
+    match config.enabled {
+        Some(true) => (),
+        _ => return,
+    }
+
+    let path = match &config.path {
+        Some(p) => PathBuf::from(p),
+        None => match dirs::cache_dir() {
+            Some(d) => d.join(DEFAULT_DIR),
+            None => {
+                log::error!("Cannot find cache directory");
+                return;
+            },
+        },
+    };
+
+    match fs::create_dir_all(&path) {
+        Ok(_) => (),
+        Err(_) => {
+            log::error!("Cannot create outbox directory");
+            return;
+        },
+    }
+
+    let max_size = config.max_size_kb.unwrap_or(DEFAULT_MAX_SIZE_KB) * 1024;
+
+    let outbox = Outbox {
+        path: path,
+        max_size: max_size as usize,
+    };
+
+    match OUTBOX.lock() {
+        Ok(mut o) => *o = Some(outbox),
+        Err(_) => log::error!("Cannot lock outbox"),
+    }
+}
+
+/// Function used to queue an event that cannot be sent to a sink
+///
+/// The event is dropped if the outbox is not enabled.
+///
+/// # Arguments
+///
+/// * `sink` - The name of the sink
+/// * `event` - The event, on a single line
+pub fn push(sink: &str, event: &str) {
+    let outbox = match OUTBOX.lock() {
+        Ok(o) => o,
+        Err(_) => return,
+    };
+
+    let result = match &*outbox {
+        Some(o) => o.push(sink, event),
+        None => error!("Outbox not enabled"),
+    };
+
+    let name = match result {
+        Ok(_) => metrics::OUTBOX_QUEUED,
+        Err(_) => metrics::OUTBOX_DROPPED,
+    };
+
+    metrics::increment(name, &[("sink", sink)]);
+}
+
+/// Function used to replay the events queued for a sink
+///
+/// The events are sent in order until one fails, the remaining ones are
+/// kept for the next replay. Returns if no event remains queued.
+///
+/// # Arguments
+///
+/// * `sink` - The name of the sink
+/// * `send` - The function sending an event, returns if it was successful
+pub fn replay<F>(sink: &str, mut send: F) -> bool
+    where F: FnMut(&str) -> bool {
+
+    let outbox = match OUTBOX.lock() {
+        Ok(o) => o,
+        Err(_) => return true,
+    };
+
+    let outbox = match &*outbox {
+        Some(o) => o,
+        None => return true,
+    };
+
+    let events = outbox.read(sink);
+
+    if events.is_empty() {
+        return true;
+    }
+
+    let sent = events.iter().take_while(|e| send(e)).count();
+
+    if sent == 0 {
+        return false;
+    }
+
+    log::debug!("{} events replayed to {}", sent, sink);
+
+    metrics::add(metrics::OUTBOX_REPLAYED, &[("sink", sink)], sent as f64);
+
+    match outbox.write(sink, &events[sent..]) {
+        Ok(_) => (),
+        Err(e) => log::error!("{}", e),
+    }
+
+    return sent == events.len();
+}
@@ -0,0 +1,91 @@
+use regex::Regex;
+use std::path;
+use std::process;
+
+use crate::platform;
+
+/// FreeBSD platform, values are read using sysctl
+pub struct FreeBsd;
+
+impl platform::Platform for FreeBsd {
+    /// Get the path of an entry of the sysfs tree (not available)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `path` - The path of the entry relatively to the sysfs root
+    fn sysfs(&self, _path: &str) -> Option<path::PathBuf> {
+        return None;
+    }
+
+    /// Get the temperatures of the CPU cores (in degrees) of each package
+    ///
+    /// Temperatures are provided by the `coretemp` or `amdtemp` drivers as
+    /// `dev.cpu.<n>.temperature` sysctl values. The package of a CPU isn't
+    /// exposed, so all the cores are reported in a single package.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `device` - Unused, all CPUs are provided by the same sysctl tree
+    /// * `pattern` - The pattern the sysctl names must match
+    fn package_temperatures(&self, _device: &str, pattern: &Regex)
+        -> Vec<Vec<u8>> {
+
+        let output = match process::Command::new("sysctl")
+            .arg("dev.cpu")
+            .output() {
+
+            Ok(o) => o,
+            Err(_) => return Vec::new(),
+        };
+
+        // Lines are formatted like `dev.cpu.0.temperature: 45.0C`
+        let temperatures: Vec<u8> = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|l| {
+                let mut parts = l.splitn(2, ':');
+                let name = parts.next()?.trim();
+                let value = parts.next()?.trim();
+
+                if !name.ends_with(".temperature") || !pattern.is_match(name) {
+                    return None;
+                }
+
+                return match value.trim_end_matches('C').parse::<f64>() {
+                    Ok(v) if v > 0.0 => Some(v as u8),
+                    _ => None,
+                };
+            })
+            .collect();
+
+        return match temperatures.is_empty() {
+            true => Vec::new(),
+            false => vec![temperatures],
+        };
+    }
+
+    /// Get the speeds of the fans (not available)
+    ///
+    /// There's no generic sysctl tree for the fans.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `device` - The name of the device providing the speeds, if any
+    /// * `pattern` - The pattern the fans names must match
+    fn fan_speeds(&self, _device: Option<&str>, _pattern: &Regex)
+        -> Vec<(String, String, u64)> {
+
+        return Vec::new();
+    }
+
+    /// Get the names of the sensors chips (not available)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn sensor_chips(&self) -> Vec<String> {
+        return Vec::new();
+    }
+}
@@ -0,0 +1,66 @@
+use regex::Regex;
+use std::path;
+
+use crate::platform;
+#[cfg(feature = "sensors")]
+use crate::platform::lm_sensors;
+#[cfg(not(feature = "sensors"))]
+use crate::platform::no_sensors as lm_sensors;
+
+/// Linux platform, values are read from sysfs and lm-sensors
+///
+/// Without the `sensors` feature, no temperature nor fan speed is provided.
+pub struct Linux;
+
+impl platform::Platform for Linux {
+    /// Get the path of an entry of the sysfs tree
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `path` - The path of the entry relatively to the sysfs root
+    fn sysfs(&self, path: &str) -> Option<path::PathBuf> {
+        return Some(path::Path::new("/").join("sys").join(path));
+    }
+
+    /// Get the temperatures of the CPU cores (in degrees) of each package
+    ///
+    /// There's one lm-sensors chip per package (e.g. `coretemp-isa-0000`
+    /// and `coretemp-isa-0001` on a dual socket machine).
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `device` - The prefix of the lm-sensors chip
+    /// * `pattern` - The pattern the features names must match
+    fn package_temperatures(&self, device: &str, pattern: &Regex)
+        -> Vec<Vec<u8>> {
+
+        return lm_sensors::package_temperatures(device, pattern);
+    }
+
+    /// Get the speeds of the fans (in RPM) as (chip, fan, speed)
+    ///
+    /// The chips are named by lm-sensors (e.g. `thinkpad-isa-0000`) and the
+    /// fans by their feature (e.g. `fan1`).
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `device` - The prefix of the lm-sensors chip, all if not given
+    /// * `pattern` - The pattern the features names must match
+    fn fan_speeds(&self, device: Option<&str>, pattern: &Regex)
+        -> Vec<(String, String, u64)> {
+
+        return lm_sensors::fan_speeds(device, pattern);
+    }
+
+    /// Get the names of the lm-sensors chips
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn sensor_chips(&self) -> Vec<String> {
+        return lm_sensors::sensor_chips();
+    }
+}
@@ -0,0 +1,131 @@
+use regex::Regex;
+use sensors::{FeatureType, Sensors, SubfeatureType};
+
+/// Function used to get the temperatures of the CPU cores (in degrees) of
+/// each package
+///
+/// There's one lm-sensors chip per package (e.g. `coretemp-isa-0000`
+/// and `coretemp-isa-0001` on a dual socket machine).
+///
+/// # Arguments
+///
+/// * `device` - The prefix of the lm-sensors chip
+/// * `pattern` - The pattern the features names must match
+pub fn package_temperatures(device: &str, pattern: &Regex) -> Vec<Vec<u8>> {
+    let mut packages: Vec<Vec<u8>> = Vec::new();
+
+    for chip in Sensors::new() {
+        if chip.prefix() != device {
+            continue;
+        }
+
+        let mut temperatures: Vec<u8> = Vec::new();
+
+        // Search for a temperature feature
+        for feature in chip {
+            match feature.feature_type() {
+                FeatureType::SENSORS_FEATURE_TEMP => (),
+                _ => continue,
+            }
+
+            if ! pattern.is_match(feature.name()) {
+                continue;
+            }
+
+            // Search for a temperature subfeature
+            for subfeature in feature {
+                match subfeature.subfeature_type() {
+                    SubfeatureType::SENSORS_SUBFEATURE_TEMP_INPUT => (),
+                    _ => continue,
+                }
+
+                let value = match subfeature.get_value() {
+                    Ok(v) => v as u8,
+                    Err(_) => continue,
+                };
+
+                if value == 0 {
+                    // Not a valid temperature
+                    continue;
+                }
+
+                temperatures.push(value);
+                break;
+            }
+        }
+
+        if !temperatures.is_empty() {
+            packages.push(temperatures);
+        }
+    }
+
+    return packages;
+}
+
+/// Function used to get the speeds of the fans (in RPM) as (chip, fan, speed)
+///
+/// The chips are named by lm-sensors (e.g. `thinkpad-isa-0000`) and the
+/// fans by their feature (e.g. `fan1`).
+///
+/// # Arguments
+///
+/// * `device` - The prefix of the lm-sensors chip, all if not given
+/// * `pattern` - The pattern the features names must match
+pub fn fan_speeds(device: Option<&str>, pattern: &Regex)
+    -> Vec<(String, String, u64)> {
+
+    let mut fans: Vec<(String, String, u64)> = Vec::new();
+
+    for chip in Sensors::new() {
+        match device {
+            Some(d) if chip.prefix() != d => continue,
+            _ => (),
+        }
+
+        let chip_name = match chip.get_name() {
+            Ok(n) => n,
+            Err(_) => continue,
+        };
+
+        for feature in chip {
+            match feature.feature_type() {
+                FeatureType::SENSORS_FEATURE_FAN => (),
+                _ => continue,
+            }
+
+            if ! pattern.is_match(feature.name()) {
+                continue;
+            }
+
+            let name = feature.name().to_string();
+
+            // Search for a speed subfeature
+            for subfeature in feature {
+                match subfeature.subfeature_type() {
+                    SubfeatureType::SENSORS_SUBFEATURE_FAN_INPUT => (),
+                    _ => continue,
+                }
+
+                match subfeature.get_value() {
+                    Ok(v) if v >= 0.0 => {
+                        fans.push((chip_name.clone(), name, v as u64));
+                    },
+
+                    _ => (),
+                }
+
+                break;
+            }
+        }
+    }
+
+    return fans;
+}
+
+/// Function used to get the names of the lm-sensors chips
+pub fn sensor_chips() -> Vec<String> {
+    return Sensors::new()
+        .into_iter()
+        .filter_map(|c| c.get_name().ok())
+        .collect();
+}
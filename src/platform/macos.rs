@@ -0,0 +1,56 @@
+use regex::Regex;
+use std::path;
+
+use crate::platform;
+
+/// macOS platform, only the values provided by systemstat are available
+pub struct MacOs;
+
+impl platform::Platform for MacOs {
+    /// Get the path of an entry of the sysfs tree (not available)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `path` - The path of the entry relatively to the sysfs root
+    fn sysfs(&self, _path: &str) -> Option<path::PathBuf> {
+        return None;
+    }
+
+    /// Get the temperatures of the CPU cores of each package (not available)
+    ///
+    /// The sensors are only reachable through the private SMC interface.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `device` - The name of the device providing the temperatures
+    /// * `pattern` - The pattern the sensors names must match
+    fn package_temperatures(&self, _device: &str, _pattern: &Regex)
+        -> Vec<Vec<u8>> {
+
+        return Vec::new();
+    }
+
+    /// Get the speeds of the fans (not available)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `device` - The name of the device providing the speeds, if any
+    /// * `pattern` - The pattern the fans names must match
+    fn fan_speeds(&self, _device: Option<&str>, _pattern: &Regex)
+        -> Vec<(String, String, u64)> {
+
+        return Vec::new();
+    }
+
+    /// Get the names of the sensors chips (not available)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn sensor_chips(&self) -> Vec<String> {
+        return Vec::new();
+    }
+}
@@ -0,0 +1,66 @@
+use regex::Regex;
+use std::path;
+
+// Includes
+#[cfg(target_os = "freebsd")]
+mod freebsd;
+#[cfg(target_os = "linux")]
+mod linux;
+#[cfg(all(target_os = "linux", feature = "sensors"))]
+mod lm_sensors;
+#[cfg(all(target_os = "linux", not(feature = "sensors")))]
+mod no_sensors;
+#[cfg(target_os = "macos")]
+mod macos;
+
+/// Operating system specific facilities used by the modules
+pub trait Platform: Sync {
+    /// Get the path of an entry of the sysfs tree (e.g. `class/backlight`)
+    ///
+    /// Returns `None` if the platform has no sysfs.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `path` - The path of the entry relatively to the sysfs root
+    fn sysfs(&self, path: &str) -> Option<path::PathBuf>;
+
+    /// Get the temperatures of the CPU cores (in degrees) of each package
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `device` - The name of the device providing the temperatures
+    /// * `pattern` - The pattern the sensors names must match
+    fn package_temperatures(&self, device: &str, pattern: &Regex)
+        -> Vec<Vec<u8>>;
+
+    /// Get the speeds of the fans (in RPM) as (device, fan, speed)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `device` - The name of the device providing the speeds, if any
+    /// * `pattern` - The pattern the fans names must match
+    fn fan_speeds(&self, device: Option<&str>, pattern: &Regex)
+        -> Vec<(String, String, u64)>;
+
+    /// Get the names of the sensors chips (e.g. `coretemp-isa-0000`)
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn sensor_chips(&self) -> Vec<String>;
+}
+
+#[cfg(target_os = "freebsd")]
+static PLATFORM: freebsd::FreeBsd = freebsd::FreeBsd;
+#[cfg(target_os = "linux")]
+static PLATFORM: linux::Linux = linux::Linux;
+#[cfg(target_os = "macos")]
+static PLATFORM: macos::MacOs = macos::MacOs;
+
+/// Function used to get the platform the program is running on
+pub fn current() -> &'static dyn Platform {
+    return &PLATFORM;
+}
@@ -0,0 +1,30 @@
+use regex::Regex;
+
+/// Function used to get the temperatures of the CPU cores (not available)
+///
+/// # Arguments
+///
+/// * `device` - The prefix of the lm-sensors chip
+/// * `pattern` - The pattern the features names must match
+pub fn package_temperatures(_device: &str, _pattern: &Regex)
+    -> Vec<Vec<u8>> {
+
+    return Vec::new();
+}
+
+/// Function used to get the speeds of the fans (not available)
+///
+/// # Arguments
+///
+/// * `device` - The prefix of the lm-sensors chip, all if not given
+/// * `pattern` - The pattern the features names must match
+pub fn fan_speeds(_device: Option<&str>, _pattern: &Regex)
+    -> Vec<(String, String, u64)> {
+
+    return Vec::new();
+}
+
+/// Function used to get the names of the lm-sensors chips (not available)
+pub fn sensor_chips() -> Vec<String> {
+    return Vec::new();
+}
@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+
+use crate::config;
+
+const BATTERY_POLICY: &str = "policy.battery";
+
+const DEFAULT_CONSERVE_ABOVE_PERCENT: u64 = 80;
+const DEFAULT_SAVE_BELOW_PERCENT: u64 = 20;
+
+const ENTRY_PERCENT: &str = "battery/percent";
+const ENTRY_PLUGGED: &str = "battery/plugged";
+
+const STATE_CONSERVE: &str = "conserve";
+const STATE_NORMAL: &str = "normal";
+const STATE_SAVE: &str = "save";
+
+/// Function used to get the write actions of a list of entries
+///
+/// The entries are written in the order of their paths.
+///
+/// # Arguments
+///
+/// * `writes` - The values to be written by path
+fn write_actions(writes: &Option<HashMap<String, String>>)
+    -> Option<Vec<String>> {
+
+    let writes = match writes {
+        Some(w) => w,
+        None => return None,
+    };
+
+    let mut paths: Vec<&String> = writes.keys().collect();
+    paths.sort();
+
+    return Some(paths
+        .iter()
+        .map(|p| format!("@write {} \"{}\"", p, writes[*p]))
+        .collect());
+}
+
+/// Function used to build the automation running the battery policy
+///
+/// # Arguments
+///
+/// * `config` - The battery policy
+fn battery(config: &config::BatteryPolicyConfig) -> config::AutomationConfig {
+    let conserve = config
+        .conserve_above_percent
+        .unwrap_or(DEFAULT_CONSERVE_ABOVE_PERCENT);
+
+    let save = config.save_below_percent.unwrap_or(DEFAULT_SAVE_BELOW_PERCENT);
+
+    let state = |writes: &Option<HashMap<String, String>>| {
+        return config::AutomationStateConfig {
+            on_enter: write_actions(writes),
+            on_exit: None,
+        };
+    };
+
+    let transition = |from: &str, to: &str, when: String| {
+        return config::AutomationTransitionConfig {
+            from: from.to_string(),
+            to: to.to_string(),
+            when: when,
+        };
+    };
+
+    let mut states = HashMap::new();
+
+    states.insert(STATE_NORMAL.to_string(), state(&config.restore));
+    states.insert(STATE_CONSERVE.to_string(), state(&config.conserve));
+    states.insert(STATE_SAVE.to_string(), state(&config.save));
+
+    return config::AutomationConfig {
+        initial: STATE_NORMAL.to_string(),
+        states: states,
+        transitions: vec![
+            transition(
+                STATE_NORMAL,
+                STATE_CONSERVE,
+                format!(
+                    "{} && {} >= {}",
+                    ENTRY_PLUGGED,
+                    ENTRY_PERCENT,
+                    conserve)),
+
+            transition(
+                STATE_NORMAL,
+                STATE_SAVE,
+                format!(
+                    "!{} && {} <= {}",
+                    ENTRY_PLUGGED,
+                    ENTRY_PERCENT,
+                    save)),
+
+            transition(STATE_CONSERVE, STATE_NORMAL, format!(
+                "!{}",
+                ENTRY_PLUGGED)),
+
+            transition(STATE_SAVE, STATE_NORMAL, format!(
+                "{} || {} > {}",
+                ENTRY_PLUGGED,
+                ENTRY_PERCENT,
+                save)),
+        ],
+    };
+}
+
+/// Function used to get the automations running the policies
+///
+/// # Arguments
+///
+/// * `config` - The configuration
+pub fn automations(config: &config::Config)
+    -> Vec<(String, config::AutomationConfig)> {
+
+    let mut automations = Vec::new();
+
+    let policy = match &config.policy {
+        Some(p) => p,
+        None => return automations,
+    };
+
+    match &policy.battery {
+        Some(b) => automations.push((BATTERY_POLICY.to_string(), battery(b))),
+        None => (),
+    }
+
+    return automations;
+}
@@ -0,0 +1,57 @@
+use std::fs;
+use std::process;
+
+/// Action checked when no action is configured
+pub const DEFAULT_ACTION_ID: &str = "org.cerebro.write-entry";
+
+/// Function used to get the start time of a process
+///
+/// It's given to polkit along with the PID so that a PID reused by another
+/// process can't be authorized.
+fn start_time(pid: u32) -> Option<String> {
+    let stat = match fs::read_to_string(format!("/proc/{}/stat", pid)) {
+        Ok(s) => s,
+        Err(_) => return None,
+    };
+
+    // The name of the command may contain spaces, fields follow its end
+    let fields = match stat.rfind(')') {
+        Some(i) => &stat[i + 1..],
+        None => return None,
+    };
+
+    // The start time is the 22nd field, the 20th after the command name
+    return fields.split_whitespace().nth(19).map(|s| s.to_string());
+}
+
+/// Function used to check that a process is authorized to do an action
+///
+/// The user may be prompted for authentication by its polkit agent.
+///
+/// # Arguments
+///
+/// * `action_id` - The identifier of the polkit action
+/// * `pid` - The PID of the process
+/// * `uid` - The UID of the process
+pub fn authorize(action_id: &str, pid: u32, uid: u32) -> bool {
+    let start_time = match start_time(pid) {
+        Some(s) => s,
+        None => return false,
+    };
+
+    let status = process::Command::new("pkcheck")
+        .args(&[
+            "--action-id",
+            action_id,
+            "--process",
+            &format!("{},{},{}", pid, start_time, uid),
+            "--allow-user-interaction"])
+        .stdout(process::Stdio::null())
+        .stderr(process::Stdio::null())
+        .status();
+
+    return match status {
+        Ok(s) => s.success(),
+        Err(_) => false,
+    };
+}
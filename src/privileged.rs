@@ -0,0 +1,313 @@
+use lazy_static::lazy_static;
+use serde_json::{json, Value};
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process;
+use std::time;
+
+use crate::config;
+use crate::error;
+use crate::sync::Mutex;
+
+const SOURCE_NFTABLES: &str = "nftables";
+const SOURCE_RAPL: &str = "rapl";
+const SOURCE_SMART: &str = "smart";
+
+/// Delay before starting the helper again after it has been refused
+const RETRY_DELAY_S: u64 = 600;
+
+lazy_static! {
+    static ref HELPER: Mutex<Option<Helper>> = Mutex::new(None);
+    static ref COMMAND: Mutex<Option<Vec<String>>> = Mutex::new(None);
+    static ref REFUSED: Mutex<Option<time::Instant>> = Mutex::new(None);
+}
+
+/// Process running the helper, requests are sent one at a time
+struct Helper {
+    child: process::Child,
+    stdin: process::ChildStdin,
+    stdout: BufReader<process::ChildStdout>,
+}
+
+impl Helper {
+    /// Start the helper process
+    fn spawn() -> Result<Helper, error::CerebroError> {
+        let command = match COMMAND.lock() {
+            Ok(c) => c.clone(),
+            Err(_) => return error!(Lock, "Cannot lock helper command"),
+        };
+
+        let command = match command {
+            Some(c) => c,
+            None => match env::current_exe() {
+                Ok(e) => vec![
+                    "pkexec".to_string(),
+                    e.display().to_string(),
+                    "helper".to_string()],
+
+                Err(_) => return error!("Cannot find executable"),
+            },
+        };
+
+        if command.is_empty() {
+            return error!("Empty helper command");
+        }
+
+        let mut child = match process::Command::new(&command[0])
+            .args(&command[1..])
+            .stdin(process::Stdio::piped())
+            .stdout(process::Stdio::piped())
+            .spawn() {
+
+            Ok(c) => c,
+            Err(_) => return error!("Cannot start privileged helper"),
+        };
+
+        let stdin = match child.stdin.take() {
+            Some(s) => s,
+            None => return error!("Cannot write to privileged helper"),
+        };
+
+        let stdout = match child.stdout.take() {
+            Some(s) => s,
+            None => return error!("Cannot read from privileged helper"),
+        };
+
+        return Ok(Helper {
+            child: child,
+            stdin: stdin,
+            stdout: BufReader::new(stdout),
+        });
+    }
+
+    /// Send a request and wait for its reply
+    fn request(&mut self, source: &str, argument: &str)
+        -> Result<String, error::CerebroError> {
+
+        let request = json!({ "source": source, "argument": argument });
+
+        match writeln!(self.stdin, "{}", request) {
+            Ok(_) => (),
+            Err(_) => return error!("Cannot write to privileged helper"),
+        }
+
+        let mut line = String::new();
+
+        match self.stdout.read_line(&mut line) {
+            Ok(0) | Err(_) => return error!("Privileged helper has exited"),
+            Ok(_) => (),
+        }
+
+        let reply: Value = match serde_json::from_str(&line) {
+            Ok(r) => r,
+            Err(_) => return error!("Invalid reply of privileged helper"),
+        };
+
+        return match (reply["value"].as_str(), reply["error"].as_str()) {
+            (Some(v), _) => Ok(v.to_string()),
+            (None, Some(e)) => error!(e),
+            (None, None) => error!("Invalid reply of privileged helper"),
+        };
+    }
+}
+
+impl Drop for Helper {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Function used to set the command starting the helper
+///
+/// By default the helper is this program started through `pkexec`.
+pub fn init(config: &config::HelperConfig) {
+    match COMMAND.lock() {
+        Ok(mut c) => *c = config.command.clone(),
+        Err(_) => (),
+    }
+}
+
+/// Function used to remember that the helper cannot be started
+///
+/// The authentication may have been cancelled or no polkit agent may be
+/// running, the user is not asked again before a delay.
+fn refuse() {
+    log::warn!(
+        "Privileged helper is unavailable, next attempt in {} s",
+        RETRY_DELAY_S);
+
+    match REFUSED.lock() {
+        Ok(mut r) => *r = Some(time::Instant::now()),
+        Err(_) => (),
+    }
+}
+
+/// Function used to check that an argument can't escape its source
+fn check_argument(argument: &str) -> error::Return {
+    if argument.is_empty() ||
+        !argument
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == ':' || c == '-') {
+
+        return error!("Invalid argument");
+    }
+
+    return success!();
+}
+
+/// Function used to run a command and get its output
+fn command_output(program: &str, args: &[&str])
+    -> Result<String, error::CerebroError> {
+
+    return match process::Command::new(program).args(args).output() {
+        Ok(o) => Ok(String::from_utf8_lossy(&o.stdout).to_string()),
+        Err(_) => error!(&format!("Cannot execute {}", program)),
+    };
+}
+
+/// Function used to read a privileged source, with the current privileges
+///
+/// Only a known list of sources can be read so that the helper can't be
+/// used to read anything else.
+///
+/// # Arguments
+///
+/// * `source` - The name of the source
+/// * `argument` - The argument of the source (device, zone, ...)
+fn read_source(source: &str, argument: &str)
+    -> Result<String, error::CerebroError> {
+
+    match source {
+        // SMART attributes of a disk (e.g. `sda`)
+        SOURCE_SMART => {
+            check_argument(argument)?;
+
+            let device = format!("/dev/{}", argument);
+
+            return command_output("smartctl", &["--json", "-a", &device]);
+        },
+
+        // Energy counter of a RAPL zone (e.g. `intel-rapl:0`)
+        SOURCE_RAPL => {
+            check_argument(argument)?;
+
+            let path = format!(
+                "/sys/class/powercap/{}/energy_uj",
+                argument);
+
+            return match fs::read_to_string(path) {
+                Ok(v) => Ok(v.trim().to_string()),
+                Err(_) => error!("Cannot read RAPL energy"),
+            };
+        },
+
+        // Whole firewall ruleset
+        SOURCE_NFTABLES => {
+            return command_output("nft", &["--json", "list", "ruleset"]);
+        },
+
+        _ => return error!("Unknown privileged source"),
+    }
+}
+
+/// Function used to read a privileged source
+///
+/// The source is read directly when running as root, otherwise the request
+/// is sent to the helper, which is started on the first request. A refused
+/// helper is not started again before a delay.
+///
+/// # Arguments
+///
+/// * `source` - The name of the source
+/// * `argument` - The argument of the source (device, zone, ...)
+pub fn read(source: &str, argument: &str)
+    -> Result<String, error::CerebroError> {
+
+    if unsafe { libc::geteuid() } == 0 {
+        return read_source(source, argument);
+    }
+
+    match REFUSED.lock() {
+        Ok(r) => match *r {
+            Some(t) if t.elapsed().as_secs() < RETRY_DELAY_S => {
+                return error!("Privileged helper is unavailable");
+            },
+
+            _ => (),
+        },
+
+        Err(_) => return error!(Lock, "Cannot lock privileged helper"),
+    }
+
+    let mut helper = match HELPER.lock() {
+        Ok(h) => h,
+        Err(_) => return error!(Lock, "Cannot lock privileged helper"),
+    };
+
+    if helper.is_none() {
+        *helper = match Helper::spawn() {
+            Ok(h) => Some(h),
+            Err(e) => {
+                refuse();
+                return Err(e);
+            },
+        };
+    }
+
+    let (result, exited) = match helper.as_mut() {
+        Some(h) => (
+            h.request(source, argument),
+            !matches!(h.child.try_wait(), Ok(None))),
+
+        None => return error!("No privileged helper"),
+    };
+
+    // Restart the helper on the next request if it's gone, unless it
+    // exited without replying (refused authentication)
+    if exited {
+        *helper = None;
+
+        if result.is_err() {
+            refuse();
+        }
+    }
+
+    return result;
+}
+
+/// Function used to serve the requests of the daemon (helper side)
+///
+/// Requests and replies are JSON objects, one per line.
+pub fn serve() {
+    let stdin = io::stdin();
+    let mut stdout = io::stdout();
+
+    for line in stdin.lock().lines() {
+        let line = match line {
+            Ok(l) => l,
+            Err(_) => break,
+        };
+
+        let request: Value = match serde_json::from_str(&line) {
+            Ok(r) => r,
+            Err(_) => Value::Null,
+        };
+
+        let source = request["source"].as_str().unwrap_or("");
+        let argument = request["argument"].as_str().unwrap_or("");
+
+        let reply = match read_source(source, argument) {
+            Ok(v) => json!({ "value": v }),
+            Err(e) => json!({ "error": e.to_string() }),
+        };
+
+        match writeln!(stdout, "{}", reply) {
+            Ok(_) => (),
+            Err(_) => break,
+        }
+
+        let _ = stdout.flush();
+    }
+}
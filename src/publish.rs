@@ -0,0 +1,287 @@
+use lazy_static::lazy_static;
+use std::collections::{HashMap, VecDeque};
+
+use crate::config;
+use crate::sync::Mutex;
+use crate::triggers;
+use crate::visibility;
+
+const SMOOTHING_EMA: &str = "ema";
+const SMOOTHING_MEDIAN: &str = "median";
+
+const DEFAULT_ALPHA: f64 = 0.3;
+const DEFAULT_WINDOW: usize = 5;
+
+/// Maximum number of decimals of a filtered value
+const MAX_DECIMALS: usize = 2;
+
+lazy_static! {
+    static ref FILTERS: Mutex<HashMap<String, Filter>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Minimum change of a value to be published
+#[derive(Clone, Copy)]
+enum Delta {
+    Absolute(f64),
+    Percent(f64),
+}
+
+impl Delta {
+    /// Parse a delta from the configuration (`0.5` or `2%`)
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The value to be parsed
+    fn parse(value: &str) -> Option<Self> {
+        let value = value.trim();
+
+        match value.strip_suffix('%') {
+            Some(percent) => match percent.trim().parse::<f64>() {
+                Ok(p) => return Some(Delta::Percent(p.abs())),
+                Err(_) => return None,
+            },
+
+            None => match value.parse::<f64>() {
+                Ok(d) => return Some(Delta::Absolute(d.abs())),
+                Err(_) => return None,
+            },
+        }
+    }
+
+    /// Check if the change between two values is significant
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `old` - The current value
+    /// * `new` - The new value
+    fn is_significant(&self, old: f64, new: f64) -> bool {
+        let change = (new - old).abs();
+
+        match self {
+            Delta::Absolute(d) => return change >= *d,
+            Delta::Percent(p) => return change >= old.abs() * p / 100.0,
+        }
+    }
+}
+
+/// Filter applied to the values of an entry
+struct Filter {
+    config: config::FilterConfig,
+    min_delta: Option<Delta>,
+    samples: VecDeque<f64>,
+    average: Option<f64>,
+}
+
+impl Filter {
+    fn new(module: &str, entry: &str, config: &config::FilterConfig) -> Self {
+        let min_delta = match &config.min_delta {
+            Some(value) => match Delta::parse(value) {
+                Some(d) => Some(d),
+                None => {
+                    log::error!(
+                        "Invalid min_delta for {}/{}: {}",
+                        module,
+                        entry,
+                        value);
+
+                    None
+                },
+            },
+
+            None => None,
+        };
+
+        Self {
+            config: config.clone(),
+            min_delta: min_delta,
+            samples: VecDeque::new(),
+            average: None,
+        }
+    }
+
+    /// Filter a new value
+    ///
+    /// Returns None if the value must not be published.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `current` - The current value
+    /// * `value` - The new value
+    fn apply(&mut self, current: &str, value: &str) -> Option<String> {
+        // Values that are not finite numbers (e.g. `NaN`) would corrupt the
+        // smoothing, they are left untouched
+        let sample = match value.trim().parse::<f64>() {
+            Ok(s) if s.is_finite() => s,
+            _ => return Some(value.to_string()),
+        };
+
+        // Keep the precision of the raw value
+        let decimals = match value.find('.') {
+            Some(i) => (value.len() - i - 1).min(MAX_DECIMALS),
+            None => 0,
+        };
+
+        let smoothed = self.smooth(sample);
+
+        if let (Some(delta), Ok(old)) =
+            (self.min_delta, current.trim().parse::<f64>()) {
+
+            if !delta.is_significant(old, smoothed) {
+                return None;
+            }
+        }
+
+        return Some(format!("{:.*}", decimals, smoothed));
+    }
+
+    /// Smooth a new sample
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `sample` - The new sample
+    fn smooth(&mut self, sample: f64) -> f64 {
+        match self.config.smoothing.as_deref() {
+            Some(SMOOTHING_EMA) => {
+                let alpha = self.config.alpha.unwrap_or(DEFAULT_ALPHA);
+
+                let average = match self.average {
+                    Some(a) => alpha * sample + (1.0 - alpha) * a,
+                    None => sample,
+                };
+
+                self.average = Some(average);
+
+                return average;
+            },
+
+            Some(SMOOTHING_MEDIAN) => {
+                let window = self.config.window.unwrap_or(DEFAULT_WINDOW);
+
+                self.samples.push_back(sample);
+
+                while self.samples.len() > window.max(1) {
+                    self.samples.pop_front();
+                }
+
+                let mut sorted: Vec<f64> =
+                    self.samples.iter().cloned().collect();
+
+                sorted.sort_by(|a, b| a.total_cmp(b));
+
+                return sorted[sorted.len() / 2];
+            },
+
+            _ => return sample,
+        }
+    }
+}
+
+/// Function used to load the filters of the entries from the configuration
+pub fn init(config: &config::Config) {
+    let mut filters = match FILTERS.lock() {
+        Ok(f) => f,
+        Err(_) => return,
+    };
+
+    filters.clear();
+
+    for (module, module_config) in config.modules.iter() {
+        let entries = match &module_config.filters {
+            Some(e) => e,
+            None => continue,
+        };
+
+        for (entry, filter_config) in entries.iter() {
+            filters.insert(
+                format!("{}/{}", module, entry),
+                Filter::new(module, entry, filter_config));
+        }
+    }
+}
+
+/// Function used to filter the new value of an entry
+///
+/// Non numeric values and entries without filter are left untouched.
+/// Returns None if the change is not significant.
+///
+/// # Arguments
+///
+/// * `module` - The name of the module
+/// * `entry` - The path of the entry in the module
+/// * `current` - The current value
+/// * `value` - The new value
+fn filter(
+    module: &str,
+    entry: &str,
+    current: &str,
+    value: &str) -> Option<String> {
+
+    let mut filters = match FILTERS.lock() {
+        Ok(f) => f,
+        Err(_) => return Some(value.to_string()),
+    };
+
+    match filters.get_mut(&format!("{}/{}", module, entry)) {
+        Some(f) => return f.apply(current, value),
+        None => return Some(value.to_string()),
+    }
+}
+
+/// Function used to publish the new value of an entry
+///
+/// The value is filtered then, if it differs significantly from the current
+/// one, it's stored and published, and the triggers of the visible entries
+/// are executed.
+///
+/// # Arguments
+///
+/// * `triggers` - The triggers of the module
+/// * `kind` - The kind of triggers to be executed
+/// * `module` - The name of the module
+/// * `entry` - The path of the entry in the module
+/// * `current` - The current value, updated
+/// * `value` - The new value
+pub fn update(
+    triggers: &Vec<triggers::Trigger>,
+    kind: triggers::Kind,
+    module: &str,
+    entry: &str,
+    current: &mut String,
+    value: &str) -> bool {
+
+    let value = match filter(module, entry, current, value) {
+        Some(v) => v,
+        None => return false,
+    };
+
+    if *current == value {
+        return false;
+    }
+
+    let old_value = current.clone();
+
+    *current = value;
+
+    log::debug!("{}: {}={}", module, entry, current);
+
+    triggers::publish(kind, module, entry, &old_value, current);
+
+    // Hidden entries are not evaluated by the triggers
+    if !visibility::is_visible(module, entry) {
+        return true;
+    }
+
+    triggers::execute_matching(
+        triggers,
+        kind,
+        module,
+        entry,
+        &old_value,
+        current);
+
+    return true;
+}
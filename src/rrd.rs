@@ -0,0 +1,247 @@
+use dirs;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::PathBuf;
+
+use crate::config;
+use crate::error;
+use crate::modules::files;
+
+/// Name of the default directory, under the data directory of the user
+const DEFAULT_DIR: &str = "cerebro/history";
+
+/// Resolution (in seconds) and number of points of the tiers: one hour of
+/// seconds, one day of minutes and thirty days of hours
+const TIERS: [(i64, usize); 3] = [(1, 3600), (60, 1440), (3600, 720)];
+
+/// Points of a series at a given resolution
+///
+/// A point is the average of the values added during its interval.
+#[derive(Clone, Deserialize, Serialize)]
+struct Tier {
+    step_s: i64,
+    capacity: usize,
+    points: VecDeque<(i64, f64)>,
+
+    /// Start, sum and number of values of the interval not yet completed
+    pending: Option<(i64, f64, u64)>,
+}
+
+impl Tier {
+    /// Tier constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `step_s` - The resolution of the tier, in seconds
+    /// * `capacity` - The number of points kept
+    fn new(step_s: i64, capacity: usize) -> Self {
+        Self {
+            step_s: step_s,
+            capacity: capacity,
+            points: VecDeque::new(),
+            pending: None,
+        }
+    }
+
+    /// Add a value
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `time` - The number of seconds since the epoch
+    /// * `value` - The value
+    fn add(&mut self, time: i64, value: f64) {
+        let start = time - time.rem_euclid(self.step_s);
+
+        match self.pending {
+            Some((s, sum, count)) if s == start => {
+                self.pending = Some((s, sum + value, count + 1));
+                return;
+            },
+
+            // Values older than the current interval are dropped
+            Some((s, _, _)) if s > start => return,
+
+            Some((s, sum, count)) => {
+                self.points.push_back((s, sum / count as f64));
+
+                while self.points.len() > self.capacity {
+                    self.points.pop_front();
+                }
+            },
+
+            None => (),
+        }
+
+        self.pending = Some((start, value, 1));
+    }
+
+    /// Get the points, including the one of the current interval
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `since` - The number of seconds since the epoch of the oldest point
+    fn points(&self, since: Option<i64>) -> Vec<(i64, f64)> {
+        let mut points: Vec<(i64, f64)> = self.points
+            .iter()
+            .cloned()
+            .collect();
+
+        match self.pending {
+            Some((s, sum, count)) => points.push((s, sum / count as f64)),
+            None => (),
+        }
+
+        return match since {
+            Some(s) => points.into_iter().filter(|p| p.0 >= s).collect(),
+            None => points,
+        };
+    }
+}
+
+/// Values of an entry downsampled at several resolutions
+#[derive(Clone, Deserialize, Serialize)]
+pub struct Series {
+    tiers: Vec<Tier>,
+}
+
+impl Series {
+    /// Series constructor
+    pub fn new() -> Self {
+        Self {
+            tiers: TIERS.iter().map(|(s, c)| Tier::new(*s, *c)).collect(),
+        }
+    }
+
+    /// Add a value to all the tiers
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `time` - The number of seconds since the epoch
+    /// * `value` - The value
+    pub fn add(&mut self, time: i64, value: f64) {
+        for tier in self.tiers.iter_mut() {
+            tier.add(time, value);
+        }
+    }
+
+    /// Get the points of a tier
+    ///
+    /// Returns `None` if there's no tier with this resolution.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `step_s` - The resolution of the tier, in seconds
+    /// * `since` - The number of seconds since the epoch of the oldest point
+    pub fn points(&self, step_s: i64, since: Option<i64>)
+        -> Option<Vec<(i64, f64)>> {
+
+        return match self.tiers.iter().find(|t| t.step_s == step_s) {
+            Some(t) => Some(t.points(since)),
+            None => None,
+        };
+    }
+}
+
+/// Function used to get the directory where the series are stored
+///
+/// # Arguments
+///
+/// * `config` - The configuration of the history
+pub fn directory(config: &config::HistoryConfig) -> Option<PathBuf> {
+    return match &config.path {
+        Some(p) => Some(files::expand_path(p)),
+        None => match dirs::data_dir() {
+            Some(d) => Some(d.join(DEFAULT_DIR)),
+            None => None,
+        },
+    };
+}
+
+/// Function used to get the file storing the series of an entry
+///
+/// # Arguments
+///
+/// * `dir` - The directory where the series are stored
+/// * `entry` - The path of the entry (e.g. `battery/percent`)
+fn file(dir: &PathBuf, entry: &str) -> PathBuf {
+    let name = entry
+        .trim_matches('/')
+        .replace('%', "%25")
+        .replace('/', "%2F");
+
+    return dir.join(format!("{}.json", name));
+}
+
+/// Function used to read the series of an entry
+///
+/// An empty series is returned if it has never been written or if the
+/// resolutions of the tiers have changed.
+///
+/// # Arguments
+///
+/// * `dir` - The directory where the series are stored
+/// * `entry` - The path of the entry (e.g. `battery/percent`)
+pub fn load(dir: &PathBuf, entry: &str)
+    -> Result<Series, error::CerebroError> {
+
+    let content = match fs::read_to_string(file(dir, entry)) {
+        Ok(c) => c,
+        Err(_) => return Ok(Series::new()),
+    };
+
+    let series: Series = match serde_json::from_str(&content) {
+        Ok(s) => s,
+        Err(_) => return error!(&format!("Invalid history of {}", entry)),
+    };
+
+    let matching = series.tiers.len() == TIERS.len() &&
+        series.tiers
+            .iter()
+            .zip(TIERS.iter())
+            .all(|(t, (s, c))| t.step_s == *s && t.capacity == *c);
+
+    return match matching {
+        true => Ok(series),
+        false => Ok(Series::new()),
+    };
+}
+
+/// Function used to write the series of an entry
+///
+/// The series is written to a temporary file then renamed so that a reader
+/// never sees a partial one.
+///
+/// # Arguments
+///
+/// * `dir` - The directory where the series are stored
+/// * `entry` - The path of the entry (e.g. `battery/percent`)
+/// * `series` - The series
+pub fn save(dir: &PathBuf, entry: &str, series: &Series) -> error::Return {
+    match fs::create_dir_all(dir) {
+        Ok(_) => (),
+        Err(_) => return error!("Cannot create history directory"),
+    }
+
+    let content = match serde_json::to_string(series) {
+        Ok(c) => c,
+        Err(_) => return error!("Cannot format history"),
+    };
+
+    let path = file(dir, entry);
+    let tmp = path.with_extension("tmp");
+
+    match fs::write(&tmp, content) {
+        Ok(_) => (),
+        Err(_) => return error!("Cannot write history"),
+    }
+
+    return match fs::rename(&tmp, &path) {
+        Ok(_) => success!(),
+        Err(_) => error!("Cannot write history"),
+    };
+}
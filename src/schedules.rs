@@ -0,0 +1,292 @@
+use std::thread;
+use std::time::Duration;
+
+use crate::config;
+use crate::error;
+use crate::triggers;
+
+/// Expressions equivalent to the shortcuts
+const SHORTCUTS: [(&str, &str); 5] = [
+    ("@hourly", "0 * * * *"),
+    ("@daily", "0 0 * * *"),
+    ("@weekly", "0 0 * * 0"),
+    ("@monthly", "0 0 1 * *"),
+    ("@yearly", "0 0 1 1 *"),
+];
+
+/// Times matched by a cron expression
+///
+/// Each field is a mask of the values it matches.
+struct Cron {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    any_day: bool,
+    any_weekday: bool,
+}
+
+/// Function used to check if a value is in a mask
+///
+/// # Arguments
+///
+/// * `mask` - The mask
+/// * `value` - The value
+fn has(mask: u64, value: i32) -> bool {
+    return value >= 0 && value < 64 && mask & (1 << value) != 0;
+}
+
+/// Function used to parse a field of a cron expression
+///
+/// A field is a list of values, of ranges (`1-5`) or of `*`, separated by
+/// `,`, each one optionally followed by a step (`*/15`).
+///
+/// # Arguments
+///
+/// * `text` - The field
+/// * `min` - The lowest value of the field
+/// * `max` - The highest value of the field
+fn parse_field(text: &str, min: u64, max: u64)
+    -> Result<u64, error::CerebroError> {
+
+    let invalid = || error!(Config, format!("Invalid cron field `{}`", text));
+
+    let mut mask = 0;
+
+    for part in text.split(',') {
+        let (range, step) = match part.find('/') {
+            Some(i) => match part[i + 1..].parse::<u64>() {
+                Ok(s) if s > 0 => (&part[..i], Some(s)),
+                _ => return invalid(),
+            },
+
+            None => (part, None),
+        };
+
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.find('-') {
+                Some(i) => match (
+                    range[..i].parse::<u64>(),
+                    range[i + 1..].parse::<u64>()) {
+
+                    (Ok(a), Ok(b)) => (a, b),
+                    _ => return invalid(),
+                },
+
+                // A single value with a step goes up to the highest one
+                None => match range.parse::<u64>() {
+                    Ok(a) if step.is_some() => (a, max),
+                    Ok(a) => (a, a),
+                    Err(_) => return invalid(),
+                },
+            },
+        };
+
+        if start < min || start > end || end > max {
+            return invalid();
+        }
+
+        for value in (start..=end).step_by(step.unwrap_or(1) as usize) {
+            mask |= 1 << value;
+        }
+    }
+
+    return Ok(mask);
+}
+
+impl Cron {
+    /// Parse a cron expression
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The expression (e.g. `*/15 8-18 * * 1-5`)
+    fn parse(text: &str) -> Result<Self, error::CerebroError> {
+        let text = match SHORTCUTS.iter().find(|(s, _)| *s == text.trim()) {
+            Some((_, e)) => *e,
+            None => text,
+        };
+
+        let fields: Vec<&str> = text.split_whitespace().collect();
+
+        if fields.len() != 5 {
+            return error!(Config, format!(
+                "Cron expression `{}` must have 5 fields",
+                text));
+        }
+
+        let mut weekdays = parse_field(fields[4], 0, 7)?;
+
+        // Sunday is either 0 or 7
+        if has(weekdays, 7) {
+            weekdays |= 1;
+        }
+
+        return Ok(Self {
+            minutes: parse_field(fields[0], 0, 59)?,
+            hours: parse_field(fields[1], 0, 23)?,
+            days: parse_field(fields[2], 1, 31)?,
+            months: parse_field(fields[3], 1, 12)?,
+            weekdays: weekdays,
+            any_day: fields[2] == "*",
+            any_weekday: fields[4] == "*",
+        });
+    }
+
+    /// Check if a time matches the expression
+    ///
+    /// As with cron, when both the day of the month and the day of the week
+    /// are restricted, a time matching either of them matches.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `tm` - The local time
+    fn matches(&self, tm: &libc::tm) -> bool {
+        if !has(self.minutes, tm.tm_min) ||
+            !has(self.hours, tm.tm_hour) ||
+            !has(self.months, tm.tm_mon + 1) {
+
+            return false;
+        }
+
+        let day = has(self.days, tm.tm_mday);
+        let weekday = has(self.weekdays, tm.tm_wday);
+
+        return match (self.any_day, self.any_weekday) {
+            (true, true) => true,
+            (true, false) => weekday,
+            (false, true) => day,
+            (false, false) => day || weekday,
+        };
+    }
+}
+
+/// Commands run at the times matching a cron expression
+pub struct Schedule {
+    name: String,
+    cron: Cron,
+    commands: Vec<String>,
+}
+
+impl Schedule {
+    /// Run the commands of the schedule
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    fn run(&self) {
+        log::info!("schedule `{}` started", self.name);
+
+        for command in self.commands.iter() {
+            // The references are resolved at each run (e.g. `$(date +%F)`)
+            let result = match config::substitute(command) {
+                Ok(c) => triggers::run(&c),
+                Err(e) => Err(e),
+            };
+
+            match result {
+                Ok(_) => (),
+                Err(e) => log::error!(
+                    "schedule `{}`: {}: {}",
+                    self.name,
+                    command,
+                    e),
+            }
+        }
+    }
+}
+
+/// Function used to get the local time
+///
+/// # Arguments
+///
+/// * `now` - The number of seconds since the epoch
+fn local_time(now: libc::time_t) -> libc::tm {
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+
+    unsafe {
+        libc::localtime_r(&now, &mut tm);
+    }
+
+    return tm;
+}
+
+/// Function used to load the schedules of the configuration
+///
+/// # Arguments
+///
+/// * `config` - The configuration
+pub fn load(config: &config::Config)
+    -> Result<Vec<Schedule>, error::CerebroError> {
+
+    let configs = match &config.schedules {
+        Some(s) => s,
+        None => return Ok(Vec::new()),
+    };
+
+    let mut names: Vec<&String> = configs.keys().collect();
+    names.sort();
+
+    let mut schedules = Vec::new();
+
+    for name in names {
+        let cron = match Cron::parse(&configs[name].cron) {
+            Ok(c) => c,
+            Err(e) => return error!(Config, format!(
+                "schedule `{}`: {}",
+                name,
+                e)),
+        };
+
+        schedules.push(Schedule {
+            name: name.clone(),
+            cron: cron,
+            commands: configs[name].commands.to_vec(),
+        });
+    }
+
+    return Ok(schedules);
+}
+
+/// Function used to run the schedules at the times they match
+///
+/// The schedules are checked at the start of every minute.
+///
+/// # Arguments
+///
+/// * `schedules` - The schedules
+pub fn start(schedules: Vec<Schedule>) {
+    if schedules.is_empty() {
+        return;
+    }
+
+    thread::spawn(move || {
+        // The current minute has started before the schedules
+        let mut last_minute =
+            Some(unsafe { libc::time(std::ptr::null_mut()) } / 60);
+
+        loop {
+            let now = unsafe { libc::time(std::ptr::null_mut()) };
+
+            // A minute is never handled twice, even if woken up early
+            if last_minute != Some(now / 60) {
+                last_minute = Some(now / 60);
+
+                let tm = local_time(now);
+
+                for schedule in schedules.iter() {
+                    if schedule.cron.matches(&tm) {
+                        schedule.run();
+                    }
+                }
+            }
+
+            // The commands may have taken some time
+            let now = unsafe { libc::time(std::ptr::null_mut()) };
+
+            thread::sleep(Duration::from_secs((60 - now % 60) as u64));
+        }
+    });
+}
@@ -0,0 +1,200 @@
+use lazy_static::lazy_static;
+use std::collections::HashMap;
+use std::io::Write;
+use std::process;
+
+use crate::error;
+use crate::sync::Mutex;
+
+/// Prefix of the values of the configuration referencing a secret
+const PREFIX: &str = "secret://";
+
+/// Attributes identifying the secrets of cerebro in the keyring
+const ATTRIBUTE_SERVICE: [&str; 2] = ["service", "cerebro"];
+const ATTRIBUTE_NAME: &str = "name";
+
+const COMMAND: &str = "secret-tool";
+
+lazy_static! {
+    static ref CACHE: Mutex<HashMap<String, String>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Function used to store a secret in the keyring
+///
+/// # Arguments
+///
+/// * `name` - The name of the secret
+/// * `value` - The value of the secret
+pub fn set(name: &str, value: &str) -> error::Return {
+    let mut child = match process::Command::new(COMMAND)
+        .arg("store")
+        .arg(format!("--label=cerebro: {}", name))
+        .args(&ATTRIBUTE_SERVICE)
+        .arg(ATTRIBUTE_NAME)
+        .arg(name)
+        .stdin(process::Stdio::piped())
+        .spawn() {
+
+        Ok(c) => c,
+        Err(_) => return error!("Cannot execute secret-tool"),
+    };
+
+    // The value is given on the standard input to not be visible in the
+    // list of the processes
+    match child.stdin.take() {
+        Some(mut stdin) => match stdin.write_all(value.as_bytes()) {
+            Ok(_) => (),
+            Err(_) => return error!("Cannot write secret"),
+        },
+
+        None => return error!("Cannot write secret"),
+    }
+
+    return match child.wait() {
+        Ok(s) if s.success() => success!(),
+        _ => error!("Cannot store secret"),
+    };
+}
+
+/// Function used to get a secret from the keyring
+///
+/// # Arguments
+///
+/// * `name` - The name of the secret
+fn lookup(name: &str) -> Option<String> {
+    match CACHE.lock() {
+        Ok(c) => match c.get(name) {
+            Some(v) => return Some(v.clone()),
+            None => (),
+        },
+
+        Err(_) => (),
+    }
+
+    let output = match process::Command::new(COMMAND)
+        .arg("lookup")
+        .args(&ATTRIBUTE_SERVICE)
+        .arg(ATTRIBUTE_NAME)
+        .arg(name)
+        .output() {
+
+        Ok(o) if o.status.success() => o,
+        _ => return None,
+    };
+
+    let value = String::from_utf8_lossy(&output.stdout).to_string();
+
+    match CACHE.lock() {
+        Ok(mut c) => {
+            c.insert(name.to_string(), value.clone());
+        },
+
+        Err(_) => (),
+    }
+
+    return Some(value);
+}
+
+/// Function used to check if a value of the configuration references a
+/// secret
+///
+/// # Arguments
+///
+/// * `value` - The value of the configuration
+pub fn is_reference(value: &str) -> bool {
+    return value.starts_with(PREFIX);
+}
+
+/// Function used to resolve a value of the configuration
+///
+/// Values formatted as `secret://<name>` are replaced by the secret, other
+/// values are kept as is. Returns None if the secret cannot be found.
+///
+/// # Arguments
+///
+/// * `value` - The value of the configuration
+#[cfg_attr(
+    not(any(feature = "http", feature = "weather")),
+    allow(dead_code))]
+pub fn resolve(value: &str) -> Option<String> {
+    let name = match value.strip_prefix(PREFIX) {
+        Some(n) => n,
+        None => return Some(value.to_string()),
+    };
+
+    let secret = lookup(name);
+
+    if secret.is_none() {
+        log::error!("Cannot find secret: {}", name);
+    }
+
+    return secret;
+}
+
+/// Function used to quote a value of a curl configuration file
+///
+/// # Arguments
+///
+/// * `value` - The value
+fn curl_quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+        .replace('\t', "\\t");
+
+    return format!("\"{}\"", escaped);
+}
+
+/// Function used to run curl without exposing secrets in the list of the
+/// processes
+///
+/// The options that may hold a secret (URL, headers, ...) are given to curl
+/// on its standard input as a configuration file, as the arguments of any
+/// process can be read by all the users.
+///
+/// # Arguments
+///
+/// * `args` - The arguments of curl, without secrets
+/// * `options` - The names and values of the options that may hold secrets
+#[cfg_attr(
+    not(any(feature = "http", feature = "weather")),
+    allow(dead_code))]
+pub fn curl(args: &[String], options: &[(&str, String)])
+    -> Result<process::Output, error::CerebroError> {
+
+    let mut child = match process::Command::new("curl")
+        .arg("--config")
+        .arg("-")
+        .args(args)
+        .stdin(process::Stdio::piped())
+        .stdout(process::Stdio::piped())
+        .stderr(process::Stdio::null())
+        .spawn() {
+
+        Ok(c) => c,
+        Err(_) => return error!("Cannot execute curl"),
+    };
+
+    let config: String = options
+        .iter()
+        .map(|(n, v)| format!("{} = {}\n", n, curl_quote(v)))
+        .collect();
+
+    // The standard input is closed once written so that curl starts
+    match child.stdin.take() {
+        Some(mut stdin) => match stdin.write_all(config.as_bytes()) {
+            Ok(_) => (),
+            Err(_) => return error!("Cannot write curl configuration"),
+        },
+
+        None => return error!("Cannot write curl configuration"),
+    }
+
+    return match child.wait_with_output() {
+        Ok(o) => Ok(o),
+        Err(_) => error!("Cannot execute curl"),
+    };
+}
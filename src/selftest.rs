@@ -0,0 +1,239 @@
+use fuse::FileType;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::config;
+use crate::error;
+use crate::filesystem::{FsEntry, Mode};
+use crate::modules::module;
+use crate::modules::Module;
+use crate::sync::Mutex;
+
+const VALUE_UNKNOWN: &str = "?";
+
+/// Interval between two checks of the values while waiting for them
+const POLL_INTERVAL_MS: u64 = 200;
+
+/// Result of the check of an entry
+struct Check {
+    path: String,
+    error: Option<String>,
+}
+
+/// Function used to check the value of an entry
+///
+/// The value must be known and, for entries having a unit, numeric.
+///
+/// # Arguments
+///
+/// * `entry` - The entry
+/// * `value` - The value of the entry
+fn check_value(entry: &FsEntry, value: &str) -> Option<String> {
+    let value = value.trim();
+
+    if value.is_empty() {
+        return Some("empty value".to_string());
+    }
+
+    if value == VALUE_UNKNOWN {
+        return Some("unknown value".to_string());
+    }
+
+    return match entry.unit {
+        Some(u) => match value.parse::<f64>() {
+            Ok(_) => None,
+            Err(_) => Some(format!("not a number of {}", u.name())),
+        },
+
+        None => None,
+    };
+}
+
+/// Function used to check all the readable entries of a tree
+///
+/// # Arguments
+///
+/// * `module` - The module owning the entries
+/// * `entries` - The entries to be checked
+/// * `parent` - The path of the parent of the entries
+/// * `checks` - The results of the checks
+fn check_entries(
+    module: &dyn Module,
+    entries: &Vec<FsEntry>,
+    parent: &str,
+    checks: &mut Vec<Check>) {
+
+    for entry in entries.iter() {
+        let path = format!("{}/{}", parent, entry.name);
+
+        match entry.file_type {
+            FileType::Directory => {
+                check_entries(module, &entry.fs_entries, &path, checks);
+                continue;
+            },
+
+            FileType::RegularFile => (),
+            _ => continue,
+        }
+
+        if entry.mode == Mode::WriteOnly {
+            continue;
+        }
+
+        checks.push(Check {
+            error: check_value(entry, &module.value(entry.inode)),
+            path: path,
+        });
+    }
+}
+
+/// Function used to check the entries of a module
+///
+/// # Arguments
+///
+/// * `module` - The module to be checked
+fn check_module(module: &Arc<Mutex<dyn Module>>) -> Vec<Check> {
+    let name = match module.lock() {
+        Ok(m) => m.name().to_string(),
+        Err(_) => return Vec::new(),
+    };
+
+    // Lazy modules are only updated when their entries are read
+    module::refresh(&name);
+
+    let module = match module.lock() {
+        Ok(m) => m,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut checks = Vec::new();
+    let path = config::module_path(&name);
+
+    check_entries(&*module, &module.fs_entries(), &path, &mut checks);
+
+    if checks.is_empty() {
+        checks.push(Check {
+            path: path,
+            error: Some("no entry".to_string()),
+        });
+    }
+
+    return checks;
+}
+
+/// Function used to print the results of the checks as a table
+///
+/// # Arguments
+///
+/// * `checks` - The results of the checks
+fn print_table(checks: &Vec<Check>) {
+    let width = checks
+        .iter()
+        .map(|c| c.path.len())
+        .max()
+        .unwrap_or(0)
+        .max("ENTRY".len());
+
+    println!("{:width$}  RESULT  DETAIL", "ENTRY", width = width);
+
+    for check in checks.iter() {
+        match &check.error {
+            Some(e) => println!(
+                "{:width$}  fail    {}",
+                check.path,
+                e,
+                width = width),
+
+            None => println!("{:width$}  pass", check.path, width = width),
+        }
+    }
+}
+
+/// Function used to start the enabled modules once and check their entries
+///
+/// Every module is given up to `timeout` to produce valid values, then a
+/// table of the results is printed and the modules are stopped.
+///
+/// # Arguments
+///
+/// * `modules` - The list of modules
+/// * `config` - The configuration
+/// * `timeout` - The time given to the modules to produce their values
+pub fn run(
+    modules: &Vec<Arc<Mutex<dyn Module>>>,
+    config: &config::Config,
+    timeout: Duration) -> error::Return {
+
+    let mut started: Vec<Arc<Mutex<dyn Module>>> = Vec::new();
+    let mut checks: Vec<Check> = Vec::new();
+
+    // Start the enabled modules
+    for module in modules.iter() {
+        let mut m = match module.lock() {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+
+        let module_config = match config.modules.get(m.name()) {
+            Some(c) if c.enabled == Some(true) => c,
+            _ => continue,
+        };
+
+        match m.start(module_config) {
+            Ok(_) => started.push(module.clone()),
+            Err(e) => checks.push(Check {
+                path: m.name().to_string(),
+                error: Some(format!("cannot start: {}", e)),
+            }),
+        }
+    }
+
+    // Wait until all the values are valid or the timeout is reached
+    let start = Instant::now();
+
+    let mut results: Vec<Vec<Check>> = loop {
+        let results: Vec<Vec<Check>> = started
+            .iter()
+            .map(|m| check_module(m))
+            .collect();
+
+        let passed = results
+            .iter()
+            .all(|r| r.iter().all(|c| c.error.is_none()));
+
+        if passed || start.elapsed() >= timeout {
+            break results;
+        }
+
+        thread::sleep(Duration::from_millis(POLL_INTERVAL_MS));
+    };
+
+    for result in results.iter_mut() {
+        checks.append(result);
+    }
+
+    for module in started.iter() {
+        match module.lock() {
+            Ok(mut m) => match m.stop() {
+                Ok(_) => (),
+                Err(e) => log::error!("Cannot stop module: {}", e),
+            },
+
+            Err(_) => (),
+        }
+    }
+
+    print_table(&checks);
+
+    let failed = checks.iter().filter(|c| c.error.is_some()).count();
+
+    if failed > 0 {
+        return error!(&format!(
+            "{} of {} checks failed",
+            failed,
+            checks.len()));
+    }
+
+    return success!();
+}
@@ -0,0 +1,113 @@
+use lazy_static::lazy_static;
+use std::process;
+use std::thread;
+
+use crate::bus;
+use crate::config;
+use crate::error;
+use crate::modules::files;
+use crate::sync::Mutex;
+
+/// Player of the PulseAudio and PipeWire servers
+const PLAYER: &str = "paplay";
+
+/// Volume of the player matching 100%
+const PLAYER_VOLUME_NORM: u64 = 65536;
+
+const DEFAULT_VOLUME_PERCENT: u64 = 100;
+
+/// Entry telling if the focus mode is enabled
+const FOCUS_ENTRY: &str = "focus/enabled";
+
+const VALUE_TRUE: &str = "true";
+
+lazy_static! {
+    static ref SOUNDS: Mutex<Option<config::SoundsConfig>> = Mutex::new(None);
+}
+
+/// Function used to set the sounds that can be played
+pub fn init(config: &config::SoundsConfig) {
+    match SOUNDS.lock() {
+        Ok(mut s) => *s = Some(config.clone()),
+        Err(_) => log::error!("Cannot lock sounds"),
+    }
+}
+
+/// Function used to play a sound
+///
+/// The sound is played in the background, nothing is played while muted.
+///
+/// # Arguments
+///
+/// * `name` - The name of the sound
+/// * `volume_percent` - The volume overriding the one of the sound
+pub fn play(name: &str, volume_percent: Option<u64>) -> error::Return {
+    let (sound, mute_in_focus) = match SOUNDS.lock() {
+        Ok(s) => match &*s {
+            Some(c) => match c.sounds.get(name) {
+                Some(sound) => (sound.clone(), c.mute_in_focus != Some(false)),
+                None => return error!(
+                    Config,
+                    &format!("Unknown sound `{}`", name)),
+            },
+
+            None => return error!(Config, "No sounds configured"),
+        },
+
+        Err(_) => return error!(Lock, "Cannot lock sounds"),
+    };
+
+    if mute_in_focus && bus::value(FOCUS_ENTRY).as_deref() == Some(VALUE_TRUE) {
+        log::debug!("Sound `{}` muted by the focus mode", name);
+        return success!();
+    }
+
+    let volume = volume_percent
+        .or(sound.volume_percent)
+        .unwrap_or(DEFAULT_VOLUME_PERCENT) * PLAYER_VOLUME_NORM / 100;
+
+    let mut child = match process::Command::new(PLAYER)
+        .arg(format!("--volume={}", volume))
+        .arg(files::expand_path(&sound.file))
+        .spawn() {
+
+        Ok(c) => c,
+        Err(e) => return error!(&format!("Cannot execute {}: {:?}", PLAYER, e)),
+    };
+
+    // Reap the player once the sound is over
+    thread::spawn(move || {
+        match child.wait() {
+            Ok(s) if !s.success() => log::error!("Cannot play sound"),
+            Ok(_) => (),
+            Err(e) => log::error!("Cannot wait for {}: {:?}", PLAYER, e),
+        }
+    });
+
+    return success!();
+}
+
+/// Function used to run the sound action of a trigger
+///
+/// # Arguments
+///
+/// * `args` - The name of the sound, optionally followed by the volume
+pub fn action(args: &str) -> error::Return {
+    let mut words = args.split_whitespace();
+
+    let name = match words.next() {
+        Some(n) => n,
+        None => return error!("Sound action must be `@sound <name> [volume]`"),
+    };
+
+    let volume_percent = match words.next() {
+        Some(v) => match v.trim_end_matches('%').parse::<u64>() {
+            Ok(v) => Some(v),
+            Err(_) => return error!(&format!("Invalid volume `{}`", v)),
+        },
+
+        None => None,
+    };
+
+    return play(name, volume_percent);
+}
@@ -0,0 +1,139 @@
+use lazy_static::lazy_static;
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Condvar;
+
+use crate::event_log;
+use crate::sync::Mutex;
+
+/// Maximum number of lines queued for a reader, the oldest ones are dropped
+const MAX_QUEUED_LINES: usize = 1024;
+
+lazy_static! {
+    static ref STREAMS: Mutex<HashMap<u64, VecDeque<Vec<u8>>>> =
+        Mutex::new(HashMap::new());
+
+    static ref PUSHED: Condvar = Condvar::new();
+}
+
+/// Function used to start queueing the changes for a reader
+///
+/// # Arguments
+///
+/// * `id` - The identifier of the reader
+pub fn open(id: u64) {
+    match STREAMS.lock() {
+        Ok(mut s) => {
+            s.insert(id, VecDeque::new());
+        },
+
+        Err(_) => log::error!("Cannot lock streams"),
+    }
+}
+
+/// Function used to stop queueing the changes for a reader
+///
+/// A read in progress returns immediately.
+///
+/// # Arguments
+///
+/// * `id` - The identifier of the reader
+pub fn close(id: u64) {
+    match STREAMS.lock() {
+        Ok(mut s) => {
+            s.remove(&id);
+        },
+
+        Err(_) => log::error!("Cannot lock streams"),
+    }
+
+    PUSHED.notify_all();
+}
+
+/// Function used to queue a value change for all the readers
+///
+/// # Arguments
+///
+/// * `path` - The path of the entry
+/// * `old_value` - The previous value
+/// * `new_value` - The new value
+pub fn push(path: &str, old_value: &str, new_value: &str) {
+    let mut streams = match STREAMS.lock() {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+
+    if streams.is_empty() {
+        return;
+    }
+
+    let line = format!(
+        "{}\n",
+        json!({
+            "timestamp": event_log::timestamp(),
+            "path": path,
+            "old": old_value,
+            "new": new_value,
+        }));
+
+    for lines in streams.values_mut() {
+        if lines.len() >= MAX_QUEUED_LINES {
+            lines.pop_front();
+        }
+
+        lines.push_back(line.as_bytes().to_vec());
+    }
+
+    PUSHED.notify_all();
+}
+
+/// Function used to get the changes queued for a reader
+///
+/// Blocks until a change is queued. Returns None if the reader is closed.
+///
+/// # Arguments
+///
+/// * `id` - The identifier of the reader
+/// * `size` - The maximum number of bytes to be returned
+pub fn next(id: u64, size: usize) -> Option<Vec<u8>> {
+    let mut streams = match STREAMS.lock() {
+        Ok(s) => s,
+        Err(_) => return None,
+    };
+
+    loop {
+        let lines = match streams.get_mut(&id) {
+            Some(l) => l,
+            None => return None,
+        };
+
+        if !lines.is_empty() {
+            let mut data: Vec<u8> = Vec::new();
+
+            while let Some(line) = lines.pop_front() {
+                if data.len() + line.len() <= size {
+                    data.extend(line);
+                    continue;
+                }
+
+                // A line larger than the buffer is split between reads
+                if data.is_empty() {
+                    data.extend(&line[..size]);
+                    lines.push_front(line[size..].to_vec());
+                }
+                else {
+                    lines.push_front(line);
+                }
+
+                break;
+            }
+
+            return Some(data);
+        }
+
+        streams = match PUSHED.wait(streams) {
+            Ok(s) => s,
+            Err(_) => return None,
+        };
+    }
+}
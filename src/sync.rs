@@ -0,0 +1,62 @@
+use std::fmt;
+use std::sync;
+use std::sync::{LockResult, MutexGuard};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::metrics;
+
+/// Mutex recovering from poisoning
+///
+/// A thread panicking while holding the lock poisons a standard mutex and
+/// all the following locks fail, which permanently degrades the part of the
+/// filesystem using it. This mutex gives the data back instead: the state
+/// is the one left by the panicking thread and is refreshed by the next
+/// update.
+pub struct Mutex<T: ?Sized> {
+    reported: AtomicBool,
+    inner: sync::Mutex<T>,
+}
+
+impl<T> Mutex<T> {
+    /// Mutex constructor
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The data protected by the mutex
+    pub fn new(value: T) -> Self {
+        Self {
+            reported: AtomicBool::new(false),
+            inner: sync::Mutex::new(value),
+        }
+    }
+}
+
+impl<T: ?Sized> Mutex<T> {
+    /// Acquire the lock
+    ///
+    /// This never fails, the result is kept for compatibility with the
+    /// standard mutex. The poisoning is only reported the first time.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    pub fn lock(&self) -> LockResult<MutexGuard<'_, T>> {
+        return match self.inner.lock() {
+            Ok(g) => Ok(g),
+            Err(e) => {
+                if !self.reported.swap(true, Ordering::SeqCst) {
+                    log::warn!("Recovering from a poisoned lock");
+                    metrics::increment(metrics::POISONED_LOCKS, &[]);
+                }
+
+                Ok(e.into_inner())
+            },
+        };
+    }
+}
+
+impl<T: ?Sized + fmt::Debug> fmt::Debug for Mutex<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return self.inner.fmt(f);
+    }
+}
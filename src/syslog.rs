@@ -0,0 +1,228 @@
+use lazy_static::lazy_static;
+use serde_json::Value;
+use std::net::UdpSocket;
+use std::os::unix::net::UnixDatagram;
+use std::path::Path;
+use std::process;
+
+use crate::config;
+use crate::event_log;
+use crate::net;
+use crate::outbox;
+use crate::sync::Mutex;
+
+const APP_NAME: &str = "cerebro";
+
+/// Name of the remote target in the outbox
+const OUTBOX_SINK: &str = "syslog";
+
+const DEFAULT_FACILITY: &str = "user";
+
+/// Sockets of the local syslog daemon, the first one found is used
+const LOCAL_SOCKETS: [&str; 2] = ["/dev/log", "/var/run/log"];
+
+const SEVERITY_ERROR: u8 = 3;
+const SEVERITY_NOTICE: u8 = 5;
+
+/// Facilities as numbered by RFC 5424
+const FACILITIES: [(&str, u8); 12] = [
+    ("user", 1),
+    ("daemon", 3),
+    ("auth", 4),
+    ("syslog", 5),
+    ("local0", 16),
+    ("local1", 17),
+    ("local2", 18),
+    ("local3", 19),
+    ("local4", 20),
+    ("local5", 21),
+    ("local6", 22),
+    ("local7", 23),
+];
+
+/// The structure used to store the state of the forwarding
+struct Syslog {
+    facility: u8,
+    local: Option<UnixDatagram>,
+    remote: Option<(UdpSocket, String)>,
+    hostname: String,
+}
+
+lazy_static! {
+    static ref SYSLOG: Mutex<Option<Syslog>> = Mutex::new(None);
+}
+
+/// Function used to get the name of the host
+fn hostname() -> String {
+    let mut buffer = [0u8; 256];
+
+    let result = unsafe {
+        libc::gethostname(
+            buffer.as_mut_ptr() as *mut libc::c_char,
+            buffer.len())
+    };
+
+    if result != 0 {
+        return "-".to_string();
+    }
+
+    let length = buffer.iter().position(|b| *b == 0).unwrap_or(buffer.len());
+
+    return String::from_utf8_lossy(&buffer[..length]).to_string();
+}
+
+impl Syslog {
+    /// Send a message to the local daemon and to the remote target
+    ///
+    /// The local daemon receives a BSD (RFC 3164) message, the remote
+    /// target a RFC 5424 one. The messages of the remote target are queued
+    /// while it's unreachable and replayed before the next message.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `severity` - The severity of the message
+    /// * `message` - The message to be sent
+    fn send(&self, severity: u8, message: &str) {
+        let priority = self.facility * 8 + severity;
+
+        match &self.local {
+            Some(s) => {
+                let line = format!(
+                    "<{}>{}[{}]: {}",
+                    priority,
+                    APP_NAME,
+                    process::id(),
+                    message);
+
+                match s.send(line.as_bytes()) {
+                    Ok(_) => (),
+                    Err(e) => log::debug!("Cannot send to syslog: {}", e),
+                }
+            },
+
+            None => (),
+        }
+
+        match &self.remote {
+            Some((s, target)) => {
+                let line = format!(
+                    "<{}>1 {} {} {} {} - - {}",
+                    priority,
+                    event_log::timestamp(),
+                    self.hostname,
+                    APP_NAME,
+                    process::id(),
+                    message);
+
+                if !net::is_online() {
+                    outbox::push(OUTBOX_SINK, &line);
+                    return;
+                }
+
+                let send = |l: &str| match s.send_to(l.as_bytes(), target) {
+                    Ok(_) => true,
+                    Err(e) => {
+                        log::debug!("Cannot send to {}: {}", target, e);
+                        false
+                    },
+                };
+
+                // The message is queued behind the remaining ones to keep
+                // the order
+                if !outbox::replay(OUTBOX_SINK, send) || !send(&line) {
+                    outbox::push(OUTBOX_SINK, &line);
+                }
+            },
+
+            None => (),
+        }
+    }
+}
+
+/// Function used to enable the forwarding of the triggers to syslog
+pub fn init(config: &config::SyslogConfig) {
+    match config.enabled {
+        Some(true) => (),
+        _ => return,
+    }
+
+    let name = config.facility.as_deref().unwrap_or(DEFAULT_FACILITY);
+
+    let facility = match FACILITIES.iter().find(|(n, _)| *n == name) {
+        Some((_, f)) => *f,
+        None => {
+            log::error!("Unknown syslog facility: {}", name);
+            return;
+        },
+    };
+
+    // Local daemon
+    let local = match config.local {
+        Some(false) => None,
+
+        _ => LOCAL_SOCKETS
+            .iter()
+            .filter(|p| Path::new(p).exists())
+            .find_map(|p| {
+                let socket = UnixDatagram::unbound().ok()?;
+                socket.connect(p).ok()?;
+                return Some(socket);
+            }),
+    };
+
+    // Remote target
+    let remote = match &config.remote {
+        Some(target) => match UdpSocket::bind("0.0.0.0:0") {
+            Ok(s) => Some((s, target.clone())),
+            Err(_) => {
+                log::error!("Cannot create syslog socket");
+                None
+            },
+        },
+
+        None => None,
+    };
+
+    if local.is_none() && remote.is_none() {
+        log::error!("No syslog daemon nor remote target available");
+        return;
+    }
+
+    let syslog = Syslog {
+        facility: facility,
+        local: local,
+        remote: remote,
+        hostname: hostname(),
+    };
+
+    match SYSLOG.lock() {
+        Ok(mut s) => *s = Some(syslog),
+        Err(_) => log::error!("Cannot lock syslog"),
+    }
+}
+
+/// Function used to forward a fired trigger (if enabled)
+///
+/// # Arguments
+///
+/// * `event` - The trigger, its values and the result of its command
+/// * `success` - Whether the command has been successful
+pub fn forward(event: &Value, success: bool) {
+    let syslog = match SYSLOG.lock() {
+        Ok(s) => s,
+        Err(_) => return,
+    };
+
+    let syslog = match &*syslog {
+        Some(s) => s,
+        None => return,
+    };
+
+    let severity = match success {
+        true => SEVERITY_NOTICE,
+        false => SEVERITY_ERROR,
+    };
+
+    syslog.send(severity, &event.to_string());
+}
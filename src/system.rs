@@ -0,0 +1,57 @@
+use std::ffi::CString;
+
+use crate::error;
+
+/// Function used to run the process as another user
+///
+/// The supplementary groups are cleared and the group of the user is used.
+/// This is irreversible, so it must be done once the privileged sources
+/// have been opened. Mounting with `allow_other` then requires
+/// `user_allow_other` to be set in `/etc/fuse.conf`.
+///
+/// # Arguments
+///
+/// * `user` - The name of the user
+/// * `mountpoint` - The mountpoint, given to the user so it can mount on it
+pub fn drop_privileges(user: &str, mountpoint: &str) -> error::Return {
+    if unsafe { libc::geteuid() } != 0 {
+        return error!("Not running as root");
+    }
+
+    let name = match CString::new(user) {
+        Ok(n) => n,
+        Err(_) => return error!("Invalid user name"),
+    };
+
+    let passwd = unsafe { libc::getpwnam(name.as_ptr()) };
+
+    if passwd.is_null() {
+        return error!("Unknown user");
+    }
+
+    let (uid, gid) = unsafe { ((*passwd).pw_uid, (*passwd).pw_gid) };
+
+    let path = match CString::new(mountpoint) {
+        Ok(p) => p,
+        Err(_) => return error!("Invalid mountpoint"),
+    };
+
+    if unsafe { libc::chown(path.as_ptr(), uid, gid) } != 0 {
+        return error!("Cannot change owner of mountpoint");
+    }
+
+    // The group must be changed while still being root
+    if unsafe { libc::setgroups(0, std::ptr::null()) } != 0 {
+        return error!("Cannot clear supplementary groups");
+    }
+
+    if unsafe { libc::setgid(gid) } != 0 {
+        return error!("Cannot change group");
+    }
+
+    if unsafe { libc::setuid(uid) } != 0 {
+        return error!("Cannot change user");
+    }
+
+    return success!();
+}
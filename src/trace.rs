@@ -0,0 +1,306 @@
+use dirs;
+use lazy_static::lazy_static;
+use serde_json::{json, Map, Value};
+use std::fmt;
+use std::fs;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::time::Instant;
+use tracing::field::{Field, Visit};
+use tracing::span;
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+
+use crate::config;
+use crate::error;
+use crate::sync::Mutex;
+
+/// Name of the default file, under the runtime directory of the user
+const DEFAULT_FILE: &str = "cerebro-trace.json";
+
+const COMMAND_START: &str = "start";
+const COMMAND_STOP: &str = "stop";
+
+/// The structure used to store the state of a recording
+struct Recording {
+    path: PathBuf,
+    writer: BufWriter<fs::File>,
+    first: bool,
+}
+
+lazy_static! {
+    static ref PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+    static ref RECORDING: Mutex<Option<Recording>> = Mutex::new(None);
+
+    static ref START: Instant = Instant::now();
+}
+
+/// Checked before locking the recording, spans are mostly not recorded
+static RECORDING_ENABLED: AtomicBool = AtomicBool::new(false);
+
+/// Counter used to identify the threads in the trace
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD_ID: u64 = NEXT_THREAD_ID.fetch_add(1, Ordering::SeqCst);
+}
+
+/// Fields of a span, given as arguments of its events
+struct Fields(Map<String, Value>);
+
+impl Visit for Fields {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_string(), json!(format!("{:?}", value)));
+    }
+}
+
+/// Layer writing the spans in the Chrome trace event format
+///
+/// The file can be opened with `chrome://tracing` or Perfetto.
+struct ChromeLayer;
+
+impl ChromeLayer {
+    /// Write the beginning or the end of a span
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the span
+    /// * `phase` - `B` at the beginning, `E` at the end
+    /// * `args` - The fields of the span
+    fn write(name: &str, phase: &str, args: Value) {
+        let mut recording = match RECORDING.lock() {
+            Ok(r) => r,
+            Err(_) => return,
+        };
+
+        let recording = match &mut *recording {
+            Some(r) => r,
+            None => return,
+        };
+
+        let event = json!({
+            "name": name,
+            "cat": "cerebro",
+            "ph": phase,
+            "ts": START.elapsed().as_micros() as u64,
+            "pid": process::id(),
+            "tid": THREAD_ID.with(|t| *t),
+            "args": args,
+        });
+
+        let separator = match recording.first {
+            true => "",
+            false => ",\n",
+        };
+
+        recording.first = false;
+
+        match write!(recording.writer, "{}{}", separator, event) {
+            Ok(_) => (),
+            Err(_) => log::error!("Cannot write trace"),
+        }
+    }
+}
+
+impl<S> Layer<S> for ChromeLayer
+    where S: Subscriber + for<'a> LookupSpan<'a> {
+
+    fn on_new_span(
+        &self,
+        attrs: &span::Attributes<'_>,
+        id: &span::Id,
+        ctx: Context<'_, S>) {
+
+        let span = match ctx.span(id) {
+            Some(s) => s,
+            None => return,
+        };
+
+        let mut fields = Fields(Map::new());
+        attrs.record(&mut fields);
+
+        span.extensions_mut().insert(fields);
+    }
+
+    fn on_enter(&self, id: &span::Id, ctx: Context<'_, S>) {
+        if !RECORDING_ENABLED.load(Ordering::Relaxed) {
+            return;
+        }
+
+        let span = match ctx.span(id) {
+            Some(s) => s,
+            None => return,
+        };
+
+        let args = match span.extensions().get::<Fields>() {
+            Some(f) => Value::Object(f.0.clone()),
+            None => json!({}),
+        };
+
+        ChromeLayer::write(span.name(), "B", args);
+    }
+
+    fn on_exit(&self, id: &span::Id, ctx: Context<'_, S>) {
+        if !RECORDING_ENABLED.load(Ordering::Relaxed) {
+            return;
+        }
+
+        match ctx.span(id) {
+            Some(s) => ChromeLayer::write(s.name(), "E", json!({})),
+            None => (),
+        }
+    }
+}
+
+/// Function used to enable the instrumentation
+///
+/// Nothing is recorded until a recording is started.
+pub fn init(config: &config::TraceConfig) {
+    match config.enabled {
+        Some(true) => (),
+        _ => return,
+    }
+
+    let path = match &config.path {
+        Some(p) => PathBuf::from(p),
+        None => match dirs::runtime_dir() {
+            Some(d) => d.join(DEFAULT_FILE),
+            None => {
+                log::error!("Cannot find runtime directory");
+                return;
+            },
+        },
+    };
+
+    let subscriber = tracing_subscriber::registry().with(ChromeLayer);
+
+    match tracing::subscriber::set_global_default(subscriber) {
+        Ok(_) => (),
+        Err(_) => {
+            log::error!("Cannot install tracing subscriber");
+            return;
+        },
+    }
+
+    match PATH.lock() {
+        Ok(mut p) => *p = Some(path),
+        Err(_) => log::error!("Cannot lock trace path"),
+    }
+}
+
+/// Function used to start recording the spans
+///
+/// A previous recording of the same file is overwritten.
+fn start() -> error::Return {
+    let path = match PATH.lock() {
+        Ok(p) => match &*p {
+            Some(p) => p.clone(),
+            None => return error!("Tracing not enabled"),
+        },
+
+        Err(_) => return error!(Lock, "Cannot lock trace path"),
+    };
+
+    let mut recording = match RECORDING.lock() {
+        Ok(r) => r,
+        Err(_) => return error!(Lock, "Cannot lock trace"),
+    };
+
+    if recording.is_some() {
+        return success!();
+    }
+
+    let mut writer = match fs::File::create(&path) {
+        Ok(f) => BufWriter::new(f),
+        Err(_) => return error!("Cannot create trace file"),
+    };
+
+    match writeln!(writer, "[") {
+        Ok(_) => (),
+        Err(_) => return error!("Cannot write trace"),
+    }
+
+    *recording = Some(Recording {
+        path: path,
+        writer: writer,
+        first: true,
+    });
+
+    RECORDING_ENABLED.store(true, Ordering::SeqCst);
+
+    log::info!("Trace recording started");
+
+    return success!();
+}
+
+/// Function used to stop recording the spans
+///
+/// Spans still open are left without end in the file.
+fn stop() -> error::Return {
+    RECORDING_ENABLED.store(false, Ordering::SeqCst);
+
+    let mut recording = match RECORDING.lock() {
+        Ok(r) => r,
+        Err(_) => return error!(Lock, "Cannot lock trace"),
+    };
+
+    let mut recording = match recording.take() {
+        Some(r) => r,
+        None => return success!(),
+    };
+
+    let result = writeln!(recording.writer, "\n]")
+        .and_then(|_| recording.writer.flush());
+
+    log::info!("Trace written to {}", recording.path.display());
+
+    return match result {
+        Ok(_) => success!(),
+        Err(_) => error!("Cannot write trace"),
+    };
+}
+
+/// Function used to get the state of the recording
+///
+/// Returns `on <path>` while recording and `off` otherwise.
+pub fn status() -> String {
+    return match RECORDING.lock() {
+        Ok(r) => match &*r {
+            Some(r) => format!("on {}\n", r.path.display()),
+            None => "off\n".to_string(),
+        },
+
+        Err(_) => "off\n".to_string(),
+    };
+}
+
+/// Function used to control the recording from the control file
+///
+/// # Arguments
+///
+/// * `data` - The command written: `start` or `stop`
+pub fn control(data: &[u8]) -> error::Return {
+    return match String::from_utf8_lossy(data).trim() {
+        COMMAND_START | "1" => start(),
+        COMMAND_STOP | "0" => stop(),
+        _ => Err(error::CerebroError::fuse(
+            "Unknown trace command",
+            libc::EINVAL)),
+    };
+}
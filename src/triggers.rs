@@ -1,11 +1,52 @@
+use lazy_static::lazy_static;
 use regex::Regex;
+use serde_json::json;
 use std::cmp::Ordering;
 use std::fs;
 use std::io::{BufRead, BufReader};
 use std::path::Path;
 use std::process;
+use std::sync::Arc;
+use std::sync::mpsc::Sender;
 
+use crate::automations;
+use crate::bus;
+use crate::config;
+use crate::demand;
 use crate::error;
+use crate::event_log;
+use crate::events;
+use crate::expression;
+use crate::inhibit;
+use crate::gauge;
+use crate::metrics;
+use crate::mirror;
+use crate::modules::leds;
+use crate::modules::module;
+use crate::notifications;
+use crate::notifier;
+use crate::sounds;
+use crate::stream;
+use crate::sync::Mutex;
+use crate::syslog;
+
+/// Internal actions, run without spawning a process
+const ACTION_PREFIX: &str = "@";
+const ACTION_INHIBIT: &str = "@inhibit";
+const ACTION_LED: &str = "@led";
+const ACTION_NOTIFY: &str = "@notify";
+const ACTION_REFRESH: &str = "@refresh";
+const ACTION_ROTATE_LOGS: &str = "@rotate-logs";
+const ACTION_SNAPSHOT: &str = "@snapshot";
+const ACTION_SOUND: &str = "@sound";
+const ACTION_WRITE: &str = "@write";
+
+lazy_static! {
+    static ref SUPPRESSED_GROUPS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    static ref EVENT_SENDER: Mutex<Option<Arc<Mutex<Sender<events::Events>>>>> =
+        Mutex::new(None);
+}
 
 /// Type of trigger
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -33,6 +74,8 @@ pub struct Trigger {
     pub path: String,
     pub operator: Operator,
     pub value_to_compare: String,
+    pub group: String,
+    pub line: usize,
 
     command: String,
 }
@@ -43,7 +86,9 @@ impl Trigger {
         path: &str,
         operator: &str,
         value_to_compare: &str,
-        command: &str) -> Self {
+        command: &str,
+        group: &str,
+        line: usize) -> Self {
 
         Self {
             kind: match kind {
@@ -62,36 +107,25 @@ impl Trigger {
                 _ => Operator::None,
             },
             value_to_compare: value_to_compare.to_string(),
+            group: group.to_string(),
+            line: line,
             command: command.to_string(),
         }
     }
 
     pub fn execute(&self) -> error::Return {
-        log::debug!("{} >>> {}", self.path, self.command);
-
-        for command in self.command.split(";") {
-            let mut parsed_command = match shellwords::split(command) {
-                Ok(w) => w,
-                Err(e) =>
-                    return error!(&format!("Cannot split command: {:?}", e)),
-            };
-
-            let args = parsed_command.split_off(1);
-
-            let output = match process::Command::new(&parsed_command[0])
-                .args(args).output() {
-
-                Ok(o) => o,
-                Err(e) =>
-                    return error!(&format!("Cannot execute command: {:?}", e)),
-            };
+        let _span = tracing::trace_span!(
+            "trigger.execute",
+            path = self.path.as_str()).entered();
 
-            if !output.status.success() {
-                return error!("Command is not successful");
-            }
-        }
+        log::debug!("{} >>> {}", self.path, self.command);
 
-        return success!();
+        return match run(&self.command) {
+            Ok(_) => success!(),
+            Err(e) => Err(error::CerebroError::trigger(
+                self.line,
+                &e.to_string())),
+        };
     }
 
     pub fn matches(&self, kind: Kind, path: &str) -> bool {
@@ -124,31 +158,50 @@ impl Trigger {
 }
 
 /// Function used to load the triggers from a file
+///
+/// All the triggers of a file belong to the same group, named after the file
+/// (e.g. `notifications.triggers` defines the group `notifications`).
 fn load_file<P: AsRef<Path>>(path: P)
     -> Result<Vec<Trigger>, error::CerebroError> {
 
+    let group = match path.as_ref().file_stem() {
+        Some(g) => g.to_string_lossy().to_string(),
+        None => "".to_string(),
+    };
+
     let mut triggers: Vec<Trigger> = Vec::new();
 
+    let name = path.as_ref().display().to_string();
+
     // Open the file in read-only mode
     let file = match fs::File::open(path) {
         Ok(f) => f,
-        Err(_) => return error!("Cannot open trigger file"),
+        Err(e) => return Err(
+            error::CerebroError::io("Cannot open trigger file", e)),
     };
 
     let re_line =
         Regex::new(r"^(C|D|U) ([^ ]+) (\*|<|>|!=|==) (\*|[0-9a-zA-Z]+) (.*)")
             .unwrap();
 
-    for line in BufReader::new(file).lines() {
+    for (index, line) in BufReader::new(file).lines().enumerate() {
         let line = match line {
             Ok(l) => l,
             Err(_) => continue,
         };
 
+        // Empty lines and comments
+        if line.trim().is_empty() || line.trim_start().starts_with('#') {
+            continue;
+        }
+
         let captures = match re_line.captures(&line) {
             Some(c) => c,
             None => {
-                log::debug!("Invalid trigger: {:?}", line);
+                log::error!(
+                    "{}: {}",
+                    name,
+                    error::CerebroError::trigger(index + 1, "Invalid trigger"));
                 continue;
             },
         };
@@ -163,6 +216,19 @@ fn load_file<P: AsRef<Path>>(path: P)
             None => continue,
         };
 
+        match Regex::new(path) {
+            Ok(_) => (),
+            Err(e) => {
+                log::error!(
+                    "{}: {}",
+                    name,
+                    error::CerebroError::trigger(
+                        index + 1,
+                        &format!("Invalid path: {}", e)));
+                continue;
+            },
+        }
+
         let operator = match captures.get(3) {
             Some(o) => o.as_str(),
             None => continue,
@@ -179,7 +245,14 @@ fn load_file<P: AsRef<Path>>(path: P)
         };
 
         triggers.push(
-            Trigger::new(kind, path, operator, value_to_compare, command));
+            Trigger::new(
+                kind,
+                path,
+                operator,
+                value_to_compare,
+                command,
+                &group,
+                index + 1));
     }
 
     return Ok(triggers);
@@ -220,14 +293,217 @@ pub fn load<P: AsRef<Path>>(path: P)
 
         match load_file(p) {
             Ok(mut t) => triggers.append(&mut t),
-            Err(_) => log::error!("Error loading triggers from {}", p),
+            Err(e) => log::error!("Error loading triggers from {}: {}", p, e),
         }
     }
 
     return Ok(triggers);
 }
 
+/// Function used to set the groups of triggers that must not be executed
+pub fn set_suppressed_groups(groups: &Vec<String>) {
+    match SUPPRESSED_GROUPS.lock() {
+        Ok(mut g) => *g = groups.to_vec(),
+        Err(_) => log::error!("Cannot lock suppressed groups"),
+    }
+}
+
+/// Function used to check if a group of triggers is suppressed
+fn is_suppressed(group: &str) -> bool {
+    return match SUPPRESSED_GROUPS.lock() {
+        Ok(g) => g.iter().any(|x| x == group),
+        Err(_) => false,
+    };
+}
+
+/// Function used to execute the commands of a trigger or of an automation
+///
+/// The commands are separated by `;`, they are either programs executed
+/// without a shell or actions done internally (e.g. `@write`).
+///
+/// # Arguments
+///
+/// * `commands` - The commands
+pub fn run(commands: &str) -> error::Return {
+    for command in commands.split(";") {
+        if command.trim_start().starts_with(ACTION_PREFIX) {
+            match action(command.trim()) {
+                Ok(_) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut parsed_command = match shellwords::split(command) {
+            Ok(w) => w,
+            Err(e) => return error!(
+                &format!("Cannot split command: {:?}", e)),
+        };
+
+        if parsed_command.is_empty() {
+            continue;
+        }
+
+        let args = parsed_command.split_off(1);
+
+        let output = match process::Command::new(&parsed_command[0])
+            .args(args).output() {
+
+            Ok(o) => o,
+            Err(e) => return error!(
+                &format!("Cannot execute command: {:?}", e)),
+        };
+
+        if !output.status.success() {
+            return error!("Command is not successful");
+        }
+    }
+
+    return success!();
+}
+
+/// Function used to run an internal action
+///
+/// The supported actions are:
+/// * `@inhibit <name> on|off` to take or release an inhibitor lock
+/// * `@led <name> <value>` to drive a LED
+/// * `@notify <template>` to send a notification
+/// * `@refresh <module>` to update a module at once
+/// * `@rotate-logs` to rotate the files of the event log
+/// * `@snapshot` to take a snapshot of the values
+/// * `@sound <name> [volume]` to play a sound
+/// * `@write <path> <value>` to write a value to an entry
+///
+/// # Arguments
+///
+/// * `text` - The action and its arguments
+fn action(text: &str) -> error::Return {
+    let (name, args) = match text.find(' ') {
+        Some(i) => (&text[..i], text[i..].trim()),
+        None => (text, ""),
+    };
+
+    return match name {
+        ACTION_INHIBIT => inhibit::action(args),
+        ACTION_LED => leds::action(args),
+        ACTION_NOTIFY => notifications::send(args, &[]),
+        ACTION_REFRESH => refresh_module(args),
+        ACTION_ROTATE_LOGS => event_log::rotate(),
+        ACTION_SNAPSHOT => send_event(events::Events::TakeSnapshot),
+        ACTION_SOUND => sounds::action(args),
+        ACTION_WRITE => write_entry(args),
+        _ => error!(&format!("Unknown action `{}`", name)),
+    };
+}
+
+/// Function used to update a module without waiting for its next update
+///
+/// # Arguments
+///
+/// * `name` - The name of the module
+fn refresh_module(name: &str) -> error::Return {
+    if name.is_empty() {
+        return error!("Refresh action must be `@refresh <module>`");
+    }
+
+    // Lazy modules are updated here, the other ones stop waiting
+    module::refresh(name);
+    demand::wake(name);
+
+    return success!();
+}
+
+/// Function used to send an event to the filesystem
+///
+/// # Arguments
+///
+/// * `event` - The event
+fn send_event(event: events::Events) -> error::Return {
+    let sender = match EVENT_SENDER.lock() {
+        Ok(s) => match &*s {
+            Some(s) => s.clone(),
+            None => return error!("No filesystem to send the event to"),
+        },
+
+        Err(_) => return error!(Lock, "Cannot lock event sender"),
+    };
+
+    let sender = match sender.lock() {
+        Ok(s) => s,
+        Err(_) => return error!(Lock, "Cannot lock event sender"),
+    };
+
+    return match sender.send(event) {
+        Ok(_) => success!(),
+        Err(_) => error!("Cannot send event"),
+    };
+}
+
+/// Function used to write a value to an entry of the filesystem
+///
+/// The arguments are formatted as `<path> <value>`, the value is either an
+/// expression over the paths of entries (e.g. `als/lux / 10`) or a literal
+/// value between double quotes. The write is done by the filesystem once
+/// the trigger has returned.
+///
+/// # Arguments
+///
+/// * `args` - The arguments of the action
+fn write_entry(args: &str) -> error::Return {
+    let mut parts = args.splitn(2, ' ');
+
+    let (path, value) = match (parts.next(), parts.next()) {
+        (Some(p), Some(v)) if !p.is_empty() => (p, v.trim()),
+        _ => return error!("Write action must be `@write <path> <value>`"),
+    };
+
+    let quoted = value.len() >= 2
+        && value.starts_with('"')
+        && value.ends_with('"');
+
+    let evaluated = match quoted {
+        true => Some(value[1..value.len() - 1].to_string()),
+        false => match expression::Expression::parse(value) {
+            Ok(e) => e.evaluate(&|p| bus::value(p)).map(|v| v.to_string()),
+            Err(_) => None,
+        },
+    };
+
+    let value = match evaluated {
+        Some(v) => v,
+        None => return error!(&format!("Cannot evaluate `{}`", value)),
+    };
+
+    log::debug!(">>> write {}={}", path, value);
+
+    return send_event(events::Events::WriteEntry(
+        path.trim_start_matches('/').to_string(),
+        value));
+}
+
+/// Function used to give the channel used by the actions of the filesystem
+///
+/// # Arguments
+///
+/// * `sender` - The sender of the events handled by the filesystem
+pub fn set_event_sender(sender: Arc<Mutex<Sender<events::Events>>>) {
+    match EVENT_SENDER.lock() {
+        Ok(mut s) => *s = Some(sender),
+        Err(_) => log::error!("Cannot lock event sender"),
+    }
+}
+
 /// Function used to find all trigger that matches a pattern and execute them
+///
+/// The new value is published first.
+///
+/// # Arguments
+///
+/// * `triggers` - The triggers of the module
+/// * `kind` - The kind of triggers to be executed
+/// * `module` - The name of the module
+/// * `name` - The path of the entry in the module
+/// * `old_value` - The previous value
+/// * `new_value` - The new value
 pub fn find_all_and_execute<'a>(
     triggers: &'a Vec<Trigger>,
     kind: Kind,
@@ -236,12 +512,89 @@ pub fn find_all_and_execute<'a>(
     old_value: &str,
     new_value: &str) {
 
+    publish(kind, module, name, old_value, new_value);
+    execute_matching(triggers, kind, module, name, old_value, new_value);
+}
+
+/// Function used to publish the new value of an entry
+///
+/// Every value change goes through here, the bus, the waiting readers, the
+/// gauges, the mirrors, the streams and the event log are fed even if the
+/// triggers are not evaluated.
+///
+/// # Arguments
+///
+/// * `kind` - The kind of the change
+/// * `module` - The name of the module
+/// * `name` - The path of the entry in the module
+/// * `old_value` - The previous value
+/// * `new_value` - The new value
+pub fn publish(
+    kind: Kind,
+    module: &str,
+    name: &str,
+    old_value: &str,
+    new_value: &str) {
+
+    // The entries of an instance are under the directory of its type
+    let module = config::module_path(module);
+
+    notifier::notify(&format!("/{}/{}", module, name));
+
+    bus::publish(&module, name, new_value);
+
+    gauge::record(&format!("/{}/{}", module, name), new_value);
+
+    automations::notify();
+
+    mirror::write(kind, &module, name, new_value);
+
+    stream::push(&format!("/{}/{}", module, name), old_value, new_value);
+
+    event_log::write(
+        "value",
+        json!({
+            "kind": format!("{:?}", kind),
+            "path": format!("/{}/{}", module, name),
+            "old": old_value,
+            "new": new_value,
+        }));
+}
+
+/// Function used to execute the triggers matching the new value of an entry
+///
+/// # Arguments
+///
+/// * `triggers` - The triggers of the module
+/// * `kind` - The kind of triggers to be executed
+/// * `module` - The name of the module
+/// * `name` - The path of the entry in the module
+/// * `old_value` - The previous value
+/// * `new_value` - The new value
+pub fn execute_matching(
+    triggers: &Vec<Trigger>,
+    kind: Kind,
+    module: &str,
+    name: &str,
+    old_value: &str,
+    new_value: &str) {
+
+    // The entries of an instance are under the directory of its type
+    let module = config::module_path(module);
+
     for trigger in triggers.iter() {
         // Check path
         if ! trigger.matches(kind, &format!("/{}/{}", module, name)) {
             continue;
         }
 
+        let labels = [
+            ("group", trigger.group.as_str()),
+            ("path", trigger.path.as_str()),
+        ];
+
+        metrics::increment(metrics::TRIGGER_EVALUATIONS, &labels);
+
         log::debug!(
             "{} {:?} {} ?",
             new_value,
@@ -315,10 +668,53 @@ pub fn find_all_and_execute<'a>(
             }
         }
 
+        // Check group
+        if is_suppressed(&trigger.group) {
+            log::debug!(
+                "{} suppressed (group {})",
+                trigger.path,
+                trigger.group);
+
+            continue;
+        }
+
         // Execute trigger
-        match trigger.execute() {
+        metrics::increment(metrics::TRIGGER_EXECUTIONS, &labels);
+
+        let result = trigger.execute();
+
+        syslog::forward(
+            &json!({
+                "path": format!("/{}/{}", module, name),
+                "trigger": trigger.path,
+                "group": trigger.group,
+                "old": old_value,
+                "new": new_value,
+                "command": trigger.command,
+                "success": result.is_ok(),
+                "error": match &result {
+                    Ok(_) => None,
+                    Err(e) => Some(e.to_string()),
+                },
+            }),
+            result.is_ok());
+
+        event_log::write(
+            "trigger",
+            json!({
+                "path": format!("/{}/{}", module, name),
+                "trigger": trigger.path,
+                "group": trigger.group,
+                "command": trigger.command,
+                "success": result.is_ok(),
+            }));
+
+        match result {
             Ok(_) => (),
-            Err(e) => log::error!("{}", e),
+            Err(e) => {
+                log::error!("{}", e);
+                metrics::increment(metrics::TRIGGER_FAILURES, &labels);
+            },
         }
     }
 }
@@ -0,0 +1,121 @@
+/// Unit of the value of an entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Unit {
+    Bytes,
+    Kibibytes,
+    Mebibytes,
+    Gibibytes,
+    Percent,
+    Celsius,
+    Fahrenheit,
+    Seconds,
+    Minutes,
+    Hours,
+}
+
+const UNITS: [Unit; 10] = [
+    Unit::Bytes,
+    Unit::Kibibytes,
+    Unit::Mebibytes,
+    Unit::Gibibytes,
+    Unit::Percent,
+    Unit::Celsius,
+    Unit::Fahrenheit,
+    Unit::Seconds,
+    Unit::Minutes,
+    Unit::Hours,
+];
+
+impl Unit {
+    /// Get the symbol of the unit (e.g. `°C`)
+    pub fn symbol(&self) -> &'static str {
+        return match self {
+            Unit::Bytes => "B",
+            Unit::Kibibytes => "KiB",
+            Unit::Mebibytes => "MiB",
+            Unit::Gibibytes => "GiB",
+            Unit::Percent => "%",
+            Unit::Celsius => "°C",
+            Unit::Fahrenheit => "°F",
+            Unit::Seconds => "s",
+            Unit::Minutes => "min",
+            Unit::Hours => "h",
+        };
+    }
+
+    /// Get the name of the unit as used in the configuration (e.g. `gib`)
+    pub fn name(&self) -> &'static str {
+        return match self {
+            Unit::Bytes => "bytes",
+            Unit::Kibibytes => "kib",
+            Unit::Mebibytes => "mib",
+            Unit::Gibibytes => "gib",
+            Unit::Percent => "percent",
+            Unit::Celsius => "celsius",
+            Unit::Fahrenheit => "fahrenheit",
+            Unit::Seconds => "seconds",
+            Unit::Minutes => "minutes",
+            Unit::Hours => "hours",
+        };
+    }
+
+    /// Find a unit by its name
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the unit
+    pub fn from_name(name: &str) -> Option<Unit> {
+        return UNITS.iter().find(|u| u.name() == name).cloned();
+    }
+
+    /// Convert a value into another unit
+    ///
+    /// Returns None if the units are not of the same kind.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `value` - The value in this unit
+    /// * `to` - The unit to convert to
+    pub fn convert(&self, value: f64, to: Unit) -> Option<f64> {
+        if *self == to {
+            return Some(value);
+        }
+
+        match (self, to) {
+            (Unit::Celsius, Unit::Fahrenheit) => {
+                return Some(value * 9.0 / 5.0 + 32.0);
+            },
+
+            (Unit::Fahrenheit, Unit::Celsius) => {
+                return Some((value - 32.0) * 5.0 / 9.0);
+            },
+
+            _ => (),
+        }
+
+        // Linear units
+        let from = self.factor()?;
+        let to = to.factor()?;
+
+        if from.0 != to.0 {
+            return None;
+        }
+
+        return Some(value * from.1 / to.1);
+    }
+
+    /// Get the kind and the factor of a linear unit relatively to its base
+    fn factor(&self) -> Option<(&'static str, f64)> {
+        return match self {
+            Unit::Bytes => Some(("bytes", 1.0)),
+            Unit::Kibibytes => Some(("bytes", 1024.0)),
+            Unit::Mebibytes => Some(("bytes", 1024.0 * 1024.0)),
+            Unit::Gibibytes => Some(("bytes", 1024.0 * 1024.0 * 1024.0)),
+            Unit::Seconds => Some(("time", 1.0)),
+            Unit::Minutes => Some(("time", 60.0)),
+            Unit::Hours => Some(("time", 3600.0)),
+            _ => None,
+        };
+    }
+}
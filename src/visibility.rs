@@ -0,0 +1,233 @@
+use lazy_static::lazy_static;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+use crate::config;
+use crate::filesystem::FsEntry;
+use crate::sync::Mutex;
+
+/// Component of a pattern matching any component of a path
+const WILDCARD: &str = "*";
+
+lazy_static! {
+    static ref RULES: Mutex<HashMap<String, Rules>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Entries hidden from the tree of a module
+struct Rules {
+    hide: Vec<Vec<String>>,
+    only: Vec<Vec<String>>,
+}
+
+/// Function used to split a path or a pattern into its components
+///
+/// # Arguments
+///
+/// * `path` - The path relative to the module (e.g. `logical/0`)
+fn components(path: &str) -> Vec<String> {
+    return path
+        .split('/')
+        .filter(|c| !c.is_empty())
+        .map(|c| c.to_string())
+        .collect();
+}
+
+/// Function used to check if the first components match the second ones
+///
+/// # Arguments
+///
+/// * `a` - The components compared, of the pattern or of the path
+/// * `b` - The components they are compared to
+fn is_prefix(a: &[String], b: &[String]) -> bool {
+    if a.len() > b.len() {
+        return false;
+    }
+
+    return a.iter().zip(b.iter()).all(|(x, y)| {
+        x == WILDCARD || y == WILDCARD || x == y
+    });
+}
+
+impl Rules {
+    /// Check if an entry is visible
+    ///
+    /// Hidden entries hide their whole subtree. When only some entries are
+    /// kept, their parents are kept too.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `path` - The components of the path of the entry
+    fn is_visible(&self, path: &[String]) -> bool {
+        if self.hide.iter().any(|h| is_prefix(h, path)) {
+            return false;
+        }
+
+        if self.only.is_empty() {
+            return true;
+        }
+
+        return self.only
+            .iter()
+            .any(|o| is_prefix(o, path) || is_prefix(path, o));
+    }
+
+    /// Remove the hidden entries of a tree
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `entries` - The entries of the tree
+    /// * `parent` - The components of the path of the parent of the entries
+    fn filter_entries(&self, entries: &Vec<FsEntry>, parent: &[String])
+        -> Vec<FsEntry> {
+
+        let mut visible = Vec::new();
+
+        for entry in entries.iter() {
+            let mut path = parent.to_vec();
+            path.push(entry.name.clone());
+
+            if !self.is_visible(&path) {
+                continue;
+            }
+
+            let mut entry = entry.clone();
+            entry.fs_entries = self.filter_entries(&entry.fs_entries, &path);
+
+            visible.push(entry);
+        }
+
+        return visible;
+    }
+
+    /// Remove the hidden members of a JSON document
+    ///
+    /// The keys of the objects and the indexes of the arrays are the
+    /// components of the paths.
+    ///
+    /// # Arguments
+    ///
+    /// * `self` - The instance handle
+    /// * `value` - The JSON document
+    /// * `parent` - The components of the path of the document
+    fn filter_json(&self, value: Value, parent: &[String]) -> Value {
+        let child_path = |name: String| {
+            let mut path = parent.to_vec();
+            path.push(name);
+            path
+        };
+
+        return match value {
+            Value::Object(o) => Value::Object(o
+                .into_iter()
+                .map(|(k, v)| (child_path(k.clone()), k, v))
+                .filter(|(p, _, _)| self.is_visible(p))
+                .map(|(p, k, v)| (k, self.filter_json(v, &p)))
+                .collect::<Map<String, Value>>()),
+
+            Value::Array(a) => Value::Array(a
+                .into_iter()
+                .enumerate()
+                .map(|(i, v)| (child_path(i.to_string()), v))
+                .filter(|(p, _)| self.is_visible(p))
+                .map(|(p, v)| self.filter_json(v, &p))
+                .collect()),
+
+            v => v,
+        };
+    }
+}
+
+/// Function used to load the hidden entries of the modules
+pub fn init(config: &config::Config) {
+    let mut rules = match RULES.lock() {
+        Ok(r) => r,
+        Err(_) => return,
+    };
+
+    rules.clear();
+
+    for (module, module_config) in config.modules.iter() {
+        let hide = match &module_config.hide_entries {
+            Some(h) => h.iter().map(|p| components(p)).collect(),
+            None => Vec::new(),
+        };
+
+        let only = match &module_config.only_entries {
+            Some(o) => o.iter().map(|p| components(p)).collect(),
+            None => Vec::new(),
+        };
+
+        if hide.is_empty() && only.is_empty() {
+            continue;
+        }
+
+        rules.insert(module.clone(), Rules {
+            hide: hide,
+            only: only,
+        });
+    }
+}
+
+/// Function used to check if an entry of a module is visible
+///
+/// # Arguments
+///
+/// * `module` - The name of the module
+/// * `entry` - The path of the entry in the module
+pub fn is_visible(module: &str, entry: &str) -> bool {
+    let rules = match RULES.lock() {
+        Ok(r) => r,
+        Err(_) => return true,
+    };
+
+    return match rules.get(module) {
+        Some(r) => r.is_visible(&components(entry)),
+        None => true,
+    };
+}
+
+/// Function used to remove the hidden entries from the tree of a module
+///
+/// # Arguments
+///
+/// * `module` - The name of the module
+/// * `entries` - The entries of the module
+pub fn filter_entries(module: &str, entries: &Vec<FsEntry>) -> Vec<FsEntry> {
+    let rules = match RULES.lock() {
+        Ok(r) => r,
+        Err(_) => return entries.clone(),
+    };
+
+    return match rules.get(module) {
+        Some(r) => r.filter_entries(entries, &[]),
+        None => entries.clone(),
+    };
+}
+
+/// Function used to remove the hidden members from the JSON of a module
+///
+/// # Arguments
+///
+/// * `module` - The name of the module
+/// * `json` - The JSON output of the module
+pub fn filter_json(module: &str, json: &str) -> String {
+    let rules = match RULES.lock() {
+        Ok(r) => r,
+        Err(_) => return json.to_string(),
+    };
+
+    let rules = match rules.get(module) {
+        Some(r) => r,
+        None => return json.to_string(),
+    };
+
+    let value = match serde_json::from_str::<Value>(json) {
+        Ok(v) => v,
+        Err(_) => return json.to_string(),
+    };
+
+    return rules.filter_json(value, &[]).to_string();
+}